@@ -0,0 +1,296 @@
+-- Folders on the file system that make up the library.
+-- Picture and video paths are stored relative to the root they were scanned from.
+--
+-- Note that no rows are inserted here because the XDG pictures directory is
+-- only known at runtime. Fotema adds it as root 1 on startup if no roots exist,
+-- which matches the library_root_id default for existing pictures and videos.
+CREATE TABLE library_roots (
+        root_id         INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for library root
+        root_path_b64   TEXT UNIQUE NOT NULL, -- absolute path to root folder (base64 encoded)
+        root_path_lossy TEXT NOT NULL, -- path to root folder. Human readable for debugging.
+        is_enabled      BOOLEAN NOT NULL CHECK (is_enabled IN (0, 1)) DEFAULT 1 -- scan and show root?
+);
+
+-- Picture and video paths are now only unique within a library root, which
+-- requires rebuilding the pictures and videos tables as SQLite cannot drop a UNIQUE
+-- constraint. Views must be dropped first and recreated afterwards.
+
+DROP VIEW visual;
+DROP VIEW pictures_cleanup;
+DROP VIEW videos_cleanup;
+
+-- Dropping the pictures table will cascade deletes to tables with a foreign key
+-- on pictures, so take a copy of those tables to restore afterwards.
+
+CREATE TABLE motion_photos_backup AS SELECT * FROM motion_photos;
+CREATE TABLE pictures_geo_backup AS SELECT * FROM pictures_geo;
+CREATE TABLE pictures_face_scans_backup AS SELECT * FROM pictures_face_scans;
+CREATE TABLE pictures_faces_backup AS SELECT * FROM pictures_faces;
+
+CREATE TABLE pictures_new (
+        picture_id         INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for picture
+        library_root_id    INTEGER NOT NULL DEFAULT 1, -- library root picture was scanned from
+        picture_path_b64   TEXT NOT NULL, -- path to picture relative to library root (base64 encoded)
+        picture_path_lossy TEXT NOT NULL, --path to picture. Human readable for debugging.
+        thumbnail_path     TEXT UNIQUE, -- path to picture thumbnail. Not b64 as we only build UTF8 paths.
+        exif_created_ts    DATETIME, -- UTC timestamp for EXIF original creation date
+        exif_modified_ts   DATETIME, -- UTC timestamp for EXIF original modification date
+        is_selfie          BOOLEAN CHECK (is_selfie IN (0, 1)), -- front camera?
+        link_path_b64      TEXT NOT NULL, -- picture parent path, for linking picture/photo siblings. Base64 encoded.
+        link_path_lossy    TEXT NOT NULL, --picture parent path. Human readable for debugging.
+        content_id         TEXT,
+        metadata_version   INTEGER NOT NULL DEFAULT 0, -- code version that scanned metadata
+        orientation        INTEGER, -- EXIF orientation (1..8)
+        is_broken          BOOLEAN CHECK (is_broken IN (0, 1)),
+        fs_created_ts      DATETIME, -- UTC timestamp from file system
+        fs_modified_ts     DATETIME, -- UTC timestamp from file system
+
+        UNIQUE (library_root_id, picture_path_b64)
+);
+
+INSERT INTO pictures_new (
+        picture_id,
+        picture_path_b64,
+        picture_path_lossy,
+        thumbnail_path,
+        exif_created_ts,
+        exif_modified_ts,
+        is_selfie,
+        link_path_b64,
+        link_path_lossy,
+        content_id,
+        metadata_version,
+        orientation,
+        is_broken,
+        fs_created_ts,
+        fs_modified_ts
+)
+SELECT
+        picture_id,
+        picture_path_b64,
+        picture_path_lossy,
+        thumbnail_path,
+        exif_created_ts,
+        exif_modified_ts,
+        is_selfie,
+        link_path_b64,
+        link_path_lossy,
+        content_id,
+        metadata_version,
+        orientation,
+        is_broken,
+        fs_created_ts,
+        fs_modified_ts
+FROM pictures;
+
+DROP TABLE pictures;
+
+ALTER TABLE pictures_new RENAME TO pictures;
+
+CREATE INDEX pic_live_photo_idx ON pictures(library_root_id, link_path_b64, content_id);
+
+DELETE FROM motion_photos;
+INSERT INTO motion_photos SELECT * FROM motion_photos_backup;
+DROP TABLE motion_photos_backup;
+
+DELETE FROM pictures_geo;
+INSERT INTO pictures_geo SELECT * FROM pictures_geo_backup;
+DROP TABLE pictures_geo_backup;
+
+DELETE FROM pictures_face_scans;
+INSERT INTO pictures_face_scans SELECT * FROM pictures_face_scans_backup;
+DROP TABLE pictures_face_scans_backup;
+
+DELETE FROM pictures_faces;
+INSERT INTO pictures_faces SELECT * FROM pictures_faces_backup;
+DROP TABLE pictures_faces_backup;
+
+CREATE TABLE videos_new (
+        video_id          INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for video
+        library_root_id   INTEGER NOT NULL DEFAULT 1, -- library root video was scanned from
+        video_path_b64    TEXT NOT NULL, -- base64 encoded path to video relative to library root
+        video_path_lossy  TEXT NOT NULL, -- human readable path to video for debugging
+        link_path_b64     TEXT NOT NULL, -- base64 encoded video path minus suffix for linking with sibling photos
+        link_path_lossy   TEXT NOT NULL, -- human readable link path for debugging
+        thumbnail_path    TEXT UNIQUE, -- path to thumbnail. Not b64 as we only build UTF8 paths.
+        stream_created_ts DATETIME, -- UTC creation timestamp from video stream metadata
+        duration_millis   INTEGER, -- Duration in milliseconds of video
+        video_codec       TEXT, -- Video codec.
+        transcoded_path   TEXT, -- path to transcoded video. Not b64 as we only build UTF8 paths.
+        content_id        TEXT, -- iOS ID for linking with sibling photos
+        metadata_version  INTEGER NOT NULL DEFAULT 0, -- code version that scanned metadata
+        rotation          INTEGER, -- display matrix rotation in degrees
+        is_broken         BOOLEAN CHECK (is_broken IN (0, 1)),
+        fs_created_ts     DATETIME, -- UTC timestamp of file system creation time
+        fs_modified_ts    DATETIME, -- UTC timestamp of file system modification time
+
+        UNIQUE (library_root_id, video_path_b64)
+);
+
+INSERT INTO videos_new (
+        video_id,
+        video_path_b64,
+        video_path_lossy,
+        link_path_b64,
+        link_path_lossy,
+        thumbnail_path,
+        stream_created_ts,
+        duration_millis,
+        video_codec,
+        transcoded_path,
+        content_id,
+        metadata_version,
+        rotation,
+        is_broken,
+        fs_created_ts,
+        fs_modified_ts
+)
+SELECT
+        video_id,
+        video_path_b64,
+        video_path_lossy,
+        link_path_b64,
+        link_path_lossy,
+        thumbnail_path,
+        stream_created_ts,
+        duration_millis,
+        video_codec,
+        transcoded_path,
+        content_id,
+        metadata_version,
+        rotation,
+        is_broken,
+        fs_created_ts,
+        fs_modified_ts
+FROM videos;
+
+DROP TABLE videos;
+
+ALTER TABLE videos_new RENAME TO videos;
+
+CREATE INDEX vid_live_photo_idx ON videos(library_root_id, link_path_b64, content_id);
+
+-- Recreate views.
+-- The visual view now only includes items from enabled library roots.
+
+CREATE VIEW visual AS
+SELECT
+  -- Unique ID
+  COALESCE(pictures.picture_id, 'x') || '_' || COALESCE(videos.video_id, 'x') AS visual_id,
+  COALESCE(pictures.link_path_b64, videos.link_path_b64) AS link_path_b64,
+
+  library_roots.root_id AS library_root_id,
+  library_roots.root_path_b64,
+
+  pictures.picture_id,
+  pictures.picture_path_b64,
+  pictures.picture_path_lossy, -- for debug only. Never read in Fotema.
+  pictures.orientation AS picture_orientation,
+
+-- If the thumbnail path is absent in the database, then compute the path we know it
+-- will have. Eventually the thumbnail generation background process will create the file
+-- and it will show up in the UI without having to refresh the data.
+  CASE pictures.picture_id
+        WHEN NOT NULL THEN pictures.thumbnail_path
+        ELSE 'photo_thumbnails/' || printf('%04d', pictures.picture_id / 1000) || '/' || CAST(pictures.picture_id AS TEXT) || '_200x200.png'
+  END AS picture_thumbnail,
+
+  pictures.is_selfie,
+
+  videos.video_id,
+  videos.video_path_b64,
+  videos.video_path_lossy, -- for debug only. Never read in Fotema.
+
+-- If the thumbnail path is absent in the database, then compute the path we know it
+-- will have. Eventually the thumbnail generation background process will create the file
+-- and it will show up in the UI without having to refresh the data.
+  CASE videos.video_id
+        WHEN NOT NULL THEN videos.thumbnail_path
+        ELSE 'video_thumbnails/' || printf('%04d', videos.video_id / 1000) || '/' || CAST(videos.video_id AS TEXT) || '_200x200.png'
+  END AS video_thumbnail,
+
+  COALESCE(videos.video_codec, motion_photos.video_codec) AS video_codec,
+
+  COALESCE(videos.video_codec, motion_photos.video_codec) IN ('hevc') AS is_transcode_required,
+
+  COALESCE(videos.transcoded_path, motion_photos.transcoded_path) AS video_transcoded_path,
+
+  COALESCE(videos.rotation, motion_photos.rotation) AS video_rotation,
+
+  -- An iOS live photo is a photo and a video linked with a content ID.
+  -- However, we only really need the video part, and short (<3 seconds)
+  -- videos are possibly live photos that have a missing or misnamed photo.
+  CASE
+        WHEN videos.content_id IS NOT NULL THEN true
+        WHEN videos.duration_millis <= 3000 THEN true
+        WHEN motion_photos.video_path IS NOT NULL THEN true
+        ELSE false
+  END AS is_live_photo,
+
+  COALESCE(videos.duration_millis, motion_photos.duration_millis) as duration_millis,
+
+  motion_photos.video_path AS motion_photo_video_path,
+
+  pictures_geo.longitude AS longitude,
+  pictures_geo.latitude AS latitude,
+
+  -- Timestamp to order visual items by.
+  -- Prefer embedded metadata over file system metadata.
+  COALESCE(
+    pictures.exif_created_ts,
+    videos.stream_created_ts,
+    pictures.exif_modified_ts,
+    pictures.fs_created_ts,
+    videos.fs_created_ts,
+    pictures.fs_modified_ts,
+    videos.fs_modified_ts,
+    CURRENT_TIMESTAMP
+  ) AS ordering_ts
+FROM
+  pictures
+  FULL OUTER JOIN videos USING (library_root_id, link_path_b64, content_id)
+  FULL OUTER JOIN motion_photos USING (picture_id)
+  FULL OUTER JOIN pictures_geo USING (picture_id)
+  INNER JOIN library_roots ON library_roots.root_id = COALESCE(pictures.library_root_id, videos.library_root_id)
+WHERE COALESCE(pictures.is_broken, FALSE) IS FALSE
+AND COALESCE(videos.is_broken, FALSE) IS FALSE
+AND library_roots.is_enabled IS TRUE
+ORDER BY
+  ordering_ts ASC;
+
+CREATE VIEW pictures_cleanup AS
+
+SELECT picture_id, 'cache' AS root_name, 'picture thumbnail' AS description, thumbnail_path AS path
+FROM pictures
+
+UNION
+
+SELECT picture_id, 'cache' AS root_name, 'motion photo video' AS description, video_path AS path
+FROM motion_photos
+WHERE video_path IS NOT NULL
+
+UNION
+
+SELECT picture_id, 'cache' AS root_name, 'motion photo transcoded video' AS description, transcoded_path AS path
+FROM motion_photos
+
+WHERE transcoded_path IS NOT NULL
+
+UNION
+
+SELECT picture_id, 'data' AS root_name, 'face bounds' AS description, bounds_path AS path FROM pictures_faces
+
+UNION
+
+SELECT picture_id, 'data' AS root_name, 'face thumbnail' AS description, thumbnail_path AS path FROM pictures_faces;
+
+CREATE VIEW videos_cleanup AS
+
+SELECT video_id, 'cache' AS root_name, 'video thumbnail' AS description, thumbnail_path AS path
+FROM videos
+
+UNION
+
+SELECT video_id, 'cache' AS root_name, 'video transcode' AS description, transcoded_path AS path
+FROM videos
+WHERE transcoded_path IS NOT NULL;
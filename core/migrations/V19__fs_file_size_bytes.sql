@@ -0,0 +1,7 @@
+-- File size is stored alongside the file system modification time so a rescan
+-- can detect changed files without re-processing the whole library.
+-- Nullable because existing rows are only updated on the next scan.
+
+ALTER TABLE pictures ADD COLUMN fs_file_size_bytes INTEGER; -- file size in bytes from file system
+
+ALTER TABLE videos ADD COLUMN fs_file_size_bytes INTEGER; -- file size in bytes from file system
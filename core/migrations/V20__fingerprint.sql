@@ -0,0 +1,6 @@
+-- Fingerprint of file contents, so moved and renamed files can keep their derived data.
+ALTER TABLE pictures ADD COLUMN fingerprint TEXT;
+ALTER TABLE videos ADD COLUMN fingerprint TEXT;
+
+CREATE INDEX pictures_fingerprint_idx ON pictures (fingerprint);
+CREATE INDEX videos_fingerprint_idx ON videos (fingerprint);
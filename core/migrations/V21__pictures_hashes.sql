@@ -0,0 +1,11 @@
+-- Hashes for finding duplicate and near-duplicate pictures
+CREATE TABLE pictures_hashes (
+        picture_id         INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for picture
+        hash_version       INTEGER NOT NULL, -- code version that computed hashes
+        content_hash       TEXT NOT NULL, -- SHA-256 of entire file contents, hex encoded
+        perceptual_hash    INTEGER NOT NULL, -- 64-bit difference hash of thumbnail
+
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
+
+CREATE INDEX pictures_hashes_content_hash_idx ON pictures_hashes (content_hash);
@@ -0,0 +1,16 @@
+-- Camera that took a picture, for searching with "camera:".
+ALTER TABLE pictures ADD COLUMN camera_make TEXT; -- EXIF make, such as "Google"
+ALTER TABLE pictures ADD COLUMN camera_model TEXT; -- EXIF model, such as "Pixel 7"
+
+-- Full-text index of visual items.
+-- Paths are stored base64 encoded, so the index is rebuilt by Fotema
+-- rather than by triggers. Rows for deleted items are ignored by joining
+-- against the visual view when searching.
+CREATE VIRTUAL TABLE visual_search USING fts5(
+        visual_id UNINDEXED, -- ID from visual view
+        picture_id UNINDEXED, -- ID of picture, if visual item has a picture
+        file_name, -- file name of picture and video
+        folder_name, -- names of folders between library root and item
+        person_names, -- names of people recognized in picture
+        tokenize = 'unicode61 remove_diacritics 2'
+);
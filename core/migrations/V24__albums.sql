@@ -0,0 +1,28 @@
+-- Albums created and curated by the user.
+CREATE TABLE albums (
+        album_id           INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for album
+        name               TEXT NOT NULL, -- name chosen by user
+        created_ts         DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP, -- when album was created
+        cover_picture_id   INTEGER, -- picture chosen as cover. Null for first item.
+        cover_video_id     INTEGER, -- video chosen as cover. Null for first item.
+        FOREIGN KEY (cover_picture_id) REFERENCES pictures (picture_id) ON DELETE SET NULL,
+        FOREIGN KEY (cover_video_id) REFERENCES videos (video_id) ON DELETE SET NULL
+);
+
+-- Visual items in an album, in the order chosen by the user.
+-- Like ratings, an item with both a picture and a video is stored against the picture.
+CREATE TABLE album_items (
+        album_item_id      INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for album item
+        album_id           INTEGER NOT NULL, -- album containing item
+        picture_id         INTEGER, -- picture in album, if item has a picture
+        video_id           INTEGER, -- video in album, if item only has a video
+        position           INTEGER NOT NULL, -- ordering of item within album
+        CHECK (picture_id IS NOT NULL OR video_id IS NOT NULL),
+        UNIQUE (album_id, picture_id),
+        UNIQUE (album_id, video_id),
+        FOREIGN KEY (album_id) REFERENCES albums (album_id) ON DELETE CASCADE,
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE,
+        FOREIGN KEY (video_id) REFERENCES videos (video_id) ON DELETE CASCADE
+);
+
+CREATE INDEX album_items_album_id_idx ON album_items (album_id, position);
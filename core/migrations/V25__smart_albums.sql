@@ -0,0 +1,3 @@
+-- Smart albums are albums with a search query, such as "person:Alice after:2023-01-01",
+-- and contain every visual item matching the query rather than album_items.
+ALTER TABLE albums ADD COLUMN query TEXT; -- search query of smart album. Null for curated album.
@@ -0,0 +1,16 @@
+-- Non-destructive edits of pictures. The original file is never modified; edits are
+-- applied when a picture is viewed, thumbnailed, or saved as a copy.
+-- Geometry is applied in order: quarter turns, flips, straighten, crop.
+CREATE TABLE pictures_edits (
+        picture_id         INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for picture
+        quarter_turns      INTEGER NOT NULL CHECK (quarter_turns BETWEEN 0 AND 3) DEFAULT 0, -- clockwise rotations by 90 degrees
+        flip_horizontal    BOOLEAN NOT NULL CHECK (flip_horizontal IN (0, 1)) DEFAULT 0, -- mirrored left to right?
+        flip_vertical      BOOLEAN NOT NULL CHECK (flip_vertical IN (0, 1)) DEFAULT 0, -- mirrored top to bottom?
+        straighten_degrees REAL NOT NULL CHECK (straighten_degrees BETWEEN -45 AND 45) DEFAULT 0, -- clockwise rotation to level horizon
+        crop_x             REAL CHECK (crop_x BETWEEN 0 AND 1), -- left edge of crop as fraction of width. Null if not cropped.
+        crop_y             REAL CHECK (crop_y BETWEEN 0 AND 1), -- top edge of crop as fraction of height. Null if not cropped.
+        crop_width         REAL CHECK (crop_width > 0 AND crop_width <= 1), -- crop width as fraction of width. Null if not cropped.
+        crop_height        REAL CHECK (crop_height > 0 AND crop_height <= 1), -- crop height as fraction of height. Null if not cropped.
+        updated_ts         DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP, -- when edit was last saved
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
@@ -0,0 +1,9 @@
+-- Colour and exposure adjustments of pictures. Zero is no adjustment.
+-- Adjustments are applied after the geometry of the edit.
+ALTER TABLE pictures_edits ADD COLUMN exposure REAL NOT NULL CHECK (exposure BETWEEN -2 AND 2) DEFAULT 0; -- brightness in stops
+ALTER TABLE pictures_edits ADD COLUMN contrast REAL NOT NULL CHECK (contrast BETWEEN -1 AND 1) DEFAULT 0;
+ALTER TABLE pictures_edits ADD COLUMN highlights REAL NOT NULL CHECK (highlights BETWEEN -1 AND 1) DEFAULT 0;
+ALTER TABLE pictures_edits ADD COLUMN shadows REAL NOT NULL CHECK (shadows BETWEEN -1 AND 1) DEFAULT 0;
+ALTER TABLE pictures_edits ADD COLUMN saturation REAL NOT NULL CHECK (saturation BETWEEN -1 AND 1) DEFAULT 0; -- -1 is black and white
+ALTER TABLE pictures_edits ADD COLUMN temperature REAL NOT NULL CHECK (temperature BETWEEN -1 AND 1) DEFAULT 0; -- cool to warm
+ALTER TABLE pictures_edits ADD COLUMN tint REAL NOT NULL CHECK (tint BETWEEN -1 AND 1) DEFAULT 0; -- green to magenta
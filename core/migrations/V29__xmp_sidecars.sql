@@ -0,0 +1,20 @@
+-- Keywords read from dc:subject in XMP sidecars. Newline separated.
+ALTER TABLE pictures ADD COLUMN keywords TEXT;
+
+-- Digest of the metadata last written to the XMP sidecar of a picture,
+-- so that sidecars are only rewritten when the metadata has changed.
+ALTER TABLE pictures ADD COLUMN sidecar_digest TEXT;
+
+-- Recreate full-text index with keywords.
+-- The index is rebuilt by Fotema, so no need to copy the existing rows.
+DROP TABLE visual_search;
+
+CREATE VIRTUAL TABLE visual_search USING fts5(
+        visual_id UNINDEXED, -- ID from visual view
+        picture_id UNINDEXED, -- ID of picture, if visual item has a picture
+        file_name, -- file name of picture and video
+        folder_name, -- names of folders between library root and item
+        person_names, -- names of people recognized in picture
+        keywords, -- keywords of picture
+        tokenize = 'unicode61 remove_diacritics 2'
+);
@@ -0,0 +1,18 @@
+-- Descriptions, such as captions from Google Photos Takeout sidecars.
+ALTER TABLE pictures ADD COLUMN description TEXT;
+ALTER TABLE videos ADD COLUMN description TEXT;
+
+-- Recreate full-text index with descriptions.
+-- The index is rebuilt by Fotema, so no need to copy the existing rows.
+DROP TABLE visual_search;
+
+CREATE VIRTUAL TABLE visual_search USING fts5(
+        visual_id UNINDEXED, -- ID from visual view
+        picture_id UNINDEXED, -- ID of picture, if visual item has a picture
+        file_name, -- file name of picture and video
+        folder_name, -- names of folders between library root and item
+        person_names, -- names of people recognized in picture
+        keywords, -- keywords of picture
+        description, -- description of picture or video
+        tokenize = 'unicode61 remove_diacritics 2'
+);
@@ -0,0 +1,9 @@
+-- Rules for which files under a library root are scanned.
+-- Globs are stored one per line and matched against paths relative to the root.
+-- Hidden directories are skipped by default, including for existing roots.
+
+ALTER TABLE library_roots ADD COLUMN include_globs TEXT NOT NULL DEFAULT ''; -- only scan matching files
+ALTER TABLE library_roots ADD COLUMN exclude_globs TEXT NOT NULL DEFAULT ''; -- don't scan matching files or folders
+ALTER TABLE library_roots ADD COLUMN skip_hidden BOOLEAN NOT NULL CHECK (skip_hidden IN (0, 1)) DEFAULT 1; -- skip folders starting with a dot?
+ALTER TABLE library_roots ADD COLUMN min_file_size_bytes INTEGER NOT NULL DEFAULT 0; -- skip smaller files. Zero for no minimum.
+ALTER TABLE library_roots ADD COLUMN min_dimension INTEGER NOT NULL DEFAULT 0; -- skip pictures with a smaller width or height. Zero for no minimum.
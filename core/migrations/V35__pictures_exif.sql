@@ -0,0 +1,161 @@
+-- Camera, lens, and exposure settings from the EXIF metadata of pictures.
+CREATE TABLE pictures_exif (
+        picture_id         INTEGER PRIMARY KEY UNIQUE NOT NULL, -- unique ID for picture
+        camera_make        TEXT, -- EXIF make, such as "Google"
+        camera_model       TEXT, -- EXIF model, such as "Pixel 7"
+        lens_make          TEXT, -- EXIF lens make, such as "Apple"
+        lens_model         TEXT, -- EXIF lens model, such as "iPhone 13 back camera 5.1mm f/1.6"
+        focal_length       REAL, -- focal length in millimetres
+        focal_length_35mm  INTEGER, -- focal length in millimetres of equivalent 35mm film lens
+        f_number           REAL, -- aperture, such as 1.8 for f/1.8
+        exposure_time      REAL, -- exposure time in seconds
+        iso                INTEGER, -- ISO speed
+        is_flash_fired     BOOLEAN CHECK (is_flash_fired IN (0, 1)), -- did the flash fire?
+        width              INTEGER, -- width in pixels recorded by camera
+        height             INTEGER, -- height in pixels recorded by camera
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
+
+-- Keep the camera make and model of pictures that have already been scanned.
+-- The rest of the settings are read when metadata is scanned again.
+INSERT INTO pictures_exif (picture_id, camera_make, camera_model)
+SELECT picture_id, camera_make, camera_model
+FROM pictures
+WHERE camera_make IS NOT NULL OR camera_model IS NOT NULL;
+
+-- Recreate visual view to include the camera and lens.
+DROP VIEW visual;
+
+ALTER TABLE pictures DROP COLUMN camera_make;
+ALTER TABLE pictures DROP COLUMN camera_model;
+
+CREATE VIEW visual AS
+SELECT
+  -- Unique ID
+  COALESCE(pictures.picture_id, 'x') || '_' || COALESCE(videos.video_id, 'x') AS visual_id,
+  COALESCE(pictures.link_path_b64, videos.link_path_b64) AS link_path_b64,
+
+  library_roots.root_id AS library_root_id,
+  library_roots.root_path_b64,
+
+  pictures.picture_id,
+  pictures.picture_path_b64,
+  pictures.picture_path_lossy, -- for debug only. Never read in Fotema.
+  pictures.orientation AS picture_orientation,
+
+-- If the thumbnail path is absent in the database, then compute the path we know it
+-- will have. Eventually the thumbnail generation background process will create the file
+-- and it will show up in the UI without having to refresh the data.
+  CASE pictures.picture_id
+        WHEN NOT NULL THEN pictures.thumbnail_path
+        ELSE 'photo_thumbnails/' || printf('%04d', pictures.picture_id / 1000) || '/' || CAST(pictures.picture_id AS TEXT) || '_200x200.png'
+  END AS picture_thumbnail,
+
+  pictures.is_selfie,
+
+  -- RAW file of a picture developed from it, such as when a camera saves RAW+JPEG.
+  (
+    SELECT raws.picture_path_b64
+    FROM pictures AS raws
+    WHERE raws.library_root_id = pictures.library_root_id
+    AND raws.link_path_b64 = pictures.link_path_b64
+    AND raws.is_raw IS TRUE
+    AND pictures.is_raw IS FALSE
+    ORDER BY raws.picture_id
+    LIMIT 1
+  ) AS raw_picture_path_b64,
+
+  videos.video_id,
+  videos.video_path_b64,
+  videos.video_path_lossy, -- for debug only. Never read in Fotema.
+
+-- If the thumbnail path is absent in the database, then compute the path we know it
+-- will have. Eventually the thumbnail generation background process will create the file
+-- and it will show up in the UI without having to refresh the data.
+  CASE videos.video_id
+        WHEN NOT NULL THEN videos.thumbnail_path
+        ELSE 'video_thumbnails/' || printf('%04d', videos.video_id / 1000) || '/' || CAST(videos.video_id AS TEXT) || '_200x200.png'
+  END AS video_thumbnail,
+
+  COALESCE(videos.video_codec, motion_photos.video_codec) AS video_codec,
+
+  COALESCE(videos.video_codec, motion_photos.video_codec) IN ('hevc') AS is_transcode_required,
+
+  COALESCE(videos.transcoded_path, motion_photos.transcoded_path) AS video_transcoded_path,
+
+  COALESCE(videos.rotation, motion_photos.rotation) AS video_rotation,
+
+  -- An iOS live photo is a photo and a video linked with a content ID.
+  -- However, we only really need the video part, and short (<3 seconds)
+  -- videos are possibly live photos that have a missing or misnamed photo.
+  CASE
+        WHEN videos.content_id IS NOT NULL THEN true
+        WHEN videos.duration_millis <= 3000 THEN true
+        WHEN motion_photos.video_path IS NOT NULL THEN true
+        ELSE false
+  END AS is_live_photo,
+
+  COALESCE(videos.duration_millis, motion_photos.duration_millis) as duration_millis,
+
+  motion_photos.video_path AS motion_photo_video_path,
+
+  -- Number of frames of an animated picture, such as a GIF.
+  pictures.frame_count,
+
+  -- Camera and lens a picture was taken with.
+  pictures_exif.camera_make,
+  pictures_exif.camera_model,
+  pictures_exif.lens_make,
+  pictures_exif.lens_model,
+
+  pictures_geo.longitude AS longitude,
+  pictures_geo.latitude AS latitude,
+
+  -- Favourites and ratings of live photos are stored against the picture.
+  COALESCE(pictures_ratings.is_favourite, videos_ratings.is_favourite, FALSE) AS is_favourite,
+  COALESCE(pictures_ratings.stars, videos_ratings.stars) AS rating,
+
+  -- Timestamp to order visual items by.
+  -- Prefer dates corrected by the user, then embedded metadata over file system metadata.
+  COALESCE(
+    pictures.override_created_ts,
+    videos.override_created_ts,
+    pictures.exif_created_ts,
+    videos.stream_created_ts,
+    pictures.exif_modified_ts,
+    pictures.fs_created_ts,
+    videos.fs_created_ts,
+    pictures.fs_modified_ts,
+    videos.fs_modified_ts,
+    CURRENT_TIMESTAMP
+  ) AS ordering_ts
+FROM
+  pictures
+  FULL OUTER JOIN videos USING (library_root_id, link_path_b64, content_id)
+  FULL OUTER JOIN motion_photos USING (picture_id)
+  FULL OUTER JOIN pictures_geo USING (picture_id)
+  LEFT OUTER JOIN pictures_exif ON pictures_exif.picture_id = pictures.picture_id
+  LEFT OUTER JOIN pictures_ratings ON pictures_ratings.picture_id = pictures.picture_id
+  LEFT OUTER JOIN videos_ratings ON videos_ratings.video_id = videos.video_id
+  INNER JOIN library_roots ON library_roots.root_id = COALESCE(pictures.library_root_id, videos.library_root_id)
+WHERE COALESCE(pictures.is_broken, FALSE) IS FALSE
+AND COALESCE(videos.is_broken, FALSE) IS FALSE
+AND library_roots.is_enabled IS TRUE
+-- A RAW file with a developed sibling, such as a JPEG, is shown as part of the sibling.
+AND NOT (
+  COALESCE(pictures.is_raw, FALSE) IS TRUE
+  AND EXISTS (
+    SELECT 1 FROM pictures AS developed
+    WHERE developed.library_root_id = pictures.library_root_id
+    AND developed.link_path_b64 = pictures.link_path_b64
+    AND developed.is_raw IS FALSE
+  )
+)
+-- Items in the trash are hidden until restored.
+AND NOT EXISTS (
+  SELECT 1 FROM trash
+  WHERE trash.picture_id = pictures.picture_id
+  OR trash.video_id = videos.video_id
+)
+ORDER BY
+  ordering_ts ASC;
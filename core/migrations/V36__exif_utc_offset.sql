@@ -0,0 +1,15 @@
+-- Offset from UTC in seconds where a picture was taken, either from the EXIF data or
+-- inferred from the GPS coordinates. Null if unknown.
+ALTER TABLE pictures ADD COLUMN exif_utc_offset INTEGER;
+
+-- EXIF timestamps are the local time where a picture was taken saved as if it were UTC,
+-- but some were saved with the offset as a suffix, which breaks ordering of the text values.
+-- Normalise them to the same format as other timestamps.
+-- The offsets will be restored when metadata is next scanned.
+UPDATE pictures
+SET exif_created_ts = strftime('%Y-%m-%d %H:%M:%S+00:00', exif_created_ts)
+WHERE exif_created_ts IS NOT NULL;
+
+UPDATE pictures
+SET exif_modified_ts = strftime('%Y-%m-%d %H:%M:%S+00:00', exif_modified_ts)
+WHERE exif_modified_ts IS NOT NULL;
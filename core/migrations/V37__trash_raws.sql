@@ -0,0 +1,20 @@
+-- RAW pictures moved to the trash along with the picture developed from them,
+-- such as when a camera saves RAW+JPEG. Recorded when the developed picture is trashed,
+-- so the pair stays hidden, and is restored and deleted, together.
+CREATE TABLE trash_raws (
+        trash_id           INTEGER NOT NULL, -- trashed item the RAW picture belongs to
+        picture_id         INTEGER UNIQUE NOT NULL, -- RAW picture moved to trash
+        FOREIGN KEY (trash_id) REFERENCES trash (trash_id) ON DELETE CASCADE,
+        FOREIGN KEY (picture_id) REFERENCES pictures (picture_id) ON DELETE CASCADE
+);
+
+-- Record RAW pictures of developed pictures that are already in the trash.
+INSERT OR IGNORE INTO trash_raws (trash_id, picture_id)
+SELECT trash.trash_id, raws.picture_id
+FROM trash
+INNER JOIN pictures AS developed ON developed.picture_id = trash.picture_id
+INNER JOIN pictures AS raws
+    ON raws.library_root_id = developed.library_root_id
+    AND raws.link_path_b64 = developed.link_path_b64
+WHERE developed.is_raw IS FALSE
+AND raws.is_raw IS TRUE;
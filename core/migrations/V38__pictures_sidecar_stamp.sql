@@ -0,0 +1,5 @@
+-- File system modification time and size of the XMP sidecar of a picture when it was last scanned,
+-- so that changes made to the sidecar by other applications cause the metadata to be read again.
+-- Null if the picture had no sidecar.
+ALTER TABLE pictures ADD COLUMN sidecar_fs_modified_ts DATETIME;
+ALTER TABLE pictures ADD COLUMN sidecar_fs_file_size_bytes INTEGER;
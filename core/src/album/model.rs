@@ -0,0 +1,52 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::visual::VisualId;
+use std::fmt::Display;
+
+/// Database ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AlbumId(i64);
+
+impl AlbumId {
+    pub fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    /// FIXME replace this with a To/From SQL implementation.
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+}
+
+impl Display for AlbumId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// An album created by the user.
+#[derive(Debug, Clone)]
+pub struct Album {
+    pub album_id: AlbumId,
+
+    pub name: String,
+
+    /// Number of visual items in album.
+    pub item_count: usize,
+
+    /// Item chosen as album cover, or the first item if no cover has been chosen.
+    /// None if album is empty.
+    pub cover: Option<VisualId>,
+
+    /// Search query for a smart album, which contains every item matching the query.
+    /// None for an album curated by the user.
+    pub query: Option<String>,
+}
+
+impl Album {
+    pub fn is_smart(&self) -> bool {
+        self.query.is_some()
+    }
+}
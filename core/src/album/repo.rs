@@ -0,0 +1,343 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{Album, AlbumId};
+use crate::search;
+use crate::visual::VisualId;
+
+use anyhow::*;
+use rusqlite;
+use rusqlite::params;
+use rusqlite::Row;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Condition for joining album items to the visual view.
+/// A video-only item matches a visual item if the video has since been paired with a picture.
+const ITEM_JOIN: &str = "(album_items.picture_id = visual.picture_id
+    OR (album_items.picture_id IS NULL AND album_items.video_id = visual.video_id))";
+
+/// Repository of albums created by the user.
+/// Repository is backed by a Sqlite database.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        let repo = Repository { con };
+        Ok(repo)
+    }
+
+    /// All albums, ordered by name.
+    pub fn all(&self) -> Result<Vec<Album>> {
+        let mut albums = self.all_without_smart_items()?;
+
+        // Smart album contents can't be counted in SQL because the query must be parsed first.
+        for album in albums.iter_mut().filter(|x| x.is_smart()) {
+            let visual_ids = self.visual_ids(album.album_id)?;
+            album.item_count = visual_ids.len();
+            album.cover = album.cover.take().or_else(|| visual_ids.into_iter().next());
+        }
+
+        Ok(albums)
+    }
+
+    fn all_without_smart_items(&self) -> Result<Vec<Album>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(&format!(
+            "SELECT
+                albums.album_id,
+                albums.name,
+                albums.query,
+                (
+                    SELECT COUNT(*)
+                    FROM album_items
+                    INNER JOIN visual ON {0}
+                    WHERE album_items.album_id = albums.album_id
+                ) AS item_count,
+                COALESCE(
+                    (
+                        SELECT visual.visual_id
+                        FROM visual
+                        WHERE visual.picture_id = albums.cover_picture_id
+                        OR (albums.cover_picture_id IS NULL AND visual.video_id = albums.cover_video_id)
+                        LIMIT 1
+                    ),
+                    (
+                        SELECT visual.visual_id
+                        FROM album_items
+                        INNER JOIN visual ON {0}
+                        WHERE album_items.album_id = albums.album_id
+                        ORDER BY album_items.position ASC
+                        LIMIT 1
+                    )
+                ) AS cover_visual_id
+            FROM albums
+            ORDER BY albums.name COLLATE NOCASE ASC",
+            ITEM_JOIN
+        ))?;
+
+        let result = stmt.query_map([], Self::to_album)?.flatten().collect();
+
+        Ok(result)
+    }
+
+    pub fn create(&mut self, name: &str) -> Result<AlbumId> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached("INSERT INTO albums (name) VALUES (?1)")?;
+        stmt.execute(params![name])?;
+        Ok(AlbumId::new(con.last_insert_rowid()))
+    }
+
+    /// Creates a smart album containing every visual item matching a query.
+    pub fn create_smart(&mut self, name: &str, query: &search::Query) -> Result<AlbumId> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached("INSERT INTO albums (name, query) VALUES (?1, ?2)")?;
+        stmt.execute(params![name, query.to_string()])?;
+        Ok(AlbumId::new(con.last_insert_rowid()))
+    }
+
+    /// Changes the query of a smart album.
+    pub fn set_query(&mut self, album_id: AlbumId, query: &search::Query) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "UPDATE albums SET query = ?2 WHERE album_id = ?1 AND query IS NOT NULL",
+        )?;
+        stmt.execute(params![album_id.id(), query.to_string()])?;
+        Ok(())
+    }
+
+    pub fn rename(&mut self, album_id: AlbumId, name: &str) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached("UPDATE albums SET name = ?2 WHERE album_id = ?1")?;
+        stmt.execute(params![album_id.id(), name])?;
+        Ok(())
+    }
+
+    /// Deletes an album. The items in the album are not deleted from the library.
+    pub fn delete(&mut self, album_id: AlbumId) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached("DELETE FROM albums WHERE album_id = ?1")?;
+        stmt.execute(params![album_id.id()])?;
+        Ok(())
+    }
+
+    /// IDs of the visual items in an album, in album order.
+    /// Items in a smart album are in ascending timestamp order.
+    pub fn visual_ids(&self, album_id: AlbumId) -> Result<Vec<VisualId>> {
+        let query: Option<String> = {
+            let con = self.con.lock().unwrap();
+            let mut stmt = con.prepare_cached("SELECT query FROM albums WHERE album_id = ?1")?;
+            stmt.query_row(params![album_id.id()], |row| row.get(0))?
+        };
+
+        if let Some(query) = query {
+            let query = search::Query::parse(&query)?;
+            return search::Repository::open(self.con.clone())?.search(&query);
+        }
+
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(&format!(
+            "SELECT visual.visual_id
+            FROM album_items
+            INNER JOIN visual ON {}
+            WHERE album_items.album_id = ?1
+            ORDER BY album_items.position ASC",
+            ITEM_JOIN
+        ))?;
+
+        let result = stmt
+            .query_map(params![album_id.id()], |row| {
+                row.get("visual_id").map(VisualId::new)
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Adds visual items to the end of an album. Items already in the album are not moved.
+    pub fn add(&mut self, album_id: AlbumId, visual_ids: &[VisualId]) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let is_smart: bool = tx.query_row(
+                "SELECT query IS NOT NULL FROM albums WHERE album_id = ?1",
+                params![album_id.id()],
+                |row| row.get(0),
+            )?;
+
+            if is_smart {
+                bail!("Cannot add items to smart album {}", album_id);
+            }
+
+            let mut position: i64 = tx.query_row(
+                "SELECT COALESCE(MAX(position) + 1, 0) FROM album_items WHERE album_id = ?1",
+                params![album_id.id()],
+                |row| row.get(0),
+            )?;
+
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO album_items (album_id, picture_id, video_id, position)
+                SELECT ?1, visual.picture_id, IIF(visual.picture_id IS NULL, visual.video_id, NULL), ?3
+                FROM visual
+                WHERE visual.visual_id = ?2
+                ON CONFLICT DO NOTHING",
+            )?;
+
+            for visual_id in visual_ids {
+                if stmt.execute(params![album_id.id(), visual_id.id(), position])? > 0 {
+                    position += 1;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Removes visual items from an album. The items are not deleted from the library.
+    pub fn remove(&mut self, album_id: AlbumId, visual_ids: &[VisualId]) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(&format!(
+                "DELETE FROM album_items
+                WHERE album_item_id IN (
+                    SELECT album_items.album_item_id
+                    FROM album_items
+                    INNER JOIN visual ON {}
+                    WHERE album_items.album_id = ?1
+                    AND visual.visual_id = ?2
+                )",
+                ITEM_JOIN
+            ))?;
+
+            for visual_id in visual_ids {
+                stmt.execute(params![album_id.id(), visual_id.id()])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Moves an item in an album to the position of another item, such as when
+    /// the user drags one item onto another.
+    pub fn move_item(&mut self, album_id: AlbumId, item: &VisualId, target: &VisualId) -> Result<()> {
+        let mut visual_ids = self.visual_ids(album_id)?;
+        if move_item(&mut visual_ids, item, target) {
+            self.reorder(album_id, &visual_ids)?;
+        }
+        Ok(())
+    }
+
+    /// Sets the order of items in an album.
+    pub fn reorder(&mut self, album_id: AlbumId, visual_ids: &[VisualId]) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(&format!(
+                "UPDATE album_items
+                SET position = ?3
+                WHERE album_item_id IN (
+                    SELECT album_items.album_item_id
+                    FROM album_items
+                    INNER JOIN visual ON {}
+                    WHERE album_items.album_id = ?1
+                    AND visual.visual_id = ?2
+                )",
+                ITEM_JOIN
+            ))?;
+
+            for (position, visual_id) in visual_ids.iter().enumerate() {
+                stmt.execute(params![album_id.id(), visual_id.id(), position as i64])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Sets the album cover to a visual item.
+    pub fn set_cover(&mut self, album_id: AlbumId, visual_id: &VisualId) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "UPDATE albums
+            SET
+                cover_picture_id = visual.picture_id,
+                cover_video_id = IIF(visual.picture_id IS NULL, visual.video_id, NULL)
+            FROM visual
+            WHERE albums.album_id = ?1
+            AND visual.visual_id = ?2",
+        )?;
+        stmt.execute(params![album_id.id(), visual_id.id()])?;
+        Ok(())
+    }
+
+    fn to_album(row: &Row<'_>) -> rusqlite::Result<Album> {
+        let album_id = row.get("album_id").map(AlbumId::new)?;
+        let name = row.get("name")?;
+        let item_count: i64 = row.get("item_count")?;
+        let cover: Option<String> = row.get("cover_visual_id")?;
+        let query = row.get("query")?;
+
+        Ok(Album {
+            album_id,
+            name,
+            item_count: item_count as usize,
+            cover: cover.map(VisualId::new),
+            query,
+        })
+    }
+}
+
+/// Moves an item to the index of a target item, shifting the items between them.
+/// Returns false if either item is missing.
+fn move_item(visual_ids: &mut Vec<VisualId>, item: &VisualId, target: &VisualId) -> bool {
+    let Some(from) = visual_ids.iter().position(|x| x == item) else {
+        return false;
+    };
+
+    let Some(to) = visual_ids.iter().position(|x| x == target) else {
+        return false;
+    };
+
+    let item = visual_ids.remove(from);
+    visual_ids.insert(to, item);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<VisualId> {
+        ids.iter().map(|x| VisualId::new(x.to_string())).collect()
+    }
+
+    #[test]
+    fn move_item_forwards_and_backwards() {
+        let mut visual_ids = ids(&["a", "b", "c", "d"]);
+
+        assert!(move_item(&mut visual_ids, &VisualId::new("a".into()), &VisualId::new("c".into())));
+        assert_eq!(visual_ids, ids(&["b", "c", "a", "d"]));
+
+        assert!(move_item(&mut visual_ids, &VisualId::new("d".into()), &VisualId::new("b".into())));
+        assert_eq!(visual_ids, ids(&["d", "b", "c", "a"]));
+    }
+
+    #[test]
+    fn move_item_missing() {
+        let mut visual_ids = ids(&["a", "b"]);
+        assert!(!move_item(&mut visual_ids, &VisualId::new("x".into()), &VisualId::new("a".into())));
+        assert_eq!(visual_ids, ids(&["a", "b"]));
+    }
+}
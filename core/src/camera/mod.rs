@@ -0,0 +1,9 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod model;
+pub mod repo;
+
+pub use model::CameraSettings;
+pub use repo::Repository;
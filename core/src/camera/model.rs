@@ -0,0 +1,163 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+/// Camera, lens, and exposure settings of a picture, from its EXIF metadata.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CameraSettings {
+    /// Manufacturer of camera. Such as "Google".
+    pub camera_make: Option<String>,
+
+    /// Model of camera. Such as "Pixel 7".
+    pub camera_model: Option<String>,
+
+    /// Manufacturer of lens. Such as "Apple".
+    pub lens_make: Option<String>,
+
+    /// On iPhone the lens model tells you if it was the front or back camera.
+    pub lens_model: Option<String>,
+
+    /// Focal length in millimetres.
+    pub focal_length: Option<f64>,
+
+    /// Focal length in millimetres of the equivalent lens on a 35mm film camera.
+    pub focal_length_35mm: Option<u32>,
+
+    /// Aperture as an f-number. Such as 1.8 for ƒ/1.8.
+    pub f_number: Option<f64>,
+
+    /// Exposure time in seconds.
+    pub exposure_time: Option<f64>,
+
+    /// ISO speed.
+    pub iso: Option<u32>,
+
+    /// Did the flash fire?
+    pub is_flash_fired: Option<bool>,
+
+    /// Width in pixels, as recorded by the camera.
+    pub width: Option<u32>,
+
+    /// Height in pixels, as recorded by the camera.
+    pub height: Option<u32>,
+}
+
+impl CameraSettings {
+    /// Name of camera. Such as "Google Pixel 7".
+    pub fn camera_name(&self) -> Option<String> {
+        camera_name(self.camera_make.as_deref(), self.camera_model.as_deref())
+    }
+
+    /// Name of lens, prefixed with the lens make if the lens model doesn't include it.
+    pub fn lens_name(&self) -> Option<String> {
+        camera_name(self.lens_make.as_deref(), self.lens_model.as_deref())
+    }
+
+    /// Focal length, with the 35mm equivalent if it differs. Such as "6.9 mm (26 mm)".
+    pub fn focal_length_text(&self) -> Option<String> {
+        let focal_length = self.focal_length.map(|x| format!("{} mm", round(x)));
+        let focal_length_35mm = self.focal_length_35mm.map(|x| format!("{} mm", x));
+
+        match (focal_length, focal_length_35mm) {
+            (Some(x), Some(y)) if x != y => Some(format!("{} ({})", x, y)),
+            (x, y) => x.or(y),
+        }
+    }
+
+    /// Aperture. Such as "ƒ/1.8".
+    pub fn aperture_text(&self) -> Option<String> {
+        self.f_number.map(|x| format!("ƒ/{}", round(x)))
+    }
+
+    /// Exposure time, as a fraction of a second if it is shorter than a second.
+    /// Such as "1/250 s" or "2.5 s".
+    pub fn exposure_time_text(&self) -> Option<String> {
+        let seconds = self.exposure_time?;
+        if seconds < 1.0 {
+            Some(format!("1/{} s", (1.0 / seconds).round()))
+        } else {
+            Some(format!("{} s", round(seconds)))
+        }
+    }
+
+    /// ISO speed. Such as "ISO 100".
+    pub fn iso_text(&self) -> Option<String> {
+        self.iso.map(|x| format!("ISO {}", x))
+    }
+
+    /// Width and height. Such as "4032 ⨉ 3024".
+    pub fn dimensions_text(&self) -> Option<String> {
+        match (self.width, self.height) {
+            (Some(width), Some(height)) => Some(format!("{} ⨉ {}", width, height)),
+            _ => None,
+        }
+    }
+}
+
+/// Name of a camera or lens from its make and model.
+/// Many cameras repeat the make in the model, such as "Canon" and "Canon EOS R5",
+/// so the make is only prefixed if the model doesn't already start with it.
+pub fn camera_name(make: Option<&str>, model: Option<&str>) -> Option<String> {
+    match (make, model) {
+        (Some(make), Some(model)) if model.to_lowercase().starts_with(&make.to_lowercase()) => {
+            Some(model.into())
+        }
+        (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+        (make, model) => make.or(model).map(String::from),
+    }
+}
+
+/// Rounds to one decimal place, so 1.79 is shown as 1.8 and 2.0 as 2.
+fn round(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camera_name() {
+        assert_eq!(
+            camera_name(Some("Google"), Some("Pixel 7")),
+            Some("Google Pixel 7".into())
+        );
+        assert_eq!(
+            camera_name(Some("Canon"), Some("Canon EOS R5")),
+            Some("Canon EOS R5".into())
+        );
+        assert_eq!(camera_name(None, Some("Pixel 7")), Some("Pixel 7".into()));
+        assert_eq!(camera_name(Some("Google"), None), Some("Google".into()));
+        assert_eq!(camera_name(None, None), None);
+    }
+
+    #[test]
+    fn test_exposure_text() {
+        let settings = CameraSettings {
+            focal_length: Some(6.86),
+            focal_length_35mm: Some(26),
+            f_number: Some(1.78),
+            exposure_time: Some(0.004),
+            iso: Some(100),
+            ..Default::default()
+        };
+
+        assert_eq!(settings.focal_length_text(), Some("6.9 mm (26 mm)".into()));
+        assert_eq!(settings.aperture_text(), Some("ƒ/1.8".into()));
+        assert_eq!(settings.exposure_time_text(), Some("1/250 s".into()));
+        assert_eq!(settings.iso_text(), Some("ISO 100".into()));
+
+        let settings = CameraSettings {
+            focal_length: Some(50.0),
+            focal_length_35mm: Some(50),
+            f_number: Some(2.0),
+            exposure_time: Some(2.5),
+            ..Default::default()
+        };
+
+        assert_eq!(settings.focal_length_text(), Some("50 mm".into()));
+        assert_eq!(settings.aperture_text(), Some("ƒ/2".into()));
+        assert_eq!(settings.exposure_time_text(), Some("2.5 s".into()));
+        assert_eq!(settings.iso_text(), None);
+    }
+}
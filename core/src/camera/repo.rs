@@ -0,0 +1,73 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::CameraSettings;
+use crate::photo::PictureId;
+
+use anyhow::*;
+use rusqlite;
+use rusqlite::OptionalExtension;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of camera, lens, and exposure settings read from picture EXIF metadata.
+/// Settings are written when picture metadata is scanned, so this repository only reads them.
+/// Repository is backed by a Sqlite database.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        let repo = Repository { con };
+        Ok(repo)
+    }
+
+    /// Gets the camera settings of a picture, which are empty if the picture has no
+    /// EXIF metadata or hasn't been scanned yet.
+    pub fn get(&self, picture_id: PictureId) -> Result<CameraSettings> {
+        let con = self.con.lock().unwrap();
+
+        let mut stmt = con.prepare_cached(
+            "SELECT
+                camera_make,
+                camera_model,
+                lens_make,
+                lens_model,
+                focal_length,
+                focal_length_35mm,
+                f_number,
+                exposure_time,
+                iso,
+                is_flash_fired,
+                width,
+                height
+            FROM pictures_exif
+            WHERE picture_id = ?1",
+        )?;
+
+        let settings = stmt
+            .query_row([picture_id.id()], |row| {
+                Ok(CameraSettings {
+                    camera_make: row.get("camera_make")?,
+                    camera_model: row.get("camera_model")?,
+                    lens_make: row.get("lens_make")?,
+                    lens_model: row.get("lens_model")?,
+                    focal_length: row.get("focal_length")?,
+                    focal_length_35mm: row.get("focal_length_35mm")?,
+                    f_number: row.get("f_number")?,
+                    exposure_time: row.get("exposure_time")?,
+                    iso: row.get("iso")?,
+                    is_flash_fired: row.get("is_flash_fired")?,
+                    width: row.get("width")?,
+                    height: row.get("height")?,
+                })
+            })
+            .optional()?;
+
+        Ok(settings.unwrap_or_default())
+    }
+}
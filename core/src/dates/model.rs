@@ -0,0 +1,122 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use chrono::*;
+
+/// Date and time of an item as corrected by the user.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DateOverride {
+    /// When the item was taken. Like the EXIF timestamps, this is the local time
+    /// where the item was taken saved as if it were UTC.
+    pub created_at: Option<DateTime<Utc>>,
+
+    /// Offset from UTC where the item was taken.
+    pub utc_offset: Option<FixedOffset>,
+}
+
+impl DateOverride {
+    /// True if nothing has been corrected.
+    pub fn is_empty(&self) -> bool {
+        self.created_at.is_none() && self.utc_offset.is_none()
+    }
+}
+
+/// A correction to the date and time of items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateChange {
+    /// Set the local date and time, and optionally the offset from UTC.
+    Set(NaiveDateTime, Option<FixedOffset>),
+
+    /// Shift the date and time, such as for a camera with a wrong clock.
+    Shift(TimeDelta),
+
+    /// Set the offset from UTC where items were taken, keeping their local times.
+    TimeZone(FixedOffset),
+
+    /// Remove corrections, going back to the dates from the file metadata.
+    Revert,
+}
+
+impl DateChange {
+    /// Applies the change to the current correction of an item.
+    /// `ordering_ts` is the date and time the item is currently shown with.
+    pub fn apply(&self, ordering_ts: DateTime<Utc>, current: DateOverride) -> DateOverride {
+        match *self {
+            DateChange::Set(local, utc_offset) => DateOverride {
+                created_at: Some(local.and_utc()),
+                utc_offset: utc_offset.or(current.utc_offset),
+            },
+            DateChange::Shift(delta) => DateOverride {
+                created_at: Some(ordering_ts + delta),
+                ..current
+            },
+            DateChange::TimeZone(utc_offset) => DateOverride {
+                utc_offset: Some(utc_offset),
+                ..current
+            },
+            DateChange::Revert => DateOverride::default(),
+        }
+    }
+}
+
+/// Parses a local date and time entered by the user, such as "2024-06-30 14:05".
+/// Seconds and time are optional. A date without a time is taken to be midday,
+/// so it stays on the same day in any time zone.
+pub fn parse_local(text: &str) -> Option<NaiveDateTime> {
+    let text = text.trim();
+
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(12, 0, 0))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        parse_local(text).unwrap().and_utc()
+    }
+
+    #[test]
+    fn test_parse_local() {
+        assert_eq!(
+            parse_local(" 2024-06-30 14:05 "),
+            NaiveDate::from_ymd_opt(2024, 6, 30).and_then(|d| d.and_hms_opt(14, 5, 0))
+        );
+        assert_eq!(
+            parse_local("2024-06-30"),
+            NaiveDate::from_ymd_opt(2024, 6, 30).and_then(|d| d.and_hms_opt(12, 0, 0))
+        );
+        assert_eq!(parse_local("30/06/2024"), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let ordering_ts = utc("2024-01-01 10:00:00");
+        let plus_two = FixedOffset::east_opt(2 * 3600);
+
+        let shifted =
+            DateChange::Shift(TimeDelta::hours(-3)).apply(ordering_ts, DateOverride::default());
+        assert_eq!(shifted.created_at, Some(utc("2024-01-01 07:00:00")));
+        assert_eq!(shifted.utc_offset, None);
+
+        let zoned = DateChange::TimeZone(plus_two.unwrap()).apply(ordering_ts, shifted);
+        assert_eq!(zoned.created_at, shifted.created_at);
+        assert_eq!(zoned.utc_offset, plus_two);
+
+        // Setting the date keeps the time zone unless one is given.
+        let set =
+            DateChange::Set(parse_local("2023-05-06").unwrap(), None).apply(ordering_ts, zoned);
+        assert_eq!(set.created_at, Some(utc("2023-05-06 12:00:00")));
+        assert_eq!(set.utc_offset, plus_two);
+
+        assert!(DateChange::Revert.apply(ordering_ts, set).is_empty());
+    }
+}
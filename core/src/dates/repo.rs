@@ -0,0 +1,97 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{DateChange, DateOverride};
+use crate::visual::Visual;
+
+use anyhow::*;
+use chrono::*;
+use rusqlite;
+use rusqlite::params;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of dates and times corrected by the user.
+/// Repository is backed by a Sqlite database.
+///
+/// Corrections of a visual item with both a picture and a video,
+/// such as a live photo, are stored against the picture.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        let repo = Repository { con };
+        Ok(repo)
+    }
+
+    /// Gets the correction of an item, which is empty if the item hasn't been corrected.
+    pub fn get(&self, visual: &Visual) -> Result<DateOverride> {
+        let con = self.con.lock().unwrap();
+
+        let (sql, id) = if let Some(picture_id) = visual.picture_id {
+            (
+                "SELECT override_created_ts, override_utc_offset FROM pictures WHERE picture_id = ?1",
+                picture_id.id(),
+            )
+        } else if let Some(video_id) = visual.video_id {
+            (
+                "SELECT override_created_ts, override_utc_offset FROM videos WHERE video_id = ?1",
+                video_id.id(),
+            )
+        } else {
+            return Ok(DateOverride::default());
+        };
+
+        let mut stmt = con.prepare_cached(sql)?;
+        let value = stmt.query_row([id], |row| {
+            let created_at: Option<DateTime<Utc>> = row.get("override_created_ts")?;
+            let utc_offset: Option<i32> = row.get("override_utc_offset")?;
+            Ok(DateOverride {
+                created_at,
+                utc_offset: utc_offset.and_then(FixedOffset::east_opt),
+            })
+        })?;
+
+        Ok(value)
+    }
+
+    /// Sets the correction of an item. An empty correction reverts to the date from
+    /// the file metadata.
+    pub fn set(&mut self, visual: &Visual, value: &DateOverride) -> Result<()> {
+        let con = self.con.lock().unwrap();
+
+        let utc_offset = value.utc_offset.map(|x| x.local_minus_utc());
+
+        if let Some(picture_id) = visual.picture_id {
+            let mut stmt = con.prepare_cached(
+                "UPDATE pictures
+                SET override_created_ts = ?2, override_utc_offset = ?3
+                WHERE picture_id = ?1",
+            )?;
+            stmt.execute(params![picture_id.id(), value.created_at, utc_offset])?;
+        } else if let Some(video_id) = visual.video_id {
+            let mut stmt = con.prepare_cached(
+                "UPDATE videos
+                SET override_created_ts = ?2, override_utc_offset = ?3
+                WHERE video_id = ?1",
+            )?;
+            stmt.execute(params![video_id.id(), value.created_at, utc_offset])?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies a change to the date of an item, returning the previous correction
+    /// so that the change can be undone.
+    pub fn apply(&mut self, visual: &Visual, change: DateChange) -> Result<DateOverride> {
+        let previous = self.get(visual)?;
+        let value = change.apply(visual.ordering_ts, previous);
+        self.set(visual, &value)?;
+        Ok(previous)
+    }
+}
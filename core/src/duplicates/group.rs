@@ -0,0 +1,277 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::hash::hamming_distance;
+use super::model::{DuplicateGroup, HashedPicture};
+use std::collections::HashMap;
+
+/// Default maximum number of differing perceptual hash bits for pictures to be near-duplicates.
+pub const DEFAULT_THRESHOLD: u32 = 6;
+
+/// Groups pictures that are exact copies, or whose perceptual hashes differ by no more
+/// than `threshold` bits. Pictures without copies are not included in any group.
+pub fn group(pictures: Vec<HashedPicture>, threshold: u32) -> Vec<DuplicateGroup> {
+    let mut sets = DisjointSets::new(pictures.len());
+
+    // Exact copies
+    let mut by_content: HashMap<&str, usize> = HashMap::new();
+    for (index, pic) in pictures.iter().enumerate() {
+        if let Some(first) = by_content.get(pic.content_hash.as_str()) {
+            sets.union(*first, index);
+        } else {
+            by_content.insert(&pic.content_hash, index);
+        }
+    }
+
+    // Near-duplicates.
+    // Each picture is compared only with the earlier pictures the BK-tree can't rule out.
+    let mut tree = BkTree::default();
+    for (index, pic) in pictures.iter().enumerate() {
+        for near in tree.find(pic.perceptual_hash, threshold) {
+            sets.union(near, index);
+        }
+        tree.insert(pic.perceptual_hash, index);
+    }
+
+    let mut groups: HashMap<usize, Vec<HashedPicture>> = HashMap::new();
+    for (index, pic) in pictures.into_iter().enumerate() {
+        groups.entry(sets.find(index)).or_default().push(pic);
+    }
+
+    let mut groups: Vec<DuplicateGroup> = groups
+        .into_values()
+        .filter(|pics| pics.len() > 1)
+        .map(|mut pics| {
+            pics.sort_by(|a, b| {
+                b.fs_file_size_bytes
+                    .cmp(&a.fs_file_size_bytes)
+                    .then_with(|| a.path.cmp(&b.path))
+            });
+            let is_exact = pics.iter().all(|x| x.content_hash == pics[0].content_hash);
+            DuplicateGroup {
+                is_exact,
+                pictures: pics,
+            }
+        })
+        .collect();
+
+    // Stable ordering for display
+    groups.sort_by(|a, b| a.pictures[0].path.cmp(&b.pictures[0].path));
+
+    groups
+}
+
+/// BK-tree of perceptual hashes, for finding hashes within a Hamming distance
+/// without comparing every pair.
+#[derive(Default)]
+struct BkTree {
+    nodes: Vec<BkNode>,
+}
+
+struct BkNode {
+    hash: u64,
+    index: usize,
+
+    /// Child nodes keyed by their distance from this node.
+    children: Vec<(u32, usize)>,
+}
+
+impl BkTree {
+    fn insert(&mut self, hash: u64, index: usize) {
+        let new_node = self.nodes.len();
+        self.nodes.push(BkNode {
+            hash,
+            index,
+            children: Vec::new(),
+        });
+
+        if new_node == 0 {
+            return;
+        }
+
+        let mut node = 0;
+        loop {
+            let distance = hamming_distance(self.nodes[node].hash, hash);
+            let child = self.nodes[node]
+                .children
+                .iter()
+                .find(|(d, _)| *d == distance)
+                .map(|(_, child)| *child);
+
+            if let Some(child) = child {
+                node = child;
+            } else {
+                self.nodes[node].children.push((distance, new_node));
+                return;
+            }
+        }
+    }
+
+    /// Indices of hashes no more than `threshold` bits from `hash`.
+    fn find(&self, hash: u64, threshold: u32) -> Vec<usize> {
+        let mut found = Vec::new();
+        if self.nodes.is_empty() {
+            return found;
+        }
+
+        let mut pending = vec![0];
+        while let Some(node) = pending.pop() {
+            let node = &self.nodes[node];
+            let distance = hamming_distance(node.hash, hash);
+            if distance <= threshold {
+                found.push(node.index);
+            }
+
+            // By the triangle inequality, only children this close can be within the threshold.
+            let min = distance.saturating_sub(threshold);
+            let max = distance + threshold;
+            pending.extend(
+                node.children
+                    .iter()
+                    .filter(|(d, _)| min <= *d && *d <= max)
+                    .map(|(_, child)| *child),
+            );
+        }
+
+        found
+    }
+}
+
+/// Union-find over indices.
+struct DisjointSets {
+    parents: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Compress path so later finds are quicker
+        let mut index = index;
+        while self.parents[index] != root {
+            let next = self.parents[index];
+            self.parents[index] = root;
+            index = next;
+        }
+
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a != b {
+            self.parents[b] = a;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::photo::PictureId;
+    use std::path::PathBuf;
+
+    fn pic(id: i64, content_hash: &str, perceptual_hash: u64, size: u64) -> HashedPicture {
+        HashedPicture {
+            picture_id: PictureId::new(id),
+            path: PathBuf::from(format!("{}.jpg", id)),
+            thumbnail_path: None,
+            fs_file_size_bytes: Some(size),
+            content_hash: content_hash.into(),
+            perceptual_hash,
+        }
+    }
+
+    #[test]
+    fn test_exact_copies() {
+        let groups = group(
+            vec![
+                pic(1, "a", 0, 10),
+                pic(2, "a", 0xffff_0000, 10),
+                pic(3, "b", u64::MAX, 10),
+            ],
+            DEFAULT_THRESHOLD,
+        );
+        assert_eq!(1, groups.len());
+        assert!(groups[0].is_exact);
+        assert_eq!(2, groups[0].pictures.len());
+    }
+
+    #[test]
+    fn test_near_duplicates() {
+        let groups = group(
+            vec![
+                pic(1, "a", 0b0000, 10),
+                pic(2, "b", 0b0011, 20),
+                pic(3, "c", u64::MAX, 10),
+            ],
+            DEFAULT_THRESHOLD,
+        );
+        assert_eq!(1, groups.len());
+        assert!(!groups[0].is_exact);
+
+        // Largest first
+        assert_eq!(PictureId::new(2), groups[0].pictures[0].picture_id);
+    }
+
+    #[test]
+    fn test_near_duplicates_chained() {
+        // 1 and 3 are too far apart, but both are near 2.
+        let groups = group(
+            vec![
+                pic(1, "a", 0b0000_0000, 10),
+                pic(2, "b", 0b0000_1111, 10),
+                pic(3, "c", 0b1111_1111, 10),
+                pic(4, "d", u64::MAX, 10),
+            ],
+            4,
+        );
+        assert_eq!(1, groups.len());
+        assert_eq!(3, groups[0].pictures.len());
+    }
+
+    #[test]
+    fn test_bk_tree_matches_pairwise() {
+        // Hashes spread over a range of distances from each other.
+        let hashes: Vec<u64> = (0..200u64)
+            .map(|x| x.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> (x % 60))
+            .collect();
+
+        let mut tree = BkTree::default();
+        for (index, hash) in hashes.iter().enumerate() {
+            tree.insert(*hash, index);
+        }
+
+        for threshold in [0, 3, 6, 20] {
+            for hash in &hashes {
+                let mut found = tree.find(*hash, threshold);
+                found.sort();
+
+                let expected: Vec<usize> = (0..hashes.len())
+                    .filter(|i| hamming_distance(hashes[*i], *hash) <= threshold)
+                    .collect();
+                assert_eq!(expected, found);
+            }
+        }
+    }
+
+    #[test]
+    fn test_no_duplicates() {
+        let groups = group(
+            vec![pic(1, "a", 0, 10), pic(2, "b", u64::MAX, 10)],
+            DEFAULT_THRESHOLD,
+        );
+        assert!(groups.is_empty());
+    }
+}
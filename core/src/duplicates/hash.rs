@@ -0,0 +1,93 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::*;
+use image::imageops::{self, FilterType};
+use image::GrayImage;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Version of hashing code. Increment to recompute hashes of all pictures.
+pub const VERSION: u32 = 1;
+
+/// Computes a hash of the entire contents of a file.
+pub fn content_hash(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    let hash = hasher
+        .finalize()
+        .iter()
+        .map(|x| format!("{:02x}", x))
+        .collect::<String>();
+
+    Ok(hash)
+}
+
+/// Computes a perceptual hash of an image, such as a thumbnail.
+/// Copies of a picture that have been resized or re-encoded will have the same, or
+/// nearly the same, perceptual hash.
+pub fn perceptual_hash(path: &Path) -> Result<u64> {
+    let image = image::open(path)?.to_luma8();
+    Ok(difference_hash(&image))
+}
+
+/// Difference hash (dHash). Each bit records if a pixel is brighter than its
+/// right neighbour in a 9x8 greyscale version of the image.
+fn difference_hash(image: &GrayImage) -> u64 {
+    let small = imageops::resize(image, 9, 8, FilterType::Triangle);
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    hash
+}
+
+/// Number of bits that differ between two perceptual hashes.
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    fn gradient(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, y| {
+            Luma([((x * 255 / width + y * 7) % 256) as u8])
+        })
+    }
+
+    #[test]
+    fn test_resized_image_has_similar_hash() {
+        let large = gradient(400, 400);
+        let small = imageops::resize(&large, 100, 100, FilterType::Lanczos3);
+        let distance = hamming_distance(difference_hash(&large), difference_hash(&small));
+        assert!(distance <= 4, "distance was {}", distance);
+    }
+
+    #[test]
+    fn test_different_images_have_different_hash() {
+        let a = gradient(200, 200);
+        let b = GrayImage::from_fn(200, 200, |x, y| Luma([((x ^ y) % 256) as u8]));
+        let distance = hamming_distance(difference_hash(&a), difference_hash(&b));
+        assert!(distance > 10, "distance was {}", distance);
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        assert_eq!(0, hamming_distance(0b1010, 0b1010));
+        assert_eq!(2, hamming_distance(0b1010, 0b0110));
+        assert_eq!(64, hamming_distance(0, u64::MAX));
+    }
+}
@@ -0,0 +1,14 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod group;
+pub mod hash;
+pub mod model;
+pub mod repo;
+
+pub use group::group;
+pub use model::DuplicateGroup;
+pub use model::HashedPicture;
+pub use model::PictureHashes;
+pub use repo::Repository;
@@ -0,0 +1,46 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::photo::PictureId;
+use std::path::PathBuf;
+
+/// Hashes computed for a picture.
+#[derive(Debug, Clone)]
+pub struct PictureHashes {
+    pub picture_id: PictureId,
+
+    /// Hash of entire file contents. Equal for exact copies.
+    pub content_hash: String,
+
+    /// Hash of image appearance. Similar for resized or re-encoded copies.
+    pub perceptual_hash: u64,
+}
+
+/// A picture with hashes, as needed for finding and displaying duplicates.
+#[derive(Debug, Clone)]
+pub struct HashedPicture {
+    pub picture_id: PictureId,
+
+    /// Full path to picture.
+    pub path: PathBuf,
+
+    /// Full path to square thumbnail.
+    pub thumbnail_path: Option<PathBuf>,
+
+    pub fs_file_size_bytes: Option<u64>,
+
+    pub content_hash: String,
+
+    pub perceptual_hash: u64,
+}
+
+/// Pictures that are copies of each other.
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    /// Are all pictures exact copies, or are some only similar?
+    pub is_exact: bool,
+
+    /// Largest file first, as it is most likely to be the original.
+    pub pictures: Vec<HashedPicture>,
+}
@@ -0,0 +1,180 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::group;
+use super::hash;
+use super::model::{DuplicateGroup, HashedPicture, PictureHashes};
+use crate::path_encoding;
+use crate::photo::PictureId;
+
+use anyhow::*;
+use rusqlite;
+use rusqlite::params;
+use rusqlite::Row;
+use std::path::{Path, PathBuf};
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of picture hashes for finding duplicates.
+/// Repository is backed by a Sqlite database.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Base path for photo thumbnails
+    cache_dir_base_path: PathBuf,
+
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(
+        cache_dir_base_path: &Path,
+        con: Arc<Mutex<rusqlite::Connection>>,
+    ) -> Result<Repository> {
+        let cache_dir_base_path = PathBuf::from(cache_dir_base_path);
+
+        let repo = Repository {
+            cache_dir_base_path,
+            con,
+        };
+
+        Ok(repo)
+    }
+
+    /// Gets pictures with a thumbnail that haven't been hashed, or were hashed by an
+    /// older version of the hashing code.
+    /// Returns tuples of picture ID, picture path, and thumbnail path.
+    pub fn find_need_hashes(&self) -> Result<Vec<(PictureId, PathBuf, PathBuf)>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    library_roots.root_path_b64,
+                    pictures.picture_path_b64,
+                    pictures.thumbnail_path
+                FROM pictures
+                INNER JOIN library_roots ON library_roots.root_id = pictures.library_root_id
+                LEFT OUTER JOIN pictures_hashes USING (picture_id)
+                WHERE (pictures_hashes.picture_id IS NULL OR pictures_hashes.hash_version < ?1)
+                AND pictures.thumbnail_path IS NOT NULL
+                AND COALESCE(pictures.is_broken, FALSE) IS FALSE
+                AND library_roots.is_enabled IS TRUE",
+        )?;
+
+        let result = stmt
+            .query_map([hash::VERSION], |row| {
+                let picture_id = row.get("picture_id").map(PictureId::new)?;
+                let path = self.to_picture_path(row)?;
+
+                let thumbnail_path: String = row.get("thumbnail_path")?;
+                let thumbnail_path = self.cache_dir_base_path.join(thumbnail_path);
+
+                Ok((picture_id, path, thumbnail_path))
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    pub fn add_hashes(&mut self, hashes: Vec<PictureHashes>) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO pictures_hashes (
+                    picture_id,
+                    hash_version,
+                    content_hash,
+                    perceptual_hash
+                ) VALUES (
+                    ?1, ?2, ?3, ?4
+                ) ON CONFLICT (picture_id) DO UPDATE SET
+                    hash_version = ?2,
+                    content_hash = ?3,
+                    perceptual_hash = ?4
+                ",
+            )?;
+
+            for h in hashes {
+                stmt.execute(params![
+                    h.picture_id.id(),
+                    hash::VERSION,
+                    h.content_hash,
+                    // Sqlite integers are signed, so store the bits as an i64.
+                    h.perceptual_hash as i64,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Finds groups of duplicate pictures.
+    /// `threshold` is the maximum number of differing perceptual hash bits for pictures
+    /// to be near-duplicates.
+    pub fn find_duplicates(&self, threshold: u32) -> Result<Vec<DuplicateGroup>> {
+        // Grouping can take a while, so don't hold the connection while doing it.
+        let pictures: Vec<HashedPicture> = {
+            let con = self.con.lock().unwrap();
+            let mut stmt = con.prepare(
+                "SELECT
+                    pictures.picture_id,
+                    library_roots.root_path_b64,
+                    pictures.picture_path_b64,
+                    pictures.thumbnail_path,
+                    pictures.fs_file_size_bytes,
+                    pictures_hashes.content_hash,
+                    pictures_hashes.perceptual_hash
+                FROM pictures
+                INNER JOIN library_roots ON library_roots.root_id = pictures.library_root_id
+                INNER JOIN pictures_hashes USING (picture_id)
+                WHERE library_roots.is_enabled IS TRUE",
+            )?;
+
+            let rows = stmt.query_map([], |row| self.to_hashed_picture(row))?;
+            rows.flatten().collect()
+        };
+
+        Ok(group::group(pictures, threshold))
+    }
+
+    fn to_picture_path(&self, row: &Row<'_>) -> rusqlite::Result<PathBuf> {
+        let root_path: String = row.get("root_path_b64")?;
+        let root_path =
+            path_encoding::from_base64(&root_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+        let picture_path: String = row.get("picture_path_b64")?;
+        let picture_path =
+            path_encoding::from_base64(&picture_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+        Ok(root_path.join(picture_path))
+    }
+
+    fn to_hashed_picture(&self, row: &Row<'_>) -> rusqlite::Result<HashedPicture> {
+        let picture_id = row.get("picture_id").map(PictureId::new)?;
+        let path = self.to_picture_path(row)?;
+
+        let thumbnail_path = row
+            .get("thumbnail_path")
+            .map(|p: String| self.cache_dir_base_path.join(p))
+            .ok();
+
+        let fs_file_size_bytes = row.get("fs_file_size_bytes")?;
+        let content_hash = row.get("content_hash")?;
+
+        let perceptual_hash: i64 = row.get("perceptual_hash")?;
+
+        Ok(HashedPicture {
+            picture_id,
+            path,
+            thumbnail_path,
+            fs_file_size_bytes,
+            content_hash,
+            perceptual_hash: perceptual_hash as u64,
+        })
+    }
+}
@@ -0,0 +1,148 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::Edit;
+use super::render;
+use anyhow::*;
+
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, ExtendedColorType, ImageEncoder, RgbImage};
+
+use fast_image_resize as fr;
+use fr::images::Image;
+use fr::{ResizeOptions, Resizer};
+
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use strum::EnumIter;
+
+/// Quality of exported JPEG and AVIF files, from 1 to 100.
+pub const DEFAULT_QUALITY: u8 = 90;
+
+/// AVIF encoding speed, from 1 (slowest) to 10 (fastest).
+/// Slower speeds give smaller files, but take far too long for exporting many pictures.
+const AVIF_SPEED: u8 = 6;
+
+/// File formats an edited picture can be exported as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum ExportFormat {
+    Jpeg,
+    Png,
+    /// Lossless WebP.
+    WebP,
+    Avif,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Jpeg => "jpg",
+            ExportFormat::Png => "png",
+            ExportFormat::WebP => "webp",
+            ExportFormat::Avif => "avif",
+        }
+    }
+}
+
+/// How to export an edited picture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+
+    /// Longest edge in pixels to scale down to. None to keep the full size.
+    pub max_edge: Option<u32>,
+
+    /// Quality from 1 to 100. Ignored for lossless formats.
+    pub quality: u8,
+}
+
+/// Saves a copy of a loaded picture with an edit applied.
+pub fn save_copy(
+    edit: &Edit,
+    image: DynamicImage,
+    options: &ExportOptions,
+    copy_path: &Path,
+) -> Result<()> {
+    // None of the formats need an alpha channel for photos, and JPEG can't have one.
+    let image = render::apply(edit, image).into_rgb8();
+    let image = scale_to_fit(image, options.max_edge)?;
+
+    // Write to temporary file first and then move so that an interrupted write
+    // doesn't leave a corrupt copy.
+    let temporary_path = copy_path.with_extension("tmp");
+
+    let file = std::fs::File::create(&temporary_path)?;
+    let mut file = BufWriter::new(file);
+
+    encode(&image, options, &mut file)?;
+
+    file.flush()?;
+    drop(file);
+
+    std::fs::rename(temporary_path, copy_path)?;
+
+    Ok(())
+}
+
+/// Scales an image down so its longest edge fits, if it doesn't already.
+pub fn scale_to_fit(image: RgbImage, max_edge: Option<u32>) -> Result<RgbImage> {
+    match max_edge {
+        Some(max_edge) if image.width().max(image.height()) > max_edge => {
+            scale_down(image, max_edge)
+        }
+        _ => Ok(image),
+    }
+}
+
+/// Encodes an image in the format of the export options.
+pub fn encode<W: Write>(image: &RgbImage, options: &ExportOptions, writer: W) -> Result<()> {
+    let (width, height) = image.dimensions();
+    let quality = options.quality.clamp(1, 100);
+
+    match options.format {
+        ExportFormat::Jpeg => JpegEncoder::new_with_quality(writer, quality).write_image(
+            image.as_raw(),
+            width,
+            height,
+            ExtendedColorType::Rgb8,
+        )?,
+        ExportFormat::Png => PngEncoder::new(writer).write_image(
+            image.as_raw(),
+            width,
+            height,
+            ExtendedColorType::Rgb8,
+        )?,
+        ExportFormat::WebP => WebPEncoder::new_lossless(writer).write_image(
+            image.as_raw(),
+            width,
+            height,
+            ExtendedColorType::Rgb8,
+        )?,
+        ExportFormat::Avif => AvifEncoder::new_with_speed_quality(writer, AVIF_SPEED, quality)
+            .write_image(image.as_raw(), width, height, ExtendedColorType::Rgb8)?,
+    };
+
+    Ok(())
+}
+
+/// Scales an image down so its longest edge fits, keeping its aspect ratio.
+fn scale_down(image: RgbImage, max_edge: u32) -> Result<RgbImage> {
+    let (width, height) = image.dimensions();
+    let scale = max_edge as f64 / width.max(height) as f64;
+    let dst_width = ((width as f64 * scale).round() as u32).max(1);
+    let dst_height = ((height as f64 * scale).round() as u32).max(1);
+
+    // WARNING src_image and dst_image must use the same pixel type.
+    // PixelType::U8x3 == RGB8
+    let src_image = DynamicImage::ImageRgb8(image);
+    let mut dst_image = Image::new(dst_width, dst_height, fr::PixelType::U8x3);
+
+    Resizer::new().resize(&src_image, &mut dst_image, &ResizeOptions::new())?;
+
+    RgbImage::from_raw(dst_width, dst_height, dst_image.into_vec())
+        .ok_or_else(|| anyhow!("Resized image has unexpected size"))
+}
@@ -0,0 +1,17 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod export;
+pub mod model;
+pub mod render;
+pub mod repo;
+
+pub use export::ExportFormat;
+pub use export::ExportOptions;
+pub use model::Adjustment;
+pub use model::Adjustments;
+pub use model::CropRect;
+pub use model::Edit;
+pub use model::Preset;
+pub use repo::Repository;
@@ -0,0 +1,284 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use strum::EnumIter;
+
+/// Most a picture can be straightened, in degrees either way.
+pub const MAX_STRAIGHTEN_DEGREES: f64 = 45.0;
+
+/// Area of a picture to keep, as fractions of the width and height of the picture
+/// so that a crop applies equally to a full size image and a preview.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropRect {
+    /// Left edge, from 0 to 1.
+    pub x: f64,
+
+    /// Top edge, from 0 to 1.
+    pub y: f64,
+
+    pub width: f64,
+
+    pub height: f64,
+}
+
+impl CropRect {
+    /// Crop rectangle clamped to lie within the picture.
+    pub fn clamped(&self) -> CropRect {
+        let x = self.x.clamp(0.0, 1.0);
+        let y = self.y.clamp(0.0, 1.0);
+        CropRect {
+            x,
+            y,
+            width: self.width.clamp(0.0, 1.0 - x),
+            height: self.height.clamp(0.0, 1.0 - y),
+        }
+    }
+
+    /// Crop rectangle after the picture is rotated by 90 degrees clockwise.
+    fn rotated_clockwise(&self) -> CropRect {
+        CropRect {
+            x: 1.0 - self.y - self.height,
+            y: self.x,
+            width: self.height,
+            height: self.width,
+        }
+    }
+
+    /// Crop rectangle after the picture is rotated by 90 degrees counter-clockwise.
+    fn rotated_counter_clockwise(&self) -> CropRect {
+        CropRect {
+            x: self.y,
+            y: 1.0 - self.x - self.width,
+            width: self.height,
+            height: self.width,
+        }
+    }
+
+    fn flipped_horizontal(&self) -> CropRect {
+        CropRect {
+            x: 1.0 - self.x - self.width,
+            ..*self
+        }
+    }
+
+    fn flipped_vertical(&self) -> CropRect {
+        CropRect {
+            y: 1.0 - self.y - self.height,
+            ..*self
+        }
+    }
+}
+
+/// A colour or exposure adjustment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Adjustment {
+    Exposure,
+    Contrast,
+    Highlights,
+    Shadows,
+    Saturation,
+    Temperature,
+    Tint,
+}
+
+impl Adjustment {
+    /// Smallest and largest value of adjustment. Zero is always no adjustment.
+    pub fn range(&self) -> (f64, f64) {
+        match self {
+            // Exposure is in stops, so each step doubles or halves the light.
+            Adjustment::Exposure => (-2.0, 2.0),
+            _ => (-1.0, 1.0),
+        }
+    }
+}
+
+/// Colour and exposure adjustments of a picture. The default makes no adjustment.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Adjustments {
+    /// Brightness in stops.
+    pub exposure: f64,
+
+    /// Difference between light and dark tones.
+    pub contrast: f64,
+
+    /// Brighten or recover the lightest tones.
+    pub highlights: f64,
+
+    /// Brighten or deepen the darkest tones.
+    pub shadows: f64,
+
+    /// Intensity of colours. The lowest value is black and white.
+    pub saturation: f64,
+
+    /// White balance from cool blue to warm orange.
+    pub temperature: f64,
+
+    /// White balance from green to magenta.
+    pub tint: f64,
+}
+
+impl Adjustments {
+    /// True if no adjustment is made.
+    pub fn is_original(&self) -> bool {
+        *self == Adjustments::default()
+    }
+
+    pub fn get(&self, adjustment: Adjustment) -> f64 {
+        match adjustment {
+            Adjustment::Exposure => self.exposure,
+            Adjustment::Contrast => self.contrast,
+            Adjustment::Highlights => self.highlights,
+            Adjustment::Shadows => self.shadows,
+            Adjustment::Saturation => self.saturation,
+            Adjustment::Temperature => self.temperature,
+            Adjustment::Tint => self.tint,
+        }
+    }
+
+    /// Sets an adjustment, clamped to its range.
+    pub fn set(&mut self, adjustment: Adjustment, value: f64) {
+        let (min, max) = adjustment.range();
+        let value = value.clamp(min, max);
+        match adjustment {
+            Adjustment::Exposure => self.exposure = value,
+            Adjustment::Contrast => self.contrast = value,
+            Adjustment::Highlights => self.highlights = value,
+            Adjustment::Shadows => self.shadows = value,
+            Adjustment::Saturation => self.saturation = value,
+            Adjustment::Temperature => self.temperature = value,
+            Adjustment::Tint => self.tint = value,
+        }
+    }
+}
+
+/// Ready-made combinations of adjustments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Preset {
+    Vivid,
+    Warm,
+    Cool,
+    Mono,
+    Fade,
+    Dramatic,
+}
+
+impl Preset {
+    pub fn adjustments(&self) -> Adjustments {
+        let none = Adjustments::default();
+        match self {
+            Preset::Vivid => Adjustments {
+                contrast: 0.2,
+                saturation: 0.35,
+                ..none
+            },
+            Preset::Warm => Adjustments {
+                temperature: 0.4,
+                saturation: 0.1,
+                ..none
+            },
+            Preset::Cool => Adjustments {
+                temperature: -0.4,
+                ..none
+            },
+            Preset::Mono => Adjustments {
+                contrast: 0.15,
+                saturation: -1.0,
+                ..none
+            },
+            Preset::Fade => Adjustments {
+                contrast: -0.3,
+                shadows: 0.3,
+                saturation: -0.3,
+                ..none
+            },
+            Preset::Dramatic => Adjustments {
+                contrast: 0.35,
+                highlights: -0.4,
+                shadows: -0.2,
+                saturation: -0.2,
+                ..none
+            },
+        }
+    }
+}
+
+/// Non-destructive edit of a picture. The original file is never modified.
+///
+/// Geometry is applied in a fixed order: quarter turns, flips, straighten, and
+/// finally crop. Colour and exposure adjustments are applied last. The methods for
+/// rotating and flipping adjust the other fields so that each operation applies to
+/// the picture as currently shown.
+///
+/// The default edit leaves a picture unchanged.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Edit {
+    /// Clockwise rotations by 90 degrees, from 0 to 3.
+    pub quarter_turns: u8,
+
+    /// Mirror left to right.
+    pub flip_horizontal: bool,
+
+    /// Mirror top to bottom.
+    pub flip_vertical: bool,
+
+    /// Clockwise rotation to level a horizon, in degrees. The straightened picture
+    /// is cropped to the largest rectangle of the same aspect ratio without empty corners.
+    pub straighten_degrees: f64,
+
+    /// Area to keep after all other geometry is applied. None if not cropped.
+    pub crop: Option<CropRect>,
+
+    /// Colour and exposure adjustments, applied after geometry.
+    pub adjustments: Adjustments,
+}
+
+impl Edit {
+    /// True if the edit leaves a picture unchanged.
+    pub fn is_original(&self) -> bool {
+        *self == Edit::default()
+    }
+
+    /// True if the edit swaps the width and height of a picture.
+    pub fn is_transposed(&self) -> bool {
+        self.quarter_turns % 2 == 1
+    }
+
+    pub fn rotate_clockwise(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 1) % 4;
+        // Rotating after a flip is the same as flipping the other axis after rotating.
+        std::mem::swap(&mut self.flip_horizontal, &mut self.flip_vertical);
+        self.crop = self.crop.map(|c| c.rotated_clockwise());
+    }
+
+    pub fn rotate_counter_clockwise(&mut self) {
+        self.quarter_turns = (self.quarter_turns + 3) % 4;
+        std::mem::swap(&mut self.flip_horizontal, &mut self.flip_vertical);
+        self.crop = self.crop.map(|c| c.rotated_counter_clockwise());
+    }
+
+    pub fn flip_horizontal(&mut self) {
+        self.flip_horizontal = !self.flip_horizontal;
+        // Mirroring reverses the direction of straightening.
+        self.straighten_degrees = -self.straighten_degrees;
+        self.crop = self.crop.map(|c| c.flipped_horizontal());
+    }
+
+    pub fn flip_vertical(&mut self) {
+        self.flip_vertical = !self.flip_vertical;
+        self.straighten_degrees = -self.straighten_degrees;
+        self.crop = self.crop.map(|c| c.flipped_vertical());
+    }
+
+    pub fn set_straighten_degrees(&mut self, degrees: f64) {
+        self.straighten_degrees = degrees.clamp(-MAX_STRAIGHTEN_DEGREES, MAX_STRAIGHTEN_DEGREES);
+    }
+
+    /// Sets the area to keep. A crop covering the whole picture is the same as no crop.
+    pub fn set_crop(&mut self, crop: Option<CropRect>) {
+        self.crop = crop
+            .map(|c| c.clamped())
+            .filter(|c| c.width > 0.0 && c.height > 0.0)
+            .filter(|c| *c != CropRect { x: 0.0, y: 0.0, width: 1.0, height: 1.0 });
+    }
+}
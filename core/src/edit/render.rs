@@ -0,0 +1,422 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{Adjustments, Edit};
+use crate::photo::raw;
+use anyhow::*;
+
+use gdk4::prelude::*;
+use image::{DynamicImage, Rgba, RgbaImage};
+use rayon::prelude::*;
+use std::path::Path;
+
+/// Loads a picture with Glycin, with the orientation from its metadata applied.
+pub async fn load(path: &Path) -> Result<DynamicImage> {
+    load_with_orientation(path, true).await
+}
+
+/// Loads a picture with Glycin, optionally with the orientation from its metadata applied.
+/// Without orientation, pixels are as stored in the file.
+pub async fn load_with_orientation(path: &Path, apply_orientation: bool) -> Result<DynamicImage> {
+    // Glycin can't decode camera RAW files, so load a preview instead.
+    if raw::is_raw(path) {
+        let preview = raw::preview(path)?;
+        let image = load_file(preview.file.path(), false).await?;
+        return Ok(if apply_orientation {
+            raw::orient(image, preview.orientation)
+        } else {
+            image
+        });
+    }
+
+    load_file(path, apply_orientation).await
+}
+
+async fn load_file(path: &Path, apply_orientation: bool) -> Result<DynamicImage> {
+    let file = gio::File::for_path(path);
+
+    let mut loader = glycin::Loader::new(file);
+    loader.sandbox_selector(glycin::SandboxSelector::FlatpakSpawn);
+    loader.apply_transformations(apply_orientation);
+
+    let image = loader.load().await?;
+    let frame = image.next_frame().await?;
+
+    Ok(from_texture(&frame.texture()))
+}
+
+/// Copies a texture, such as a frame decoded by Glycin, to an image.
+pub fn from_texture(texture: &gdk4::Texture) -> DynamicImage {
+    let width = texture.width() as u32;
+    let height = texture.height() as u32;
+    let stride = width as usize * 4;
+
+    // Downloaded pixels are premultiplied BGRA on little-endian and ARGB on big-endian.
+    let mut data = vec![0u8; stride * height as usize];
+    texture.download(&mut data, stride);
+
+    for pixel in data.chunks_exact_mut(4) {
+        let [b, g, r, a] = if cfg!(target_endian = "little") {
+            [pixel[0], pixel[1], pixel[2], pixel[3]]
+        } else {
+            [pixel[3], pixel[2], pixel[1], pixel[0]]
+        };
+
+        let unpremultiply = |c: u8| match a {
+            0 => 0,
+            255 => c,
+            _ => ((c as u32 * 255 + a as u32 / 2) / a as u32).min(255) as u8,
+        };
+
+        pixel.copy_from_slice(&[unpremultiply(r), unpremultiply(g), unpremultiply(b), a]);
+    }
+
+    let image = RgbaImage::from_raw(width, height, data).expect("buffer must match dimensions");
+    DynamicImage::ImageRgba8(image)
+}
+
+/// Copies an image to a texture for display.
+pub fn to_texture(image: &DynamicImage) -> gdk4::Texture {
+    let image = image.to_rgba8();
+    let (width, height) = image.dimensions();
+    let bytes = gdk4::glib::Bytes::from_owned(image.into_raw());
+
+    gdk4::MemoryTexture::new(
+        width as i32,
+        height as i32,
+        gdk4::MemoryFormat::R8g8b8a8,
+        &bytes,
+        width as usize * 4,
+    )
+    .upcast()
+}
+
+/// Applies an edit to an image.
+pub fn apply(edit: &Edit, image: DynamicImage) -> DynamicImage {
+    if edit.is_original() {
+        return image;
+    }
+
+    let image = match edit.quarter_turns % 4 {
+        1 => image.rotate90(),
+        2 => image.rotate180(),
+        3 => image.rotate270(),
+        _ => image,
+    };
+
+    let image = if edit.flip_horizontal {
+        image.fliph()
+    } else {
+        image
+    };
+    let image = if edit.flip_vertical {
+        image.flipv()
+    } else {
+        image
+    };
+
+    let image = if edit.straighten_degrees != 0.0 {
+        straighten(&image, edit.straighten_degrees)
+    } else {
+        image
+    };
+
+    let image = if let Some(crop) = edit.crop.map(|c| c.clamped()) {
+        let (width, height) = (image.width() as f64, image.height() as f64);
+        let x = (crop.x * width).round() as u32;
+        let y = (crop.y * height).round() as u32;
+        let crop_width = ((crop.width * width).round() as u32).max(1);
+        let crop_height = ((crop.height * height).round() as u32).max(1);
+        image.crop_imm(x, y, crop_width, crop_height)
+    } else {
+        image
+    };
+
+    // Adjust after cropping so there are fewer pixels to adjust.
+    if edit.adjustments.is_original() {
+        image
+    } else {
+        adjust(&edit.adjustments, image)
+    }
+}
+
+/// Applies colour and exposure adjustments to every pixel of an image.
+///
+/// White balance and exposure are applied to linear light, as a camera would.
+/// Tone and colour adjustments are applied to gamma encoded values, which
+/// better match how light and dark tones are perceived.
+fn adjust(adjustments: &Adjustments, image: DynamicImage) -> DynamicImage {
+    let luts = Luts::new();
+
+    let exposure = 2f32.powf(adjustments.exposure as f32);
+    let temperature = adjustments.temperature as f32;
+    let tint = adjustments.tint as f32;
+    let gains = [
+        exposure * (1.0 + 0.15 * temperature),
+        exposure * (1.0 - 0.15 * tint),
+        exposure * (1.0 - 0.15 * temperature),
+    ];
+
+    let highlights = adjustments.highlights as f32;
+    let shadows = adjustments.shadows as f32;
+    let contrast = 1.0 + adjustments.contrast as f32;
+    let saturation = 1.0 + adjustments.saturation as f32;
+
+    let mut image = image.into_rgba8();
+    let row_len = image.width() as usize * 4;
+
+    image.par_chunks_mut(row_len).for_each(|row| {
+        for pixel in row.chunks_exact_mut(4) {
+            let mut rgb = [0f32; 3];
+            for ((c, p), gain) in rgb.iter_mut().zip(pixel.iter()).zip(gains) {
+                *c = luts.to_srgb(luts.to_linear[*p as usize] * gain);
+            }
+
+            // Lift or lower dark and light tones without moving black or white.
+            let tone = luma(&rgb);
+            if tone > 0.0 {
+                let toned = tone
+                    + shadows * tone * (1.0 - tone).powi(2)
+                    + highlights * tone.powi(2) * (1.0 - tone);
+                let scale = toned / tone;
+                rgb.iter_mut().for_each(|c| *c *= scale);
+            }
+
+            rgb.iter_mut()
+                .for_each(|c| *c = (*c - 0.5) * contrast + 0.5);
+
+            let grey = luma(&rgb);
+            rgb.iter_mut()
+                .for_each(|c| *c = grey + (*c - grey) * saturation);
+
+            for (p, c) in pixel.iter_mut().zip(rgb) {
+                *p = (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+    });
+
+    DynamicImage::ImageRgba8(image)
+}
+
+/// Relative luminance of gamma encoded sRGB.
+fn luma(rgb: &[f32; 3]) -> f32 {
+    0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2]
+}
+
+/// Lookup tables for converting between gamma encoded sRGB and linear light,
+/// which is much faster than computing powers for every pixel.
+struct Luts {
+    to_linear: [f32; 256],
+    to_srgb: Vec<f32>,
+}
+
+impl Luts {
+    const SRGB_STEPS: usize = 4096;
+
+    fn new() -> Self {
+        let mut to_linear = [0f32; 256];
+        for (i, v) in to_linear.iter_mut().enumerate() {
+            let c = i as f32 / 255.0;
+            *v = if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            };
+        }
+
+        let to_srgb = (0..=Self::SRGB_STEPS)
+            .map(|i| {
+                let l = i as f32 / Self::SRGB_STEPS as f32;
+                if l <= 0.0031308 {
+                    l * 12.92
+                } else {
+                    1.055 * l.powf(1.0 / 2.4) - 0.055
+                }
+            })
+            .collect();
+
+        Luts { to_linear, to_srgb }
+    }
+
+    /// Gamma encode linear light, clipping anything brighter than white.
+    fn to_srgb(&self, linear: f32) -> f32 {
+        let index = (linear.clamp(0.0, 1.0) * Self::SRGB_STEPS as f32).round() as usize;
+        self.to_srgb[index]
+    }
+}
+
+/// Rotates an image clockwise by an arbitrary angle and crops it to the largest
+/// rectangle with the same aspect ratio that has no empty corners.
+fn straighten(image: &DynamicImage, degrees: f64) -> DynamicImage {
+    let src = image.to_rgba8();
+    let (width, height) = (src.width() as f64, src.height() as f64);
+
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (sin_abs, cos_abs) = (sin.abs(), cos.abs());
+
+    // Scale that fits the rotated rectangle inside the original rectangle.
+    let scale = f64::min(
+        width / (width * cos_abs + height * sin_abs),
+        height / (width * sin_abs + height * cos_abs),
+    );
+
+    let dst_width = ((width * scale).floor() as u32).max(1);
+    let dst_height = ((height * scale).floor() as u32).max(1);
+
+    let (src_cx, src_cy) = (width / 2.0, height / 2.0);
+    let (dst_cx, dst_cy) = (dst_width as f64 / 2.0, dst_height as f64 / 2.0);
+
+    let mut dst = RgbaImage::new(dst_width, dst_height);
+
+    dst.par_chunks_mut(dst_width as usize * 4)
+        .enumerate()
+        .for_each(|(row, pixels)| {
+            let dy = row as f64 + 0.5 - dst_cy;
+            for (col, pixel) in pixels.chunks_mut(4).enumerate() {
+                let dx = col as f64 + 0.5 - dst_cx;
+
+                // Rotate destination point back into the source image.
+                let sx = cos * dx + sin * dy + src_cx - 0.5;
+                let sy = -sin * dx + cos * dy + src_cy - 0.5;
+
+                pixel.copy_from_slice(&bilinear(&src, sx, sy).0);
+            }
+        });
+
+    DynamicImage::ImageRgba8(dst)
+}
+
+/// Samples an image between pixels, clamping to the edges.
+fn bilinear(image: &RgbaImage, x: f64, y: f64) -> Rgba<u8> {
+    let max_x = image.width() as f64 - 1.0;
+    let max_y = image.height() as f64 - 1.0;
+    let x = x.clamp(0.0, max_x);
+    let y = y.clamp(0.0, max_y);
+
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let x1 = (x0 + 1.0).min(max_x);
+    let y1 = (y0 + 1.0).min(max_y);
+    let (fx, fy) = (x - x0, y - y0);
+
+    let p00 = image.get_pixel(x0 as u32, y0 as u32);
+    let p10 = image.get_pixel(x1 as u32, y0 as u32);
+    let p01 = image.get_pixel(x0 as u32, y1 as u32);
+    let p11 = image.get_pixel(x1 as u32, y1 as u32);
+
+    let mut out = [0u8; 4];
+    for (i, channel) in out.iter_mut().enumerate() {
+        let top = p00[i] as f64 * (1.0 - fx) + p10[i] as f64 * fx;
+        let bottom = p01[i] as f64 * (1.0 - fx) + p11[i] as f64 * fx;
+        *channel = (top * (1.0 - fy) + bottom * fy).round() as u8;
+    }
+
+    Rgba(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit::model::{CropRect, Preset};
+
+    fn image(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([x as u8, y as u8, 0, 255])
+        }))
+    }
+
+    #[test]
+    fn original_is_unchanged() {
+        let result = apply(&Edit::default(), image(4, 3));
+        assert_eq!((4, 3), (result.width(), result.height()));
+    }
+
+    #[test]
+    fn quarter_turn_swaps_dimensions() {
+        let mut edit = Edit::default();
+        edit.rotate_clockwise();
+
+        let result = apply(&edit, image(4, 3)).to_rgba8();
+        assert_eq!((3, 4), result.dimensions());
+
+        // Bottom left pixel moves to top left.
+        assert_eq!(Rgba([0, 2, 0, 255]), *result.get_pixel(0, 0));
+    }
+
+    #[test]
+    fn crop_follows_rotation() {
+        let mut edit = Edit::default();
+        edit.set_crop(Some(CropRect {
+            x: 0.0,
+            y: 0.0,
+            width: 0.5,
+            height: 0.5,
+        }));
+        edit.rotate_clockwise();
+
+        let result = apply(&edit, image(4, 4)).to_rgba8();
+        assert_eq!((2, 2), result.dimensions());
+
+        // Top left quarter moved to top right.
+        assert_eq!(Rgba([0, 1, 0, 255]), *result.get_pixel(0, 0));
+    }
+
+    #[test]
+    fn flip_is_undone_by_rotation_and_flip() {
+        let mut edit = Edit::default();
+        edit.flip_horizontal();
+        edit.rotate_clockwise();
+        edit.rotate_clockwise();
+        edit.flip_vertical();
+
+        let original = image(4, 3).to_rgba8();
+        let result = apply(&edit, image(4, 3)).to_rgba8();
+        assert_eq!(original, result);
+    }
+
+    #[test]
+    fn straighten_has_no_empty_corners() {
+        let src =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(40, 30, Rgba([255, 255, 255, 255])));
+        let mut edit = Edit::default();
+        edit.set_straighten_degrees(10.0);
+
+        let result = apply(&edit, src).to_rgba8();
+        assert!(result.width() < 40 && result.height() < 30);
+        assert!(result.pixels().all(|p| p.0 == [255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn mono_preset_removes_colour() {
+        let edit = Edit {
+            adjustments: Preset::Mono.adjustments(),
+            ..Edit::default()
+        };
+
+        let src = DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, Rgba([200, 80, 40, 255])));
+        let result = apply(&edit, src).to_rgba8();
+
+        let [r, g, b, a] = result.get_pixel(0, 0).0;
+        assert!(r == g && g == b);
+        assert_eq!(255, a);
+    }
+
+    #[test]
+    fn exposure_brightens_without_moving_black() {
+        let mut edit = Edit::default();
+        edit.adjustments.exposure = 1.0;
+
+        let src = DynamicImage::ImageRgba8(RgbaImage::from_fn(2, 1, |x, _| {
+            if x == 0 {
+                Rgba([0, 0, 0, 255])
+            } else {
+                Rgba([100, 100, 100, 255])
+            }
+        }));
+        let result = apply(&edit, src).to_rgba8();
+
+        assert_eq!(Rgba([0, 0, 0, 255]), *result.get_pixel(0, 0));
+        assert!(result.get_pixel(1, 0).0[0] > 100);
+    }
+}
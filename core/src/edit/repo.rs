@@ -0,0 +1,243 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{Adjustments, CropRect, Edit};
+use crate::photo::PictureId;
+
+use anyhow::*;
+use rusqlite;
+use rusqlite::params;
+use rusqlite::Row;
+use std::collections::HashMap;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of non-destructive picture edits.
+/// Repository is backed by a Sqlite database.
+///
+/// Saving or reverting an edit clears the thumbnail path of the picture so
+/// that the thumbnail is regenerated with the edit applied.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        let repo = Repository { con };
+        Ok(repo)
+    }
+
+    /// Edit of a picture, or None if the picture is unedited.
+    pub fn get(&self, picture_id: PictureId) -> Result<Option<Edit>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "SELECT
+                quarter_turns,
+                flip_horizontal,
+                flip_vertical,
+                straighten_degrees,
+                crop_x,
+                crop_y,
+                crop_width,
+                crop_height,
+                exposure,
+                contrast,
+                highlights,
+                shadows,
+                saturation,
+                temperature,
+                tint
+            FROM pictures_edits
+            WHERE picture_id = ?1",
+        )?;
+
+        let result = stmt
+            .query_map(params![picture_id.id()], Self::to_edit)?
+            .flatten()
+            .next();
+
+        Ok(result)
+    }
+
+    /// Edits of all edited pictures.
+    pub fn all(&self) -> Result<HashMap<PictureId, Edit>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                picture_id,
+                quarter_turns,
+                flip_horizontal,
+                flip_vertical,
+                straighten_degrees,
+                crop_x,
+                crop_y,
+                crop_width,
+                crop_height,
+                exposure,
+                contrast,
+                highlights,
+                shadows,
+                saturation,
+                temperature,
+                tint
+            FROM pictures_edits",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| {
+                let picture_id = row.get("picture_id").map(PictureId::new)?;
+                Self::to_edit(row).map(|edit| (picture_id, edit))
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Saves the edit of a picture. Saving an edit that leaves the picture unchanged
+    /// is the same as reverting to the original.
+    pub fn save(&mut self, picture_id: PictureId, edit: &Edit) -> Result<()> {
+        if edit.is_original() {
+            return self.revert(picture_id);
+        }
+
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let crop = edit.crop.map(|c| c.clamped());
+            let adjustments = &edit.adjustments;
+
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO pictures_edits (
+                    picture_id,
+                    quarter_turns,
+                    flip_horizontal,
+                    flip_vertical,
+                    straighten_degrees,
+                    crop_x,
+                    crop_y,
+                    crop_width,
+                    crop_height,
+                    exposure,
+                    contrast,
+                    highlights,
+                    shadows,
+                    saturation,
+                    temperature,
+                    tint,
+                    updated_ts
+                ) VALUES (
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9,
+                    ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                    CURRENT_TIMESTAMP
+                )
+                ON CONFLICT (picture_id) DO UPDATE SET
+                    quarter_turns = ?2,
+                    flip_horizontal = ?3,
+                    flip_vertical = ?4,
+                    straighten_degrees = ?5,
+                    crop_x = ?6,
+                    crop_y = ?7,
+                    crop_width = ?8,
+                    crop_height = ?9,
+                    exposure = ?10,
+                    contrast = ?11,
+                    highlights = ?12,
+                    shadows = ?13,
+                    saturation = ?14,
+                    temperature = ?15,
+                    tint = ?16,
+                    updated_ts = CURRENT_TIMESTAMP",
+            )?;
+
+            stmt.execute(params![
+                picture_id.id(),
+                edit.quarter_turns % 4,
+                edit.flip_horizontal,
+                edit.flip_vertical,
+                edit.straighten_degrees,
+                crop.map(|c| c.x),
+                crop.map(|c| c.y),
+                crop.map(|c| c.width),
+                crop.map(|c| c.height),
+                adjustments.exposure,
+                adjustments.contrast,
+                adjustments.highlights,
+                adjustments.shadows,
+                adjustments.saturation,
+                adjustments.temperature,
+                adjustments.tint,
+            ])?;
+
+            Self::clear_thumbnail(&tx, picture_id)?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Discards the edit of a picture so it is shown as the original again.
+    pub fn revert(&mut self, picture_id: PictureId) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached("DELETE FROM pictures_edits WHERE picture_id = ?1")?;
+            let deleted = stmt.execute(params![picture_id.id()])?;
+
+            if deleted > 0 {
+                Self::clear_thumbnail(&tx, picture_id)?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn clear_thumbnail(tx: &rusqlite::Transaction<'_>, picture_id: PictureId) -> Result<()> {
+        let mut stmt =
+            tx.prepare_cached("UPDATE pictures SET thumbnail_path = NULL WHERE picture_id = ?1")?;
+        stmt.execute(params![picture_id.id()])?;
+        Ok(())
+    }
+
+    fn to_edit(row: &Row<'_>) -> rusqlite::Result<Edit> {
+        let crop_x: Option<f64> = row.get("crop_x")?;
+        let crop_y: Option<f64> = row.get("crop_y")?;
+        let crop_width: Option<f64> = row.get("crop_width")?;
+        let crop_height: Option<f64> = row.get("crop_height")?;
+
+        let crop = match (crop_x, crop_y, crop_width, crop_height) {
+            (Some(x), Some(y), Some(width), Some(height)) => Some(CropRect {
+                x,
+                y,
+                width,
+                height,
+            }),
+            _ => None,
+        };
+
+        let adjustments = Adjustments {
+            exposure: row.get("exposure")?,
+            contrast: row.get("contrast")?,
+            highlights: row.get("highlights")?,
+            shadows: row.get("shadows")?,
+            saturation: row.get("saturation")?,
+            temperature: row.get("temperature")?,
+            tint: row.get("tint")?,
+        };
+
+        Ok(Edit {
+            quarter_turns: row.get("quarter_turns")?,
+            flip_horizontal: row.get("flip_horizontal")?,
+            flip_vertical: row.get("flip_vertical")?,
+            straighten_degrees: row.get("straighten_degrees")?,
+            crop,
+            adjustments,
+        })
+    }
+}
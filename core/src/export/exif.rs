@@ -0,0 +1,253 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::edit::ExportFormat;
+use crate::photo::raw;
+use anyhow::*;
+use exif::experimental::Writer;
+use exif::{Context, Field, In, Tag, Value};
+use std::fs;
+use std::io::{BufReader, Cursor};
+use std::path::Path;
+use tracing::{debug, warn};
+
+/// Most EXIF data that fits in a JPEG APP1 segment.
+const MAX_JPEG_EXIF_BYTES: usize = 65535 - 2 - EXIF_HEADER.len();
+
+/// Header of a JPEG APP1 segment holding EXIF data.
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+/// Tags that describe the original image data, which no longer apply once re-encoded.
+const IMAGE_DATA_TAGS: [Tag; 8] = [
+    Tag::ImageWidth,
+    Tag::ImageLength,
+    Tag::PixelXDimension,
+    Tag::PixelYDimension,
+    Tag::BitsPerSample,
+    Tag::Compression,
+    Tag::PhotometricInterpretation,
+    Tag::SamplesPerPixel,
+];
+
+/// Reads the EXIF metadata of an original picture and encodes it for an exported copy.
+/// The embedded thumbnail and maker notes are dropped, as are the GPS location and the
+/// orientation unless kept. None if the original has no metadata to keep.
+pub fn for_export(
+    original: &Path,
+    keep_location: bool,
+    keep_orientation: bool,
+) -> Result<Option<Vec<u8>>> {
+    let exif = if raw::is_raw(original) {
+        raw::read_exif(original)
+    } else {
+        let file = fs::File::open(original)?;
+        let mut reader = BufReader::new(file);
+        exif::Reader::new()
+            .read_from_container(&mut reader)
+            .map_err(Error::from)
+    };
+
+    let Result::Ok(exif) = exif else {
+        debug!("No EXIF metadata in {:?}", original);
+        return Ok(None);
+    };
+
+    let keep = |field: &&Field| {
+        field.ifd_num == In::PRIMARY
+            && !matches!(field.value, Value::Unknown(..))
+            // Maker notes have offsets into the original file, so can't be moved.
+            && field.tag != Tag::MakerNote
+            && !IMAGE_DATA_TAGS.contains(&field.tag)
+            && (keep_location || field.tag.context() != Context::Gps)
+            && (keep_orientation || field.tag != Tag::Orientation)
+    };
+
+    let fields: Vec<&Field> = exif.fields().filter(keep).collect();
+
+    if fields.is_empty() {
+        return Ok(None);
+    }
+
+    let mut writer = Writer::new();
+    for field in fields {
+        writer.push_field(field);
+    }
+
+    let mut buf = Cursor::new(Vec::new());
+    writer.write(&mut buf, exif.little_endian())?;
+
+    Ok(Some(buf.into_inner()))
+}
+
+/// Can pictures in a format be written with EXIF metadata?
+pub fn can_embed(format: ExportFormat) -> bool {
+    matches!(format, ExportFormat::Jpeg | ExportFormat::Png)
+}
+
+/// Adds EXIF metadata to an encoded picture.
+/// Pictures in formats that can't be written with metadata are returned unchanged.
+pub fn embed(format: ExportFormat, encoded: Vec<u8>, exif: &[u8]) -> Vec<u8> {
+    match format {
+        ExportFormat::Jpeg => embed_jpeg(encoded, exif),
+        ExportFormat::Png => embed_png(encoded, exif),
+        _ => {
+            debug!("Not writing EXIF metadata to {:?}", format);
+            encoded
+        }
+    }
+}
+
+/// Inserts an APP1 segment after the start of image marker and any JFIF APP0 segment.
+fn embed_jpeg(jpeg: Vec<u8>, exif: &[u8]) -> Vec<u8> {
+    if exif.len() > MAX_JPEG_EXIF_BYTES {
+        warn!("EXIF metadata too big for JPEG: {} bytes", exif.len());
+        return jpeg;
+    }
+
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        warn!("Not a JPEG, so not writing EXIF metadata");
+        return jpeg;
+    }
+
+    let mut insert_at = 2;
+    if jpeg.get(2..4) == Some(&[0xFF, 0xE0]) {
+        if let Some(&[high, low]) = jpeg.get(4..6) {
+            insert_at += 2 + u16::from_be_bytes([high, low]) as usize;
+        }
+    }
+
+    let insert_at = insert_at.min(jpeg.len());
+    let length = (2 + EXIF_HEADER.len() + exif.len()) as u16;
+
+    let mut out = Vec::with_capacity(jpeg.len() + 4 + EXIF_HEADER.len() + exif.len());
+    out.extend_from_slice(&jpeg[..insert_at]);
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&length.to_be_bytes());
+    out.extend_from_slice(EXIF_HEADER);
+    out.extend_from_slice(exif);
+    out.extend_from_slice(&jpeg[insert_at..]);
+    out
+}
+
+/// Inserts an eXIf chunk after the IHDR chunk, as it must come before the image data.
+fn embed_png(png: Vec<u8>, exif: &[u8]) -> Vec<u8> {
+    // Signature, then IHDR chunk of length, type, 13 bytes of data, and CRC.
+    const IHDR_END: usize = 8 + 4 + 4 + 13 + 4;
+
+    if png.len() < IHDR_END || png.get(12..16) != Some(b"IHDR") {
+        warn!("Not a PNG, so not writing EXIF metadata");
+        return png;
+    }
+
+    let mut chunk = Vec::with_capacity(12 + exif.len());
+    chunk.extend_from_slice(&(exif.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(b"eXIf");
+    chunk.extend_from_slice(exif);
+    let crc = crc32(&chunk[4..]);
+    chunk.extend_from_slice(&crc.to_be_bytes());
+
+    let mut out = Vec::with_capacity(png.len() + chunk.len());
+    out.extend_from_slice(&png[..IHDR_END]);
+    out.extend_from_slice(&chunk);
+    out.extend_from_slice(&png[IHDR_END..]);
+    out
+}
+
+/// CRC-32 as used by PNG chunks.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::jpeg::JpegEncoder;
+    use image::{ExtendedColorType, ImageEncoder};
+    use std::io::Write;
+
+    fn exif_with(fields: &[Field]) -> Vec<u8> {
+        let mut writer = Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut buf = Cursor::new(Vec::new());
+        writer.write(&mut buf, false).unwrap();
+        buf.into_inner()
+    }
+
+    fn field(tag: Tag, value: Value) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value,
+        }
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+
+    #[test]
+    fn test_for_export_strips_location_and_orientation() {
+        let exif = exif_with(&[
+            field(Tag::Make, Value::Ascii(vec![b"Fotema".to_vec()])),
+            field(Tag::Orientation, Value::Short(vec![6])),
+            field(Tag::GPSLatitudeRef, Value::Ascii(vec![b"N".to_vec()])),
+        ]);
+
+        let mut jpeg = Vec::new();
+        JpegEncoder::new(&mut jpeg)
+            .write_image(&[0; 12], 2, 2, ExtendedColorType::Rgb8)
+            .unwrap();
+        let jpeg = embed(ExportFormat::Jpeg, jpeg, &exif);
+
+        let mut file = tempfile::Builder::new().suffix(".jpg").tempfile().unwrap();
+        file.write_all(&jpeg).unwrap();
+
+        let read = |keep_location, keep_orientation| {
+            let exif = for_export(file.path(), keep_location, keep_orientation)
+                .unwrap()
+                .unwrap();
+            let exif = exif::Reader::new().read_raw(exif).unwrap();
+            let mut tags: Vec<Tag> = exif.fields().map(|f| f.tag).collect();
+            tags.sort_by_key(|tag| tag.number());
+            tags
+        };
+
+        assert_eq!(
+            read(true, true),
+            vec![Tag::GPSLatitudeRef, Tag::Make, Tag::Orientation]
+        );
+        assert_eq!(read(false, false), vec![Tag::Make]);
+    }
+
+    #[test]
+    fn test_embed_png() {
+        let exif = exif_with(&[field(Tag::Make, Value::Ascii(vec![b"Fotema".to_vec()]))]);
+
+        let mut png = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut png)
+            .write_image(&[0; 12], 2, 2, ExtendedColorType::Rgb8)
+            .unwrap();
+        let png = embed(ExportFormat::Png, png, &exif);
+
+        let image = image::load_from_memory(&png).unwrap();
+        assert_eq!(image.width(), 2);
+
+        let mut reader = Cursor::new(png);
+        let read = exif::Reader::new()
+            .read_from_container(&mut reader)
+            .unwrap();
+        assert!(read.get_field(Tag::Make, In::PRIMARY).is_some());
+    }
+}
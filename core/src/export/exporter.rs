@@ -0,0 +1,245 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::exif;
+use super::model::{Destination, ExportSettings};
+use super::template::{self, UniqueNames};
+use crate::edit;
+use crate::visual::Visual;
+use crate::PictureId;
+use anyhow::*;
+
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tracing::{error, info};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Where exported files are written to.
+enum Sink {
+    Folder(PathBuf),
+
+    /// Archive is written to a temporary path and renamed when finished,
+    /// so an interrupted export doesn't leave a broken archive.
+    Zip {
+        path: PathBuf,
+        temporary_path: PathBuf,
+        writer: ZipWriter<BufWriter<fs::File>>,
+    },
+}
+
+impl Sink {
+    fn open(destination: &Destination) -> Result<Sink> {
+        match destination {
+            Destination::Folder(path) => {
+                fs::create_dir_all(path)?;
+                Ok(Sink::Folder(path.clone()))
+            }
+            Destination::Zip(path) => {
+                let temporary_path = path.with_extension("zip.part");
+                let file = fs::File::create(&temporary_path)?;
+                Ok(Sink::Zip {
+                    path: path.clone(),
+                    temporary_path,
+                    writer: ZipWriter::new(BufWriter::new(file)),
+                })
+            }
+        }
+    }
+
+    /// Is there already a file with this name outside of the export?
+    fn is_taken(&self, name: &str) -> bool {
+        match self {
+            Sink::Folder(path) => path.join(name).exists(),
+            Sink::Zip { .. } => false,
+        }
+    }
+
+    fn add_bytes(&mut self, name: &str, bytes: &[u8]) -> Result<()> {
+        match self {
+            Sink::Folder(path) => {
+                // Write to temporary file first and then move so that an interrupted write
+                // doesn't leave a corrupt file.
+                let path = path.join(name);
+                let temporary_path = path.with_extension("tmp");
+                fs::write(&temporary_path, bytes)?;
+                fs::rename(temporary_path, path)?;
+            }
+            Sink::Zip { writer, .. } => {
+                writer.start_file(name, zip_options(bytes.len() as u64))?;
+                writer.write_all(bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn add_file(&mut self, name: &str, source: &Path) -> Result<()> {
+        match self {
+            Sink::Folder(path) => {
+                fs::copy(source, path.join(name))?;
+            }
+            Sink::Zip { writer, .. } => {
+                let size = fs::metadata(source)?.len();
+                writer.start_file(name, zip_options(size))?;
+                let mut file = fs::File::open(source)?;
+                std::io::copy(&mut file, writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        if let Sink::Zip {
+            path,
+            temporary_path,
+            writer,
+        } = self
+        {
+            let mut file = writer.finish()?;
+            file.flush()?;
+            drop(file);
+            fs::rename(temporary_path, path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Pictures and videos are already compressed, so storing is as good as compressing.
+fn zip_options(size: u64) -> SimpleFileOptions {
+    SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(size > u32::MAX as u64)
+}
+
+/// Exports pictures and videos, such as for sharing, into a folder or zip archive.
+/// Pictures are converted and scaled, with their edits applied. Videos are copied as they are.
+pub struct Exporter {
+    edit_repo: edit::Repository,
+    settings: ExportSettings,
+    sink: Sink,
+    names: UniqueNames,
+
+    /// Count of items exported so far, for the {index} placeholder.
+    index: usize,
+}
+
+impl Exporter {
+    /// Creates the destination folder or archive.
+    pub fn create(edit_repo: edit::Repository, settings: ExportSettings) -> Result<Exporter> {
+        let sink = Sink::open(&settings.destination)?;
+        Ok(Exporter {
+            edit_repo,
+            settings,
+            sink,
+            names: UniqueNames::default(),
+            index: 0,
+        })
+    }
+
+    /// Exports the files of an item.
+    pub async fn export(&mut self, visual: &Visual) -> Result<()> {
+        self.index += 1;
+
+        let template = if self.settings.file_name_template.trim().is_empty() {
+            template::DEFAULT_TEMPLATE
+        } else {
+            &self.settings.file_name_template
+        };
+
+        let stem = template::render(template, visual, self.index);
+
+        let video_path = if visual.picture_path.is_none() {
+            visual.video_path.as_ref()
+        } else if self.settings.include_motion_videos {
+            visual
+                .motion_photo_video_path
+                .as_ref()
+                .or(visual.video_path.as_ref())
+        } else {
+            None
+        };
+
+        let video_extension = video_path
+            .and_then(|path| path.extension())
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+
+        let picture_extension = visual
+            .picture_path
+            .as_ref()
+            .map(|_| self.settings.image.format.extension().to_string());
+
+        let extensions: Vec<&str> = picture_extension
+            .iter()
+            .chain(video_extension.iter())
+            .map(|extension| extension.as_str())
+            .collect();
+
+        let sink = &self.sink;
+        let stem = self
+            .names
+            .claim(&stem, &extensions, |name| sink.is_taken(name));
+
+        if let (Some(path), Some(extension)) = (&visual.picture_path, &picture_extension) {
+            let name = format!("{}.{}", stem, extension);
+            info!("Exporting {:?} as {}", path, name);
+            let bytes = self.encode_picture(path, visual.picture_id).await?;
+            self.sink.add_bytes(&name, &bytes)?;
+        }
+
+        if let (Some(path), Some(extension)) = (video_path, &video_extension) {
+            let name = format!("{}.{}", stem, extension);
+            info!("Exporting {:?} as {}", path, name);
+            self.sink.add_file(&name, path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Completes the export. An archive isn't readable until finished.
+    pub fn finish(self) -> Result<()> {
+        self.sink.finish()
+    }
+
+    async fn encode_picture(&self, path: &Path, picture_id: Option<PictureId>) -> Result<Vec<u8>> {
+        let edit = picture_id
+            .and_then(|picture_id| {
+                self.edit_repo
+                    .get(picture_id)
+                    .inspect_err(|e| error!("Failed loading edit for {}: {}", picture_id, e))
+                    .ok()
+                    .flatten()
+            })
+            .unwrap_or_default();
+
+        // Pixels can only be left as stored if the orientation metadata goes with them.
+        let apply_orientation = self.settings.apply_orientation
+            || !edit.is_original()
+            || !self.settings.keep_metadata
+            || !exif::can_embed(self.settings.image.format);
+
+        let image = edit::render::load_with_orientation(path, apply_orientation).await?;
+
+        // None of the formats need an alpha channel for photos, and JPEG can't have one.
+        let image = edit::render::apply(&edit, image).into_rgb8();
+        let image = edit::export::scale_to_fit(image, self.settings.image.max_edge)?;
+
+        let mut bytes = Vec::new();
+        edit::export::encode(&image, &self.settings.image, &mut bytes)?;
+
+        if !self.settings.keep_metadata {
+            return Ok(bytes);
+        }
+
+        let metadata = exif::for_export(path, self.settings.keep_location, !apply_orientation)
+            .inspect_err(|e| error!("Failed reading EXIF metadata of {:?}: {}", path, e))
+            .ok()
+            .flatten();
+
+        match metadata {
+            Some(metadata) => Ok(exif::embed(self.settings.image.format, bytes, &metadata)),
+            None => Ok(bytes),
+        }
+    }
+}
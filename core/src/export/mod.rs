@@ -0,0 +1,12 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod exif;
+pub mod exporter;
+pub mod model;
+pub mod template;
+
+pub use exporter::Exporter;
+pub use model::Destination;
+pub use model::ExportSettings;
@@ -0,0 +1,44 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::edit::ExportOptions;
+use std::path::PathBuf;
+
+/// Where exported files are written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    /// Folder to write files into.
+    Folder(PathBuf),
+
+    /// Zip archive to write files into. Replaced if it already exists.
+    Zip(PathBuf),
+}
+
+/// How to export pictures and videos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSettings {
+    pub destination: Destination,
+
+    /// Format, size, and quality of exported pictures.
+    pub image: ExportOptions,
+
+    /// Copy EXIF metadata, such as the camera and date, from the original picture.
+    /// Only JPEG and PNG files are written with metadata.
+    pub keep_metadata: bool,
+
+    /// Keep the GPS location when keeping metadata.
+    pub keep_location: bool,
+
+    /// Rotate and flip pictures to match their orientation metadata.
+    /// Otherwise, pixels are exported as stored and the orientation metadata is kept.
+    /// Pictures are always oriented if their metadata isn't kept, or if they have been
+    /// edited, as edits apply to the oriented picture.
+    pub apply_orientation: bool,
+
+    /// Also export the videos of live photos and motion photos.
+    pub include_motion_videos: bool,
+
+    /// Template for file names, without an extension. See `template::render`.
+    pub file_name_template: String,
+}
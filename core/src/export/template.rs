@@ -0,0 +1,122 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::visual::Visual;
+use std::collections::HashSet;
+
+/// Default template for exported file names, which keeps the original names.
+pub const DEFAULT_TEMPLATE: &str = "{original_name}";
+
+/// Name for a file when the template gives an empty name.
+const FALLBACK_NAME: &str = "export";
+
+/// File name, without an extension, for an exported item.
+/// Supported placeholders are {original_name}, {folder}, {year}, {month}, {day},
+/// {hour}, {minute}, {second}, and {index}, which counts exported items from 1.
+pub fn render(template: &str, visual: &Visual, index: usize) -> String {
+    let original_name = visual
+        .path()
+        .and_then(|path| path.file_stem())
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let folder = visual.folder_name().unwrap_or_default();
+
+    let date = |format: &str| visual.ordering_ts.format(format).to_string();
+
+    let name = template
+        .replace("{original_name}", &original_name)
+        .replace("{folder}", &folder)
+        .replace("{year}", &date("%Y"))
+        .replace("{month}", &date("%m"))
+        .replace("{day}", &date("%d"))
+        .replace("{hour}", &date("%H"))
+        .replace("{minute}", &date("%M"))
+        .replace("{second}", &date("%S"))
+        .replace("{index}", &format!("{:04}", index));
+
+    // Exported files all go in one folder.
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c == '/' || c == '\\' || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    let name = name.trim().trim_start_matches('.');
+
+    if name.is_empty() {
+        FALLBACK_NAME.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Chooses file names that haven't been used yet in an export.
+#[derive(Debug, Default)]
+pub struct UniqueNames {
+    /// Lowercase names, as some file systems ignore case.
+    used: HashSet<String>,
+}
+
+impl UniqueNames {
+    /// Picks a stem that isn't in use with any of the extensions, adding a numeric suffix if needed.
+    /// All files of an item get the same stem, so a live photo's picture and video stay paired.
+    /// `is_taken` reports names already taken outside of this export, such as existing files.
+    pub fn claim<F>(&mut self, stem: &str, extensions: &[&str], is_taken: F) -> String
+    where
+        F: Fn(&str) -> bool,
+    {
+        let names = |stem: &str| -> Vec<String> {
+            extensions
+                .iter()
+                .map(|extension| format!("{}.{}", stem, extension))
+                .collect()
+        };
+
+        let stem = (0..)
+            .map(|suffix| match suffix {
+                0 => stem.to_string(),
+                _ => format!("{} ({})", stem, suffix),
+            })
+            .find(|stem| {
+                names(stem)
+                    .iter()
+                    .all(|name| !self.used.contains(&name.to_lowercase()) && !is_taken(name))
+            })
+            .expect("unbounded suffixes");
+
+        for name in names(&stem) {
+            self.used.insert(name.to_lowercase());
+        }
+
+        stem
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claim() {
+        let mut names = UniqueNames::default();
+        let never = |_: &str| false;
+
+        assert_eq!(names.claim("IMG_1", &["jpg", "mov"], never), "IMG_1");
+        assert_eq!(names.claim("img_1", &["JPG"], never), "img_1 (1)");
+
+        // Both files of a live photo need a free name.
+        assert_eq!(names.claim("IMG_2", &["jpg"], never), "IMG_2");
+        assert_eq!(names.claim("IMG_2", &["webp", "mov"], never), "IMG_2");
+        assert_eq!(names.claim("IMG_2", &["avif", "mov"], never), "IMG_2 (1)");
+
+        let existing = |name: &str| name == "IMG_3.jpg";
+        assert_eq!(names.claim("IMG_3", &["jpg"], existing), "IMG_3 (1)");
+    }
+}
@@ -0,0 +1,251 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+/// Copying and moving the original files of items to a folder.
+/// The file operations themselves are passed in by the caller, so the UI can use
+/// GIO, which can move files between file systems.
+use std::ffi::OsStr;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use tracing::{error, info};
+
+/// Inserts a numeric suffix before the first extension, so "IMG_1.jpg.xmp" becomes
+/// "IMG_1 (2).jpg.xmp". Suffix 0 keeps the name unchanged.
+fn with_suffix(name: &OsStr, suffix: usize) -> String {
+    let name = name.to_string_lossy();
+    if suffix == 0 {
+        return name.to_string();
+    }
+
+    // Skip first character so hidden files keep their leading dot.
+    match name.char_indices().skip(1).find(|(_, c)| *c == '.') {
+        Some((i, _)) => format!("{} ({}){}", &name[..i], suffix, &name[i..]),
+        None => format!("{} ({})", name, suffix),
+    }
+}
+
+/// Paths in a folder for the files of an item, avoiding existing files.
+/// All files of an item get the same suffix, so a live photo's picture and video stay paired.
+pub fn target_paths(folder: &Path, files: &[PathBuf]) -> Vec<PathBuf> {
+    (0..)
+        .map(|suffix| {
+            files
+                .iter()
+                .map(|file| folder.join(with_suffix(file.file_name().unwrap_or_default(), suffix)))
+                .collect::<Vec<PathBuf>>()
+        })
+        .find(|targets| targets.iter().all(|target| !target.exists()))
+        .unwrap_or_default()
+}
+
+/// Copies the files of each item to a folder, returning paths of copies.
+pub fn copy_files<F, E>(folder: &Path, files: Vec<Vec<PathBuf>>, mut copy_file: F) -> Vec<PathBuf>
+where
+    F: FnMut(&Path, &Path) -> Result<(), E>,
+    E: Display,
+{
+    let mut copies = Vec::new();
+
+    for item_files in files {
+        let targets = target_paths(folder, &item_files);
+        for (source, target) in item_files.iter().zip(targets) {
+            info!("Copying {:?} to {:?}", source, target);
+            match copy_file(source, &target) {
+                Ok(_) => copies.push(target),
+                Err(e) => error!("Failed copying {:?} to {:?}: {}", source, target, e),
+            }
+        }
+    }
+
+    copies
+}
+
+/// Moves the files of each item to a folder, returning original and new paths of moved files.
+/// Items are moved one at a time, so items with the same file names don't get the same paths.
+pub fn move_files<F, E>(
+    folder: &Path,
+    files: Vec<Vec<PathBuf>>,
+    mut move_file: F,
+) -> Vec<(PathBuf, PathBuf)>
+where
+    F: FnMut(&Path, &Path) -> Result<(), E>,
+    E: Display,
+{
+    let mut moves = Vec::new();
+
+    for item_files in files {
+        // Files already in the folder would only be renamed.
+        if item_files.iter().all(|file| file.parent() == Some(folder)) {
+            continue;
+        }
+
+        let targets = target_paths(folder, &item_files);
+        let item_moves = item_files.into_iter().zip(targets).collect();
+        moves.extend(move_back(item_moves, &mut move_file));
+    }
+
+    moves
+}
+
+/// Moves files from first path to second path, returning the successful moves.
+/// Undoing a move with the paths swapped only moves back files that were moved.
+pub fn move_back<F, E>(moves: Vec<(PathBuf, PathBuf)>, mut move_file: F) -> Vec<(PathBuf, PathBuf)>
+where
+    F: FnMut(&Path, &Path) -> Result<(), E>,
+    E: Display,
+{
+    moves
+        .into_iter()
+        .filter(|(source, target)| {
+            info!("Moving {:?} to {:?}", source, target);
+            move_file(source, target)
+                .inspect_err(|e| error!("Failed moving {:?} to {:?}: {}", source, target, e))
+                .is_ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn rename(source: &Path, target: &Path) -> std::io::Result<()> {
+        fs::rename(source, target)
+    }
+
+    #[test]
+    fn test_with_suffix() {
+        assert_eq!(with_suffix(OsStr::new("IMG_1.jpg"), 0), "IMG_1.jpg");
+        assert_eq!(with_suffix(OsStr::new("IMG_1.jpg"), 2), "IMG_1 (2).jpg");
+        assert_eq!(
+            with_suffix(OsStr::new("IMG_1.jpg.xmp"), 2),
+            "IMG_1 (2).jpg.xmp"
+        );
+        assert_eq!(with_suffix(OsStr::new("IMG_1"), 2), "IMG_1 (2)");
+        assert_eq!(with_suffix(OsStr::new(".hidden"), 2), ".hidden (2)");
+    }
+
+    #[test]
+    fn test_target_paths_keeps_live_photo_paired() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path();
+
+        let files = vec![
+            PathBuf::from("/pictures/IMG_1.HEIC"),
+            PathBuf::from("/pictures/IMG_1.MOV"),
+        ];
+
+        assert_eq!(
+            target_paths(folder, &files),
+            vec![folder.join("IMG_1.HEIC"), folder.join("IMG_1.MOV")]
+        );
+
+        // Only the video is taken, but the picture gets the same suffix.
+        fs::write(folder.join("IMG_1.MOV"), b"taken").unwrap();
+        fs::write(folder.join("IMG_1 (1).HEIC"), b"taken").unwrap();
+
+        assert_eq!(
+            target_paths(folder, &files),
+            vec![folder.join("IMG_1 (2).HEIC"), folder.join("IMG_1 (2).MOV")]
+        );
+    }
+
+    #[test]
+    fn test_move_files_skips_items_in_folder() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+
+        let outside = source.path().join("IMG_1.jpg");
+        let inside = target.path().join("IMG_2.jpg");
+        fs::write(&outside, b"outside").unwrap();
+        fs::write(&inside, b"inside").unwrap();
+
+        let moves = move_files(
+            target.path(),
+            vec![vec![outside.clone()], vec![inside.clone()]],
+            rename,
+        );
+
+        assert_eq!(moves, vec![(outside, target.path().join("IMG_1.jpg"))]);
+        assert!(inside.exists());
+        assert!(!target.path().join("IMG_2 (1).jpg").exists());
+    }
+
+    #[test]
+    fn test_move_files_with_same_names() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+
+        fs::write(first.path().join("IMG_1.jpg"), b"first").unwrap();
+        fs::write(second.path().join("IMG_1.jpg"), b"second").unwrap();
+
+        let moves = move_files(
+            target.path(),
+            vec![
+                vec![first.path().join("IMG_1.jpg")],
+                vec![second.path().join("IMG_1.jpg")],
+            ],
+            rename,
+        );
+
+        assert_eq!(moves.len(), 2);
+        assert_eq!(fs::read(target.path().join("IMG_1.jpg")).unwrap(), b"first");
+        assert_eq!(
+            fs::read(target.path().join("IMG_1 (1).jpg")).unwrap(),
+            b"second"
+        );
+    }
+
+    #[test]
+    fn test_undo_partial_move() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+
+        let present = source.path().join("IMG_1.jpg");
+        let missing = source.path().join("IMG_2.jpg");
+        fs::write(&present, b"present").unwrap();
+
+        let moves = move_files(
+            target.path(),
+            vec![vec![present.clone()], vec![missing.clone()]],
+            rename,
+        );
+
+        // Only the successful move is recorded for undoing.
+        assert_eq!(
+            moves,
+            vec![(present.clone(), target.path().join("IMG_1.jpg"))]
+        );
+
+        let undo = moves.into_iter().map(|(from, to)| (to, from)).collect();
+        let undone = move_back(undo, rename);
+
+        assert_eq!(
+            undone,
+            vec![(target.path().join("IMG_1.jpg"), present.clone())]
+        );
+        assert_eq!(fs::read(&present).unwrap(), b"present");
+        assert!(!missing.exists());
+        assert_eq!(fs::read_dir(target.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_copy_files() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+
+        let picture = source.path().join("IMG_1.jpg");
+        fs::write(&picture, b"picture").unwrap();
+        fs::write(target.path().join("IMG_1.jpg"), b"taken").unwrap();
+
+        let copies = copy_files(target.path(), vec![vec![picture.clone()]], |from, to| {
+            fs::copy(from, to).map(|_| ())
+        });
+
+        assert_eq!(copies, vec![target.path().join("IMG_1 (1).jpg")]);
+        assert!(picture.exists());
+    }
+}
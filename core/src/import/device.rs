@@ -0,0 +1,335 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::library;
+use crate::media;
+use crate::photo;
+use crate::video;
+use anyhow::*;
+use chrono::prelude::*;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::{error, info};
+
+/// Folder that cameras and phones keep pictures and videos in.
+const DCIM: &str = "DCIM";
+
+/// Default template for the path of an imported file within a library root.
+pub const DEFAULT_TEMPLATE: &str = "{year}/{year}-{month}/{original_name}";
+
+/// Replaces date placeholders for files without a date.
+const UNDATED: &str = "undated";
+
+/// A picture or video on a device that isn't in the library yet.
+#[derive(Debug, Clone)]
+pub struct ImportItem {
+    /// Path of file on the device.
+    pub path: PathBuf,
+
+    /// Fingerprint of file contents.
+    pub fingerprint: String,
+
+    /// When the picture or video was taken, in local time.
+    /// From metadata if possible, otherwise from the file system.
+    pub taken_at: Option<NaiveDateTime>,
+
+    pub size_bytes: u64,
+}
+
+/// Should files be copied, or moved, from the device?
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    Copy,
+    Move,
+}
+
+/// Finds the DCIM folder of a mounted volume.
+/// Phones mounted with MTP have a DCIM folder within each storage folder,
+/// such as "Internal shared storage".
+pub fn find_dcim(mount_path: &Path) -> Option<PathBuf> {
+    let child_dcim = |dir: &Path| -> Option<PathBuf> {
+        fs::read_dir(dir)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .find(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(DCIM))
+            })
+    };
+
+    child_dcim(mount_path).or_else(|| {
+        fs::read_dir(mount_path)
+            .ok()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .find_map(|path| child_dcim(&path))
+    })
+}
+
+/// Finds pictures and videos under a folder that aren't already in the library.
+/// Files with the same contents as another file on the device are only listed once.
+pub fn find_new_items(source: &Path, known: &HashSet<String>) -> Result<Vec<ImportItem>> {
+    // Sidecars aren't imported by themselves.
+    let files = media::Scanner::build(source)?
+        .scan_all()?
+        .into_iter()
+        .filter(|file| file.media_type.is_picture() || file.media_type.is_video());
+
+    let mut seen = known.clone();
+    let mut items = Vec::new();
+
+    for file in files {
+        let path = file.path;
+        let is_picture = file.media_type.is_picture();
+
+        let fingerprint = match library::fingerprint(&path) {
+            Result::Ok(fingerprint) => fingerprint,
+            Err(e) => {
+                error!("Failed fingerprinting {:?}: {}", path, e);
+                continue;
+            }
+        };
+
+        if !seen.insert(fingerprint.clone()) {
+            continue;
+        }
+
+        let taken_at = if is_picture {
+            photo::metadata::from_path(&path)
+                .ok()
+                .and_then(|m| m.created_at)
+                .map(|x| x.naive_local())
+        } else {
+            // Video times are stored as UTC.
+            video::metadata::from_path(&path)
+                .ok()
+                .and_then(|m| m.created_at)
+                .map(|x| x.with_timezone(&Local).naive_local())
+        };
+
+        let taken_at = taken_at.or_else(|| {
+            file.fs_modified_at
+                .map(|x| x.with_timezone(&Local).naive_local())
+        });
+
+        items.push(ImportItem {
+            path,
+            fingerprint,
+            taken_at,
+            size_bytes: file.fs_file_size_bytes,
+        });
+    }
+
+    items.sort_by(|a, b| a.taken_at.cmp(&b.taken_at).then(a.path.cmp(&b.path)));
+
+    info!("Found {} new items in {:?}", items.len(), source);
+
+    Ok(items)
+}
+
+/// Relative path for an item from a template.
+/// Supported placeholders are {year}, {month}, {day}, {original_name}, {stem}, and {extension}.
+/// Parent folder components, such as "..", are dropped so an item can't escape the library root.
+pub fn render_template(template: &str, item: &ImportItem) -> PathBuf {
+    let file_name = item.path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = item.path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = item.path.extension().unwrap_or_default().to_string_lossy();
+
+    let date = |format: &str| {
+        item.taken_at
+            .map(|x| x.format(format).to_string())
+            .unwrap_or_else(|| UNDATED.to_string())
+    };
+
+    let rendered = template
+        .replace("{year}", &date("%Y"))
+        .replace("{month}", &date("%m"))
+        .replace("{day}", &date("%d"))
+        .replace("{original_name}", &file_name)
+        .replace("{stem}", &stem)
+        .replace("{extension}", &extension);
+
+    let path: PathBuf = Path::new(&rendered)
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect();
+
+    // A template without a file name would put every item at the same path.
+    let has_file_name = template.contains("{original_name}") || template.contains("{stem}");
+    if path.as_os_str().is_empty() || !has_file_name {
+        path.join(file_name.as_ref())
+    } else {
+        path
+    }
+}
+
+/// Inserts a numeric suffix before the extension, so "IMG_1.jpg" becomes "IMG_1 (2).jpg".
+fn with_suffix(path: &Path, suffix: usize) -> PathBuf {
+    if suffix == 0 {
+        return path.to_path_buf();
+    }
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{} ({}).{}", stem, suffix, extension.to_string_lossy()),
+        None => format!("{} ({})", stem, suffix),
+    };
+
+    path.with_file_name(name)
+}
+
+/// Copies or moves an item into a library root at the path given by a template.
+/// Returns the path of the imported file, or None if a file with the same contents
+/// was already at the path. A different file at the path gets a numeric suffix.
+pub fn import_item(
+    library_root: &Path,
+    template: &str,
+    item: &ImportItem,
+    mode: ImportMode,
+) -> Result<Option<PathBuf>> {
+    let path = library_root.join(render_template(template, item));
+
+    let target = (0..)
+        .map(|suffix| with_suffix(&path, suffix))
+        .find(|candidate| {
+            !candidate.exists()
+                || library::fingerprint(candidate).is_ok_and(|x| x == item.fingerprint)
+        })
+        .expect("unbounded suffixes");
+
+    if target.exists() {
+        info!("{:?} already imported as {:?}", item.path, target);
+        return Ok(None);
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    info!("Importing {:?} to {:?}", item.path, target);
+
+    let modified = fs::metadata(&item.path)?.modified();
+
+    fs::copy(&item.path, &target)?;
+
+    // Keep the modification time, which is the fallback for the date of a file
+    // without metadata.
+    if let Result::Ok(modified) = modified {
+        let file = fs::File::options().write(true).open(&target)?;
+        file.set_modified(modified)?;
+    }
+
+    if mode == ImportMode::Move {
+        fs::remove_file(&item.path)?;
+    }
+
+    Ok(Some(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(path: &str, taken_at: Option<&str>) -> ImportItem {
+        ImportItem {
+            path: PathBuf::from(path),
+            fingerprint: String::new(),
+            taken_at: taken_at.map(|x| NaiveDateTime::parse_from_str(x, "%Y-%m-%d %H:%M").unwrap()),
+            size_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let dated = item(
+            "/media/sd/DCIM/100CANON/IMG_0001.JPG",
+            Some("2024-03-09 10:00"),
+        );
+
+        assert_eq!(
+            render_template(DEFAULT_TEMPLATE, &dated),
+            PathBuf::from("2024/2024-03/IMG_0001.JPG")
+        );
+
+        assert_eq!(
+            render_template("{year}/{month}/{day}/{stem}.{extension}", &dated),
+            PathBuf::from("2024/03/09/IMG_0001.JPG")
+        );
+
+        let undated = item("/media/sd/DCIM/IMG_0002.JPG", None);
+        assert_eq!(
+            render_template(DEFAULT_TEMPLATE, &undated),
+            PathBuf::from("undated/undated-undated/IMG_0002.JPG")
+        );
+
+        // Items can't escape the library root, and always keep a file name.
+        assert_eq!(
+            render_template("/../{year}", &dated),
+            PathBuf::from("2024/IMG_0001.JPG")
+        );
+    }
+
+    #[test]
+    fn test_with_suffix() {
+        assert_eq!(
+            with_suffix(Path::new("a/IMG_1.jpg"), 0),
+            PathBuf::from("a/IMG_1.jpg")
+        );
+        assert_eq!(
+            with_suffix(Path::new("a/IMG_1.jpg"), 2),
+            PathBuf::from("a/IMG_1 (2).jpg")
+        );
+    }
+
+    #[test]
+    fn test_find_dcim() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = dir.path().join("Internal shared storage");
+        fs::create_dir_all(storage.join("DCIM/Camera")).unwrap();
+
+        assert_eq!(find_dcim(dir.path()), Some(storage.join("DCIM")));
+        assert_eq!(find_dcim(&storage.join("DCIM/Camera")), None);
+    }
+
+    #[test]
+    fn test_import_item_deduplicates() {
+        let device = tempfile::tempdir().unwrap();
+        let root = tempfile::tempdir().unwrap();
+
+        let source = device.path().join("IMG_0001.JPG");
+        fs::write(&source, b"first").unwrap();
+
+        let mut first = item(source.to_str().unwrap(), Some("2024-03-09 10:00"));
+        first.fingerprint = library::fingerprint(&source).unwrap();
+
+        let imported =
+            import_item(root.path(), DEFAULT_TEMPLATE, &first, ImportMode::Copy).unwrap();
+        assert_eq!(
+            imported,
+            Some(root.path().join("2024/2024-03/IMG_0001.JPG"))
+        );
+
+        // Same contents are not imported twice.
+        let imported =
+            import_item(root.path(), DEFAULT_TEMPLATE, &first, ImportMode::Copy).unwrap();
+        assert_eq!(imported, None);
+
+        // Different contents with the same name get a suffix.
+        fs::write(&source, b"second").unwrap();
+        let mut second = first.clone();
+        second.fingerprint = library::fingerprint(&source).unwrap();
+
+        let imported =
+            import_item(root.path(), DEFAULT_TEMPLATE, &second, ImportMode::Move).unwrap();
+        assert_eq!(
+            imported,
+            Some(root.path().join("2024/2024-03/IMG_0001 (1).JPG"))
+        );
+        assert!(!source.exists());
+    }
+}
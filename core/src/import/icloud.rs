@@ -0,0 +1,51 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::path::Path;
+
+/// Suffix iCloud Photos adds to the video of a live photo when downloading from the web.
+const HEVC_SUFFIX: &str = "_HEVC";
+
+/// Extensions of photos that a downloaded live photo video might keep in its file name.
+const PHOTO_EXTENSIONS: [&str; 3] = ["heic", "jpg", "jpeg"];
+
+/// Stem of the file name of a live photo video for linking it with its photo.
+/// iCloud and Takeout downloads name the video "IMG_1234_HEVC.MOV" or "IMG_1234.HEIC.MP4"
+/// rather than "IMG_1234.MOV", which wouldn't link with "IMG_1234.HEIC".
+pub fn video_link_stem(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+
+    let stem = stem
+        .len()
+        .checked_sub(HEVC_SUFFIX.len())
+        .filter(|&i| stem.is_char_boundary(i))
+        .filter(|&i| stem[i..].eq_ignore_ascii_case(HEVC_SUFFIX))
+        .map(|i| &stem[..i])
+        .unwrap_or(stem);
+
+    let stem = Path::new(stem)
+        .extension()
+        .and_then(|x| x.to_str())
+        .filter(|x| PHOTO_EXTENSIONS.iter().any(|e| x.eq_ignore_ascii_case(e)))
+        .and_then(|_| Path::new(stem).file_stem()?.to_str())
+        .unwrap_or(stem);
+
+    Some(stem.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_video_link_stem() {
+        let stem = |x| video_link_stem(Path::new(x));
+        assert_eq!(stem("IMG_1234.MOV"), Some("IMG_1234".into()));
+        assert_eq!(stem("IMG_1234_HEVC.MOV"), Some("IMG_1234".into()));
+        assert_eq!(stem("IMG_1234_hevc.mov"), Some("IMG_1234".into()));
+        assert_eq!(stem("IMG_1234.HEIC.MP4"), Some("IMG_1234".into()));
+        assert_eq!(stem("IMG_1234.mp4"), Some("IMG_1234".into()));
+        assert_eq!(stem("Holiday.2019.mp4"), Some("Holiday.2019".into()));
+    }
+}
@@ -0,0 +1,199 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::*;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Longest file name of a Takeout JSON sidecar. Takeout truncates longer names.
+const MAX_SIDECAR_FILE_NAME_CHARS: usize = 51;
+
+/// Newer Takeout archives insert this between the file name and ".json".
+const SUPPLEMENTAL_METADATA: &str = ".supplemental-metadata";
+
+/// Google Photos appends this to the file names of edited copies,
+/// which share the sidecar of the original.
+const EDITED_SUFFIX: &str = "-edited";
+
+/// Metadata from the JSON sidecar Google Photos Takeout writes beside each file.
+/// Google Photos strips some EXIF data when uploading, and keeps changes made
+/// in Google Photos only in the sidecar.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TakeoutMetadata {
+    /// photoTakenTime. Unlike EXIF, this is a true UTC time.
+    pub taken_at: Option<DateTime<Utc>>,
+
+    /// geoData, or else geoDataExif, as latitude and longitude.
+    pub location: Option<(f64, f64)>,
+
+    /// Caption entered in Google Photos.
+    pub description: Option<String>,
+}
+
+/// Possible sidecar paths for a file, in order of preference.
+/// Takeout names sidecars after the original file name, so a duplicate such as
+/// "IMG_1234(1).jpg" has a sidecar of "IMG_1234.jpg(1).json".
+pub fn sidecar_paths(path: &Path) -> Vec<PathBuf> {
+    let Some(stem) = path.file_stem().map(|x| x.to_string_lossy().to_string()) else {
+        return Vec::new();
+    };
+
+    let extension = path
+        .extension()
+        .map(|x| format!(".{}", x.to_string_lossy()))
+        .unwrap_or_default();
+
+    let (stem, counter) = split_counter(&stem);
+    let stem = stem.strip_suffix(EDITED_SUFFIX).unwrap_or(stem);
+
+    let original = format!("{}{}", stem, extension);
+
+    let names = [
+        format!("{}{}", original, SUPPLEMENTAL_METADATA),
+        original,
+        stem.to_string(),
+    ];
+
+    names
+        .iter()
+        .map(|name| {
+            let max_chars =
+                MAX_SIDECAR_FILE_NAME_CHARS.saturating_sub(".json".len() + counter.chars().count());
+            let name: String = name.chars().take(max_chars).collect();
+            path.with_file_name(format!("{}{}.json", name, counter))
+        })
+        .collect()
+}
+
+/// Splits a duplicate counter, such as "(1)", from the end of a file stem.
+fn split_counter(stem: &str) -> (&str, &str) {
+    let counter_start = stem
+        .strip_suffix(')')
+        .and_then(|x| x.rfind('('))
+        .filter(|&i| i > 0)
+        .filter(|&i| {
+            let digits = &stem[i + 1..stem.len() - 1];
+            !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+        });
+
+    match counter_start {
+        Some(i) => (&stem[..i], &stem[i..]),
+        None => (stem, ""),
+    }
+}
+
+/// Path of an existing sidecar for a file.
+pub fn find_sidecar(path: &Path) -> Option<PathBuf> {
+    sidecar_paths(path).into_iter().find(|p| p.is_file())
+}
+
+/// Reads metadata from the Takeout sidecar of a file. None if the file has no sidecar.
+pub fn read_sidecar(path: &Path) -> Result<Option<TakeoutMetadata>> {
+    let Some(sidecar) = find_sidecar(path) else {
+        return Ok(None);
+    };
+
+    let json = fs::read_to_string(&sidecar)?;
+    parse_metadata(&json).map(Some)
+}
+
+/// Parses the metadata Fotema understands from a Takeout sidecar.
+pub fn parse_metadata(json: &str) -> Result<TakeoutMetadata> {
+    let value: Value = serde_json::from_str(json)?;
+
+    // Timestamps are seconds since the epoch, as strings.
+    let taken_at = value
+        .pointer("/photoTakenTime/timestamp")
+        .and_then(|x| x.as_str())
+        .and_then(|x| x.parse::<i64>().ok())
+        .and_then(|x| DateTime::from_timestamp(x, 0));
+
+    let location = ["geoData", "geoDataExif"]
+        .iter()
+        .filter_map(|key| value.get(key))
+        .filter_map(|geo| {
+            let latitude = geo.get("latitude")?.as_f64()?;
+            let longitude = geo.get("longitude")?.as_f64()?;
+            Some((latitude, longitude))
+        })
+        // Takeout has a location of zero for pictures without a location.
+        .find(|&(latitude, longitude)| latitude != 0.0 || longitude != 0.0);
+
+    let description = value
+        .get("description")
+        .and_then(|x| x.as_str())
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty());
+
+    Ok(TakeoutMetadata {
+        taken_at,
+        location,
+        description,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sidecar_paths() {
+        let paths = sidecar_paths(Path::new("/takeout/IMG_1234(1).jpg"));
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/takeout/IMG_1234.jpg.supplemental-metadata(1).json"),
+                PathBuf::from("/takeout/IMG_1234.jpg(1).json"),
+                PathBuf::from("/takeout/IMG_1234(1).json"),
+            ]
+        );
+
+        let paths = sidecar_paths(Path::new("/takeout/IMG_1234-edited.jpg"));
+        assert_eq!(paths[1], PathBuf::from("/takeout/IMG_1234.jpg.json"));
+
+        // Long names are truncated.
+        let paths = sidecar_paths(Path::new(
+            "/takeout/Screenshot_20190618-093752_Samsung Internet.jpg",
+        ));
+        assert_eq!(
+            paths[0],
+            PathBuf::from("/takeout/Screenshot_20190618-093752_Samsung Internet.jp.json")
+        );
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let json = r#"{
+            "title": "IMG_1234.jpg",
+            "description": "Picnic ",
+            "photoTakenTime": {
+                "timestamp": "1563270000",
+                "formatted": "16 Jul 2019, 09:40:00 UTC"
+            },
+            "geoData": {
+                "latitude": 0.0,
+                "longitude": 0.0,
+                "altitude": 0.0
+            },
+            "geoDataExif": {
+                "latitude": 51.5,
+                "longitude": -0.12,
+                "altitude": 10.0
+            }
+        }"#;
+
+        let metadata = parse_metadata(json).unwrap();
+        assert_eq!(
+            metadata.taken_at.map(|x| x.to_rfc3339()),
+            Some("2019-07-16T09:40:00+00:00".to_string())
+        );
+        assert_eq!(metadata.location, Some((51.5, -0.12)));
+        assert_eq!(metadata.description, Some("Picnic".to_string()));
+
+        let metadata = parse_metadata(r#"{"description": ""}"#).unwrap();
+        assert_eq!(metadata, TakeoutMetadata::default());
+    }
+}
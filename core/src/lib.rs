@@ -2,15 +2,29 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod album;
+pub mod camera;
 pub mod database;
+pub mod dates;
+pub mod duplicates;
+pub mod edit;
+pub mod export;
+pub mod file_ops;
+pub mod import;
+pub mod library;
 pub mod machine_learning;
+pub mod media;
 pub mod path_encoding;
 pub mod people;
 pub mod photo;
+pub mod rating;
+pub mod search;
 pub mod time;
+pub mod trash;
 pub mod video;
 pub mod visual;
 
+pub use library::LibraryRootId;
 pub use people::model::FaceId;
 pub use people::model::PersonId;
 pub use photo::model::PictureId;
@@ -0,0 +1,209 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// File system attributes used to detect if a file has changed since it was last scanned.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FileStamp {
+    pub fs_modified_at: Option<DateTime<Utc>>,
+
+    /// Absent for files stored before file sizes were recorded.
+    pub fs_file_size_bytes: Option<u64>,
+}
+
+impl FileStamp {
+    /// Has a file changed since it was stored with this stamp?
+    /// An unknown stored file size is not treated as a change, otherwise the first
+    /// scan after upgrading would invalidate the whole library.
+    pub fn is_changed(&self, scanned: &FileStamp) -> bool {
+        if self.fs_modified_at != scanned.fs_modified_at {
+            return true;
+        }
+
+        match (self.fs_file_size_bytes, scanned.fs_file_size_bytes) {
+            (Some(stored), Some(scanned)) => stored != scanned,
+            _ => false,
+        }
+    }
+
+    /// Is the stored stamp missing details that a scan has found?
+    pub fn is_incomplete(&self, scanned: &FileStamp) -> bool {
+        self.fs_file_size_bytes.is_none() && scanned.fs_file_size_bytes.is_some()
+    }
+}
+
+/// Difference between files found by a scan and files stored in the database.
+#[derive(Debug)]
+pub struct ScanDiff<T> {
+    /// Files not yet in the database.
+    pub added: Vec<T>,
+
+    /// Files whose contents have changed and whose derived data must be regenerated.
+    pub changed: Vec<T>,
+
+    /// Unchanged files whose stored stamp should be updated, but whose derived
+    /// data is still valid.
+    pub refreshed: Vec<T>,
+
+    /// Stored files that were not found by the scan.
+    pub removed: Vec<PathBuf>,
+}
+
+impl<T> ScanDiff<T> {
+    /// Compares scanned files with stored files.
+    /// The `stamp` function gets the path and file stamp of a scanned file.
+    pub fn build<F>(stored: &HashMap<PathBuf, FileStamp>, scanned: Vec<T>, stamp: F) -> Self
+    where
+        F: Fn(&T) -> (PathBuf, FileStamp),
+    {
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        let mut refreshed = Vec::new();
+
+        let mut seen = std::collections::HashSet::with_capacity(scanned.len());
+
+        for file in scanned {
+            let (path, scanned_stamp) = stamp(&file);
+            match stored.get(&path) {
+                None => added.push(file),
+                Some(stored_stamp) if stored_stamp.is_changed(&scanned_stamp) => changed.push(file),
+                Some(stored_stamp) if stored_stamp.is_incomplete(&scanned_stamp) => {
+                    refreshed.push(file)
+                }
+                Some(_) => {}
+            }
+            seen.insert(path);
+        }
+
+        let removed = stored
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .cloned()
+            .collect();
+
+        Self {
+            added,
+            changed,
+            refreshed,
+            removed,
+        }
+    }
+
+    pub fn summary(&self) -> ScanSummary {
+        ScanSummary {
+            added: self.added.len(),
+            changed: self.changed.len(),
+            removed: self.removed.len(),
+            moved: 0,
+        }
+    }
+}
+
+/// Counts of files that differ between a scan and the database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanSummary {
+    pub added: usize,
+    pub changed: usize,
+    pub removed: usize,
+
+    /// Files that were found at a new path and kept their existing database entry.
+    pub moved: usize,
+}
+
+impl ScanSummary {
+    /// Total count of files that differ.
+    pub fn total(&self) -> usize {
+        self.added + self.changed + self.removed + self.moved
+    }
+}
+
+impl std::ops::AddAssign for ScanSummary {
+    fn add_assign(&mut self, other: Self) {
+        self.added += other.added;
+        self.changed += other.changed;
+        self.removed += other.removed;
+        self.moved += other.moved;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn stamp(secs: i64, size: Option<u64>) -> FileStamp {
+        FileStamp {
+            fs_modified_at: Utc.timestamp_opt(secs, 0).single(),
+            fs_file_size_bytes: size,
+        }
+    }
+
+    fn build(
+        stored: Vec<(&str, FileStamp)>,
+        scanned: Vec<(&str, FileStamp)>,
+    ) -> ScanDiff<(PathBuf, FileStamp)> {
+        let stored = stored
+            .into_iter()
+            .map(|(p, s)| (PathBuf::from(p), s))
+            .collect();
+        let scanned = scanned
+            .into_iter()
+            .map(|(p, s)| (PathBuf::from(p), s))
+            .collect();
+        ScanDiff::build(&stored, scanned, |x| x.clone())
+    }
+
+    #[test]
+    fn test_added_and_removed() {
+        let diff = build(
+            vec![("a.jpg", stamp(1, Some(10)))],
+            vec![("b.jpg", stamp(1, Some(10)))],
+        );
+        assert_eq!(1, diff.added.len());
+        assert_eq!(PathBuf::from("b.jpg"), diff.added[0].0);
+        assert_eq!(vec![PathBuf::from("a.jpg")], diff.removed);
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn test_unchanged() {
+        let diff = build(
+            vec![("a.jpg", stamp(1, Some(10)))],
+            vec![("a.jpg", stamp(1, Some(10)))],
+        );
+        assert_eq!(0, diff.summary().total());
+        assert!(diff.refreshed.is_empty());
+    }
+
+    #[test]
+    fn test_changed_modification_time() {
+        let diff = build(
+            vec![("a.jpg", stamp(1, Some(10)))],
+            vec![("a.jpg", stamp(2, Some(10)))],
+        );
+        assert_eq!(1, diff.changed.len());
+    }
+
+    #[test]
+    fn test_changed_size() {
+        let diff = build(
+            vec![("a.jpg", stamp(1, Some(10)))],
+            vec![("a.jpg", stamp(1, Some(11)))],
+        );
+        assert_eq!(1, diff.changed.len());
+    }
+
+    #[test]
+    fn test_unknown_stored_size_is_refreshed_not_changed() {
+        let diff = build(
+            vec![("a.jpg", stamp(1, None))],
+            vec![("a.jpg", stamp(1, Some(10)))],
+        );
+        assert!(diff.changed.is_empty());
+        assert_eq!(1, diff.refreshed.len());
+    }
+}
@@ -0,0 +1,89 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::*;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Size of each sample of file contents.
+const SAMPLE_SIZE: u64 = 16 * 1024;
+
+/// Computes a fingerprint of a file's contents that is cheap enough to compute for every
+/// file in a library. Rather than hashing the whole file, the file size and samples from
+/// the start, middle, and end of the file are hashed.
+/// Used to recognise a file that has been moved or renamed.
+pub fn fingerprint(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+
+    let offsets = if size <= SAMPLE_SIZE * 3 {
+        vec![0]
+    } else {
+        vec![0, size / 2 - SAMPLE_SIZE / 2, size - SAMPLE_SIZE]
+    };
+
+    let mut buf = Vec::with_capacity(SAMPLE_SIZE as usize * 3);
+
+    for offset in offsets {
+        file.seek(SeekFrom::Start(offset))?;
+        // A small file is read entirely as one sample.
+        let limit = if size <= SAMPLE_SIZE * 3 { size } else { SAMPLE_SIZE };
+        buf.clear();
+        (&mut file).take(limit).read_to_end(&mut buf)?;
+        hasher.update(&buf);
+    }
+
+    let hash = hasher
+        .finalize()
+        .iter()
+        .map(|x| format!("{:02x}", x))
+        .collect::<String>();
+
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_temp(data: &[u8]) -> tempfile::NamedTempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(data).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    #[test]
+    fn test_same_contents_same_fingerprint() {
+        let data: Vec<u8> = (0..200_000).map(|x| (x % 251) as u8).collect();
+        let a = write_temp(&data);
+        let b = write_temp(&data);
+        assert_eq!(fingerprint(a.path()).unwrap(), fingerprint(b.path()).unwrap());
+    }
+
+    #[test]
+    fn test_different_contents_different_fingerprint() {
+        let data: Vec<u8> = (0..200_000).map(|x| (x % 251) as u8).collect();
+        let a = write_temp(&data);
+
+        let mut data = data;
+        data[199_999] ^= 0xff;
+        let b = write_temp(&data);
+
+        assert_ne!(fingerprint(a.path()).unwrap(), fingerprint(b.path()).unwrap());
+    }
+
+    #[test]
+    fn test_small_file() {
+        let a = write_temp(b"hello");
+        let b = write_temp(b"hellp");
+        assert_ne!(fingerprint(a.path()).unwrap(), fingerprint(b.path()).unwrap());
+    }
+}
@@ -0,0 +1,18 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod diff;
+pub mod fingerprint;
+pub mod model;
+pub mod repo;
+pub mod rules;
+
+pub use diff::FileStamp;
+pub use diff::ScanDiff;
+pub use diff::ScanSummary;
+pub use fingerprint::fingerprint;
+pub use model::LibraryRoot;
+pub use model::LibraryRootId;
+pub use repo::Repository;
+pub use rules::ScanRules;
@@ -0,0 +1,53 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::rules::ScanRules;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// Database ID of library root
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LibraryRootId(i64);
+
+impl LibraryRootId {
+    pub fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    /// FIXME replace this with a To/From SQL implementation.
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+}
+
+impl Display for LibraryRootId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A folder on the file system containing pictures and videos.
+#[derive(Debug, Clone)]
+pub struct LibraryRoot {
+    /// Database primary key for library root
+    pub library_root_id: LibraryRootId,
+
+    /// Full path to root folder.
+    pub path: PathBuf,
+
+    /// Should root be scanned and shown in the library?
+    pub is_enabled: bool,
+
+    /// Which files under the root should be scanned?
+    pub rules: ScanRules,
+}
+
+impl LibraryRoot {
+    /// Can the root be scanned and cleaned?
+    /// A root on a removable drive or network share might be enabled but not mounted,
+    /// in which case its pictures and videos must not be treated as deleted.
+    pub fn is_available(&self) -> bool {
+        self.is_enabled && self.path.is_dir()
+    }
+}
@@ -0,0 +1,480 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{LibraryRoot, LibraryRootId};
+use super::rules::ScanRules;
+
+use crate::path_encoding;
+use anyhow::*;
+use rusqlite;
+use rusqlite::params;
+use rusqlite::Row;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of library root folders.
+/// Repository is backed by a Sqlite database.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    /// Builds a Repository.
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        let repo = Repository { con };
+        Ok(repo)
+    }
+
+    /// Adds the default library root if no roots exist.
+    /// The default root must have an ID of 1 as pictures and videos scanned before
+    /// library roots existed are associated with root 1.
+    pub fn add_default_if_empty(&mut self, path: &Path) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO library_roots (
+                    root_id,
+                    root_path_b64,
+                    root_path_lossy,
+                    is_enabled
+                )
+                SELECT 1, ?1, ?2, TRUE
+                WHERE NOT EXISTS (SELECT 1 FROM library_roots)",
+            )?;
+
+            stmt.execute(params![path_encoding::to_base64(path), path.to_string_lossy(),])?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Adds a new library root.
+    /// Fails if the path is already a root, or is inside or contains an existing root,
+    /// as otherwise the same file would be in the library twice.
+    pub fn add(&mut self, path: &Path) -> Result<LibraryRootId> {
+        if !path.is_absolute() {
+            bail!("{:?} is not an absolute path", path);
+        }
+
+        for root in self.all()? {
+            if path.starts_with(&root.path) || root.path.starts_with(path) {
+                bail!("{:?} overlaps with library root {:?}", path, root.path);
+            }
+        }
+
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let root_id = {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO library_roots (
+                    root_path_b64,
+                    root_path_lossy,
+                    is_enabled
+                ) VALUES (
+                    ?1, ?2, TRUE
+                )",
+            )?;
+
+            stmt.execute(params![path_encoding::to_base64(path), path.to_string_lossy(),])?;
+            tx.last_insert_rowid()
+        };
+
+        tx.commit()?;
+        Ok(LibraryRootId::new(root_id))
+    }
+
+    /// Removes a library root.
+    /// Pictures and videos under the root are removed by the next cleanup.
+    pub fn remove(&mut self, root_id: LibraryRootId) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare("DELETE FROM library_roots WHERE root_id = ?1")?;
+
+        stmt.execute([root_id.id()])?;
+
+        Ok(())
+    }
+
+    /// Enables or disables a library root.
+    /// Pictures and videos under a disabled root are not scanned or shown, but are
+    /// retained so re-enabling a root is cheap.
+    pub fn set_enabled(&mut self, root_id: LibraryRootId, is_enabled: bool) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE library_roots
+                SET
+                    is_enabled = ?2
+                WHERE root_id = ?1",
+            )?;
+
+            stmt.execute(params![root_id.id(), is_enabled])?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Updates the scan rules of a library root.
+    /// Pictures and videos excluded by the new rules are removed by the next cleanup.
+    pub fn set_rules(&mut self, root_id: LibraryRootId, rules: &ScanRules) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE library_roots
+                SET
+                    include_globs = ?2,
+                    exclude_globs = ?3,
+                    skip_hidden = ?4,
+                    min_file_size_bytes = ?5,
+                    min_dimension = ?6
+                WHERE root_id = ?1",
+            )?;
+
+            stmt.execute(params![
+                root_id.id(),
+                rules.include_globs.join("\n"),
+                rules.exclude_globs.join("\n"),
+                rules.skip_hidden,
+                rules.min_file_size_bytes,
+                rules.min_dimension,
+            ])?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Gets all library roots, in order they were added.
+    pub fn all(&self) -> Result<Vec<LibraryRoot>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                root_id,
+                root_path_b64,
+                is_enabled,
+                include_globs,
+                exclude_globs,
+                skip_hidden,
+                min_file_size_bytes,
+                min_dimension
+            FROM library_roots
+            ORDER BY root_id ASC",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| self.to_library_root(row))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Gets all library roots that are enabled and present on the file system.
+    pub fn all_available(&self) -> Result<Vec<LibraryRoot>> {
+        let roots = self.all()?;
+        Ok(roots.into_iter().filter(|r| r.is_available()).collect())
+    }
+
+    /// Gets fingerprints of all pictures and videos in the library, for recognising
+    /// files that have already been imported.
+    pub fn fingerprints(&self) -> Result<HashSet<String>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT fingerprint FROM pictures WHERE fingerprint IS NOT NULL
+            UNION
+            SELECT fingerprint FROM videos WHERE fingerprint IS NOT NULL",
+        )?;
+
+        let result = stmt.query_map([], |row| row.get(0))?.flatten().collect();
+
+        Ok(result)
+    }
+
+    fn to_library_root(&self, row: &Row<'_>) -> rusqlite::Result<LibraryRoot> {
+        let library_root_id = row.get("root_id").map(LibraryRootId::new)?;
+
+        let path: String = row.get("root_path_b64")?;
+        let path: PathBuf =
+            path_encoding::from_base64(&path).map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+        let is_enabled = row.get("is_enabled")?;
+
+        // Globs are stored one per line.
+        let to_globs = |globs: String| -> Vec<String> {
+            globs
+                .lines()
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty())
+                .map(String::from)
+                .collect()
+        };
+
+        let rules = ScanRules {
+            include_globs: row.get("include_globs").map(to_globs)?,
+            exclude_globs: row.get("exclude_globs").map(to_globs)?,
+            skip_hidden: row.get("skip_hidden")?,
+            min_file_size_bytes: row.get("min_file_size_bytes")?,
+            min_dimension: row.get("min_dimension")?,
+        };
+
+        Ok(LibraryRoot {
+            library_root_id,
+            path,
+            is_enabled,
+            rules,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database;
+    use crate::media::{MediaType, ScannedFile};
+    use crate::photo;
+    use crate::video;
+    use crate::visual;
+
+    fn scanned(path: PathBuf) -> ScannedFile {
+        ScannedFile {
+            path,
+            media_type: MediaType::Photo,
+            fs_created_at: None,
+            fs_modified_at: None,
+            fs_file_size_bytes: 1024,
+            fingerprint: None,
+        }
+    }
+
+    fn count(con: &rusqlite::Connection, table: &str) -> i64 {
+        con.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn add_rejects_overlapping_roots() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let mut repo = Repository::open(con).unwrap();
+
+        repo.add(Path::new("/pictures")).unwrap();
+
+        assert!(repo.add(Path::new("/pictures")).is_err());
+        assert!(repo.add(Path::new("/pictures/holiday")).is_err());
+        assert!(repo.add(Path::new("/")).is_err());
+        assert!(repo.add(Path::new("pictures")).is_err());
+
+        // Only whole path components overlap.
+        repo.add(Path::new("/pictures2")).unwrap();
+
+        let paths: Vec<PathBuf> = repo.all().unwrap().into_iter().map(|x| x.path).collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("/pictures"), PathBuf::from("/pictures2")]
+        );
+    }
+
+    #[test]
+    fn add_default_if_empty() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let mut repo = Repository::open(con).unwrap();
+
+        repo.add_default_if_empty(Path::new("/pictures")).unwrap();
+        repo.add_default_if_empty(Path::new("/other")).unwrap();
+
+        let roots = repo.all().unwrap();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].library_root_id, LibraryRootId::new(1));
+        assert_eq!(roots[0].path, PathBuf::from("/pictures"));
+        assert!(roots[0].is_enabled);
+    }
+
+    #[test]
+    fn add_default_if_empty_with_existing_root() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let mut repo = Repository::open(con).unwrap();
+
+        repo.add(Path::new("/pictures")).unwrap();
+        repo.add_default_if_empty(Path::new("/other")).unwrap();
+
+        let paths: Vec<PathBuf> = repo.all().unwrap().into_iter().map(|x| x.path).collect();
+        assert_eq!(paths, vec![PathBuf::from("/pictures")]);
+    }
+
+    #[test]
+    fn disabled_and_removed_roots_are_hidden() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+        let mut repo = Repository::open(con.clone()).unwrap();
+
+        let first_id = repo.add(Path::new("/pictures")).unwrap();
+        let second_id = repo.add(Path::new("/camera")).unwrap();
+
+        let mut photo_repo =
+            photo::Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+
+        for root in repo.all().unwrap() {
+            photo_repo
+                .add_all(&root, &[scanned(root.path.join("party.jpg"))])
+                .unwrap();
+        }
+
+        let visual_repo = visual::Repository::open(Path::new("/cache"), con.clone()).unwrap();
+        assert_eq!(visual_repo.all().unwrap().len(), 2);
+
+        repo.set_enabled(first_id, false).unwrap();
+
+        let roots = repo.all().unwrap();
+        assert!(!roots[0].is_enabled);
+        assert!(roots[1].is_enabled);
+
+        let visuals = visual_repo.all().unwrap();
+        assert_eq!(visuals.len(), 1);
+        assert_eq!(
+            visuals[0].picture_path,
+            Some(PathBuf::from("/camera/party.jpg"))
+        );
+
+        // Pictures under a disabled root are kept.
+        assert_eq!(photo_repo.all().unwrap().len(), 1);
+        assert_eq!(count(&con.lock().unwrap(), "pictures"), 2);
+
+        repo.set_enabled(first_id, true).unwrap();
+        assert_eq!(visual_repo.all().unwrap().len(), 2);
+
+        repo.remove(second_id).unwrap();
+
+        let roots = repo.all().unwrap();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].library_root_id, first_id);
+
+        let visuals = visual_repo.all().unwrap();
+        assert_eq!(visuals.len(), 1);
+        assert_eq!(
+            visuals[0].picture_path,
+            Some(PathBuf::from("/pictures/party.jpg"))
+        );
+    }
+
+    #[test]
+    fn migration_keeps_items_scanned_before_library_roots() {
+        let mut con = rusqlite::Connection::open_in_memory().unwrap();
+
+        database::migrations::runner()
+            .set_target(refinery::Target::Version(17))
+            .run(&mut con)
+            .unwrap();
+
+        let picture_path = path_encoding::to_base64(Path::new("party.jpg"));
+        let video_path = path_encoding::to_base64(Path::new("party.mp4"));
+        let link_path = path_encoding::to_base64(Path::new("party"));
+
+        con.execute(
+            "INSERT INTO pictures (
+                picture_id, picture_path_b64, picture_path_lossy,
+                link_path_b64, link_path_lossy, thumbnail_path
+            ) VALUES (1, ?1, 'party.jpg', ?2, 'party', 'photo_thumbnails/1.png')",
+            params![picture_path, link_path],
+        )
+        .unwrap();
+
+        con.execute(
+            "INSERT INTO videos (
+                video_id, video_path_b64, video_path_lossy,
+                link_path_b64, link_path_lossy, thumbnail_path
+            ) VALUES (1, ?1, 'party.mp4', ?2, 'party', 'video_thumbnails/1.png')",
+            params![video_path, link_path],
+        )
+        .unwrap();
+
+        con.execute(
+            "INSERT INTO motion_photos (picture_id, video_path) VALUES (1, 'motion_photos/1.mp4')",
+            [],
+        )
+        .unwrap();
+
+        con.execute(
+            "INSERT INTO pictures_geo (picture_id, longitude, latitude) VALUES (1, 151.2, -33.9)",
+            [],
+        )
+        .unwrap();
+
+        con.execute(
+            "INSERT INTO pictures_face_scans (picture_id, is_broken, scan_ts, face_count)
+            VALUES (1, FALSE, '2024-01-01 00:00:00', 1)",
+            [],
+        )
+        .unwrap();
+
+        con.execute(
+            "INSERT INTO pictures_faces (
+                face_id, model_name, picture_id,
+                thumbnail_path, bounds_path,
+                bounds_x, bounds_y, bounds_width, bounds_height,
+                right_eye_x, right_eye_y, left_eye_x, left_eye_y, nose_x, nose_y,
+                right_mouth_corner_x, right_mouth_corner_y,
+                left_mouth_corner_x, left_mouth_corner_y,
+                confidence
+            ) VALUES (
+                1, 'test', 1, 'faces/1_thumb.png', 'faces/1_bounds.png',
+                0, 0, 10, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0.9
+            )",
+            [],
+        )
+        .unwrap();
+
+        database::migrations::runner().run(&mut con).unwrap();
+
+        // Rebuilding the pictures table must not cascade deletes to its child tables.
+        for table in [
+            "pictures",
+            "videos",
+            "motion_photos",
+            "pictures_geo",
+            "pictures_face_scans",
+            "pictures_faces",
+        ] {
+            assert_eq!(count(&con, table), 1, "{table}");
+        }
+
+        let con = Arc::new(Mutex::new(con));
+
+        let mut repo = Repository::open(con.clone()).unwrap();
+        repo.add_default_if_empty(Path::new("/pictures")).unwrap();
+
+        let photo_repo =
+            photo::Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+        let pics = photo_repo.all().unwrap();
+        assert_eq!(pics.len(), 1);
+        assert_eq!(pics[0].path, PathBuf::from("/pictures/party.jpg"));
+
+        let video_repo =
+            video::Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+        let vids = video_repo.all().unwrap();
+        assert_eq!(vids.len(), 1);
+        assert_eq!(vids[0].path, PathBuf::from("/pictures/party.mp4"));
+
+        let visual_repo = visual::Repository::open(Path::new("/cache"), con.clone()).unwrap();
+        let visuals = visual_repo.all().unwrap();
+        assert_eq!(visuals.len(), 2);
+        assert!(visuals
+            .iter()
+            .any(|x| x.picture_path == Some(PathBuf::from("/pictures/party.jpg"))));
+        assert!(visuals
+            .iter()
+            .any(|x| x.video_path == Some(PathBuf::from("/pictures/party.mp4"))));
+    }
+}
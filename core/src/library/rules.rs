@@ -0,0 +1,257 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use image::ImageReader;
+use std::fs;
+use std::path::Path;
+
+/// Marker files that exclude the directory containing them, and everything below it,
+/// from the library. `.nomedia` is the Android convention for folders of app data.
+pub const IGNORE_MARKERS: [&str; 2] = [".nomedia", ".fotemaignore"];
+
+/// Rules deciding which files under a library root are scanned into the library.
+///
+/// Globs are matched against paths relative to the library root. A glob without a
+/// `/` matches a file or directory name at any depth. `*` matches within a single
+/// path component, `**` matches across components, and `?` matches one character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanRules {
+    /// If not empty, only files matching one of these globs are scanned.
+    pub include_globs: Vec<String>,
+
+    /// Files and directories matching any of these globs are not scanned.
+    pub exclude_globs: Vec<String>,
+
+    /// Don't scan directories whose names start with a dot.
+    pub skip_hidden: bool,
+
+    /// Don't scan files smaller than this. Zero for no minimum.
+    pub min_file_size_bytes: u64,
+
+    /// Don't scan pictures with a width or height smaller than this. Zero for no minimum.
+    pub min_dimension: u32,
+}
+
+impl Default for ScanRules {
+    fn default() -> Self {
+        Self {
+            include_globs: Vec::new(),
+            exclude_globs: Vec::new(),
+            skip_hidden: true,
+            min_file_size_bytes: 0,
+            min_dimension: 0,
+        }
+    }
+}
+
+impl ScanRules {
+    /// Should the directory, and everything below it, be skipped?
+    /// Only the directory itself is checked, not its parents.
+    /// The root is never skipped, as it was explicitly chosen by the user.
+    pub fn is_ignored_dir(&self, root: &Path, dir: &Path) -> bool {
+        let Some(relative) = relative_path(root, dir) else {
+            return false;
+        };
+
+        if self.skip_hidden && dir.file_name().is_some_and(is_hidden) {
+            return true;
+        }
+
+        if IGNORE_MARKERS.iter().any(|x| dir.join(x).exists()) {
+            return true;
+        }
+
+        self.exclude_globs
+            .iter()
+            .any(|x| glob_matches(x, &relative))
+    }
+
+    /// Should the file be skipped?
+    /// Only the file itself is checked, not the directories containing it.
+    pub fn is_ignored_file(&self, root: &Path, path: &Path) -> bool {
+        let Some(relative) = relative_path(root, path) else {
+            return false;
+        };
+
+        if !self.include_globs.is_empty()
+            && !self
+                .include_globs
+                .iter()
+                .any(|x| glob_matches(x, &relative))
+        {
+            return true;
+        }
+
+        if self
+            .exclude_globs
+            .iter()
+            .any(|x| glob_matches(x, &relative))
+        {
+            return true;
+        }
+
+        self.min_file_size_bytes > 0
+            && fs::metadata(path).is_ok_and(|x| x.len() < self.min_file_size_bytes)
+    }
+
+    /// Is the picture smaller than the minimum dimension?
+    /// Only the image header is read. Pictures whose dimensions can't be read cheaply,
+    /// such as camera RAW files, are never too small.
+    pub fn is_too_small(&self, path: &Path) -> bool {
+        if self.min_dimension == 0 {
+            return false;
+        }
+
+        ImageReader::open(path)
+            .ok()
+            .and_then(|x| x.into_dimensions().ok())
+            .is_some_and(|(width, height)| width.min(height) < self.min_dimension)
+    }
+
+    /// Is a file, or directory, under the root excluded by these rules?
+    /// Unlike `is_ignored_dir` and `is_ignored_file`, all parent directories
+    /// between the root and the path are checked too.
+    pub fn is_excluded(&self, root: &Path, path: &Path) -> bool {
+        if !path.starts_with(root) {
+            return false;
+        }
+
+        let is_dir_ignored = path
+            .ancestors()
+            .skip(1)
+            .take_while(|x| *x != root)
+            .any(|x| self.is_ignored_dir(root, x));
+
+        if is_dir_ignored {
+            return true;
+        }
+
+        if path.is_dir() {
+            self.is_ignored_dir(root, path)
+        } else {
+            self.is_ignored_file(root, path)
+        }
+    }
+}
+
+fn is_hidden(name: &std::ffi::OsStr) -> bool {
+    name.to_string_lossy().starts_with('.')
+}
+
+/// Path relative to the root, with `/` separators. None for the root itself.
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let components: Vec<_> = relative
+        .components()
+        .map(|x| x.as_os_str().to_string_lossy())
+        .collect();
+
+    Some(components.join("/")).filter(|x| !x.is_empty())
+}
+
+/// Does the glob match the whole of the relative path?
+fn glob_matches(glob: &str, path: &str) -> bool {
+    let glob = glob.trim().trim_matches('/');
+    if glob.is_empty() {
+        return false;
+    }
+
+    let glob: Vec<char> = if glob.contains('/') {
+        glob.chars().collect()
+    } else {
+        "**/".chars().chain(glob.chars()).collect()
+    };
+
+    let path: Vec<char> = path.chars().collect();
+
+    matches(&glob, &path)
+}
+
+fn matches(glob: &[char], path: &[char]) -> bool {
+    match glob {
+        [] => path.is_empty(),
+        // Zero or more whole directories.
+        ['*', '*', '/', rest @ ..] => {
+            matches(rest, path)
+                || (0..path.len()).any(|i| path[i] == '/' && matches(rest, &path[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=path.len()).any(|i| matches(rest, &path[i..])),
+        ['*', rest @ ..] => (0..=path.len())
+            .take_while(|i| *i == 0 || path[i - 1] != '/')
+            .any(|i| matches(rest, &path[i..])),
+        ['?', rest @ ..] => path.first().is_some_and(|x| *x != '/') && matches(rest, &path[1..]),
+        [c, rest @ ..] => path.first() == Some(c) && matches(rest, &path[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*.png", "a.png"));
+        assert!(glob_matches("*.png", "Screenshots/a.png"));
+        assert!(!glob_matches("*.png", "a.jpg"));
+
+        assert!(glob_matches("Screenshots", "Screenshots"));
+        assert!(glob_matches("Screenshots", "Phone/Screenshots"));
+        assert!(!glob_matches("Screenshots", "Phone/Screenshots/a.png"));
+
+        assert!(glob_matches("DCIM/**", "DCIM/Camera/a.jpg"));
+        assert!(!glob_matches("DCIM/**", "Other/DCIM/a.jpg"));
+        assert!(glob_matches("**/cache/*", "a/b/cache/c.jpg"));
+        assert!(!glob_matches("DCIM/*", "DCIM/Camera/a.jpg"));
+
+        assert!(glob_matches("IMG_????.jpg", "IMG_0001.jpg"));
+        assert!(!glob_matches("IMG_????.jpg", "IMG_01.jpg"));
+
+        assert!(!glob_matches("", "a.jpg"));
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        fs::create_dir_all(root.join(".cache")).unwrap();
+        fs::create_dir_all(root.join("WhatsApp/Media")).unwrap();
+        fs::create_dir_all(root.join("Screenshots")).unwrap();
+        fs::create_dir_all(root.join("Camera")).unwrap();
+        fs::write(root.join("WhatsApp/.nomedia"), b"").unwrap();
+
+        let file = |x: &str| -> PathBuf {
+            let path = root.join(x);
+            fs::write(&path, b"not really a picture").unwrap();
+            path
+        };
+
+        let hidden = file(".cache/a.jpg");
+        let marked = file("WhatsApp/Media/a.jpg");
+        let screenshot = file("Screenshots/a.png");
+        let camera = file("Camera/a.jpg");
+
+        let mut rules = ScanRules {
+            exclude_globs: vec![String::from("Screenshots")],
+            ..Default::default()
+        };
+
+        assert!(rules.is_excluded(root, &hidden));
+        assert!(rules.is_excluded(root, &marked));
+        assert!(rules.is_excluded(root, &screenshot));
+        assert!(!rules.is_excluded(root, &camera));
+        assert!(!rules.is_excluded(root, root));
+
+        rules.skip_hidden = false;
+        assert!(!rules.is_excluded(root, &hidden));
+
+        rules.include_globs = vec![String::from("*.png")];
+        assert!(rules.is_excluded(root, &camera));
+
+        rules.include_globs.clear();
+        rules.min_file_size_bytes = 1024;
+        assert!(rules.is_excluded(root, &camera));
+    }
+}
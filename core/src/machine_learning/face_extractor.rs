@@ -2,7 +2,10 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use crate::edit;
 use crate::photo::model::PictureId;
+use crate::photo::raw;
+use crate::photo::xmp::Region;
 use anyhow::*;
 
 use super::nms::Nms;
@@ -21,6 +24,13 @@ use image::DynamicImage;
 use itertools::*;
 use tracing::{debug, error, info};
 
+/// Model name of faces imported from face regions rather than detected.
+pub const IMPORTED_MODEL_NAME: &str = "xmp";
+
+/// Confidence of imported faces. Lower than most detected faces so that a detected
+/// face, with real landmarks, is preferred as the face of a person for face recognition.
+const IMPORTED_CONFIDENCE: f32 = 0.5;
+
 #[derive(Debug, Clone)]
 pub struct Rect {
     pub x: f32,
@@ -221,13 +231,7 @@ impl FaceExtractor {
             faces
         );
 
-        let base_path = {
-            // Create a directory per 1000 thumbnails
-            let partition = (picture_id.id() / 1000) as i32;
-            let partition = format!("{:0>4}", partition);
-            let file_name = format!("{}", picture_id);
-            self.base_path.join(partition).join(file_name)
-        };
+        let base_path = self.picture_base_path(picture_id);
 
         faces.sort_by_key(|x| x.1.clone());
 
@@ -244,63 +248,6 @@ impl FaceExtractor {
         let faces = faces_flat_grouped
             .into_iter()
             .map(|(model_name, index, f)| {
-                if !base_path.exists() {
-                    let _ = std::fs::create_dir_all(&base_path);
-                }
-
-                // Extract face and save to thumbnail.
-                // The bounding box is pretty tight, so make it a bit bigger.
-                // Also, make the box a square.
-
-                let longest: f32 = if f.rect.width < f.rect.height {
-                    f.rect.width
-                } else {
-                    f.rect.height
-                };
-
-                let mut longest = longest * 1.6;
-                let mut half_longest = longest / 2.0;
-
-                let (centre_x, centre_y) = Self::centre(&f);
-
-                // Normalize thumbnail to be a square.
-                if (original_image.width() as f32) < centre_x + half_longest {
-                    half_longest = original_image.width() as f32 - centre_x;
-                    longest = half_longest * 2.0;
-                }
-                if (original_image.height() as f32) < centre_y + half_longest {
-                    half_longest = original_image.height() as f32 - centre_y;
-                    longest = half_longest * 2.0;
-                }
-
-                if centre_x < half_longest {
-                    half_longest = centre_x;
-                    longest = half_longest * 2.0;
-                }
-
-                if centre_y < half_longest {
-                    half_longest = centre_y;
-                    longest = half_longest * 2.0;
-                }
-
-                // Don't panic when x or y would be < zero
-                let mut x = centre_x - half_longest;
-                if x < 0.0 {
-                    x = 0.0;
-                }
-                let mut y = centre_y - half_longest;
-                if y < 0.0 {
-                    y = 0.0;
-                }
-
-                // FIXME use fast_image_resize instead of image-rs
-                let thumbnail =
-                    original_image.crop_imm(x as u32, y as u32, longest as u32, longest as u32);
-                let thumbnail = thumbnail.thumbnail(200, 200);
-                let thumbnail_path =
-                    base_path.join(format!("{}_{}_thumbnail.png", index, model_name));
-                let _ = thumbnail.save(&thumbnail_path);
-
                 let bounds = Rect {
                     x: f.rect.x,
                     y: f.rect.y,
@@ -308,16 +255,14 @@ impl FaceExtractor {
                     height: f.rect.height,
                 };
 
-                let bounds_img = original_image.crop_imm(
-                    bounds.x as u32,
-                    bounds.y as u32,
-                    bounds.width as u32,
-                    bounds.height as u32,
+                let (thumbnail_path, bounds_path) = Self::save_face_images(
+                    &original_image,
+                    &base_path,
+                    &format!("{}_{}", index, model_name),
+                    &bounds,
+                    Self::centre(&f),
                 );
 
-                let bounds_path = base_path.join(format!("{}_{}_original.png", index, model_name));
-                let _ = bounds_img.save(&bounds_path);
-
                 Face {
                     thumbnail_path,
                     bounds_path,
@@ -334,6 +279,171 @@ impl FaceExtractor {
         Ok(faces)
     }
 
+    /// Saves faces from regions found by other photo management tools, such as
+    /// the MWG regions written by digiKam. Regions have no facial landmarks, so
+    /// landmarks are estimated from typical proportions of a face so that the faces
+    /// can be used for face recognition.
+    pub async fn import_faces(
+        &self,
+        picture_id: &PictureId,
+        picture_path: &Path,
+        regions: &[Region],
+    ) -> Result<Vec<(Face, String)>> {
+        info!(
+            "Importing {} face regions for {:?}",
+            regions.len(),
+            picture_path
+        );
+
+        let original_image = Self::open_image(picture_path).await?;
+        let base_path = self.picture_base_path(picture_id);
+
+        let image_width = original_image.width() as f32;
+        let image_height = original_image.height() as f32;
+
+        let faces = regions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, region)| {
+                let name = region.name.clone()?;
+
+                // Regions are centre-based fractions of the picture.
+                let width = (region.width as f32 * image_width).min(image_width);
+                let height = (region.height as f32 * image_height).min(image_height);
+                let x =
+                    (region.x as f32 * image_width - width / 2.0).clamp(0.0, image_width - width);
+                let y = (region.y as f32 * image_height - height / 2.0)
+                    .clamp(0.0, image_height - height);
+
+                if width < 1.0 || height < 1.0 {
+                    return None;
+                }
+
+                let bounds = Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                };
+
+                let landmark = |fx: f32, fy: f32| (x + width * fx, y + height * fy);
+                let landmarks = vec![
+                    landmark(0.3, 0.4),
+                    landmark(0.7, 0.4),
+                    landmark(0.5, 0.6),
+                    landmark(0.35, 0.8),
+                    landmark(0.65, 0.8),
+                ];
+
+                let (thumbnail_path, bounds_path) = Self::save_face_images(
+                    &original_image,
+                    &base_path,
+                    &format!("{}_{}", index, IMPORTED_MODEL_NAME),
+                    &bounds,
+                    // Midpoint between the eyes, like detected faces.
+                    landmark(0.5, 0.4),
+                );
+
+                let face = Face {
+                    thumbnail_path,
+                    bounds_path,
+                    bounds,
+                    confidence: IMPORTED_CONFIDENCE,
+                    landmarks: Some(landmarks),
+                    model_name: IMPORTED_MODEL_NAME.into(),
+                };
+
+                Some((face, name))
+            })
+            .collect();
+
+        Ok(faces)
+    }
+
+    /// Directory for the face images of a picture.
+    fn picture_base_path(&self, picture_id: &PictureId) -> PathBuf {
+        // Create a directory per 1000 thumbnails
+        let partition = (picture_id.id() / 1000) as i32;
+        let partition = format!("{:0>4}", partition);
+        let file_name = format!("{}", picture_id);
+        self.base_path.join(partition).join(file_name)
+    }
+
+    /// Saves a square thumbnail around a face and an image cropped to the exact bounds
+    /// of the face. Returns the thumbnail path and the bounds path.
+    fn save_face_images(
+        original_image: &DynamicImage,
+        base_path: &Path,
+        file_prefix: &str,
+        bounds: &Rect,
+        (centre_x, centre_y): (f32, f32),
+    ) -> (PathBuf, PathBuf) {
+        if !base_path.exists() {
+            let _ = std::fs::create_dir_all(base_path);
+        }
+
+        // Extract face and save to thumbnail.
+        // The bounding box is pretty tight, so make it a bit bigger.
+        // Also, make the box a square.
+
+        let longest: f32 = if bounds.width < bounds.height {
+            bounds.width
+        } else {
+            bounds.height
+        };
+
+        let mut longest = longest * 1.6;
+        let mut half_longest = longest / 2.0;
+
+        // Normalize thumbnail to be a square.
+        if (original_image.width() as f32) < centre_x + half_longest {
+            half_longest = original_image.width() as f32 - centre_x;
+            longest = half_longest * 2.0;
+        }
+        if (original_image.height() as f32) < centre_y + half_longest {
+            half_longest = original_image.height() as f32 - centre_y;
+            longest = half_longest * 2.0;
+        }
+
+        if centre_x < half_longest {
+            half_longest = centre_x;
+            longest = half_longest * 2.0;
+        }
+
+        if centre_y < half_longest {
+            half_longest = centre_y;
+            longest = half_longest * 2.0;
+        }
+
+        // Don't panic when x or y would be < zero
+        let mut x = centre_x - half_longest;
+        if x < 0.0 {
+            x = 0.0;
+        }
+        let mut y = centre_y - half_longest;
+        if y < 0.0 {
+            y = 0.0;
+        }
+
+        // FIXME use fast_image_resize instead of image-rs
+        let thumbnail = original_image.crop_imm(x as u32, y as u32, longest as u32, longest as u32);
+        let thumbnail = thumbnail.thumbnail(200, 200);
+        let thumbnail_path = base_path.join(format!("{}_thumbnail.png", file_prefix));
+        let _ = thumbnail.save(&thumbnail_path);
+
+        let bounds_img = original_image.crop_imm(
+            bounds.x as u32,
+            bounds.y as u32,
+            bounds.width as u32,
+            bounds.height as u32,
+        );
+
+        let bounds_path = base_path.join(format!("{}_original.png", file_prefix));
+        let _ = bounds_img.save(&bounds_path);
+
+        (thumbnail_path, bounds_path)
+    }
+
     /// Computes the centre of a face.
     fn centre(f: &DetectedFace) -> (f32, f32) {
         if let Some(ref landmarks) = f.landmarks {
@@ -350,6 +460,11 @@ impl FaceExtractor {
     }
 
     async fn open_image(source_path: &Path) -> Result<DynamicImage> {
+        // Glycin can't decode camera RAW files, so find faces in the preview.
+        if raw::is_raw(source_path) {
+            return edit::render::load(source_path).await;
+        }
+
         let file = gio::File::for_path(source_path);
 
         let mut loader = glycin::Loader::new(file);
@@ -0,0 +1,260 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::photo::raw;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Number of bytes read from the start of a file to recognise its format.
+const MAGIC_LEN: usize = 32;
+
+/// Kind of file found when scanning the library.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MediaType {
+    /// A still picture, such as a JPEG or HEIC.
+    Photo,
+
+    /// A video, such as an MP4 or QuickTime movie.
+    Video,
+
+    /// A camera RAW picture.
+    Raw,
+
+    /// A picture format that is usually animated, such as a GIF.
+    /// PNG and WebP files are photos even if animated, as their animation is only
+    /// known once the file is read.
+    Animated,
+
+    /// An XMP sidecar holding metadata for a picture or video.
+    Sidecar,
+}
+
+impl MediaType {
+    /// Is the file stored in the pictures table?
+    pub fn is_picture(&self) -> bool {
+        matches!(
+            self,
+            MediaType::Photo | MediaType::Raw | MediaType::Animated
+        )
+    }
+
+    /// Is the file stored in the videos table?
+    pub fn is_video(&self) -> bool {
+        *self == MediaType::Video
+    }
+}
+
+/// A file format that can be scanned into the library.
+#[derive(Debug, Clone)]
+pub struct MediaFormat {
+    pub media_type: MediaType,
+
+    /// Lower case file name extensions, without the dot.
+    pub extensions: Vec<&'static str>,
+
+    /// Recognises the format from the first bytes of a file, for files with a missing
+    /// or unknown extension. None if the format can't be recognised from its contents.
+    pub magic: Option<fn(&[u8]) -> bool>,
+}
+
+/// Registry of the file formats that are scanned into the library.
+/// Formats are recognised by extension, and then by contents for files whose
+/// extension isn't registered.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    formats: Vec<MediaFormat>,
+}
+
+impl Default for Registry {
+    /// Registry of all formats Fotema supports.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+
+        registry.register(MediaType::Photo, &["avif"], Some(is_avif));
+        registry.register(MediaType::Photo, &["heic", "heif"], Some(is_heic));
+        registry.register(MediaType::Photo, &["jpeg", "jpg"], Some(is_jpeg));
+        registry.register(MediaType::Photo, &["jxl"], Some(is_jxl));
+        registry.register(MediaType::Photo, &["png"], Some(is_png));
+        registry.register(MediaType::Photo, &["tiff", "tif"], Some(is_tiff));
+        registry.register(MediaType::Photo, &["webp"], Some(is_webp));
+
+        registry.register(MediaType::Animated, &["gif"], Some(is_gif));
+        registry.register(MediaType::Animated, &["apng"], None);
+
+        // Most RAW formats are TIFF files, so are only recognised by extension.
+        registry.register(MediaType::Raw, &raw::RAW_SUFFIXES, None);
+
+        registry.register(MediaType::Video, &["mov"], Some(is_quicktime));
+        registry.register(MediaType::Video, &["mp4", "m4v"], Some(is_mp4));
+
+        registry.register(MediaType::Sidecar, &["xmp"], None);
+
+        registry
+    }
+}
+
+impl Registry {
+    /// Registry without any formats.
+    pub fn empty() -> Self {
+        Self {
+            formats: Vec::new(),
+        }
+    }
+
+    /// Adds a format. Formats registered first take precedence when recognising
+    /// a file by its contents.
+    pub fn register(
+        &mut self,
+        media_type: MediaType,
+        extensions: &[&'static str],
+        magic: Option<fn(&[u8]) -> bool>,
+    ) {
+        self.formats.push(MediaFormat {
+            media_type,
+            extensions: extensions.to_vec(),
+            magic,
+        });
+    }
+
+    /// All registered formats.
+    pub fn formats(&self) -> &[MediaFormat] {
+        &self.formats
+    }
+
+    /// Media type of a file, or None if the file isn't a registered format.
+    /// Only reads the file if its extension isn't registered.
+    pub fn classify(&self, path: &Path) -> Option<MediaType> {
+        self.by_extension(path).or_else(|| {
+            let mut header = Vec::with_capacity(MAGIC_LEN);
+            fs::File::open(path)
+                .ok()?
+                .take(MAGIC_LEN as u64)
+                .read_to_end(&mut header)
+                .ok()?;
+            self.by_magic(&header)
+        })
+    }
+
+    /// Media type for a file name extension.
+    pub fn by_extension(&self, path: &Path) -> Option<MediaType> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        self.formats
+            .iter()
+            .find(|x| x.extensions.contains(&ext.as_str()))
+            .map(|x| x.media_type)
+    }
+
+    /// Media type for the first bytes of a file.
+    pub fn by_magic(&self, header: &[u8]) -> Option<MediaType> {
+        self.formats
+            .iter()
+            .find(|x| x.magic.is_some_and(|magic| magic(header)))
+            .map(|x| x.media_type)
+    }
+}
+
+fn is_jpeg(header: &[u8]) -> bool {
+    header.starts_with(&[0xFF, 0xD8, 0xFF])
+}
+
+fn is_png(header: &[u8]) -> bool {
+    header.starts_with(b"\x89PNG\r\n\x1a\n")
+}
+
+fn is_gif(header: &[u8]) -> bool {
+    header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a")
+}
+
+fn is_webp(header: &[u8]) -> bool {
+    header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WEBP".as_slice())
+}
+
+fn is_tiff(header: &[u8]) -> bool {
+    header.starts_with(b"II*\0") || header.starts_with(b"MM\0*")
+}
+
+fn is_jxl(header: &[u8]) -> bool {
+    // Bare code stream, or ISO BMFF container.
+    header.starts_with(&[0xFF, 0x0A]) || header.starts_with(b"\0\0\0\x0CJXL \r\n\x87\n")
+}
+
+/// Major brand of an ISO base media file, which is the format of HEIC, AVIF, and MP4 files.
+fn brand(header: &[u8]) -> Option<&[u8]> {
+    if header.get(4..8) == Some(b"ftyp".as_slice()) {
+        header.get(8..12)
+    } else {
+        None
+    }
+}
+
+fn is_avif(header: &[u8]) -> bool {
+    brand(header).is_some_and(|x| x == b"avif" || x == b"avis")
+}
+
+fn is_heic(header: &[u8]) -> bool {
+    brand(header).is_some_and(|x| {
+        [b"heic", b"heix", b"heim", b"heis", b"mif1", b"msf1"]
+            .iter()
+            .any(|brand| x == brand.as_slice())
+    })
+}
+
+fn is_quicktime(header: &[u8]) -> bool {
+    brand(header).is_some_and(|x| x == b"qt  ")
+}
+
+fn is_mp4(header: &[u8]) -> bool {
+    brand(header).is_some_and(|x| {
+        [b"isom", b"iso2", b"mp41", b"mp42", b"avc1", b"M4V "]
+            .iter()
+            .any(|brand| x == brand.as_slice())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_by_extension() {
+        let registry = Registry::default();
+
+        let media_type = |x: &str| registry.by_extension(&PathBuf::from(x));
+
+        assert_eq!(media_type("a.JPG"), Some(MediaType::Photo));
+        assert_eq!(media_type("a.gif"), Some(MediaType::Animated));
+        assert_eq!(media_type("a.nef"), Some(MediaType::Raw));
+        assert_eq!(media_type("a.mov"), Some(MediaType::Video));
+        assert_eq!(media_type("a.xmp"), Some(MediaType::Sidecar));
+        assert_eq!(media_type("a.txt"), None);
+        assert_eq!(media_type("a"), None);
+    }
+
+    #[test]
+    fn test_by_magic() {
+        let registry = Registry::default();
+
+        assert_eq!(
+            registry.by_magic(&[0xFF, 0xD8, 0xFF, 0xE1]),
+            Some(MediaType::Photo)
+        );
+        assert_eq!(registry.by_magic(b"GIF89a"), Some(MediaType::Animated));
+        assert_eq!(
+            registry.by_magic(b"\0\0\0\x18ftypheic\0\0\0\0"),
+            Some(MediaType::Photo)
+        );
+        assert_eq!(
+            registry.by_magic(b"\0\0\0\x14ftypqt  \0\0\0\0"),
+            Some(MediaType::Video)
+        );
+        assert_eq!(
+            registry.by_magic(b"\0\0\0\x18ftypisom\0\0\0\0"),
+            Some(MediaType::Video)
+        );
+        assert_eq!(registry.by_magic(b"hello world"), None);
+        assert_eq!(registry.by_magic(&[]), None);
+    }
+}
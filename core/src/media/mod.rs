@@ -0,0 +1,15 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod media_type;
+pub mod model;
+pub mod scanner;
+pub mod store;
+
+pub use media_type::MediaFormat;
+pub use media_type::MediaType;
+pub use media_type::Registry;
+pub use model::ScannedFile;
+pub use scanner::Scanner;
+pub use store::ScannedFileStore;
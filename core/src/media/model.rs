@@ -0,0 +1,36 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::media_type::MediaType;
+use crate::library::FileStamp;
+use chrono::{DateTime, Utc};
+use std::path::PathBuf;
+
+/// A picture, video, or sidecar on the local file system that has been scanned.
+#[derive(Debug, Clone)]
+pub struct ScannedFile {
+    /// Full path to file.
+    pub path: PathBuf,
+
+    /// Kind of file, as recognised by the media type registry.
+    pub media_type: MediaType,
+
+    pub fs_created_at: Option<DateTime<Utc>>,
+
+    pub fs_modified_at: Option<DateTime<Utc>>,
+
+    pub fs_file_size_bytes: u64,
+
+    /// Fingerprint of file contents. Only computed when needed because it requires reading the file.
+    pub fingerprint: Option<String>,
+}
+
+impl ScannedFile {
+    pub fn file_stamp(&self) -> FileStamp {
+        FileStamp {
+            fs_modified_at: self.fs_modified_at,
+            fs_file_size_bytes: Some(self.fs_file_size_bytes),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::media_type::{MediaType, Registry};
+use super::model::ScannedFile;
+use crate::library::ScanRules;
+use anyhow::*;
+use chrono::prelude::*;
+use rayon::prelude::*;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing::error;
+use walkdir::WalkDir;
+
+/// Scans a file system for pictures, videos, and sidecars in one pass.
+///
+/// Directories are walked on the calling thread, which is cheap, and then files
+/// are classified and inspected in parallel.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    /// File system path to scan.
+    scan_base: PathBuf,
+
+    /// Files under the scan base to skip.
+    rules: ScanRules,
+
+    /// Formats to scan.
+    registry: Arc<Registry>,
+}
+
+impl Scanner {
+    pub fn build(scan_base: &Path) -> Result<Self> {
+        fs::create_dir_all(scan_base)?;
+        let scan_base = PathBuf::from(scan_base);
+        Ok(Self {
+            scan_base,
+            rules: ScanRules::default(),
+            registry: Arc::new(Registry::default()),
+        })
+    }
+
+    /// Replaces the default rules for which files to skip.
+    pub fn with_rules(mut self, rules: ScanRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Replaces the default registry of formats to scan.
+    pub fn with_registry(mut self, registry: Registry) -> Self {
+        self.registry = Arc::new(registry);
+        self
+    }
+
+    /// Scans all files in the base directory.
+    pub fn scan_all(&self) -> Result<Vec<ScannedFile>> {
+        let paths = self.walk(&self.scan_base);
+        Ok(self.scan_files(paths))
+    }
+
+    /// Scans files at the given paths. Directories are scanned recursively and
+    /// paths that no longer exist are skipped.
+    pub fn scan_paths(&self, paths: &[PathBuf]) -> Result<Vec<ScannedFile>> {
+        let mut files: Vec<PathBuf> = paths
+            .iter()
+            .filter(|x| x.exists())
+            .filter(|x| !self.rules.is_excluded(&self.scan_base, x))
+            .flat_map(|x| self.walk(x))
+            .collect();
+
+        // A directory and a file within it might both have been given.
+        files.sort();
+        files.dedup();
+
+        Ok(self.scan_files(files))
+    }
+
+    /// Scans a single file, which must be a registered format.
+    pub fn scan_one(&self, path: &Path) -> Result<ScannedFile> {
+        let Some(media_type) = self.registry.classify(path) else {
+            bail!("Unsupported file format: {:?}", path);
+        };
+
+        Self::inspect(path, media_type)
+    }
+
+    /// Paths of all files under a base path, skipping ignored directories.
+    fn walk(&self, base: &Path) -> Vec<PathBuf> {
+        WalkDir::new(base)
+            .into_iter()
+            .filter_entry(|x| {
+                // skip ignored directories and everything below them
+                !x.file_type().is_dir() || !self.rules.is_ignored_dir(&self.scan_base, x.path())
+            })
+            .inspect(|x| {
+                let _ = x
+                    .as_ref()
+                    .inspect_err(|e| error!("Failed walking: {:?}", e));
+            })
+            .flatten() // skip files we failed to read
+            .filter(|x| x.path().is_file()) // only process files
+            .map(|x| x.into_path())
+            .collect()
+    }
+
+    /// Classifies and inspects files in parallel, skipping unsupported and excluded files.
+    /// Files are returned in order of path.
+    fn scan_files(&self, paths: Vec<PathBuf>) -> Vec<ScannedFile> {
+        let mut files: Vec<ScannedFile> = paths
+            .into_par_iter()
+            .filter_map(|path| {
+                let media_type = self.registry.classify(&path)?;
+
+                if self.rules.is_ignored_file(&self.scan_base, &path) {
+                    return None;
+                }
+
+                // Dimensions can only be cheaply read from pictures.
+                if media_type.is_picture() && self.rules.is_too_small(&path) {
+                    return None;
+                }
+
+                Self::inspect(&path, media_type)
+                    .inspect_err(|e| error!("Failed scanning: {:?}", e))
+                    .ok()
+            })
+            .collect();
+
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+    }
+
+    /// Reads file system metadata of a file.
+    fn inspect(path: &Path, media_type: MediaType) -> Result<ScannedFile> {
+        let file = fs::File::open(path)?;
+
+        let metadata = file.metadata()?;
+
+        let fs_created_at = metadata.created().map(Into::<DateTime<Utc>>::into).ok();
+
+        let fs_modified_at = metadata.modified().map(Into::<DateTime<Utc>>::into).ok();
+
+        let fs_file_size_bytes = metadata.len();
+
+        let scanned = ScannedFile {
+            path: PathBuf::from(path),
+            media_type,
+            fs_created_at,
+            fs_modified_at,
+            fs_file_size_bytes,
+            fingerprint: None,
+        };
+
+        Ok(scanned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        fs::create_dir_all(root.join("Camera")).unwrap();
+        fs::create_dir_all(root.join(".thumbnails")).unwrap();
+        fs::write(root.join("Camera/a.jpg"), b"").unwrap();
+        fs::write(root.join("Camera/a.xmp"), b"").unwrap();
+        fs::write(root.join("Camera/b.MOV"), b"").unwrap();
+        fs::write(root.join("Camera/c"), b"GIF89a").unwrap();
+        fs::write(root.join("Camera/notes.txt"), b"").unwrap();
+        fs::write(root.join(".thumbnails/d.png"), b"").unwrap();
+
+        let scanner = Scanner::build(root).unwrap();
+        let scanned: Vec<(PathBuf, MediaType)> = scanner
+            .scan_all()
+            .unwrap()
+            .into_iter()
+            .map(|x| (x.path, x.media_type))
+            .collect();
+
+        assert_eq!(
+            scanned,
+            vec![
+                (root.join("Camera/a.jpg"), MediaType::Photo),
+                (root.join("Camera/a.xmp"), MediaType::Sidecar),
+                (root.join("Camera/b.MOV"), MediaType::Video),
+                (root.join("Camera/c"), MediaType::Animated),
+            ]
+        );
+    }
+}
@@ -0,0 +1,35 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::ScannedFile;
+use crate::library::{FileStamp, LibraryRoot};
+use anyhow::*;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// A repository that scanned files are stored in, such as the pictures or videos
+/// repository, so that one scan task can store files of every media type.
+pub trait ScannedFileStore {
+    /// Database ID of a stored file.
+    type Id: Copy + Display;
+
+    /// Gets file system modification times and sizes of all files under a library root,
+    /// keyed by full path.
+    fn find_file_stamps(&self, root: &LibraryRoot) -> Result<HashMap<PathBuf, FileStamp>>;
+
+    /// Finds a stored file with the given fingerprint whose file is missing, which implies
+    /// the file has been moved or renamed.
+    fn find_moved(&self, fingerprint: &str) -> Result<Option<Self::Id>>;
+
+    /// Updates the path of a file that has moved, keeping everything derived from it.
+    fn relocate(&mut self, id: Self::Id, root: &LibraryRoot, file: &ScannedFile) -> Result<()>;
+
+    /// Adds scanned files under a library root.
+    fn add_all(&mut self, root: &LibraryRoot, files: &[ScannedFile]) -> Result<()>;
+
+    /// Invalidates derived state of files that have changed on the file system.
+    /// Returns paths of derived files that should be deleted.
+    fn invalidate(&mut self, root: &LibraryRoot, files: &[ScannedFile]) -> Result<Vec<PathBuf>>;
+}
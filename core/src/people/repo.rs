@@ -21,13 +21,14 @@ use std::path::{Path, PathBuf};
 use std::result::Result::Ok;
 use std::sync::{Arc, Mutex};
 
+/// Minimum overlap of a confirmed face with a newly detected face for the
+/// new face to be linked to the same person.
+const RELINK_MIN_OVERLAP: f32 = 0.5;
+
 /// Repository of people data.
 /// Repository is backed by a Sqlite database.
 #[derive(Debug, Clone)]
 pub struct Repository {
-    /// Base path to picture library on file system
-    library_base_path: PathBuf,
-
     /// Base path for photo thumbnails and motion photo videos
     data_dir_base_path: PathBuf,
 
@@ -38,19 +39,12 @@ pub struct Repository {
 impl Repository {
     /// Builds a Repository and creates operational tables.
     pub fn open(
-        library_base_path: &Path,
         data_dir_base_path: &Path,
         con: Arc<Mutex<rusqlite::Connection>>,
     ) -> Result<Repository> {
-        if !library_base_path.is_dir() {
-            bail!("{:?} is not a directory", library_base_path);
-        }
-
-        let library_base_path = PathBuf::from(library_base_path);
         let data_dir_base_path = PathBuf::from(data_dir_base_path);
 
         let repo = Repository {
-            library_base_path,
             data_dir_base_path,
             con,
         };
@@ -65,6 +59,7 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
+                    library_roots.root_path_b64,
                     pictures.picture_path_b64,
                     COALESCE(
                         pictures.exif_created_ts,
@@ -74,9 +69,11 @@ impl Repository {
                         CURRENT_TIMESTAMP
                     ) AS ordering_ts
                 FROM pictures
+                INNER JOIN library_roots ON library_roots.root_id = pictures.library_root_id
                 LEFT OUTER JOIN pictures_face_scans USING (picture_id)
                 WHERE pictures_face_scans.picture_id IS NULL
                 AND COALESCE(pictures.is_broken, FALSE) IS FALSE
+                AND library_roots.is_enabled IS TRUE
                 ORDER BY ordering_ts DESC",
         )?;
 
@@ -93,8 +90,10 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
+                    library_roots.root_path_b64,
                     pictures.picture_path_b64
                 FROM pictures
+                INNER JOIN library_roots ON library_roots.root_id = pictures.library_root_id
                 WHERE pictures.picture_id = ?1",
         )?;
 
@@ -460,76 +459,78 @@ impl Repository {
 
             scan_insert_stmt.execute(params![picture_id.id(), false, faces.len(),])?;
 
-            let mut face_insert_stmt = tx.prepare_cached(
-                "INSERT INTO pictures_faces (
-                    picture_id,
-                    thumbnail_path,
-                    bounds_path,
-
-                    model_name,
+            Self::insert_faces(&tx, &self.data_dir_base_path, picture_id, faces)?;
+        }
 
-                    bounds_x,
-                    bounds_y,
-                    bounds_width,
-                    bounds_height,
+        tx.commit()?;
+        Ok(())
+    }
 
-                    right_eye_x,
-                    right_eye_y,
+    /// Adds faces imported from face regions written by other photo management tools.
+    /// Faces are confirmed as the named people, and people that don't exist yet are added.
+    /// The picture is marked as scanned so that faces won't be detected in it.
+    pub fn add_imported_faces(
+        &mut self,
+        picture_id: &PictureId,
+        faces: &[(face_extractor::Face, String)],
+    ) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
 
-                    left_eye_x,
-                    left_eye_y,
+        {
+            let mut scan_insert_stmt = tx.prepare_cached(
+                "INSERT INTO pictures_face_scans (
+                    picture_id,
+                    is_broken,
+                    face_count,
+                    scan_ts
+                ) VALUES (
+                    ?1, FALSE, ?2, CURRENT_TIMESTAMP
+                ) ON CONFLICT (picture_id) DO UPDATE SET
+                    is_broken = FALSE,
+                    face_count = ?2,
+                    scan_ts = CURRENT_TIMESTAMP
+                ",
+            )?;
 
-                    nose_x,
-                    nose_y,
+            scan_insert_stmt.execute(params![picture_id.id(), faces.len(),])?;
 
-                    right_mouth_corner_x,
-                    right_mouth_corner_y,
+            let (detected, names): (Vec<_>, Vec<_>) = faces.iter().cloned().unzip();
+            let face_ids =
+                Self::insert_faces(&tx, &self.data_dir_base_path, picture_id, &detected)?;
 
-                    left_mouth_corner_x,
-                    left_mouth_corner_y,
+            let mut find_person_stmt = tx.prepare_cached(
+                "SELECT person_id FROM people WHERE name = ?1 ORDER BY person_id LIMIT 1",
+            )?;
 
-                    confidence,
+            let mut insert_person_stmt = tx.prepare_cached(
+                "INSERT INTO people (name, thumbnail_path)
+                SELECT ?2 AS name, thumbnail_path FROM pictures_faces WHERE face_id = ?1",
+            )?;
 
-                    is_ignored
-                ) VALUES (
-                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
-                    ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, false
-                )
-                ",
+            let mut update_face_stmt = tx.prepare_cached(
+                "UPDATE pictures_faces
+                SET
+                    person_id = ?2,
+                    is_confirmed = TRUE
+                WHERE face_id = ?1",
             )?;
 
-            for face in faces {
-                // convert to relative path before saving to database
-                let thumbnail_path = face.thumbnail_path.strip_prefix(&self.data_dir_base_path)?;
-                let bounds_path = face.bounds_path.strip_prefix(&self.data_dir_base_path)?;
-
-                let right_eye = face.right_eye();
-                let left_eye = face.left_eye();
-                let nose = face.nose();
-                let right_mouth_corner = face.right_mouth_corner();
-                let left_mouth_corner = face.left_mouth_corner();
-
-                face_insert_stmt.execute(params![
-                    picture_id.id(),
-                    thumbnail_path.to_string_lossy(),
-                    bounds_path.to_string_lossy(),
-                    face.model_name,
-                    face.bounds.x,
-                    face.bounds.y,
-                    face.bounds.width,
-                    face.bounds.height,
-                    right_eye.map(|x| x.0),
-                    right_eye.map(|x| x.1),
-                    left_eye.map(|x| x.0),
-                    left_eye.map(|x| x.1),
-                    nose.map(|x| x.0),
-                    nose.map(|x| x.1),
-                    right_mouth_corner.map(|x| x.0),
-                    right_mouth_corner.map(|x| x.1),
-                    left_mouth_corner.map(|x| x.0),
-                    left_mouth_corner.map(|x| x.1),
-                    face.confidence
-                ])?;
+            for (face_id, name) in face_ids.into_iter().zip(names) {
+                let person_id: Option<i64> = find_person_stmt
+                    .query_map([&name], |row| row.get(0))?
+                    .flatten()
+                    .next();
+
+                let person_id = match person_id {
+                    Some(person_id) => person_id,
+                    None => {
+                        insert_person_stmt.execute(params![face_id.id(), name])?;
+                        tx.last_insert_rowid()
+                    }
+                };
+
+                update_face_stmt.execute(params![face_id.id(), person_id])?;
             }
         }
 
@@ -537,6 +538,209 @@ impl Repository {
         Ok(())
     }
 
+    /// Inserts faces for a picture, returning the ID of each face.
+    /// Faces confirmed as a person before the picture was invalidated are re-linked to the
+    /// new face that best overlaps them. A confirmed face that no new face overlaps is kept,
+    /// unless a new face has been saved over its images.
+    fn insert_faces(
+        tx: &rusqlite::Transaction<'_>,
+        data_dir_base_path: &Path,
+        picture_id: &PictureId,
+        faces: &[face_extractor::Face],
+    ) -> Result<Vec<FaceId>> {
+        let mut face_ids = Vec::with_capacity(faces.len());
+
+        let mut confirmed_stmt = tx.prepare_cached(
+            "SELECT
+                face_id,
+                person_id,
+                thumbnail_path,
+                bounds_path,
+                bounds_x,
+                bounds_y,
+                bounds_width,
+                bounds_height
+            FROM pictures_faces
+            WHERE picture_id = ?1
+            AND is_confirmed IS TRUE
+            AND person_id IS NOT NULL",
+        )?;
+
+        let confirmed: Vec<(FaceId, PersonId, String, String, Rect)> = confirmed_stmt
+            .query_map([picture_id.id()], |row| {
+                let bounds = Rect {
+                    x: row.get("bounds_x")?,
+                    y: row.get("bounds_y")?,
+                    width: row.get("bounds_width")?,
+                    height: row.get("bounds_height")?,
+                };
+                Ok((
+                    row.get("face_id").map(FaceId::new)?,
+                    row.get("person_id").map(PersonId::new)?,
+                    row.get("thumbnail_path")?,
+                    row.get("bounds_path")?,
+                    bounds,
+                ))
+            })?
+            .flatten()
+            .collect();
+
+        // Person, and the old face thumbnail, for each new face matched by overlap
+        // with a confirmed face.
+        let mut relinked: Vec<Option<(PersonId, String)>> = vec![None; faces.len()];
+
+        let mut delete_face_stmt =
+            tx.prepare_cached("DELETE FROM pictures_faces WHERE face_id = ?1")?;
+
+        for (face_id, person_id, thumbnail_path, bounds_path, bounds) in confirmed {
+            let best = faces
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| relinked[*index].is_none())
+                .map(|(index, face)| {
+                    let new_bounds = Rect {
+                        x: face.bounds.x,
+                        y: face.bounds.y,
+                        width: face.bounds.width,
+                        height: face.bounds.height,
+                    };
+                    (index, overlap(&bounds, &new_bounds))
+                })
+                .filter(|(_, overlap)| *overlap >= RELINK_MIN_OVERLAP)
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(index, _)| index);
+
+            let is_overwritten = faces.iter().any(|face| {
+                face.thumbnail_path == data_dir_base_path.join(&thumbnail_path)
+                    || face.bounds_path == data_dir_base_path.join(&bounds_path)
+            });
+
+            if let Some(index) = best {
+                relinked[index] = Some((person_id, thumbnail_path));
+                delete_face_stmt.execute([face_id.id()])?;
+            } else if is_overwritten {
+                delete_face_stmt.execute([face_id.id()])?;
+            }
+        }
+
+        let mut face_insert_stmt = tx.prepare_cached(
+            "INSERT INTO pictures_faces (
+                picture_id,
+                thumbnail_path,
+                bounds_path,
+
+                model_name,
+
+                bounds_x,
+                bounds_y,
+                bounds_width,
+                bounds_height,
+
+                right_eye_x,
+                right_eye_y,
+
+                left_eye_x,
+                left_eye_y,
+
+                nose_x,
+                nose_y,
+
+                right_mouth_corner_x,
+                right_mouth_corner_y,
+
+                left_mouth_corner_x,
+                left_mouth_corner_y,
+
+                confidence,
+
+                is_ignored
+            ) VALUES (
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
+                ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, false
+            )
+            ",
+        )?;
+
+        for face in faces {
+            // convert to relative path before saving to database
+            let thumbnail_path = face.thumbnail_path.strip_prefix(data_dir_base_path)?;
+            let bounds_path = face.bounds_path.strip_prefix(data_dir_base_path)?;
+
+            let right_eye = face.right_eye();
+            let left_eye = face.left_eye();
+            let nose = face.nose();
+            let right_mouth_corner = face.right_mouth_corner();
+            let left_mouth_corner = face.left_mouth_corner();
+
+            face_insert_stmt.execute(params![
+                picture_id.id(),
+                thumbnail_path.to_string_lossy(),
+                bounds_path.to_string_lossy(),
+                face.model_name,
+                face.bounds.x,
+                face.bounds.y,
+                face.bounds.width,
+                face.bounds.height,
+                right_eye.map(|x| x.0),
+                right_eye.map(|x| x.1),
+                left_eye.map(|x| x.0),
+                left_eye.map(|x| x.1),
+                nose.map(|x| x.0),
+                nose.map(|x| x.1),
+                right_mouth_corner.map(|x| x.0),
+                right_mouth_corner.map(|x| x.1),
+                left_mouth_corner.map(|x| x.0),
+                left_mouth_corner.map(|x| x.1),
+                face.confidence
+            ])?;
+
+            face_ids.push(FaceId::new(tx.last_insert_rowid()));
+        }
+
+        let mut relink_stmt = tx.prepare_cached(
+            "UPDATE pictures_faces
+            SET
+                person_id = ?2,
+                is_confirmed = TRUE
+            WHERE face_id = ?1",
+        )?;
+
+        // A person thumbnail is a face thumbnail, which might now be of a different face,
+        // so people using an old face thumbnail are moved to the new face thumbnail.
+        // Thumbnail paths are unique, so they are moved via a temporary path in case two
+        // people swap thumbnails.
+        let mut unlink_thumbnail_stmt = tx.prepare_cached(
+            "UPDATE people
+            SET thumbnail_path = 'relink/' || person_id
+            WHERE person_id = ?1
+            AND thumbnail_path = ?2",
+        )?;
+
+        let mut relink_thumbnail_stmt = tx.prepare_cached(
+            "UPDATE people
+            SET thumbnail_path = (SELECT thumbnail_path FROM pictures_faces WHERE face_id = ?2)
+            WHERE person_id = ?1
+            AND thumbnail_path = 'relink/' || person_id",
+        )?;
+
+        let relinked: Vec<(FaceId, PersonId, String)> = face_ids
+            .iter()
+            .zip(relinked)
+            .filter_map(|(face_id, x)| x.map(|(person_id, path)| (*face_id, person_id, path)))
+            .collect();
+
+        for (face_id, person_id, old_thumbnail_path) in &relinked {
+            relink_stmt.execute(params![face_id.id(), person_id.id()])?;
+            unlink_thumbnail_stmt.execute(params![person_id.id(), old_thumbnail_path])?;
+        }
+
+        for (face_id, person_id, _) in &relinked {
+            relink_thumbnail_stmt.execute(params![person_id.id(), face_id.id()])?;
+        }
+
+        Ok(face_ids)
+    }
+
     /// Add a new named person derived from a face.
     pub fn add_person(&mut self, face_id: FaceId, name: &str) -> Result<()> {
         let mut con = self.con.lock().unwrap();
@@ -592,6 +796,52 @@ impl Repository {
         Ok(())
     }
 
+    /// User is marking a whole picture as a person, such as when assigning a person
+    /// to many pictures at once. Only possible if the picture has exactly one face
+    /// that isn't ignored or already a person.
+    /// Returns the face that was marked, so the change can be undone with `mark_not_person`.
+    pub fn assign_person(
+        &mut self,
+        picture_id: PictureId,
+        person_id: PersonId,
+    ) -> Result<Option<FaceId>> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let face_id = {
+            let mut stmt = tx.prepare_cached(
+                "SELECT face_id
+                FROM pictures_faces
+                WHERE picture_id = ?1
+                AND is_ignored = FALSE
+                AND person_id IS NULL",
+            )?;
+
+            let face_ids: Vec<FaceId> = stmt
+                .query_map([picture_id.id()], |row| row.get(0).map(FaceId::new))?
+                .flatten()
+                .collect();
+
+            let [face_id] = face_ids[..] else {
+                return Ok(None);
+            };
+
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures_faces
+                SET
+                    person_id = ?2,
+                    is_confirmed = TRUE
+                WHERE face_id = ?1",
+            )?;
+
+            stmt.execute(params![face_id.id(), person_id.id(),])?;
+            face_id
+        };
+
+        tx.commit()?;
+        Ok(Some(face_id))
+    }
+
     /// Face recognition is automatically marking a face as a person
     pub fn mark_as_person_unconfirmed(
         &mut self,
@@ -689,10 +939,14 @@ impl Repository {
     fn to_picture_id_path_tuple(&self, row: &Row<'_>) -> rusqlite::Result<(PictureId, PathBuf)> {
         let picture_id = row.get("picture_id").map(PictureId::new)?;
 
+        let root_path: String = row.get("root_path_b64")?;
+        let root_path =
+            path_encoding::from_base64(&root_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
+
         let picture_path: String = row.get("picture_path_b64")?;
         let picture_path =
             path_encoding::from_base64(&picture_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
-        let picture_path = self.library_base_path.join(picture_path);
+        let picture_path = root_path.join(picture_path);
 
         std::result::Result::Ok((picture_id, picture_path))
     }
@@ -824,3 +1078,53 @@ impl Repository {
         std::result::Result::Ok(person)
     }
 }
+
+/// Intersection over union of two face bounds. 1.0 for the same bounds and 0.0 for
+/// bounds that don't touch.
+fn overlap(a: &Rect, b: &Rect) -> f32 {
+    let width = (a.x + a.width).min(b.x + b.width) - a.x.max(b.x);
+    let height = (a.y + a.height).min(b.y + b.height) - a.y.max(b.y);
+
+    if width <= 0.0 || height <= 0.0 {
+        return 0.0;
+    }
+
+    let intersection = width * height;
+    let union = a.width * a.height + b.width * b.height - intersection;
+    intersection / union
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn overlap_of_same_bounds() {
+        let a = rect(10.0, 20.0, 100.0, 100.0);
+        assert_eq!(overlap(&a, &a), 1.0);
+    }
+
+    #[test]
+    fn overlap_of_disjoint_bounds() {
+        let a = rect(0.0, 0.0, 10.0, 10.0);
+        let b = rect(20.0, 0.0, 10.0, 10.0);
+        assert_eq!(overlap(&a, &b), 0.0);
+    }
+
+    #[test]
+    fn overlap_of_shifted_bounds() {
+        // Half of each face overlaps the other.
+        let a = rect(0.0, 0.0, 100.0, 100.0);
+        let b = rect(50.0, 0.0, 100.0, 100.0);
+        assert_eq!(overlap(&a, &b), 1.0 / 3.0);
+    }
+}
@@ -0,0 +1,238 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::*;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Formats that can be animated.
+enum Format {
+    Gif,
+    WebP,
+    Png,
+}
+
+impl Format {
+    /// Enough of the file to identify the format.
+    const HEADER_LEN: u64 = 12;
+
+    fn from_header(header: &[u8]) -> Option<Self> {
+        if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+            Some(Format::Gif)
+        } else if header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WEBP".as_slice()) {
+            Some(Format::WebP)
+        } else if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Format::Png)
+        } else {
+            None
+        }
+    }
+}
+
+/// Number of frames of an animated GIF, WebP, or PNG. None if the picture isn't animated.
+/// Only the file structure is read, so no frames are decoded.
+pub fn frame_count(path: &Path) -> Result<Option<u32>> {
+    let mut file = File::open(path)?;
+
+    // Most pictures can't be animated, so only read the rest of the file if it might be.
+    let mut data = Vec::new();
+    file.by_ref()
+        .take(Format::HEADER_LEN)
+        .read_to_end(&mut data)?;
+
+    let Some(format) = Format::from_header(&data) else {
+        return Ok(None);
+    };
+
+    file.read_to_end(&mut data)?;
+
+    let count = match format {
+        Format::Gif => gif_frame_count(&data),
+        Format::WebP => webp_frame_count(&data),
+        Format::Png => png_frame_count(&data),
+    };
+
+    Ok(count.filter(|x| *x > 1))
+}
+
+/// Counts image descriptors, skipping over color tables, extensions, and image data.
+fn gif_frame_count(data: &[u8]) -> Option<u32> {
+    let color_table_size = |packed: u8| {
+        if packed & 0x80 != 0 {
+            3 * (1 << ((packed & 0x07) + 1))
+        } else {
+            0
+        }
+    };
+
+    // Sub-blocks of data end with an empty block.
+    let skip_sub_blocks = |mut i: usize| -> Option<usize> {
+        loop {
+            let size = *data.get(i)? as usize;
+            i += 1 + size;
+            if size == 0 {
+                return Some(i);
+            }
+        }
+    };
+
+    // Header and logical screen descriptor.
+    let mut i = 13 + color_table_size(*data.get(10)?);
+    let mut frames = 0;
+
+    loop {
+        match *data.get(i)? {
+            // Extension, such as graphic control or application extension.
+            0x21 => i = skip_sub_blocks(i + 2)?,
+            // Image descriptor, then LZW minimum code size and image data.
+            0x2C => {
+                frames += 1;
+                i += 10 + color_table_size(*data.get(i + 9)?);
+                i = skip_sub_blocks(i + 1)?;
+            }
+            // Trailer, or anything unexpected after the last frame.
+            _ => return Some(frames),
+        }
+    }
+}
+
+/// Counts animation frame chunks of an extended WebP with the animation flag set.
+fn webp_frame_count(data: &[u8]) -> Option<u32> {
+    let mut i = 12;
+    let mut is_animated = false;
+    let mut frames = 0;
+
+    while let Some(header) = data.get(i..i + 8) {
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        match &header[0..4] {
+            b"VP8X" => is_animated = data.get(i + 8).is_some_and(|flags| flags & 0x02 != 0),
+            b"ANMF" => frames += 1,
+            _ => {}
+        }
+
+        // Chunks are padded to an even size.
+        i += 8 + size + (size & 1);
+    }
+
+    Some(frames).filter(|_| is_animated)
+}
+
+/// Reads the frame count from the animation control chunk of an APNG,
+/// which must come before the image data.
+fn png_frame_count(data: &[u8]) -> Option<u32> {
+    let mut i = 8;
+
+    while let Some(header) = data.get(i..i + 8) {
+        let size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        match &header[4..8] {
+            b"acTL" => {
+                let frames = data.get(i + 8..i + 12)?;
+                return Some(u32::from_be_bytes([
+                    frames[0], frames[1], frames[2], frames[3],
+                ]));
+            }
+            b"IDAT" | b"IEND" => return None,
+            _ => {}
+        }
+
+        // Length, type, data, and CRC.
+        i += 12 + size;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gif(frames: usize) -> Vec<u8> {
+        let mut data = b"GIF89a".to_vec();
+        // 1x1 with a global color table of two colors.
+        data.extend_from_slice(&[1, 0, 1, 0, 0x80, 0, 0]);
+        data.extend_from_slice(&[0, 0, 0, 255, 255, 255]);
+        // Netscape looping extension.
+        data.extend_from_slice(&[0x21, 0xFF, 11]);
+        data.extend_from_slice(b"NETSCAPE2.0");
+        data.extend_from_slice(&[3, 1, 0, 0, 0]);
+        for _ in 0..frames {
+            // Graphic control extension.
+            data.extend_from_slice(&[0x21, 0xF9, 4, 0, 10, 0, 0, 0]);
+            // Image descriptor, then image data.
+            data.extend_from_slice(&[0x2C, 0, 0, 0, 0, 1, 0, 1, 0, 0]);
+            data.extend_from_slice(&[2, 2, 0x44, 0x01, 0]);
+        }
+        data.push(0x3B);
+        data
+    }
+
+    fn chunk(fourcc: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut data = fourcc.to_vec();
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(payload);
+        if payload.len() % 2 == 1 {
+            data.push(0);
+        }
+        data
+    }
+
+    #[test]
+    fn test_frame_count() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let animated = dir.path().join("animated.gif");
+        std::fs::write(&animated, gif(3)).unwrap();
+        assert_eq!(frame_count(&animated).unwrap(), Some(3));
+
+        let still = dir.path().join("still.gif");
+        std::fs::write(&still, gif(1)).unwrap();
+        assert_eq!(frame_count(&still).unwrap(), None);
+
+        // Shorter than the header of any format.
+        let jpeg = dir.path().join("tiny.jpg");
+        std::fs::write(&jpeg, b"\xFF\xD8\xFF").unwrap();
+        assert_eq!(frame_count(&jpeg).unwrap(), None);
+    }
+
+    #[test]
+    fn test_gif_frame_count() {
+        assert_eq!(gif_frame_count(&gif(1)), Some(1));
+        assert_eq!(gif_frame_count(&gif(3)), Some(3));
+    }
+
+    #[test]
+    fn test_webp_frame_count() {
+        let mut data = b"RIFF\0\0\0\0WEBP".to_vec();
+        data.extend(chunk(b"VP8X", &[0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0]));
+        data.extend(chunk(b"ANIM", &[0; 6]));
+        data.extend(chunk(b"ANMF", &[0; 17]));
+        data.extend(chunk(b"ANMF", &[0; 16]));
+        assert_eq!(webp_frame_count(&data), Some(2));
+
+        let mut still = b"RIFF\0\0\0\0WEBP".to_vec();
+        still.extend(chunk(b"VP8X", &[0; 10]));
+        still.extend(chunk(b"VP8 ", &[0; 10]));
+        assert_eq!(webp_frame_count(&still), None);
+    }
+
+    #[test]
+    fn test_png_frame_count() {
+        let mut data = b"\x89PNG\r\n\x1a\n".to_vec();
+        data.extend_from_slice(&[0, 0, 0, 13]);
+        data.extend_from_slice(b"IHDR");
+        data.extend_from_slice(&[0; 13 + 4]);
+        data.extend_from_slice(&[0, 0, 0, 8]);
+        data.extend_from_slice(b"acTL");
+        data.extend_from_slice(&[0, 0, 0, 12, 0, 0, 0, 0]);
+        data.extend_from_slice(&[0; 4]);
+        assert_eq!(png_frame_count(&data), Some(12));
+
+        let mut still = b"\x89PNG\r\n\x1a\n".to_vec();
+        still.extend_from_slice(&[0, 0, 0, 0]);
+        still.extend_from_slice(b"IDAT");
+        still.extend_from_slice(&[0; 4]);
+        assert_eq!(png_frame_count(&still), None);
+    }
+}
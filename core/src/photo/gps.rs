@@ -85,6 +85,24 @@ impl GPSCoord {
             sec.map(exif::Rational::to_f64),
         ))
     }
+
+    /// Parse an XMP GPSCoordinate, which is either "DDD,MM,SSk" or "DDD,MM.mmk"
+    /// where k is the reference direction, such as 'N'.
+    fn position_xmp(value: &str) -> Option<(u8, f64, Option<f64>, Option<f64>)> {
+        let value = value.trim();
+        let reference = value.bytes().last()?.to_ascii_uppercase();
+        if !reference.is_ascii_alphabetic() {
+            return None;
+        }
+
+        let mut parts = value[..value.len() - 1].split(',').map(|x| x.trim().parse::<f64>());
+
+        let deg = parts.next()?.ok()?;
+        let min = parts.next().transpose().ok()?;
+        let sec = parts.next().transpose().ok()?;
+
+        Some((reference, deg, min, sec))
+    }
 }
 
 impl GPSLocation {
@@ -136,6 +154,52 @@ impl GPSLocation {
         })
     }
 
+    /// Location from XMP exif:GPSLatitude and exif:GPSLongitude values.
+    pub fn for_xmp(latitude: &str, longitude: &str) -> Option<Self> {
+        let (lat_ref, lat_deg, lat_min, lat_sec) = GPSCoord::position_xmp(latitude)?;
+        let lat_sign = GPSCoord::latitude_sign(&[vec![lat_ref]])?;
+
+        let (lon_ref, lon_deg, lon_min, lon_sec) = GPSCoord::position_xmp(longitude)?;
+        let lon_sign = GPSCoord::longitude_sign(&[vec![lon_ref]])?;
+
+        let latitude = GPSCoord {
+            sing: lat_sign,
+            deg: lat_deg,
+            min: lat_min,
+            sec: lat_sec,
+        };
+
+        let longitude = GPSCoord {
+            sing: lon_sign,
+            deg: lon_deg,
+            min: lon_min,
+            sec: lon_sec,
+        };
+
+        latitude.to_f64_safe()?;
+        longitude.to_f64_safe()?;
+
+        Some(Self {
+            latitude,
+            longitude,
+        })
+    }
+
+    /// Location from decimal degrees, such as those saved in the database.
+    pub fn for_decimal(latitude: f64, longitude: f64) -> Self {
+        let coord = |value: f64| GPSCoord {
+            sing: value >= 0.0,
+            deg: value.abs(),
+            min: None,
+            sec: None,
+        };
+
+        Self {
+            latitude: coord(latitude),
+            longitude: coord(longitude),
+        }
+    }
+
     pub fn to_cell_index(&self, resolution: Resolution) -> Result<CellIndex> {
         let ll = LatLng::new(self.latitude.to_f64(), self.longitude.to_f64())?;
         Ok(ll.to_cell(resolution))
@@ -2,9 +2,15 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use super::animation;
 use super::gps::GPSLocation;
 use super::model::Orientation;
+use super::raw;
+use super::timezone;
+use super::xmp;
 use super::Metadata;
+use crate::camera::CameraSettings;
+use crate::import::takeout;
 use anyhow::*;
 use chrono::prelude::*;
 use chrono::{DateTime, FixedOffset};
@@ -14,6 +20,7 @@ use std::fs;
 use std::io::BufReader;
 use std::path::Path;
 use std::result::Result::Ok;
+use tracing::warn;
 
 /// This version number should be incremented each time metadata scanning has
 /// a bug fix or feature addition that changes the metadata produced.
@@ -25,23 +32,38 @@ use std::result::Result::Ok;
 /// 1. Orientation.
 /// 2. Motion photos.
 /// 3. GPS coordinates.
-pub const VERSION: u32 = 3;
+/// 4. Camera make and model.
+/// 5. XMP rating.
+/// 6. XMP sidecar date, location, and keywords.
+/// 7. Google Photos Takeout date, location, and description.
+/// 8. Time zone from GPS coordinates when there is no EXIF offset.
+/// 9. Camera RAW files.
+/// 10. Frame count of animated pictures.
+/// 11. Lens, exposure settings, flash, and dimensions.
+/// 12. Offset from UTC saved separately from the local time.
+/// 13. Unknown offset for Google Photos Takeout times without a location.
+pub const VERSION: u32 = 13;
 
 /// Extract EXIF metadata from file
 pub fn from_path(path: &Path) -> Result<Metadata> {
-    let file = fs::File::open(path)?;
-    let file = &mut BufReader::new(file);
-    let exif_data = {
-        match exif::Reader::new().read_from_container(file) {
-            Ok(exif) => exif,
-            Err(_) => {
-                // Assume this error is when there is no EXIF data.
-                return Ok(Metadata::default());
-            }
-        }
+    let exif_data = if raw::is_raw(path) {
+        raw::read_exif(path)
+    } else {
+        let file = fs::File::open(path)?;
+        let file = &mut BufReader::new(file);
+        exif::Reader::new()
+            .read_from_container(file)
+            .map_err(Error::from)
     };
 
-    let mut metadata = from_exif(exif_data)?;
+    let mut metadata = match exif_data {
+        Ok(exif_data) => from_exif(exif_data)?,
+        Err(_) => {
+            // Assume this error is when there is no EXIF data.
+            // A sidecar might still have metadata.
+            Metadata::default()
+        }
+    };
 
     // FIXME what is a better way of doing this?
     //
@@ -64,9 +86,67 @@ pub fn from_path(path: &Path) -> Result<Metadata> {
         metadata.orientation = None;
     }
 
+    let takeout = takeout::read_sidecar(path).unwrap_or_else(|e| {
+        warn!("Failed reading Takeout sidecar for {:?}: {}", path, e);
+        None
+    });
+
+    if let Some(takeout) = takeout {
+        // Google Photos strips some EXIF data, so fill in what is missing.
+        metadata.location = metadata.location.or_else(|| {
+            takeout
+                .location
+                .map(|(latitude, longitude)| GPSLocation::for_decimal(latitude, longitude))
+        });
+        metadata.created_at = metadata.created_at.or_else(|| {
+            takeout
+                .taken_at
+                .map(|x| to_local_time(x, metadata.location.as_ref()))
+        });
+        metadata.description = takeout.description;
+    }
+
+    metadata.frame_count = animation::frame_count(path).unwrap_or_else(|e| {
+        warn!("Failed reading frame count for {:?}: {}", path, e);
+        None
+    });
+
+    metadata.rating = xmp::read_rating(path, true).unwrap_or_else(|e| {
+        warn!("Failed reading XMP rating for {:?}: {}", path, e);
+        None
+    });
+
+    let sidecar = xmp::read_sidecar(path).unwrap_or_else(|e| {
+        warn!("Failed reading XMP sidecar for {:?}: {}", path, e);
+        None
+    });
+
+    if let Some(sidecar) = sidecar {
+        // Sidecars are what photo management tools update, so they take precedence
+        // over the EXIF data in the file.
+        metadata.created_at = sidecar.created_at.or(metadata.created_at);
+        metadata.location = sidecar.location.or(metadata.location);
+        metadata.keywords = sidecar.subjects;
+    }
+
     Ok(metadata)
 }
 
+/// Converts a UTC time to the local time where a picture was taken, kept as if it were UTC
+/// like EXIF timestamps. Without a location the time zone is unknown, so the UTC time is
+/// kept with a zero offset, which is saved as an unknown offset.
+fn to_local_time(
+    date_time: DateTime<Utc>,
+    location: Option<&GPSLocation>,
+) -> DateTime<FixedOffset> {
+    let Some(offset) = location.and_then(|x| timezone::offset_for_utc(x, &date_time.naive_utc()))
+    else {
+        return date_time.fixed_offset();
+    };
+
+    offset.from_utc_datetime(&date_time.with_timezone(&offset).naive_local())
+}
+
 /// Extract EXIF metadata from raw buffer
 pub fn from_raw(data: Vec<u8>) -> Result<Metadata> {
     let exif_data = {
@@ -83,10 +163,11 @@ pub fn from_raw(data: Vec<u8>) -> Result<Metadata> {
 }
 
 fn from_exif(exif_data: Exif) -> Result<Metadata> {
+    /// Parses a local date and time, and the offset from UTC if there is one.
     fn parse_date_time(
         date_time_field: Option<&exif::Field>,
         time_offset_field: Option<&exif::Field>,
-    ) -> Option<DateTime<FixedOffset>> {
+    ) -> Option<(NaiveDateTime, Option<FixedOffset>)> {
         let date_time_field = date_time_field?;
 
         let mut date_time = match date_time_field.value {
@@ -105,8 +186,10 @@ fn from_exif(exif_data: Exif) -> Result<Metadata> {
             };
         }
 
-        let offset = date_time.offset.unwrap_or(0); // offset in minutes
-        let offset = FixedOffset::east_opt((offset as i32) * 60)?;
+        // offset in minutes
+        let offset = date_time
+            .offset
+            .and_then(|x| FixedOffset::east_opt((x as i32) * 60));
 
         let date = NaiveDate::from_ymd_opt(
             date_time.year.into(),
@@ -120,23 +203,54 @@ fn from_exif(exif_data: Exif) -> Result<Metadata> {
             date_time.second.into(),
         )?;
 
-        let naive_date_time = date.and_time(time);
-        Some(offset.from_utc_datetime(&naive_date_time))
+        Some((date.and_time(time), offset))
     }
 
+    let location = gps_location(&exif_data);
+
+    // Many cameras don't save an offset, so find the time zone from where the picture was taken.
+    // Without either, assume UTC. The local time is kept as if it were UTC in either case.
+    let with_offset = |(local, offset): (NaiveDateTime, Option<FixedOffset>)| {
+        let offset = offset
+            .or_else(|| {
+                location
+                    .as_ref()
+                    .and_then(|x| timezone::offset_for_local(x, &local))
+            })
+            .unwrap_or(Utc.fix());
+        offset.from_utc_datetime(&local)
+    };
+
     let created_at = parse_date_time(
         exif_data.get_field(exif::Tag::DateTimeOriginal, exif::In::PRIMARY),
         exif_data.get_field(exif::Tag::OffsetTimeOriginal, exif::In::PRIMARY),
-    );
+    )
+    .map(with_offset);
 
     let modified_at = parse_date_time(
         exif_data.get_field(exif::Tag::DateTime, exif::In::PRIMARY),
         exif_data.get_field(exif::Tag::OffsetTime, exif::In::PRIMARY),
-    );
-
-    let lens_model = exif_data
-        .get_field(exif::Tag::LensModel, exif::In::PRIMARY)
-        .map(|e| e.display_value().to_string());
+    )
+    .map(with_offset);
+
+    let camera = CameraSettings {
+        camera_make: ascii_value(&exif_data, exif::Tag::Make),
+        camera_model: ascii_value(&exif_data, exif::Tag::Model),
+        lens_make: ascii_value(&exif_data, exif::Tag::LensMake),
+        lens_model: ascii_value(&exif_data, exif::Tag::LensModel),
+        focal_length: rational_value(&exif_data, exif::Tag::FocalLength),
+        focal_length_35mm: uint_value(&exif_data, exif::Tag::FocalLengthIn35mmFilm)
+            .filter(|x| *x > 0), // zero means unknown
+        f_number: rational_value(&exif_data, exif::Tag::FNumber),
+        exposure_time: rational_value(&exif_data, exif::Tag::ExposureTime),
+        iso: uint_value(&exif_data, exif::Tag::PhotographicSensitivity),
+        // Lowest bit of the flash tag is set if the flash fired.
+        is_flash_fired: uint_value(&exif_data, exif::Tag::Flash).map(|x| x & 1 == 1),
+        width: uint_value(&exif_data, exif::Tag::PixelXDimension)
+            .or_else(|| uint_value(&exif_data, exif::Tag::ImageWidth)),
+        height: uint_value(&exif_data, exif::Tag::PixelYDimension)
+            .or_else(|| uint_value(&exif_data, exif::Tag::ImageLength)),
+    };
 
     // How to orient and flip the image.
     // Note that libheif will automatically apply the transformations when loading the image
@@ -148,20 +262,55 @@ fn from_exif(exif_data: Exif) -> Result<Metadata> {
 
     let content_id = ios_content_id(&exif_data);
 
-    let location = gps_location(&exif_data);
-
     let metadata = Metadata {
         created_at,
         modified_at,
-        lens_model,
+        camera,
         orientation,
         content_id,
         location,
+        rating: None,
+        keywords: Vec::new(),
+        description: None,
+        frame_count: None,
     };
 
     Ok(metadata)
 }
 
+/// Get a trimmed ASCII value.
+/// Unlike display_value(), the value will not be surrounded with quotes.
+fn ascii_value(exif_data: &Exif, tag: exif::Tag) -> Option<String> {
+    let field = exif_data.get_field(tag, exif::In::PRIMARY)?;
+    match field.value {
+        exif::Value::Ascii(ref vec) if !vec.is_empty() => {
+            let value = String::from_utf8_lossy(&vec[0]).trim().to_string();
+            Some(value).filter(|x| !x.is_empty())
+        }
+        _ => None,
+    }
+}
+
+/// Get the first value of an unsigned integer field.
+fn uint_value(exif_data: &Exif, tag: exif::Tag) -> Option<u32> {
+    exif_data
+        .get_field(tag, exif::In::PRIMARY)?
+        .value
+        .get_uint(0)
+}
+
+/// Get the first value of a rational field, such as an f-number.
+/// Zero and invalid values, such as from a zero denominator, are treated as absent.
+fn rational_value(exif_data: &Exif, tag: exif::Tag) -> Option<f64> {
+    let field = exif_data.get_field(tag, exif::In::PRIMARY)?;
+    match field.value {
+        exif::Value::Rational(ref vec) if !vec.is_empty() => {
+            Some(vec[0].to_f64()).filter(|x| x.is_finite() && *x > 0.0)
+        }
+        _ => None,
+    }
+}
+
 /// Parse GPS latitude and longitude from EXIF data
 /// Mostly borrowed from Loupe.
 /// See https://gitlab.gnome.org/GNOME/loupe/-/blob/main/src/metadata.rs
@@ -258,4 +407,47 @@ mod tests {
             content_id
         );
     }
+
+    #[test]
+    fn test_camera_settings() {
+        let dir = env!("CARGO_MANIFEST_DIR");
+        let file = Path::new(dir).join("resources/test/Frog.jpg");
+        let metadata = from_path(&file).unwrap();
+
+        let camera = metadata.camera;
+        assert_eq!(camera.camera_name(), Some("Canon EOS 5D Mark III".into()));
+        assert_eq!(camera.focal_length_text(), Some("280 mm".into()));
+        assert_eq!(camera.aperture_text(), Some("ƒ/5.6".into()));
+        assert_eq!(camera.exposure_time_text(), Some("1/500 s".into()));
+        assert_eq!(camera.iso, Some(8000));
+        assert_eq!(camera.is_flash_fired, Some(false));
+        assert_eq!(camera.dimensions_text(), Some("1600 ⨉ 1067".into()));
+    }
+
+    #[test]
+    fn test_to_local_time_without_location() {
+        let taken_at = Utc.with_ymd_and_hms(2024, 1, 15, 22, 30, 0).unwrap();
+        let local = to_local_time(taken_at, None);
+
+        // Offset is unknown, not the offset of the machine scanning the picture.
+        assert_eq!(local.offset().local_minus_utc(), 0);
+        assert_eq!(local.naive_utc(), taken_at.naive_utc());
+    }
+
+    #[test]
+    fn test_to_local_time_with_location() {
+        let taken_at = Utc.with_ymd_and_hms(2024, 1, 15, 22, 30, 0).unwrap();
+        let sydney = GPSLocation::for_decimal(-33.8688, 151.2093);
+        let local = to_local_time(taken_at, Some(&sydney));
+
+        // Daylight saving time in Sydney in January.
+        assert_eq!(local.offset().local_minus_utc(), 11 * 60 * 60);
+        assert_eq!(
+            local.naive_utc(),
+            NaiveDate::from_ymd_opt(2024, 1, 16)
+                .unwrap()
+                .and_hms_opt(9, 30, 0)
+                .unwrap()
+        );
+    }
 }
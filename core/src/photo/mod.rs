@@ -2,18 +2,20 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod animation;
 pub mod gps;
 pub mod metadata;
 pub mod model;
 pub mod motion_photo;
+pub mod raw;
 pub mod repo;
-pub mod scanner;
 pub mod thumbnail;
+pub mod timezone;
+pub mod xmp;
 
 pub use model::PictureId;
 
 pub use model::Metadata;
 pub use motion_photo::MotionPhotoExtractor;
 pub use repo::Repository;
-pub use scanner::Scanner;
 pub use thumbnail::Thumbnailer;
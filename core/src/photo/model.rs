@@ -3,13 +3,17 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use super::gps::GPSLocation;
+use crate::camera::CameraSettings;
+use crate::library::LibraryRootId;
+use crate::people::model::Rect;
 use chrono::{DateTime, FixedOffset, TimeDelta, Utc};
+use sha2::{Digest, Sha256};
 use std::fmt::Display;
 use std::path::PathBuf;
 use strum::{AsRefStr, EnumIter};
 
 /// Database ID of picture
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PictureId(i64);
 
 impl PictureId {
@@ -38,6 +42,9 @@ pub struct Picture {
     /// Database primary key for picture
     pub picture_id: PictureId,
 
+    /// Library root picture was scanned from.
+    pub library_root_id: LibraryRootId,
+
     /// Full path to square preview image
     pub thumbnail_path: Option<PathBuf>,
 
@@ -48,21 +55,6 @@ pub struct Picture {
     pub is_selfie: Option<bool>,
 }
 
-// scanner
-
-/// A picture on the local file system that has been scanned.
-#[derive(Debug, Clone)]
-pub struct ScannedFile {
-    /// Full path to picture file.
-    pub path: PathBuf,
-
-    pub fs_created_at: Option<DateTime<Utc>>,
-
-    pub fs_modified_at: Option<DateTime<Utc>>,
-
-    pub fs_file_size_bytes: u64,
-}
-
 /// Extra (non-filesystem) metadata for videos
 
 // EXIF data can include an orientation, which is a number from 1 to 8 that describes
@@ -113,6 +105,17 @@ pub enum Orientation {
 }
 
 impl Orientation {
+    /// True if the width and height of a picture are swapped when it is shown.
+    pub fn is_transposed(&self) -> bool {
+        matches!(
+            self,
+            Orientation::West
+                | Orientation::WestMirrored
+                | Orientation::East
+                | Orientation::EastMirrored
+        )
+    }
+
     pub fn from_degrees(degrees: i32) -> Self {
         match degrees {
             0 => Orientation::North,
@@ -142,12 +145,14 @@ impl From<u32> for Orientation {
 
 #[derive(Debug, Default, Clone)]
 pub struct Metadata {
+    /// Local time where the picture was taken kept as if it were UTC, with the offset
+    /// from UTC if known. Use naive_utc() for the local date and time.
     pub created_at: Option<DateTime<FixedOffset>>,
 
     pub modified_at: Option<DateTime<FixedOffset>>,
 
-    /// On iPhone the lens model tells you if it was the front or back camera.
-    pub lens_model: Option<String>,
+    /// Camera, lens, and exposure settings.
+    pub camera: CameraSettings,
 
     // iOS id for linking a video with a photo
     pub content_id: Option<String>,
@@ -159,16 +164,94 @@ pub struct Metadata {
 
     // GPS location
     pub location: Option<GPSLocation>,
+
+    /// Star rating from xmp:Rating. Zero if explicitly unrated.
+    pub rating: Option<u8>,
+
+    /// Keywords from dc:subject in an XMP sidecar.
+    pub keywords: Vec<String>,
+
+    /// Description from a Google Photos Takeout sidecar.
+    pub description: Option<String>,
+
+    /// Number of frames of an animated GIF, WebP, or PNG. None for a still picture.
+    pub frame_count: Option<u32>,
 }
 
 impl Metadata {
     pub fn is_selfie(&self) -> bool {
-        self.lens_model
+        self.camera
+            .lens_model
             .as_ref()
             .is_some_and(|x| x.contains("front"))
     }
 }
 
+/// What Fotema knows about a picture that is written to its XMP sidecar.
+#[derive(Debug, Clone)]
+pub struct PictureSidecar {
+    pub picture_id: PictureId,
+
+    /// Full path to picture file.
+    pub path: PathBuf,
+
+    pub orientation: Option<Orientation>,
+
+    /// Creation timestamp. Like the EXIF timestamps, this is the local time
+    /// the picture was taken saved as if it were UTC.
+    pub created_at: Option<DateTime<Utc>>,
+
+    /// Offset from UTC where the picture was taken, if assigned by the user.
+    pub utc_offset: Option<FixedOffset>,
+
+    /// Latitude and longitude in decimal degrees.
+    pub location: Option<(f64, f64)>,
+
+    /// Star rating from 1 to 5.
+    pub rating: Option<u8>,
+
+    pub keywords: Vec<String>,
+
+    /// Names and bounds of faces confirmed as people, in pixels of the picture as shown.
+    pub faces: Vec<(String, Rect)>,
+
+    /// Digest of the sidecar metadata when it was last written.
+    pub written_digest: Option<String>,
+}
+
+impl PictureSidecar {
+    /// Digest of the sidecar metadata, for telling if it has changed since last written.
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!(
+            "{:?}{:?}{:?}{:?}{:?}{:?}",
+            self.orientation,
+            self.created_at,
+            self.location,
+            self.rating,
+            self.keywords,
+            self.faces
+        ));
+
+        // Only hashed when assigned, so digests of sidecars written before
+        // time zones could be assigned are unchanged.
+        if let Some(utc_offset) = self.utc_offset {
+            hasher.update(format!("{:?}", utc_offset));
+        }
+
+        hasher
+            .finalize()
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect::<String>()
+    }
+
+    /// True if the sidecar metadata has changed since last written.
+    pub fn is_stale(&self) -> bool {
+        self.written_digest.as_ref() != Some(&self.digest())
+    }
+}
+
 /// A video extracted from a motion photo
 #[derive(Debug, Clone)]
 pub struct MotionPhotoVideo {
@@ -0,0 +1,343 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::Orientation;
+use anyhow::*;
+use image::{DynamicImage, RgbImage};
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use tempfile::NamedTempFile;
+
+/// Suffixes of camera RAW files.
+pub const RAW_SUFFIXES: [&str; 8] = ["arw", "cr2", "cr3", "dng", "nef", "orf", "raf", "rw2"];
+
+/// True if the path has the suffix of a camera RAW file.
+pub fn is_raw(path: &Path) -> bool {
+    path.extension()
+        .and_then(|x| x.to_str())
+        .is_some_and(|ext| RAW_SUFFIXES.iter().any(|x| ext.eq_ignore_ascii_case(x)))
+}
+
+/// Preview of a RAW file written to a temporary file, so it can be loaded with Glycin.
+#[derive(Debug)]
+pub struct Preview {
+    pub file: NamedTempFile,
+
+    /// How to orient the preview. Like the sensor data, previews are stored unrotated.
+    pub orientation: Orientation,
+}
+
+/// Reads EXIF metadata from a RAW file.
+pub fn read_exif(path: &Path) -> Result<exif::Exif> {
+    let data = fs::read(path)?;
+    exif_from_bytes(data)
+}
+
+/// Writes a preview of a RAW file to a temporary file. Uses the JPEG preview rendered
+/// by the camera, or develops the sensor data if there isn't one.
+pub fn preview(path: &Path) -> Result<Preview> {
+    let data = fs::read(path)?;
+
+    let orientation = orientation(&data);
+
+    if let Some(jpeg) = embedded_preview(&data) {
+        let mut file = tempfile::Builder::new().suffix(".jpg").tempfile()?;
+        file.write_all(jpeg)?;
+        file.flush()?;
+        return Ok(Preview { file, orientation });
+    }
+
+    let image = demosaic(&data)?;
+    let file = tempfile::Builder::new().suffix(".png").tempfile()?;
+    image.save(file.path())?;
+    Ok(Preview { file, orientation })
+}
+
+/// Rotates and flips a picture as described by its EXIF orientation.
+pub fn orient(image: DynamicImage, orientation: Orientation) -> DynamicImage {
+    match orientation {
+        Orientation::North => image,
+        Orientation::NorthMirrored => image.fliph(),
+        Orientation::South => image.rotate180(),
+        Orientation::SouthMirrored => image.flipv(),
+        Orientation::WestMirrored => image.rotate90().fliph(),
+        Orientation::West => image.rotate90(),
+        Orientation::EastMirrored => image.rotate270().fliph(),
+        Orientation::East => image.rotate270(),
+    }
+}
+
+fn orientation(data: &[u8]) -> Orientation {
+    exif_from_bytes(data.to_vec())
+        .ok()
+        .and_then(|exif_data| {
+            exif_data
+                .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+                .and_then(|e| e.value.get_uint(0))
+        })
+        .map(Orientation::from)
+        .unwrap_or_default()
+}
+
+/// Most RAW formats are TIFF files, but some need their containers unwrapped first.
+fn exif_from_bytes(mut data: Vec<u8>) -> Result<exif::Exif> {
+    let reader = exif::Reader::new();
+
+    // Olympus and Panasonic use their own magic numbers in an otherwise TIFF header.
+    if data.starts_with(b"IIRO") || data.starts_with(b"IIRS") || data.starts_with(b"IIU\0") {
+        data[2] = 0x2a;
+        data[3] = 0;
+        return Ok(reader.read_raw(data)?);
+    } else if data.starts_with(b"MMOR") {
+        data[2] = 0;
+        data[3] = 0x2a;
+        return Ok(reader.read_raw(data)?);
+    }
+
+    // Fujifilm RAF files embed a JPEG preview that has the EXIF metadata.
+    if data.starts_with(b"FUJIFILMCCD-RAW") {
+        let jpeg = raf_preview(&data).context("RAF file has no preview")?;
+        return Ok(reader.read_from_container(&mut Cursor::new(jpeg))?);
+    }
+
+    // Canon CR3 files are ISO base media files with the main TIFF metadata in a CMT1 box.
+    // The CMT2 box has the EXIF tags, but can't be read without the main metadata,
+    // so the EXIF modification date stands in for the creation date.
+    if data.get(4..12) == Some(b"ftypcrx ".as_slice()) {
+        let tiff = find_box(&data, b"CMT1").context("CR3 file has no metadata")?;
+        return Ok(reader.read_raw(tiff.to_vec())?);
+    }
+
+    Ok(reader.read_from_container(&mut Cursor::new(&data))?)
+}
+
+/// Fujifilm RAF header has the offset and length of the preview at bytes 84 and 88.
+fn raf_preview(data: &[u8]) -> Option<&[u8]> {
+    let read_u32 = |at: usize| {
+        data.get(at..at + 4)
+            .map(|x| u32::from_be_bytes([x[0], x[1], x[2], x[3]]) as usize)
+    };
+    let offset = read_u32(84)?;
+    let length = read_u32(88)?;
+    data.get(offset..offset.checked_add(length)?)
+}
+
+/// Content of the first ISO base media box of a type, wherever it is nested.
+fn find_box<'a>(data: &'a [u8], box_type: &[u8; 4]) -> Option<&'a [u8]> {
+    let at = data.windows(4).position(|x| x == box_type)?;
+    let start = at.checked_sub(4)?;
+    let size = u32::from_be_bytes(data.get(start..at)?.try_into().ok()?) as usize;
+    data.get(at + 4..start.checked_add(size)?)
+}
+
+/// Largest JPEG preview in a RAW file, which is usually close to full size.
+fn embedded_preview(data: &[u8]) -> Option<&[u8]> {
+    if data.starts_with(b"FUJIFILMCCD-RAW") {
+        return raf_preview(data);
+    }
+
+    let mut largest: Option<&[u8]> = None;
+    let mut offset = 0;
+
+    while let Some(start) = data[offset..]
+        .windows(3)
+        .position(|x| x == [0xFF, 0xD8, 0xFF])
+        .map(|x| x + offset)
+    {
+        match jpeg_length(&data[start..]) {
+            Some(length) => {
+                let jpeg = &data[start..start + length];
+                largest = Some(largest.filter(|x| x.len() >= jpeg.len()).unwrap_or(jpeg));
+                offset = start + length;
+            }
+            None => offset = start + 3,
+        }
+    }
+
+    largest
+}
+
+/// Length of a JPEG that common decoders can load, from the start of image marker
+/// to the end of image marker. RAW sensor data is often stored as a lossless JPEG,
+/// which isn't a preview and can't be loaded, so has no length.
+fn jpeg_length(data: &[u8]) -> Option<usize> {
+    let mut i = 2;
+    let mut is_loadable = false;
+
+    // Skip over marker segments, which can contain thumbnails, up to the start of scan.
+    loop {
+        if *data.get(i)? != 0xFF {
+            return None;
+        }
+
+        let marker = *data.get(i + 1)?;
+        match marker {
+            0xFF => {
+                i += 1;
+                continue;
+            }
+            0xD9 => return None,
+            0x01 | 0xD0..=0xD7 => {
+                i += 2;
+                continue;
+            }
+            // Baseline, extended, and progressive frames.
+            0xC0..=0xC2 => is_loadable = true,
+            // Lossless, hierarchical, and arithmetic coded frames.
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return None,
+            _ => {}
+        }
+
+        let length = u16::from_be_bytes([*data.get(i + 2)?, *data.get(i + 3)?]) as usize;
+        if length < 2 {
+            return None;
+        }
+
+        i += 2 + length;
+
+        if marker == 0xDA {
+            break;
+        }
+    }
+
+    if !is_loadable {
+        return None;
+    }
+
+    // Within compressed data, 0xFF is always followed by zero or a marker,
+    // so the first end of image marker ends the JPEG.
+    data.get(i..)?
+        .windows(2)
+        .position(|x| x == [0xFF, 0xD9])
+        .map(|x| i + x + 2)
+}
+
+/// Develops the sensor data of a RAW file at half size, for when there is no preview.
+/// Each 2x2 block of sensor pixels becomes one pixel. Only white balance is applied,
+/// not the camera's color profile, so this is only good enough for a thumbnail.
+fn demosaic(data: &[u8]) -> Result<DynamicImage> {
+    let raw = rawloader::decode(&mut Cursor::new(data))
+        .map_err(|e| anyhow!("Failed decoding RAW: {:?}", e))?;
+
+    let rawloader::RawImageData::Integer(ref pixels) = raw.data else {
+        bail!("Unsupported RAW data");
+    };
+
+    if raw.cpp != 1 {
+        bail!(
+            "Unsupported RAW sensor with {} components per pixel",
+            raw.cpp
+        );
+    }
+
+    let [top, right, bottom, left] = raw.crops;
+    let width = raw.width.saturating_sub(left + right) / 2;
+    let height = raw.height.saturating_sub(top + bottom) / 2;
+
+    if width == 0 || height == 0 {
+        bail!("RAW image is empty");
+    }
+
+    let black = raw.blacklevels[0] as f32;
+    let white = (raw.whitelevels[0] as f32).max(black + 1.0);
+
+    // White balance relative to green. Cameras without coefficients are left neutral.
+    let [red, green, blue, _] = raw.wb_coeffs;
+    let balance = if green.is_normal() && red.is_normal() && blue.is_normal() {
+        [red / green, 1.0, blue / green]
+    } else {
+        [1.0, 1.0, 1.0]
+    };
+
+    let mut image = RgbImage::new(width as u32, height as u32);
+
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let mut sums = [0.0f32; 3];
+        let mut counts = [0u32; 3];
+
+        for (dy, dx) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+            let row = top + y as usize * 2 + dy;
+            let col = left + x as usize * 2 + dx;
+
+            // Colors are red, green, blue, and a second green on some sensors.
+            let channel = match raw.cfa.color_at(row, col) {
+                0 => 0,
+                1 | 3 => 1,
+                2 => 2,
+                _ => continue,
+            };
+
+            let value = pixels.get(row * raw.width + col).copied().unwrap_or(0) as f32;
+            sums[channel] += ((value - black) / (white - black)).clamp(0.0, 1.0);
+            counts[channel] += 1;
+        }
+
+        for channel in 0..3 {
+            if counts[channel] > 0 {
+                let value = sums[channel] / counts[channel] as f32 * balance[channel];
+                pixel[channel] = (value.clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0).round() as u8;
+            }
+        }
+    }
+
+    Ok(DynamicImage::ImageRgb8(image))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal JPEG markers with a frame of the given type.
+    fn jpeg(frame: u8, scan: &[u8]) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        // An APP1 segment with a nested thumbnail, which must be skipped.
+        data.extend_from_slice(&[0xFF, 0xE1, 0x00, 0x08, 0xFF, 0xD8, 0xFF, 0xD9, 0x00, 0x00]);
+        data.extend_from_slice(&[0xFF, frame, 0x00, 0x02]);
+        data.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x02]);
+        data.extend_from_slice(scan);
+        data.extend_from_slice(&[0xFF, 0xD9]);
+        data
+    }
+
+    #[test]
+    fn test_is_raw() {
+        assert!(is_raw(Path::new("/a/IMG_0001.CR2")));
+        assert!(is_raw(Path::new("/a/DSC_0001.nef")));
+        assert!(!is_raw(Path::new("/a/IMG_0001.jpg")));
+        assert!(!is_raw(Path::new("/a/nef")));
+    }
+
+    #[test]
+    fn test_embedded_preview() {
+        let small = jpeg(0xC0, &[1, 2, 0xFF, 0x00, 3]);
+        let large = jpeg(0xC2, &[1, 2, 3, 4, 5, 6, 7, 8, 9, 0xFF, 0xD0, 10]);
+        let lossless = jpeg(0xC3, &[0; 64]);
+
+        let mut data = b"II*\0".to_vec();
+        data.extend_from_slice(&small);
+        data.extend_from_slice(&[0; 16]);
+        data.extend_from_slice(&lossless);
+        data.extend_from_slice(&large);
+        data.extend_from_slice(&[0; 16]);
+
+        assert_eq!(embedded_preview(&data), Some(large.as_slice()));
+        assert_eq!(jpeg_length(&lossless), None);
+        assert_eq!(embedded_preview(b"II*\0 no previews"), None);
+    }
+
+    #[test]
+    fn test_find_box() {
+        let mut data = vec![0, 0, 0, 24];
+        data.extend_from_slice(b"moov");
+        data.extend_from_slice(&[0, 0, 0, 12]);
+        data.extend_from_slice(b"CMT1");
+        data.extend_from_slice(b"II*\0");
+        data.extend_from_slice(&[0, 0, 0, 8]);
+        data.extend_from_slice(b"CMT2");
+
+        assert_eq!(find_box(&data, b"CMT1"), Some(b"II*\0".as_slice()));
+        assert_eq!(find_box(&data, b"CMT3"), None);
+    }
+}
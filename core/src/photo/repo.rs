@@ -2,17 +2,22 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::photo::model::{Picture, PictureId, ScannedFile};
+use crate::media::{MediaType, ScannedFile, ScannedFileStore};
+use crate::people::model::Rect;
+use crate::photo::model::{Orientation, Picture, PictureId, PictureSidecar};
 
 use super::metadata;
 use super::model::MotionPhotoVideo;
 use super::motion_photo;
 use super::Metadata;
+use crate::library::{FileStamp, LibraryRoot, LibraryRootId};
 use crate::path_encoding;
-use anyhow::{bail, Result};
+use anyhow::Result;
+use chrono::FixedOffset;
 use rusqlite;
 use rusqlite::params;
 use rusqlite::Row;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
@@ -20,9 +25,6 @@ use std::sync::{Arc, Mutex};
 /// Repository is backed by a Sqlite database.
 #[derive(Debug, Clone)]
 pub struct Repository {
-    /// Base path to picture library on file system
-    library_base_path: PathBuf,
-
     /// Base path cache directory for photo thumbnails and motion photo videos
     cache_dir_base_path: PathBuf,
 
@@ -36,21 +38,14 @@ pub struct Repository {
 impl Repository {
     /// Builds a Repository and creates operational tables.
     pub fn open(
-        library_base_path: &Path,
         cache_dir_base_path: &Path,
         data_dir_base_path: &Path,
         con: Arc<Mutex<rusqlite::Connection>>,
     ) -> Result<Repository> {
-        if !library_base_path.is_dir() {
-            bail!("{:?} is not a directory", library_base_path);
-        }
-
-        let library_base_path = PathBuf::from(library_base_path);
         let cache_dir_base_path = PathBuf::from(cache_dir_base_path);
         let data_dir_base_path = PathBuf::from(data_dir_base_path);
 
         let repo = Repository {
-            library_base_path,
             cache_dir_base_path,
             data_dir_base_path,
             con,
@@ -71,11 +66,49 @@ impl Repository {
                     exif_created_ts = ?3,
                     exif_modified_ts = ?4,
                     is_selfie = ?5,
-                    content_id = ?6,
-                    orientation = ?7
+                    -- Keep a content ID shared with a sibling video if the picture has none.
+                    content_id = COALESCE(?6, content_id),
+                    orientation = ?7,
+                    keywords = ?8,
+                    description = ?9,
+                    frame_count = ?10,
+                    exif_utc_offset = ?11
                 WHERE picture_id = ?1",
             )?;
 
+            let mut update_exif = tx.prepare_cached(
+                "INSERT INTO pictures_exif (
+                    picture_id,
+                    camera_make,
+                    camera_model,
+                    lens_make,
+                    lens_model,
+                    focal_length,
+                    focal_length_35mm,
+                    f_number,
+                    exposure_time,
+                    iso,
+                    is_flash_fired,
+                    width,
+                    height
+                ) VALUES (
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13
+                ) ON CONFLICT (picture_id) DO UPDATE SET
+                    camera_make = ?2,
+                    camera_model = ?3,
+                    lens_make = ?4,
+                    lens_model = ?5,
+                    focal_length = ?6,
+                    focal_length_35mm = ?7,
+                    f_number = ?8,
+                    exposure_time = ?9,
+                    iso = ?10,
+                    is_flash_fired = ?11,
+                    width = ?12,
+                    height = ?13
+                ",
+            )?;
+
             let mut update_geo = tx.prepare_cached(
                 "INSERT INTO pictures_geo (
                     picture_id,
@@ -89,15 +122,55 @@ impl Repository {
                 ",
             )?;
 
+            // Embedded ratings are only imported once, so that rescanning doesn't
+            // overwrite ratings set by the user.
+            let mut import_rating = tx.prepare_cached(
+                "INSERT INTO pictures_ratings (
+                    picture_id,
+                    stars
+                ) VALUES (
+                    ?1, NULLIF(?2, 0)
+                ) ON CONFLICT (picture_id) DO NOTHING
+                ",
+            )?;
+
             for (picture_id, metadata) in pics {
+                // Save the local time as if it were UTC, so all timestamps have the same format
+                // and order correctly. The offset is saved separately.
+                let utc_offset = metadata
+                    .created_at
+                    .map(|x| x.offset().local_minus_utc())
+                    .filter(|x| *x != 0); // zero offset usually means unknown
+
                 update_pictures.execute(params![
                     picture_id.id(),
                     metadata::VERSION,
-                    metadata.created_at,
-                    metadata.modified_at,
+                    metadata.created_at.map(|x| x.naive_utc().and_utc()),
+                    metadata.modified_at.map(|x| x.naive_utc().and_utc()),
                     metadata.is_selfie(),
                     metadata.content_id,
                     metadata.orientation.map(|x| x as u8),
+                    Some(metadata.keywords.join("\n")).filter(|x| !x.is_empty()),
+                    metadata.description,
+                    metadata.frame_count,
+                    utc_offset,
+                ])?;
+
+                let camera = metadata.camera;
+                update_exif.execute(params![
+                    picture_id.id(),
+                    camera.camera_make,
+                    camera.camera_model,
+                    camera.lens_make,
+                    camera.lens_model,
+                    camera.focal_length,
+                    camera.focal_length_35mm,
+                    camera.f_number,
+                    camera.exposure_time,
+                    camera.iso,
+                    camera.is_flash_fired,
+                    camera.width,
+                    camera.height,
                 ])?;
 
                 if let Some(location) = metadata.location {
@@ -110,6 +183,10 @@ impl Repository {
                         update_geo.execute(params![picture_id.id(), latitude, longitude,])?;
                     }
                 }
+
+                if let Some(rating) = metadata.rating {
+                    import_rating.execute(params![picture_id.id(), rating])?;
+                }
             }
         }
 
@@ -163,7 +240,8 @@ impl Repository {
     }
 
     /// Add all Pictures received from a vector.
-    pub fn add_all(&mut self, pics: &Vec<ScannedFile>) -> Result<()> {
+    /// Pictures must be under the given library root.
+    pub fn add_all(&mut self, root: &LibraryRoot, pics: &[ScannedFile]) -> Result<()> {
         let mut con = self.con.lock().unwrap();
         let tx = con.transaction()?;
 
@@ -176,18 +254,24 @@ impl Repository {
                     picture_path_b64,
                     picture_path_lossy,
                     link_path_b64,
-                    link_path_lossy
+                    link_path_lossy,
+                    library_root_id,
+                    fs_file_size_bytes,
+                    fingerprint,
+                    is_raw
                 ) VALUES (
-                    ?1, ?2, ?3, ?4, ?5, ?6
-                ) ON CONFLICT (picture_path_b64) DO UPDATE SET
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10
+                ) ON CONFLICT (library_root_id, picture_path_b64) DO UPDATE SET
                     fs_created_ts = ?1,
-                    fs_modified_ts = ?2
+                    fs_modified_ts = ?2,
+                    fs_file_size_bytes = ?8,
+                    fingerprint = COALESCE(?9, fingerprint)
                 ",
             )?;
 
             for pic in pics {
                 // convert to relative path before saving to database
-                let picture_path = pic.path.strip_prefix(&self.library_base_path)?;
+                let picture_path = pic.path.strip_prefix(&root.path)?;
                 let picture_path_b64 = path_encoding::to_base64(picture_path);
 
                 // Path without suffix so sibling pictures and videos can be related
@@ -206,6 +290,309 @@ impl Repository {
                     picture_path.to_string_lossy(),
                     link_path_b64,
                     link_path.to_string_lossy(),
+                    root.library_root_id.id(),
+                    pic.fs_file_size_bytes,
+                    pic.fingerprint,
+                    pic.media_type == MediaType::Raw,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Invalidates derived state of pictures that have changed on the file system since
+    /// they were last scanned, so that they are processed again as if they were new.
+    /// File system timestamps and sizes are updated.
+    /// Returns paths of derived files that should be deleted.
+    pub fn invalidate(&mut self, root: &LibraryRoot, pics: &[ScannedFile]) -> Result<Vec<PathBuf>> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let mut cleanup_paths = Vec::new();
+
+        {
+            let mut find_picture_id = tx.prepare_cached(
+                "SELECT picture_id
+                FROM pictures
+                WHERE library_root_id = ?1
+                AND picture_path_b64 = ?2",
+            )?;
+
+            // Face thumbnails might be shared with a person, so must not be deleted.
+            // Confirmed faces are kept, so their images must not be deleted either.
+            let mut find_cleanup = tx.prepare_cached(
+                "SELECT root_name, path
+                FROM pictures_cleanup
+                WHERE picture_id = ?1
+                AND path IS NOT NULL
+                AND path NOT IN (SELECT thumbnail_path FROM people)
+                AND path NOT IN (SELECT thumbnail_path FROM pictures_faces WHERE is_confirmed IS TRUE)
+                AND path NOT IN (SELECT bounds_path FROM pictures_faces WHERE is_confirmed IS TRUE)",
+            )?;
+
+            let mut update_picture = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    fs_created_ts = ?2,
+                    fs_modified_ts = ?3,
+                    fs_file_size_bytes = ?4,
+                    fingerprint = ?5,
+                    thumbnail_path = NULL,
+                    metadata_version = 0,
+                    is_broken = NULL
+                WHERE picture_id = ?1",
+            )?;
+
+            let mut delete_motion_photo =
+                tx.prepare_cached("DELETE FROM motion_photos WHERE picture_id = ?1")?;
+
+            let mut delete_geo = tx.prepare_cached("DELETE FROM pictures_geo WHERE picture_id = ?1")?;
+
+            let mut delete_exif =
+                tx.prepare_cached("DELETE FROM pictures_exif WHERE picture_id = ?1")?;
+
+            let mut delete_face_scan =
+                tx.prepare_cached("DELETE FROM pictures_face_scans WHERE picture_id = ?1")?;
+
+            // Faces confirmed as a person are kept so that the person isn't lost when the
+            // picture is scanned for faces again. See people::Repository::add_face_scans.
+            let mut delete_faces = tx.prepare_cached(
+                "DELETE FROM pictures_faces WHERE picture_id = ?1 AND is_confirmed IS FALSE",
+            )?;
+
+            let mut delete_hashes =
+                tx.prepare_cached("DELETE FROM pictures_hashes WHERE picture_id = ?1")?;
+
+            for pic in pics {
+                let picture_path = pic.path.strip_prefix(&root.path)?;
+                let picture_path_b64 = path_encoding::to_base64(picture_path);
+
+                let picture_id: i64 = find_picture_id.query_row(
+                    params![root.library_root_id.id(), picture_path_b64],
+                    |row| row.get(0),
+                )?;
+
+                let paths: Vec<PathBuf> = find_cleanup
+                    .query_map([picture_id], |row| self.to_cleanup_path(row))?
+                    .flatten()
+                    .collect();
+                cleanup_paths.extend(paths);
+
+                delete_motion_photo.execute([picture_id])?;
+                delete_geo.execute([picture_id])?;
+                delete_exif.execute([picture_id])?;
+                delete_face_scan.execute([picture_id])?;
+                delete_faces.execute([picture_id])?;
+                delete_hashes.execute([picture_id])?;
+
+                update_picture.execute(params![
+                    picture_id,
+                    pic.fs_created_at,
+                    pic.fs_modified_at,
+                    pic.fs_file_size_bytes,
+                    pic.fingerprint,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(cleanup_paths)
+    }
+
+    /// Finds a picture with the given fingerprint whose file is missing, which implies the
+    /// file has been moved or renamed.
+    /// pictures under unavailable library roots are ignored, as their files are only
+    /// missing because the root is unmounted. Pictures in the trash, including RAW files
+    /// trashed with their developed picture, are also ignored.
+    pub fn find_moved(&self, fingerprint: &str) -> Result<Option<PictureId>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    pictures.picture_path_b64,
+                    library_roots.root_path_b64
+                FROM pictures
+                INNER JOIN library_roots ON library_roots.root_id = pictures.library_root_id
+                WHERE pictures.fingerprint = ?1
+                AND library_roots.is_enabled IS TRUE
+                AND pictures.picture_id NOT IN (SELECT picture_id FROM trash WHERE picture_id IS NOT NULL)
+                AND pictures.picture_id NOT IN (SELECT picture_id FROM trash_raws)",
+        )?;
+
+        let candidates: Vec<(PictureId, PathBuf, PathBuf)> = stmt
+            .query_map([fingerprint], |row| {
+                let picture_id = row.get("picture_id").map(PictureId::new)?;
+
+                let picture_path: String = row.get("picture_path_b64")?;
+                let picture_path = path_encoding::from_base64(&picture_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+                let root_path: String = row.get("root_path_b64")?;
+                let root_path = path_encoding::from_base64(&root_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+                std::result::Result::Ok((picture_id, root_path.join(&picture_path), root_path))
+            })?
+            .flatten()
+            .collect();
+
+        let moved = candidates
+            .into_iter()
+            .find(|(_, path, root_path)| root_path.is_dir() && !path.exists())
+            .map(|(picture_id, _, _)| picture_id);
+
+        Ok(moved)
+    }
+
+    /// Updates the path of a picture that has moved, keeping everything derived from it.
+    /// The new path must be under the given library root.
+    pub fn relocate(&mut self, picture_id: PictureId, root: &LibraryRoot, pic: &ScannedFile) -> Result<()> {
+        let picture_path = pic.path.strip_prefix(&root.path)?;
+        let picture_path_b64 = path_encoding::to_base64(picture_path);
+
+        // Path without suffix so sibling pictures and videos can be related
+        let link_path = picture_path
+            .file_stem()
+            .and_then(|x| x.to_str())
+            .expect("Must exist");
+
+        let link_path = picture_path.with_file_name(link_path);
+        let link_path_b64 = path_encoding::to_base64(&link_path);
+
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "UPDATE pictures
+            SET
+                library_root_id = ?2,
+                picture_path_b64 = ?3,
+                picture_path_lossy = ?4,
+                link_path_b64 = ?5,
+                link_path_lossy = ?6,
+                fs_created_ts = ?7,
+                fs_modified_ts = ?8,
+                fs_file_size_bytes = ?9
+            WHERE picture_id = ?1",
+        )?;
+
+        stmt.execute(params![
+            picture_id.id(),
+            root.library_root_id.id(),
+            picture_path_b64,
+            picture_path.to_string_lossy(),
+            link_path_b64,
+            link_path.to_string_lossy(),
+            pic.fs_created_at,
+            pic.fs_modified_at,
+            pic.fs_file_size_bytes,
+        ])?;
+
+        Ok(())
+    }
+
+    /// Gets file system modification times and sizes of all pictures under a library root,
+    /// keyed by full path.
+    pub fn find_file_stamps(&self, root: &LibraryRoot) -> Result<HashMap<PathBuf, FileStamp>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    picture_path_b64,
+                    fs_modified_ts,
+                    fs_file_size_bytes
+                FROM pictures
+                WHERE library_root_id = ?1",
+        )?;
+
+        let result = stmt
+            .query_map([root.library_root_id.id()], |row| {
+                let picture_path: String = row.get("picture_path_b64")?;
+                let picture_path = path_encoding::from_base64(&picture_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+                let stamp = FileStamp {
+                    fs_modified_at: row.get("fs_modified_ts")?,
+                    fs_file_size_bytes: row.get("fs_file_size_bytes")?,
+                };
+
+                std::result::Result::Ok((root.path.join(picture_path), stamp))
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Gets the file stamp of the XMP sidecar of each picture under a library root, as of when
+    /// it was last scanned. The stamp is None for pictures that had no sidecar.
+    /// Pictures in the trash are ignored, as their sidecars are moved to the trash with them.
+    pub fn find_sidecar_stamps(
+        &self,
+        root: &LibraryRoot,
+    ) -> Result<HashMap<PathBuf, Option<FileStamp>>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    picture_path_b64,
+                    sidecar_fs_modified_ts,
+                    sidecar_fs_file_size_bytes
+                FROM pictures
+                WHERE library_root_id = ?1
+                AND picture_id NOT IN (SELECT picture_id FROM trash WHERE picture_id IS NOT NULL)
+                AND picture_id NOT IN (SELECT picture_id FROM trash_raws)",
+        )?;
+
+        let result = stmt
+            .query_map([root.library_root_id.id()], |row| {
+                let picture_path: String = row.get("picture_path_b64")?;
+                let picture_path = path_encoding::from_base64(&picture_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+                let stamp = FileStamp {
+                    fs_modified_at: row.get("sidecar_fs_modified_ts")?,
+                    fs_file_size_bytes: row.get("sidecar_fs_file_size_bytes")?,
+                };
+                let stamp = Some(stamp)
+                    .filter(|x| x.fs_modified_at.is_some() || x.fs_file_size_bytes.is_some());
+
+                std::result::Result::Ok((root.path.join(picture_path), stamp))
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Records the file stamps of XMP sidecars that have been added, changed, or removed,
+    /// and marks the metadata of their pictures to be scanned again.
+    pub fn invalidate_sidecars(
+        &mut self,
+        root: &LibraryRoot,
+        sidecars: &[(PathBuf, Option<FileStamp>)],
+    ) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "UPDATE pictures
+                SET
+                    metadata_version = 0,
+                    sidecar_fs_modified_ts = ?3,
+                    sidecar_fs_file_size_bytes = ?4
+                WHERE library_root_id = ?1
+                AND picture_path_b64 = ?2",
+            )?;
+
+            for (picture_path, stamp) in sidecars {
+                let picture_path = picture_path.strip_prefix(&root.path)?;
+                let picture_path_b64 = path_encoding::to_base64(picture_path);
+
+                stmt.execute(params![
+                    root.library_root_id.id(),
+                    picture_path_b64,
+                    stamp.and_then(|x| x.fs_modified_at),
+                    stamp.and_then(|x| x.fs_file_size_bytes),
                 ])?;
             }
         }
@@ -215,11 +602,15 @@ impl Repository {
     }
 
     /// Gets all pictures in the repository, in ascending order of modification timestamp.
+    /// Pictures in the trash, including RAW files trashed with their developed picture,
+    /// are excluded, as their files are missing until restored.
     pub fn all(&self) -> Result<Vec<Picture>> {
         let con = self.con.lock().unwrap();
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
+                    pictures.library_root_id,
+                    library_roots.root_path_b64,
                     pictures.picture_path_b64,
                     pictures.thumbnail_path,
                     COALESCE(
@@ -231,7 +622,11 @@ impl Repository {
                       ) AS ordering_ts,
                     pictures.is_selfie
                 FROM pictures
+                INNER JOIN library_roots ON library_roots.root_id = pictures.library_root_id
                 WHERE COALESCE(is_broken, FALSE) IS FALSE
+                AND library_roots.is_enabled IS TRUE
+                AND pictures.picture_id NOT IN (SELECT picture_id FROM trash WHERE picture_id IS NOT NULL)
+                AND pictures.picture_id NOT IN (SELECT picture_id FROM trash_raws)
                 ORDER BY ordering_ts ASC",
         )?;
 
@@ -251,6 +646,8 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
+                    pictures.library_root_id,
+                    library_roots.root_path_b64,
                     pictures.picture_path_b64,
                     pictures.thumbnail_path,
                     COALESCE(
@@ -262,8 +659,10 @@ impl Repository {
                       ) AS ordering_ts,
                     pictures.is_selfie
                 FROM pictures
+                INNER JOIN library_roots ON library_roots.root_id = pictures.library_root_id
                 WHERE metadata_version < ?1
                 AND COALESCE(is_broken, FALSE) IS FALSE
+                AND library_roots.is_enabled IS TRUE
                 ORDER BY ordering_ts ASC",
         )?;
 
@@ -281,6 +680,8 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     pictures.picture_id,
+                    pictures.library_root_id,
+                    library_roots.root_path_b64,
                     pictures.picture_path_b64,
                     pictures.thumbnail_path,
                     COALESCE(
@@ -293,8 +694,10 @@ impl Repository {
                     pictures.is_selfie
                 FROM pictures
                 FULL OUTER JOIN motion_photos USING (picture_id)
+                INNER JOIN library_roots ON library_roots.root_id = pictures.library_root_id
                 WHERE COALESCE(motion_photos.extract_version, 0) < ?1
-                AND COALESCE(is_broken, FALSE) IS FALSE",
+                AND COALESCE(is_broken, FALSE) IS FALSE
+                AND library_roots.is_enabled IS TRUE",
         )?;
 
         let result = stmt
@@ -305,6 +708,124 @@ impl Repository {
         Ok(result)
     }
 
+    /// Gets metadata to write to the XMP sidecars of pictures whose metadata has changed
+    /// since their sidecars were last written. Only pictures with faces confirmed as
+    /// people, with corrected dates, or that have had a sidecar written before,
+    /// have sidecars written.
+    pub fn find_need_sidecar_write(&self) -> Result<Vec<PictureSidecar>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id,
+                    library_roots.root_path_b64,
+                    pictures.picture_path_b64,
+                    pictures.orientation,
+                    COALESCE(pictures.override_created_ts, pictures.exif_created_ts) AS created_ts,
+                    COALESCE(pictures.override_utc_offset, pictures.exif_utc_offset) AS utc_offset,
+                    pictures_geo.latitude,
+                    pictures_geo.longitude,
+                    pictures_ratings.stars,
+                    pictures.keywords,
+                    pictures.sidecar_digest
+                FROM pictures
+                INNER JOIN library_roots ON library_roots.root_id = pictures.library_root_id
+                LEFT OUTER JOIN pictures_geo USING (picture_id)
+                LEFT OUTER JOIN pictures_ratings USING (picture_id)
+                WHERE COALESCE(is_broken, FALSE) IS FALSE
+                AND library_roots.is_enabled IS TRUE
+                AND pictures.picture_id NOT IN (SELECT picture_id FROM trash WHERE picture_id IS NOT NULL)
+                AND pictures.picture_id NOT IN (SELECT picture_id FROM trash_raws)
+                AND (
+                    pictures.sidecar_digest IS NOT NULL
+                    OR pictures.override_created_ts IS NOT NULL
+                    OR pictures.override_utc_offset IS NOT NULL
+                    OR pictures.picture_id IN (
+                        SELECT picture_id
+                        FROM pictures_faces
+                        WHERE is_confirmed IS TRUE
+                        AND person_id IS NOT NULL
+                    )
+                )",
+        )?;
+
+        let mut faces_stmt = con.prepare(
+            "SELECT
+                    people.name,
+                    pictures_faces.bounds_x,
+                    pictures_faces.bounds_y,
+                    pictures_faces.bounds_width,
+                    pictures_faces.bounds_height
+                FROM pictures_faces
+                INNER JOIN people USING (person_id)
+                WHERE pictures_faces.picture_id = ?1
+                AND pictures_faces.is_confirmed IS TRUE
+                AND pictures_faces.is_ignored IS FALSE
+                ORDER BY pictures_faces.face_id",
+        )?;
+
+        let sidecars: Vec<PictureSidecar> = stmt
+            .query_map([], |row| self.to_sidecar(row))?
+            .flatten()
+            .collect();
+
+        let mut result = Vec::new();
+
+        for mut sidecar in sidecars {
+            sidecar.faces = faces_stmt
+                .query_map([sidecar.picture_id.id()], |row| {
+                    let name: String = row.get("name")?;
+                    let bounds = Rect {
+                        x: row.get("bounds_x")?,
+                        y: row.get("bounds_y")?,
+                        width: row.get("bounds_width")?,
+                        height: row.get("bounds_height")?,
+                    };
+                    std::result::Result::Ok((name, bounds))
+                })?
+                .flatten()
+                .collect();
+
+            if sidecar.is_stale() {
+                result.push(sidecar);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Records the digest of the metadata written to the XMP sidecar of a picture.
+    pub fn mark_sidecar_written(&mut self, picture_id: PictureId, digest: &str) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached(
+            "UPDATE pictures
+            SET sidecar_digest = ?2
+            WHERE picture_id = ?1",
+        )?;
+
+        stmt.execute(params![picture_id.id(), digest])?;
+
+        Ok(())
+    }
+
+    /// Gets all pictures whose library root has been removed.
+    pub fn find_orphaned(&self) -> Result<Vec<PictureId>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    pictures.picture_id
+                FROM pictures
+                LEFT OUTER JOIN library_roots ON library_roots.root_id = pictures.library_root_id
+                WHERE library_roots.root_id IS NULL",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| row.get("picture_id").map(PictureId::new))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
     /// Gets paths of files to delete when a picture is no longer present.
     pub fn find_files_to_cleanup(&self, picture_id: PictureId) -> Result<Vec<PathBuf>> {
         let con = self.con.lock().unwrap();
@@ -398,10 +919,16 @@ impl Repository {
     fn to_picture(&self, row: &Row<'_>) -> rusqlite::Result<Picture> {
         let picture_id = row.get("picture_id").map(PictureId::new)?;
 
+        let library_root_id = row.get("library_root_id").map(LibraryRootId::new)?;
+
+        let root_path: String = row.get("root_path_b64")?;
+        let root_path =
+            path_encoding::from_base64(&root_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
+
         let picture_path: String = row.get("picture_path_b64")?;
         let picture_path =
             path_encoding::from_base64(&picture_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
-        let picture_path = self.library_base_path.join(picture_path);
+        let picture_path = root_path.join(picture_path);
 
         let thumbnail_path = row
             .get("thumbnail_path")
@@ -413,6 +940,7 @@ impl Repository {
 
         std::result::Result::Ok(Picture {
             picture_id,
+            library_root_id,
             path: picture_path,
             thumbnail_path,
             ordering_ts,
@@ -420,6 +948,43 @@ impl Repository {
         })
     }
 
+    fn to_sidecar(&self, row: &Row<'_>) -> rusqlite::Result<PictureSidecar> {
+        let picture_id = row.get("picture_id").map(PictureId::new)?;
+
+        let root_path: String = row.get("root_path_b64")?;
+        let root_path =
+            path_encoding::from_base64(&root_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+        let picture_path: String = row.get("picture_path_b64")?;
+        let picture_path =
+            path_encoding::from_base64(&picture_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+        let orientation: Option<u32> = row.get("orientation")?;
+
+        let utc_offset: Option<i32> = row.get("utc_offset")?;
+
+        let latitude: Option<f64> = row.get("latitude")?;
+        let longitude: Option<f64> = row.get("longitude")?;
+
+        let keywords: Option<String> = row.get("keywords")?;
+        let keywords = keywords
+            .map(|x| x.lines().map(String::from).collect())
+            .unwrap_or_default();
+
+        std::result::Result::Ok(PictureSidecar {
+            picture_id,
+            path: root_path.join(picture_path),
+            orientation: orientation.map(Orientation::from),
+            created_at: row.get("created_ts")?,
+            utc_offset: utc_offset.and_then(FixedOffset::east_opt),
+            location: latitude.zip(longitude),
+            rating: row.get("stars")?,
+            keywords,
+            faces: Vec::new(),
+            written_digest: row.get("sidecar_digest")?,
+        })
+    }
+
     fn to_cleanup_path(&self, row: &Row<'_>) -> rusqlite::Result<PathBuf> {
         let root_name: String = row.get("root_name")?;
 
@@ -440,3 +1005,299 @@ impl Repository {
         Ok(())
     }
 }
+
+impl ScannedFileStore for Repository {
+    type Id = PictureId;
+
+    fn find_file_stamps(&self, root: &LibraryRoot) -> Result<HashMap<PathBuf, FileStamp>> {
+        Repository::find_file_stamps(self, root)
+    }
+
+    fn find_moved(&self, fingerprint: &str) -> Result<Option<PictureId>> {
+        Repository::find_moved(self, fingerprint)
+    }
+
+    fn relocate(&mut self, id: PictureId, root: &LibraryRoot, file: &ScannedFile) -> Result<()> {
+        Repository::relocate(self, id, root, file)
+    }
+
+    fn add_all(&mut self, root: &LibraryRoot, files: &[ScannedFile]) -> Result<()> {
+        Repository::add_all(self, root, files)
+    }
+
+    fn invalidate(&mut self, root: &LibraryRoot, files: &[ScannedFile]) -> Result<Vec<PathBuf>> {
+        Repository::invalidate(self, root, files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database;
+    use crate::library;
+    use crate::rating;
+    use crate::visual;
+
+    fn scanned(path: PathBuf) -> ScannedFile {
+        ScannedFile {
+            path,
+            media_type: MediaType::Photo,
+            fs_created_at: None,
+            fs_modified_at: None,
+            fs_file_size_bytes: 1024,
+            fingerprint: None,
+        }
+    }
+
+    /// EXIF data for a picture taken at a local time without an offset.
+    fn exif_data(date_time: &str, is_in_sydney: bool) -> Vec<u8> {
+        use exif::experimental::Writer;
+        use exif::{Field, In, Tag, Value};
+
+        let field = |tag, value| Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value,
+        };
+        let ascii = |x: &str| Value::Ascii(vec![x.as_bytes().to_vec()]);
+        let dms = |d, m, s| Value::Rational(vec![(d, 1).into(), (m, 1).into(), (s, 1).into()]);
+
+        let mut fields = vec![field(Tag::DateTimeOriginal, ascii(date_time))];
+
+        if is_in_sydney {
+            // Sydney Opera House
+            fields.push(field(Tag::GPSLatitude, dms(33, 51, 24)));
+            fields.push(field(Tag::GPSLatitudeRef, ascii("S")));
+            fields.push(field(Tag::GPSLongitude, dms(151, 12, 55)));
+            fields.push(field(Tag::GPSLongitudeRef, ascii("E")));
+        }
+
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+
+        let mut data = std::io::Cursor::new(Vec::new());
+        writer.write(&mut data, false).unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn local_time_of_gps_tagged_picture() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+
+        let mut roots = library::Repository::open(con.clone()).unwrap();
+        roots.add(Path::new("/pictures")).unwrap();
+        let root = roots.all().unwrap().remove(0);
+
+        let mut repo =
+            Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+
+        let sydney_path = root.path.join("sydney.jpg");
+        let home_path = root.path.join("home.jpg");
+        repo.add_all(
+            &root,
+            &[scanned(sydney_path.clone()), scanned(home_path.clone())],
+        )
+        .unwrap();
+
+        let picture_id = |path: &Path| {
+            repo.all()
+                .unwrap()
+                .into_iter()
+                .find(|x| x.path == path)
+                .unwrap()
+                .picture_id
+        };
+        let sydney_id = picture_id(&sydney_path);
+        let home_id = picture_id(&home_path);
+
+        // 23:30 on the 31st of January in Sydney is 12:30 UTC, which is +11:00 in summer.
+        let sydney = metadata::from_raw(exif_data("2024:01:31 23:30:00", true)).unwrap();
+
+        // Taken later in local time, but earlier than the Sydney picture if offsets are mixed up.
+        let home = metadata::from_raw(exif_data("2024:02:01 09:00:00", false)).unwrap();
+
+        repo.add_metadatas(vec![(sydney_id, sydney), (home_id, home)])
+            .unwrap();
+
+        let offsets: Vec<Option<i32>> = {
+            let con = con.lock().unwrap();
+            let mut stmt = con
+                .prepare("SELECT exif_utc_offset FROM pictures WHERE picture_id IN (?1, ?2) ORDER BY picture_id = ?2")
+                .unwrap();
+            stmt.query_map([sydney_id.id(), home_id.id()], |row| row.get(0))
+                .unwrap()
+                .flatten()
+                .collect()
+        };
+        assert_eq!(offsets, vec![Some(11 * 3600), None]);
+
+        let paths: Vec<PathBuf> = repo.all().unwrap().into_iter().map(|x| x.path).collect();
+        assert_eq!(paths, vec![sydney_path.clone(), home_path]);
+
+        let visuals = crate::visual::Repository::open(Path::new("/cache"), con.clone())
+            .unwrap()
+            .all()
+            .unwrap();
+        let sydney = visuals
+            .iter()
+            .find(|x| x.picture_id == Some(sydney_id))
+            .unwrap();
+        assert_eq!(
+            sydney.year_month(),
+            crate::YearMonth::new(2024, chrono::Month::January)
+        );
+    }
+
+    #[test]
+    fn invalidate_sidecars_rescans_metadata() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+
+        let mut roots = library::Repository::open(con.clone()).unwrap();
+        roots.add(Path::new("/pictures")).unwrap();
+        let root = roots.all().unwrap().remove(0);
+
+        let mut repo =
+            Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+
+        let path = root.path.join("beach.jpg");
+        repo.add_all(&root, &[scanned(path.clone())]).unwrap();
+        let picture_id = repo.all().unwrap()[0].picture_id;
+
+        repo.add_metadatas(vec![(picture_id, Metadata::default())])
+            .unwrap();
+        assert!(repo.find_need_metadata_update().unwrap().is_empty());
+        assert_eq!(repo.find_sidecar_stamps(&root).unwrap()[&path], None);
+
+        let stamp = FileStamp {
+            fs_modified_at: chrono::DateTime::from_timestamp(1_700_000_000, 0),
+            fs_file_size_bytes: Some(512),
+        };
+        repo.invalidate_sidecars(&root, &[(path.clone(), Some(stamp))])
+            .unwrap();
+
+        assert_eq!(repo.find_need_metadata_update().unwrap().len(), 1);
+        assert_eq!(repo.find_sidecar_stamps(&root).unwrap()[&path], Some(stamp));
+    }
+
+    #[test]
+    fn rescan_keeps_user_rating() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+
+        let mut roots = library::Repository::open(con.clone()).unwrap();
+        roots.add(Path::new("/pictures")).unwrap();
+        let root = roots.all().unwrap().remove(0);
+
+        let mut repo =
+            Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+
+        repo.add_all(&root, &[scanned(root.path.join("sunset.jpg"))])
+            .unwrap();
+        let picture_id = repo.all().unwrap()[0].picture_id;
+
+        let rated = |stars| Metadata {
+            rating: Some(stars),
+            ..Metadata::default()
+        };
+
+        let visual_repo = visual::Repository::open(Path::new("/cache"), con.clone()).unwrap();
+
+        // Embedded rating is imported when there isn't one yet.
+        repo.add_metadatas(vec![(picture_id, rated(2))]).unwrap();
+        let visual = visual_repo.all().unwrap().remove(0);
+        assert_eq!(visual.rating, Some(2));
+
+        let mut ratings = rating::Repository::open(con.clone()).unwrap();
+        ratings.set_rating(&visual, Some(5)).unwrap();
+
+        // Rescanning a picture with a different embedded rating keeps the user's rating.
+        repo.add_metadatas(vec![(picture_id, rated(3))]).unwrap();
+        assert_eq!(visual_repo.all().unwrap()[0].rating, Some(5));
+    }
+
+    #[test]
+    fn invalidate_keeps_confirmed_faces() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+
+        let mut roots = library::Repository::open(con.clone()).unwrap();
+        roots.add(Path::new("/pictures")).unwrap();
+        let root = roots.all().unwrap().remove(0);
+
+        let mut repo =
+            Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+
+        let pic = scanned(root.path.join("party.jpg"));
+        repo.add_all(&root, std::slice::from_ref(&pic)).unwrap();
+        let picture_id = repo.all().unwrap()[0].picture_id;
+
+        {
+            let con = con.lock().unwrap();
+            con.execute(
+                "INSERT INTO people (person_id, thumbnail_path, name) VALUES (1, 'person.png', 'Alice')",
+                [],
+            )
+            .unwrap();
+
+            let mut insert_face = con
+                .prepare(
+                    "INSERT INTO pictures_faces (
+                        face_id, model_name, picture_id, is_confirmed, person_id,
+                        thumbnail_path, bounds_path,
+                        bounds_x, bounds_y, bounds_width, bounds_height,
+                        right_eye_x, right_eye_y, left_eye_x, left_eye_y, nose_x, nose_y,
+                        right_mouth_corner_x, right_mouth_corner_y,
+                        left_mouth_corner_x, left_mouth_corner_y,
+                        confidence
+                    ) VALUES (
+                        ?1, 'test', ?2, ?3, ?4, ?5, ?6,
+                        0, 0, 10, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0.9
+                    )",
+                )
+                .unwrap();
+
+            insert_face
+                .execute(params![
+                    1,
+                    picture_id.id(),
+                    true,
+                    1,
+                    "1_thumb.png",
+                    "1_bounds.png"
+                ])
+                .unwrap();
+            insert_face
+                .execute(params![
+                    2,
+                    picture_id.id(),
+                    false,
+                    None::<i64>,
+                    "2_thumb.png",
+                    "2_bounds.png"
+                ])
+                .unwrap();
+        }
+
+        let cleanup_paths = repo.invalidate(&root, &[pic]).unwrap();
+
+        let faces: Vec<(i64, Option<i64>)> = {
+            let con = con.lock().unwrap();
+            let mut stmt = con
+                .prepare("SELECT face_id, person_id FROM pictures_faces ORDER BY face_id")
+                .unwrap();
+            stmt.query_map([], |row| {
+                std::result::Result::Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap()
+            .flatten()
+            .collect()
+        };
+
+        assert_eq!(faces, vec![(1, Some(1))]);
+
+        // Images of the confirmed face must not be deleted.
+        assert!(cleanup_paths.contains(&PathBuf::from("/data/2_thumb.png")));
+        assert!(!cleanup_paths.contains(&PathBuf::from("/data/1_thumb.png")));
+        assert!(!cleanup_paths.contains(&PathBuf::from("/data/1_bounds.png")));
+    }
+}
@@ -2,7 +2,10 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use crate::edit::{self, Edit};
+use crate::photo::animation;
 use crate::photo::model::PictureId;
+use crate::photo::raw;
 use anyhow::*;
 
 use image::codecs::png::PngEncoder;
@@ -43,7 +46,13 @@ impl Thumbnailer {
 
     /// Computes a preview square for an image that has been inserted
     /// into the Repository. Preview image will be written to file system and path returned.
-    pub async fn thumbnail(&self, picture_id: &PictureId, picture_path: &Path) -> Result<PathBuf> {
+    /// The edit, if any, is applied before the preview square is computed.
+    pub async fn thumbnail(
+        &self,
+        picture_id: &PictureId,
+        picture_path: &Path,
+        edit: &Edit,
+    ) -> Result<PathBuf> {
         let thumbnail_path = {
             // Create a directory per 1000 thumbnails
             let partition = (picture_id.id() / 1000) as i32;
@@ -57,13 +66,28 @@ impl Thumbnailer {
         }
 
         debug!("Generating thumbnail: {:?}", picture_path);
-        Self::sandboxed_thumbnail_async(picture_path, &thumbnail_path).await?;
+        if raw::is_raw(picture_path) {
+            Self::raw_thumbnail(picture_path, &thumbnail_path, edit).await?;
+        } else {
+            Self::sandboxed_edited_thumbnail_async(picture_path, &thumbnail_path, edit).await?;
+        }
         Ok(thumbnail_path)
     }
 
+    /// Generate a thumbnail from the preview of a camera RAW file.
+    async fn raw_thumbnail(source_path: &Path, thumbnail_path: &Path, edit: &Edit) -> Result<()> {
+        let image = edit::render::load(source_path).await?;
+
+        let png_file = tempfile::Builder::new().suffix(".png").tempfile()?;
+        image.save(png_file.path())?;
+
+        Self::trusted_thumbnail(png_file.path(), thumbnail_path, edit)
+    }
+
     /// Generate a thumbnail from a file that has already been processed in a Glycin sandbox.
-    fn trusted_thumbnail(path: &Path, thumbnail_path: &Path) -> Result<()> {
-        let src_image = ImageReader::open(path)?.decode()?.into_rgb8();
+    fn trusted_thumbnail(path: &Path, thumbnail_path: &Path, edit: &Edit) -> Result<()> {
+        let src_image = ImageReader::open(path)?.decode()?;
+        let src_image = edit::render::apply(edit, src_image).into_rgb8();
 
         // WARNING src_image, dst_image, and the PngEncoder must all
         // use the _same_ pixel type or the PngEncoder will throw errors
@@ -117,6 +141,14 @@ impl Thumbnailer {
     pub async fn sandboxed_thumbnail_async(
         source_path: &Path,
         thumbnail_path: &Path,
+    ) -> Result<()> {
+        Self::sandboxed_edited_thumbnail_async(source_path, thumbnail_path, &Edit::default()).await
+    }
+
+    async fn sandboxed_edited_thumbnail_async(
+        source_path: &Path,
+        thumbnail_path: &Path,
+        edit: &Edit,
     ) -> Result<()> {
         let file = gio::File::for_path(source_path);
 
@@ -125,12 +157,23 @@ impl Thumbnailer {
 
         let image = loader.load().await?;
 
-        let frame = image.next_frame().await?;
+        let mut frame = image.next_frame().await?;
+
+        // The first frame of an animation is often blank or a title card,
+        // so a frame from the middle is more representative.
+        let frame_count = animation::frame_count(source_path)
+            .ok()
+            .flatten()
+            .unwrap_or(1);
+
+        for _ in 0..frame_count / 2 {
+            frame = image.next_frame().await?;
+        }
 
         let png_file = tempfile::Builder::new().suffix(".png").tempfile()?;
 
         frame.texture().save_to_png(png_file.path())?;
 
-        Self::trusted_thumbnail(png_file.path(), thumbnail_path)
+        Self::trusted_thumbnail(png_file.path(), thumbnail_path, edit)
     }
 }
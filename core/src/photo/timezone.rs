@@ -0,0 +1,67 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// Time zone of where a picture was taken, found from its GPS coordinates.
+// Uses the time zone boundaries embedded in tzf-rs, so works offline.
+
+use super::gps::GPSLocation;
+use chrono::{FixedOffset, NaiveDateTime, Offset, TimeZone};
+use chrono_tz::Tz;
+use std::sync::OnceLock;
+use tzf_rs::DefaultFinder;
+
+/// Loading the boundaries is slow, so only do it once.
+fn finder() -> &'static DefaultFinder {
+    static FINDER: OnceLock<DefaultFinder> = OnceLock::new();
+    FINDER.get_or_init(DefaultFinder::new)
+}
+
+/// Time zone at a location, if the location is valid and within a time zone.
+pub fn time_zone(location: &GPSLocation) -> Option<Tz> {
+    let latitude = location.latitude.to_f64_safe()?;
+    let longitude = location.longitude.to_f64_safe()?;
+    finder().get_tz_name(longitude, latitude).parse().ok()
+}
+
+/// Offset from UTC at a location for a local date and time, allowing for daylight saving.
+/// An ambiguous local time, such as when clocks go back, takes the earlier offset.
+pub fn offset_for_local(location: &GPSLocation, local: &NaiveDateTime) -> Option<FixedOffset> {
+    let tz = time_zone(location)?;
+    tz.offset_from_local_datetime(local)
+        .earliest()
+        .map(|offset| offset.fix())
+}
+
+/// Offset from UTC at a location for a UTC date and time, allowing for daylight saving.
+pub fn offset_for_utc(location: &GPSLocation, utc: &NaiveDateTime) -> Option<FixedOffset> {
+    let tz = time_zone(location)?;
+    Some(tz.offset_from_utc_datetime(utc).fix())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_for_local() {
+        // Sydney Opera House, which has daylight saving in the southern summer.
+        let sydney = GPSLocation::for_decimal(-33.8568, 151.2153);
+
+        let summer = NaiveDateTime::parse_from_str("2024-01-15 23:30:00", "%F %T").unwrap();
+        let winter = NaiveDateTime::parse_from_str("2024-07-15 23:30:00", "%F %T").unwrap();
+
+        assert_eq!(
+            offset_for_local(&sydney, &summer),
+            FixedOffset::east_opt(11 * 3600)
+        );
+        assert_eq!(
+            offset_for_local(&sydney, &winter),
+            FixedOffset::east_opt(10 * 3600)
+        );
+        assert_eq!(
+            offset_for_utc(&sydney, &summer),
+            FixedOffset::east_opt(11 * 3600)
+        );
+    }
+}
@@ -0,0 +1,926 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::gps::GPSLocation;
+use super::model::PictureSidecar;
+use anyhow::*;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Offset, TimeZone, Utc};
+use image::ImageReader;
+use roxmltree::{Document, Node};
+use std::fs;
+use std::io::{Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::result::Result::Ok;
+use tempfile::NamedTempFile;
+
+const NS_RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const NS_XMP: &str = "http://ns.adobe.com/xap/1.0/";
+const NS_DC: &str = "http://purl.org/dc/elements/1.1/";
+const NS_EXIF: &str = "http://ns.adobe.com/exif/1.0/";
+const NS_MWG_RS: &str = "http://www.metadataworkinggroup.com/schemas/regions/";
+const NS_ST_AREA: &str = "http://ns.adobe.com/xmp/sType/Area#";
+const NS_ST_DIM: &str = "http://ns.adobe.com/xap/1.0/sType/Dimensions#";
+const NS_MP_RI: &str = "http://ns.microsoft.com/photo/1.2/t/RegionInfo#";
+const NS_MP_REG: &str = "http://ns.microsoft.com/photo/1.2/t/Region#";
+
+/// Sidecar with no properties, for adding properties to.
+const EMPTY_SIDECAR: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="Fotema">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""/>
+ </rdf:RDF>
+</x:xmpmeta>
+"#;
+
+/// An area of a picture, such as a face, as a Metadata Working Group region.
+/// Coordinates are fractions of the width and height of the picture as shown,
+/// and x and y are the centre of the area.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    /// Name of person, if known.
+    pub name: Option<String>,
+
+    pub x: f64,
+
+    pub y: f64,
+
+    pub width: f64,
+
+    pub height: f64,
+}
+
+/// Metadata read from or written to XMP.
+/// When writing, properties without a value are left as they are.
+#[derive(Debug, Clone, Default)]
+pub struct XmpMetadata {
+    /// xmp:Rating. Zero if explicitly unrated.
+    pub rating: Option<u8>,
+
+    /// exif:DateTimeOriginal. Like EXIF timestamps, the local time is saved as if it were UTC.
+    pub created_at: Option<DateTime<FixedOffset>>,
+
+    /// exif:GPSLatitude and exif:GPSLongitude.
+    pub location: Option<GPSLocation>,
+
+    /// dc:subject keywords.
+    pub subjects: Vec<String>,
+
+    /// mwg-rs:AppliedToDimensions. Width and height in pixels of the picture that
+    /// face regions were found in.
+    pub region_dimensions: Option<(u32, u32)>,
+
+    /// mwg-rs:RegionList regions with a type of Face, or else Microsoft MP:RegionInfo regions.
+    /// None if there are no regions, whereas an empty list removes existing regions when written.
+    pub face_regions: Option<Vec<Region>>,
+}
+
+/// How much of a file to search for an embedded XMP packet.
+/// Packets are near the start of JPEG, PNG, and HEIC files.
+const EMBEDDED_SEARCH_BYTES: u64 = 256 * 1024;
+
+/// Possible sidecar paths for a file, in order of preference.
+/// darktable and digiKam append ".xmp" to the file name, whereas Lightroom
+/// replaces the extension.
+pub fn sidecar_paths(path: &Path) -> Vec<PathBuf> {
+    let mut appended = path.as_os_str().to_os_string();
+    appended.push(".xmp");
+
+    vec![
+        PathBuf::from(appended),
+        path.with_extension("xmp"),
+        path.with_extension("XMP"),
+    ]
+}
+
+/// Path of an existing sidecar for a file.
+pub fn find_sidecar(path: &Path) -> Option<PathBuf> {
+    sidecar_paths(path).into_iter().find(|p| p.is_file())
+}
+
+/// Reads an XMP packet embedded near the start of a file.
+pub fn embedded_packet(path: &Path) -> Result<Option<String>> {
+    let mut data = Vec::new();
+    fs::File::open(path)?
+        .take(EMBEDDED_SEARCH_BYTES)
+        .read_to_end(&mut data)?;
+
+    let start_tag = b"<x:xmpmeta";
+    let end_tag = b"</x:xmpmeta>";
+
+    let Some(start) = find(&data, start_tag) else {
+        return Ok(None);
+    };
+
+    let Some(len) = find(&data[start..], end_tag) else {
+        return Ok(None);
+    };
+
+    let packet = &data[start..start + len + end_tag.len()];
+    Ok(Some(String::from_utf8_lossy(packet).to_string()))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Reads xmp:Rating for a file. A sidecar takes precedence over an embedded packet
+/// because it is what photo management tools update.
+/// Returns Some(0) if the file is explicitly unrated or rejected.
+pub fn read_rating(path: &Path, include_embedded: bool) -> Result<Option<u8>> {
+    if let Some(sidecar) = find_sidecar(path) {
+        let xml = fs::read_to_string(&sidecar)?;
+        if let Some(rating) = parse_rating(&xml) {
+            return Ok(Some(rating));
+        }
+    }
+
+    if include_embedded {
+        if let Some(xml) = embedded_packet(path)? {
+            return Ok(parse_rating(&xml));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Writes xmp:Rating to the sidecar of a file, creating the sidecar if necessary.
+/// A rating of None is written as zero, which means unrated.
+pub fn write_rating(path: &Path, stars: Option<u8>) -> Result<()> {
+    let rating = stars.unwrap_or(0).min(5);
+
+    let (sidecar, xml) = match find_sidecar(path) {
+        Some(sidecar) => {
+            let xml = fs::read_to_string(&sidecar)?;
+            let xml = set_rating(&xml, rating)?;
+            (sidecar, xml)
+        }
+        None => {
+            let sidecar = sidecar_paths(path).remove(0);
+            (sidecar, new_sidecar(rating))
+        }
+    };
+
+    persist(&sidecar, &xml)
+}
+
+fn persist(sidecar: &Path, xml: &str) -> Result<()> {
+    let dir = sidecar.parent().ok_or_else(|| anyhow!("No parent for {:?}", sidecar))?;
+
+    // Write then rename so a sidecar is never left half written.
+    let mut file = NamedTempFile::new_in(dir)?;
+    file.write_all(xml.as_bytes())?;
+    file.persist(sidecar)?;
+
+    Ok(())
+}
+
+/// Parses xmp:Rating from an XMP document, whether it is an attribute or an element.
+pub fn parse_rating(xml: &str) -> Option<u8> {
+    let doc = Document::parse(xml).ok()?;
+    rating(&doc)
+}
+
+fn rating(doc: &Document) -> Option<u8> {
+    let value = property(&doc.root(), NS_XMP, "Rating")?;
+
+    // Rejected pictures have a rating of -1.
+    let rating = value.parse::<f64>().ok()?;
+    Some(rating.round().clamp(0.0, 5.0) as u8)
+}
+
+/// Reads metadata from the sidecar of a file. None if the file has no sidecar.
+pub fn read_sidecar(path: &Path) -> Result<Option<XmpMetadata>> {
+    let Some(sidecar) = find_sidecar(path) else {
+        return Ok(None);
+    };
+
+    let xml = fs::read_to_string(&sidecar)?;
+    parse_metadata(&xml).map(Some)
+}
+
+/// Parses the metadata Fotema understands from an XMP document.
+pub fn parse_metadata(xml: &str) -> Result<XmpMetadata> {
+    let doc = Document::parse(xml)?;
+    let root = doc.root();
+
+    let created_at = property(&root, NS_EXIF, "DateTimeOriginal").and_then(|x| parse_date(&x));
+
+    let location = match (
+        property(&root, NS_EXIF, "GPSLatitude"),
+        property(&root, NS_EXIF, "GPSLongitude"),
+    ) {
+        (Some(latitude), Some(longitude)) => GPSLocation::for_xmp(&latitude, &longitude),
+        _ => None,
+    };
+
+    let subjects = root
+        .descendants()
+        .find(|n| n.has_tag_name((NS_DC, "subject")))
+        .map(|n| {
+            n.descendants()
+                .filter(|x| x.has_tag_name((NS_RDF, "li")))
+                .filter_map(|x| x.text())
+                .map(|x| x.trim().to_string())
+                .filter(|x| !x.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let regions = root
+        .descendants()
+        .find(|n| n.has_tag_name((NS_MWG_RS, "Regions")));
+
+    let region_dimensions = regions.and_then(|regions| {
+        let dimensions = regions
+            .descendants()
+            .find(|n| n.has_tag_name((NS_MWG_RS, "AppliedToDimensions")))?;
+        let width = property(&dimensions, NS_ST_DIM, "w")?.parse::<f64>().ok()?;
+        let height = property(&dimensions, NS_ST_DIM, "h")?.parse::<f64>().ok()?;
+        Some((width as u32, height as u32))
+    });
+
+    let face_regions = regions.map(|regions| {
+        regions
+            .descendants()
+            .filter(|n| n.has_tag_name((NS_RDF, "li")))
+            .filter(|n| property(n, NS_MWG_RS, "Type").is_some_and(|t| t == "Face"))
+            .filter_map(|n| to_region(&n))
+            .collect()
+    });
+
+    // Windows Photo Gallery and older versions of Picasa only write Microsoft regions.
+    let face_regions = face_regions.or_else(|| {
+        let regions = root
+            .descendants()
+            .find(|n| n.has_tag_name((NS_MP_RI, "Regions")))?;
+        let regions = regions
+            .descendants()
+            .filter(|n| n.has_tag_name((NS_RDF, "li")))
+            .filter_map(|n| to_microsoft_region(&n))
+            .collect();
+        Some(regions)
+    });
+
+    Ok(XmpMetadata {
+        rating: rating(&doc),
+        created_at,
+        location,
+        subjects,
+        region_dimensions,
+        face_regions,
+    })
+}
+
+fn to_region(item: &Node) -> Option<Region> {
+    // Pixel units would need the dimensions to convert, but MWG requires normalized units.
+    if property(item, NS_ST_AREA, "unit").is_some_and(|x| x != "normalized") {
+        return None;
+    }
+
+    let coordinate = |name| property(item, NS_ST_AREA, name).and_then(|x| x.parse::<f64>().ok());
+
+    Some(Region {
+        name: property(item, NS_MWG_RS, "Name").filter(|x| !x.is_empty()),
+        x: coordinate("x")?,
+        y: coordinate("y")?,
+        width: coordinate("w")?,
+        height: coordinate("h")?,
+    })
+}
+
+fn to_microsoft_region(item: &Node) -> Option<Region> {
+    // Rectangle is the left, top, width, and height as fractions of the picture.
+    let rectangle = property(item, NS_MP_REG, "Rectangle")?;
+    let values: Vec<f64> = rectangle
+        .split(',')
+        .map(|x| x.trim().parse::<f64>())
+        .collect::<std::result::Result<_, _>>()
+        .ok()?;
+
+    let [left, top, width, height] = values[..] else {
+        return None;
+    };
+
+    Some(Region {
+        name: property(item, NS_MP_REG, "PersonDisplayName").filter(|x| !x.is_empty()),
+        x: left + width / 2.0,
+        y: top + height / 2.0,
+        width,
+        height,
+    })
+}
+
+/// Named face regions for a file, such as those written by digiKam, Lightroom, or
+/// Windows Photo Gallery. A sidecar takes precedence over an embedded packet.
+pub fn read_face_regions(path: &Path) -> Result<Vec<Region>> {
+    let mut metadata = read_sidecar(path)?.filter(|x| x.face_regions.is_some());
+
+    if metadata.is_none() {
+        if let Some(xml) = embedded_packet(path)? {
+            metadata = Some(parse_metadata(&xml)?);
+        }
+    }
+
+    let regions = metadata
+        .and_then(|x| x.face_regions)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|x| x.name.is_some())
+        .filter(|x| x.width > 0.0 && x.height > 0.0)
+        .collect();
+
+    Ok(regions)
+}
+
+/// Value of a property of a node or its descendants, whether it is an attribute or an element.
+fn property(node: &Node, namespace: &str, name: &str) -> Option<String> {
+    node.descendants().find_map(|n| {
+        if let Some(value) = n.attribute((namespace, name)) {
+            return Some(value.trim().to_string());
+        }
+        if n.has_tag_name((namespace, name)) {
+            return n.text().map(|x| x.trim().to_string());
+        }
+        None
+    })
+}
+
+/// Parses an XMP date. Like EXIF timestamps, the local time is kept as if it were UTC.
+fn parse_date(value: &str) -> Option<DateTime<FixedOffset>> {
+    let (naive, offset) = match DateTime::parse_from_rfc3339(value) {
+        Ok(date_time) => (date_time.naive_local(), *date_time.offset()),
+        Err(_) => {
+            // Time zone and seconds are optional in XMP dates.
+            let naive = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+                .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M"))
+                .ok()?;
+            (naive, FixedOffset::east_opt(0)?)
+        }
+    };
+
+    Some(offset.from_utc_datetime(&naive))
+}
+
+/// Writes metadata Fotema knows about a picture to its sidecar, creating the sidecar if necessary.
+pub fn write_sidecar(sidecar: &PictureSidecar) -> Result<()> {
+    let utc_offset = sidecar.utc_offset.unwrap_or(Utc.fix());
+
+    let mut metadata = XmpMetadata {
+        rating: sidecar.rating,
+        created_at: sidecar
+            .created_at
+            .map(|x| utc_offset.from_utc_datetime(&x.naive_utc())),
+        location: sidecar
+            .location
+            .map(|(latitude, longitude)| GPSLocation::for_decimal(latitude, longitude)),
+        subjects: sidecar.keywords.clone(),
+        ..XmpMetadata::default()
+    };
+
+    // People are also keywords so that tools without face regions can find them.
+    for (name, _) in sidecar.faces.iter() {
+        if !metadata.subjects.contains(name) {
+            metadata.subjects.push(name.clone());
+        }
+    }
+
+    if sidecar.faces.is_empty() {
+        // Remove regions of faces that are no longer confirmed as people, but only
+        // if Fotema has written the sidecar before so regions from other tools are kept.
+        if sidecar.written_digest.is_some() {
+            metadata.face_regions = Some(Vec::new());
+        }
+    } else {
+        // Faces were detected in the picture as shown, which for some orientations
+        // has the width and height of the stored picture swapped.
+        let (width, height) = ImageReader::open(&sidecar.path)?
+            .with_guessed_format()?
+            .into_dimensions()?;
+
+        let (width, height) = if sidecar.orientation.is_some_and(|x| x.is_transposed()) {
+            (height, width)
+        } else {
+            (width, height)
+        };
+
+        metadata.region_dimensions = Some((width, height));
+        metadata.face_regions = Some(
+            sidecar
+                .faces
+                .iter()
+                .map(|(name, bounds)| Region {
+                    name: Some(name.clone()),
+                    x: (bounds.x + bounds.width / 2.0) as f64 / width as f64,
+                    y: (bounds.y + bounds.height / 2.0) as f64 / height as f64,
+                    width: bounds.width as f64 / width as f64,
+                    height: bounds.height as f64 / height as f64,
+                })
+                .collect(),
+        );
+    }
+
+    write_metadata(&sidecar.path, &metadata)
+}
+
+/// Writes metadata to the sidecar of a file, creating the sidecar if necessary.
+/// Properties in an existing sidecar are kept unless replaced.
+pub fn write_metadata(path: &Path, metadata: &XmpMetadata) -> Result<()> {
+    let (sidecar, xml) = match find_sidecar(path) {
+        Some(sidecar) => {
+            let xml = fs::read_to_string(&sidecar)?;
+            (sidecar, xml)
+        }
+        None => (sidecar_paths(path).remove(0), EMPTY_SIDECAR.to_string()),
+    };
+
+    let xml = set_metadata(&xml, metadata)?;
+    persist(&sidecar, &xml)
+}
+
+/// Sets properties in an XMP document, preserving everything else in the document.
+fn set_metadata(xml: &str, metadata: &XmpMetadata) -> Result<String> {
+    let doc = Document::parse(xml)?;
+
+    let descriptions: Vec<Node> = doc
+        .descendants()
+        .filter(|n| n.has_tag_name((NS_RDF, "Description")))
+        .filter(|n| n.parent().is_some_and(|p| p.has_tag_name((NS_RDF, "RDF"))))
+        .collect();
+
+    let description = descriptions
+        .first()
+        .ok_or_else(|| anyhow!("No rdf:Description in XMP"))?;
+
+    // Properties to replace, by namespace and name, and as XML to insert.
+    let mut replaced: Vec<(&str, &str)> = Vec::new();
+    let mut properties = String::new();
+
+    if let Some(rating) = metadata.rating {
+        replaced.push((NS_XMP, "Rating"));
+        properties.push_str(&format!(
+            "\n   <xmp:Rating{}>{}</xmp:Rating>",
+            declare(description, &[("xmp", NS_XMP)]),
+            rating.min(5)
+        ));
+    }
+
+    if let Some(created_at) = metadata.created_at {
+        // Time zone is only written if known, as a zero offset usually means it is unknown.
+        let offset = if created_at.offset().local_minus_utc() == 0 {
+            String::new()
+        } else {
+            created_at.offset().to_string()
+        };
+
+        replaced.push((NS_EXIF, "DateTimeOriginal"));
+        properties.push_str(&format!(
+            "\n   <exif:DateTimeOriginal{}>{}{}</exif:DateTimeOriginal>",
+            declare(description, &[("exif", NS_EXIF)]),
+            created_at.naive_utc().format("%Y-%m-%dT%H:%M:%S"),
+            offset
+        ));
+    }
+
+    if let Some(location) = metadata.location {
+        replaced.push((NS_EXIF, "GPSLatitude"));
+        replaced.push((NS_EXIF, "GPSLongitude"));
+        let namespaces = declare(description, &[("exif", NS_EXIF)]);
+        properties.push_str(&format!(
+            "\n   <exif:GPSLatitude{}>{}</exif:GPSLatitude>\n   <exif:GPSLongitude{}>{}</exif:GPSLongitude>",
+            namespaces,
+            to_gps_coordinate(location.latitude.to_f64(), 'N', 'S'),
+            namespaces,
+            to_gps_coordinate(location.longitude.to_f64(), 'E', 'W'),
+        ));
+    }
+
+    if !metadata.subjects.is_empty() {
+        replaced.push((NS_DC, "subject"));
+        properties.push_str(&format!(
+            "\n   <dc:subject{}>\n    <rdf:Bag>",
+            declare(description, &[("dc", NS_DC), ("rdf", NS_RDF)])
+        ));
+        for subject in metadata.subjects.iter() {
+            properties.push_str(&format!("\n     <rdf:li>{}</rdf:li>", escape(subject)));
+        }
+        properties.push_str("\n    </rdf:Bag>\n   </dc:subject>");
+    }
+
+    if let Some(ref regions) = metadata.face_regions {
+        replaced.push((NS_MWG_RS, "Regions"));
+
+        // Region coordinates are meaningless without the dimensions they apply to.
+        if let (Some((width, height)), false) = (metadata.region_dimensions, regions.is_empty()) {
+            let namespaces = declare(
+                description,
+                &[
+                    ("mwg-rs", NS_MWG_RS),
+                    ("stArea", NS_ST_AREA),
+                    ("stDim", NS_ST_DIM),
+                    ("rdf", NS_RDF),
+                ],
+            );
+
+            properties.push_str(&format!(
+                "\n   <mwg-rs:Regions rdf:parseType=\"Resource\"{}>",
+                namespaces
+            ));
+            properties.push_str(&format!(
+                "\n    <mwg-rs:AppliedToDimensions stDim:w=\"{}\" stDim:h=\"{}\" stDim:unit=\"pixel\"/>",
+                width, height
+            ));
+            properties.push_str("\n    <mwg-rs:RegionList>\n     <rdf:Bag>");
+
+            for region in regions.iter() {
+                let name = region
+                    .name
+                    .as_ref()
+                    .map(|x| format!(" mwg-rs:Name=\"{}\"", escape(x)))
+                    .unwrap_or_default();
+
+                properties.push_str("\n      <rdf:li>");
+                properties.push_str(&format!(
+                    "\n       <rdf:Description{} mwg-rs:Type=\"Face\">",
+                    name
+                ));
+                properties.push_str(&format!(
+                    "\n        <mwg-rs:Area stArea:x=\"{:.6}\" stArea:y=\"{:.6}\" stArea:w=\"{:.6}\" stArea:h=\"{:.6}\" stArea:unit=\"normalized\"/>",
+                    region.x, region.y, region.width, region.height,
+                ));
+                properties.push_str("\n       </rdf:Description>\n      </rdf:li>");
+            }
+
+            properties.push_str("\n     </rdf:Bag>\n    </mwg-rs:RegionList>\n   </mwg-rs:Regions>");
+        }
+    }
+
+    if replaced.is_empty() {
+        return Ok(xml.to_string());
+    }
+
+    let is_replaced = |namespace: Option<&str>, name: &str| {
+        replaced.iter().any(|(ns, n)| namespace == Some(*ns) && name == *n)
+    };
+
+    // Edits to make, as ranges of the document to replace.
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+
+    for node in descriptions.iter() {
+        for attr in node.attributes().filter(|a| is_replaced(a.namespace(), a.name())) {
+            edits.push((attr.range(), String::new()));
+        }
+
+        for child in node
+            .children()
+            .filter(|c| is_replaced(c.tag_name().namespace(), c.tag_name().name()))
+        {
+            edits.push((child.range(), String::new()));
+        }
+    }
+
+    let range = description.range();
+    let element = &xml[range.clone()];
+    let name_len = element[1..]
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .unwrap_or(0);
+    let qname = &element[1..1 + name_len];
+
+    if element.ends_with("/>") {
+        edits.push((
+            range.end - 2..range.end,
+            format!(">{}\n  </{}>", properties, qname),
+        ));
+    } else {
+        let closing = element
+            .rfind("</")
+            .ok_or_else(|| anyhow!("No closing tag for {}", qname))?;
+        let at = range.start + closing;
+        edits.push((at..at, format!("{}\n  ", properties)));
+    }
+
+    // Edit from the end of the document so that earlier ranges remain valid.
+    edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+
+    let mut xml = xml.to_string();
+    for (range, text) in edits {
+        xml.replace_range(range, &text);
+    }
+
+    Ok(xml)
+}
+
+/// Namespace declarations for prefixes that aren't already declared where properties are inserted.
+fn declare(description: &Node, namespaces: &[(&str, &str)]) -> String {
+    namespaces
+        .iter()
+        .filter(|(prefix, uri)| description.lookup_namespace_uri(Some(prefix)) != Some(*uri))
+        .map(|(prefix, uri)| format!(" xmlns:{}=\"{}\"", prefix, uri))
+        .collect()
+}
+
+/// Formats decimal degrees as an XMP GPSCoordinate, such as "51,30.123456N".
+fn to_gps_coordinate(value: f64, positive: char, negative: char) -> String {
+    let direction = if value < 0.0 { negative } else { positive };
+    let value = value.abs();
+    let degrees = value.trunc();
+    let minutes = (value - degrees) * 60.0;
+    format!("{},{:.6}{}", degrees as u32, minutes, direction)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Sets xmp:Rating in an XMP document, preserving everything else in the document.
+fn set_rating(xml: &str, rating: u8) -> Result<String> {
+    let doc = Document::parse(xml)?;
+    let value = rating.to_string();
+    let mut xml = xml.to_string();
+
+    for node in doc.descendants() {
+        if let Some(attr) = node.attributes().find(|a| a.namespace() == Some(NS_XMP) && a.name() == "Rating") {
+            xml.replace_range(attr.range_value(), &value);
+            return Ok(xml);
+        }
+
+        if node.has_tag_name((NS_XMP, "Rating")) {
+            match node.first_child().filter(|n| n.is_text()) {
+                Some(text) => xml.replace_range(text.range(), &value),
+                None => bail!("Empty xmp:Rating element"),
+            }
+            return Ok(xml);
+        }
+    }
+
+    let description = doc
+        .descendants()
+        .find(|n| n.has_tag_name((NS_RDF, "Description")))
+        .ok_or_else(|| anyhow!("No rdf:Description in XMP"))?;
+
+    let (insert_at, attrs) = rating_attributes(&description, &xml, &value);
+    xml.insert_str(insert_at, &attrs);
+
+    Ok(xml)
+}
+
+/// Position after the tag name of a description element, and the attributes to insert there.
+fn rating_attributes(description: &Node, xml: &str, value: &str) -> (usize, String) {
+    let start = description.range().start;
+    let name_len = xml[start..]
+        .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+        .unwrap_or(0);
+
+    let attrs = match description.lookup_prefix(NS_XMP) {
+        Some(prefix) => format!(" {}:Rating=\"{}\"", prefix, value),
+        None => format!(" xmlns:xmp=\"{}\" xmp:Rating=\"{}\"", NS_XMP, value),
+    };
+
+    (start + name_len, attrs)
+}
+
+fn new_sidecar(rating: u8) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="Fotema">
+ <rdf:RDF xmlns:rdf="{}">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="{}"
+    xmp:Rating="{}"/>
+ </rdf:RDF>
+</x:xmpmeta>
+"#,
+        NS_RDF, NS_XMP, rating
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DARKTABLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/" x:xmptk="XMP Core 4.4.0-Exiv2">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+    xmlns:darktable="http://darktable.sf.net/"
+    xmp:Rating="3"
+    darktable:xmp_version="5"/>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    const ELEMENT: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmlns:xap="http://ns.adobe.com/xap/1.0/">
+   <xap:Rating>4</xap:Rating>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    const NO_RATING: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about="" xmlns:dc="http://purl.org/dc/elements/1.1/">
+   <dc:format>image/jpeg</dc:format>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    const DIGIKAM: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:exif="http://ns.adobe.com/exif/1.0/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/"
+    xmlns:mwg-rs="http://www.metadataworkinggroup.com/schemas/regions/"
+    xmlns:stArea="http://ns.adobe.com/xmp/sType/Area#"
+    xmlns:stDim="http://ns.adobe.com/xap/1.0/sType/Dimensions#"
+    exif:DateTimeOriginal="2023-07-14T18:30:05+02:00"
+    exif:GPSLatitude="51,30.6N"
+    exif:GPSLongitude="0,7.5W">
+   <dc:subject>
+    <rdf:Bag>
+     <rdf:li>Holiday</rdf:li>
+     <rdf:li>Beach</rdf:li>
+    </rdf:Bag>
+   </dc:subject>
+   <mwg-rs:Regions rdf:parseType="Resource">
+    <mwg-rs:AppliedToDimensions stDim:w="4000" stDim:h="3000" stDim:unit="pixel"/>
+    <mwg-rs:RegionList>
+     <rdf:Bag>
+      <rdf:li>
+       <rdf:Description mwg-rs:Name="Alice" mwg-rs:Type="Face">
+        <mwg-rs:Area stArea:x="0.5" stArea:y="0.25" stArea:w="0.1" stArea:h="0.2" stArea:unit="normalized"/>
+       </rdf:Description>
+      </rdf:li>
+      <rdf:li>
+       <rdf:Description mwg-rs:Name="Sign" mwg-rs:Type="Focus">
+        <mwg-rs:Area stArea:x="0.1" stArea:y="0.1" stArea:w="0.1" stArea:h="0.1" stArea:unit="normalized"/>
+       </rdf:Description>
+      </rdf:li>
+     </rdf:Bag>
+    </mwg-rs:RegionList>
+   </mwg-rs:Regions>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+    #[test]
+    fn test_parse_metadata() {
+        let metadata = parse_metadata(DIGIKAM).unwrap();
+
+        assert_eq!(metadata.rating, None);
+        assert_eq!(
+            metadata.created_at.map(|x| x.naive_utc().to_string()),
+            Some("2023-07-14 18:30:05".to_string())
+        );
+
+        let location = metadata.location.unwrap();
+        assert!((location.latitude.to_f64() - 51.51).abs() < 1e-9);
+        assert!((location.longitude.to_f64() + 0.125).abs() < 1e-9);
+
+        assert_eq!(metadata.subjects, vec!["Holiday", "Beach"]);
+        assert_eq!(metadata.region_dimensions, Some((4000, 3000)));
+        assert_eq!(
+            metadata.face_regions,
+            Some(vec![Region {
+                name: Some("Alice".into()),
+                x: 0.5,
+                y: 0.25,
+                width: 0.1,
+                height: 0.2,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_microsoft_regions() {
+        let xml = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/">
+ <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+  <rdf:Description rdf:about=""
+    xmlns:MP="http://ns.microsoft.com/photo/1.2/"
+    xmlns:MPRI="http://ns.microsoft.com/photo/1.2/t/RegionInfo#"
+    xmlns:MPReg="http://ns.microsoft.com/photo/1.2/t/Region#">
+   <MP:RegionInfo rdf:parseType="Resource">
+    <MPRI:Regions>
+     <rdf:Bag>
+      <rdf:li MPReg:Rectangle="0.25, 0.5, 0.1, 0.2" MPReg:PersonDisplayName="Bob"/>
+      <rdf:li MPReg:Rectangle="0.1, 0.1" MPReg:PersonDisplayName="Broken"/>
+     </rdf:Bag>
+    </MPRI:Regions>
+   </MP:RegionInfo>
+  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>"#;
+
+        let regions = parse_metadata(xml).unwrap().face_regions.unwrap();
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].name, Some("Bob".into()));
+        assert!((regions[0].x - 0.3).abs() < 1e-9);
+        assert!((regions[0].y - 0.6).abs() < 1e-9);
+        assert!((regions[0].width - 0.1).abs() < 1e-9);
+        assert!((regions[0].height - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_set_metadata() {
+        let metadata = XmpMetadata {
+            rating: Some(2),
+            created_at: parse_date("2024-01-02T03:04:05"),
+            location: Some(GPSLocation::for_decimal(-33.5, 151.25)),
+            subjects: vec!["Bob & Carol".into()],
+            region_dimensions: Some((300, 200)),
+            face_regions: Some(vec![Region {
+                name: Some("Bob & Carol".into()),
+                x: 0.5,
+                y: 0.5,
+                width: 0.25,
+                height: 0.125,
+            }]),
+        };
+
+        // Properties are replaced rather than duplicated, and others are kept.
+        for xml in [DIGIKAM, DARKTABLE, EMPTY_SIDECAR] {
+            let xml = set_metadata(xml, &metadata).unwrap();
+            let parsed = parse_metadata(&xml).unwrap();
+
+            assert_eq!(parsed.rating, Some(2));
+            assert_eq!(parsed.created_at, metadata.created_at);
+            let location = parsed.location.unwrap();
+            assert!((location.latitude.to_f64() + 33.5).abs() < 1e-6);
+            assert!((location.longitude.to_f64() - 151.25).abs() < 1e-6);
+            assert_eq!(parsed.subjects, metadata.subjects);
+            assert_eq!(parsed.region_dimensions, metadata.region_dimensions);
+            assert_eq!(parsed.face_regions, metadata.face_regions);
+            assert_eq!(xml.matches("DateTimeOriginal").count(), 2);
+        }
+
+        let xml = set_metadata(DARKTABLE, &metadata).unwrap();
+        assert!(xml.contains("darktable:xmp_version=\"5\""));
+
+        // Time zone is kept when known.
+        let with_time_zone = XmpMetadata {
+            created_at: parse_date("2024-01-02T03:04:05+10:30"),
+            ..XmpMetadata::default()
+        };
+        let xml = set_metadata(EMPTY_SIDECAR, &with_time_zone).unwrap();
+        assert!(xml.contains("2024-01-02T03:04:05+10:30"));
+        assert_eq!(
+            parse_metadata(&xml).unwrap().created_at,
+            with_time_zone.created_at
+        );
+
+        // Nothing to set leaves the document as it is.
+        let xml = set_metadata(DIGIKAM, &XmpMetadata::default()).unwrap();
+        assert_eq!(xml, DIGIKAM);
+
+        // An empty list of regions removes the existing regions.
+        let remove_regions = XmpMetadata {
+            face_regions: Some(Vec::new()),
+            ..XmpMetadata::default()
+        };
+        let xml = set_metadata(DIGIKAM, &remove_regions).unwrap();
+        assert_eq!(parse_metadata(&xml).unwrap().face_regions, None);
+        assert!(xml.contains("<rdf:li>Holiday</rdf:li>"));
+    }
+
+    #[test]
+    fn test_parse_rating() {
+        assert_eq!(parse_rating(DARKTABLE), Some(3));
+        assert_eq!(parse_rating(ELEMENT), Some(4));
+        assert_eq!(parse_rating(NO_RATING), None);
+        assert_eq!(parse_rating(&DARKTABLE.replace("\"3\"", "\"-1\"")), Some(0));
+    }
+
+    #[test]
+    fn test_set_rating() {
+        let xml = set_rating(DARKTABLE, 5).unwrap();
+        assert_eq!(parse_rating(&xml), Some(5));
+        assert!(xml.contains("darktable:xmp_version=\"5\""));
+
+        let xml = set_rating(ELEMENT, 1).unwrap();
+        assert_eq!(parse_rating(&xml), Some(1));
+
+        let xml = set_rating(NO_RATING, 2).unwrap();
+        assert_eq!(parse_rating(&xml), Some(2));
+        assert!(xml.contains("<dc:format>image/jpeg</dc:format>"));
+    }
+
+    #[test]
+    fn test_write_and_read_sidecar() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("IMG_0001.JPG");
+        fs::write(&path, b"not really a jpeg").unwrap();
+
+        write_rating(&path, Some(4)).unwrap();
+        assert!(dir.path().join("IMG_0001.JPG.xmp").is_file());
+        assert_eq!(read_rating(&path, true).unwrap(), Some(4));
+
+        write_rating(&path, None).unwrap();
+        assert_eq!(read_rating(&path, true).unwrap(), Some(0));
+    }
+}
@@ -0,0 +1,78 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::visual::Visual;
+
+use anyhow::*;
+use rusqlite;
+use rusqlite::params;
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of favourites and star ratings.
+/// Repository is backed by a Sqlite database.
+///
+/// Favourites and ratings of a visual item with both a picture and a video,
+/// such as a live photo, are stored against the picture.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        let repo = Repository { con };
+        Ok(repo)
+    }
+
+    pub fn set_favourite(&mut self, visual: &Visual, is_favourite: bool) -> Result<()> {
+        let con = self.con.lock().unwrap();
+
+        if let Some(picture_id) = visual.picture_id {
+            let mut stmt = con.prepare_cached(
+                "INSERT INTO pictures_ratings (picture_id, is_favourite)
+                VALUES (?1, ?2)
+                ON CONFLICT (picture_id) DO UPDATE SET is_favourite = ?2",
+            )?;
+            stmt.execute(params![picture_id.id(), is_favourite])?;
+        } else if let Some(video_id) = visual.video_id {
+            let mut stmt = con.prepare_cached(
+                "INSERT INTO videos_ratings (video_id, is_favourite)
+                VALUES (?1, ?2)
+                ON CONFLICT (video_id) DO UPDATE SET is_favourite = ?2",
+            )?;
+            stmt.execute(params![video_id.id(), is_favourite])?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets star rating from 1 to 5, or clears rating if None.
+    pub fn set_rating(&mut self, visual: &Visual, stars: Option<u8>) -> Result<()> {
+        if stars.is_some_and(|x| !(1..=5).contains(&x)) {
+            bail!("Rating must be between 1 and 5 stars");
+        }
+
+        let con = self.con.lock().unwrap();
+
+        if let Some(picture_id) = visual.picture_id {
+            let mut stmt = con.prepare_cached(
+                "INSERT INTO pictures_ratings (picture_id, stars)
+                VALUES (?1, ?2)
+                ON CONFLICT (picture_id) DO UPDATE SET stars = ?2",
+            )?;
+            stmt.execute(params![picture_id.id(), stars])?;
+        } else if let Some(video_id) = visual.video_id {
+            let mut stmt = con.prepare_cached(
+                "INSERT INTO videos_ratings (video_id, stars)
+                VALUES (?1, ?2)
+                ON CONFLICT (video_id) DO UPDATE SET stars = ?2",
+            )?;
+            stmt.execute(params![video_id.id(), stars])?;
+        }
+
+        Ok(())
+    }
+}
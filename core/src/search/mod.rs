@@ -0,0 +1,9 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+pub mod query;
+pub mod repo;
+
+pub use query::Query;
+pub use repo::Repository;
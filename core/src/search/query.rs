@@ -0,0 +1,412 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use anyhow::*;
+use chrono::NaiveDate;
+use std::fmt::Display;
+use std::iter::Peekable;
+use std::str::Chars;
+use std::str::FromStr;
+
+/// Kind of visual item for "is:" terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Photo,
+    Video,
+    Selfie,
+    Motion,
+    Favourite,
+}
+
+/// A single condition of a search query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    /// Free text matched against file names, folder names, person names, keywords,
+    /// and descriptions.
+    Text(String),
+
+    /// "person:Alice"
+    Person(String),
+
+    /// "year:2023"
+    Year(i32),
+
+    /// "month:3"
+    Month(u32),
+
+    /// "month:2023-03"
+    YearMonth(i32, u32),
+
+    /// "after:2023-03-01" matches items on or after a date.
+    After(NaiveDate),
+
+    /// "before:2023-03-31" matches items on or before a date.
+    Before(NaiveDate),
+
+    /// "folder:Holidays"
+    Folder(String),
+
+    /// "is:video"
+    Kind(Kind),
+
+    /// "has:location"
+    HasLocation,
+
+    /// camera:"Pixel 7"
+    Camera(String),
+
+    /// lens:"50mm"
+    Lens(String),
+
+    /// "rating:4" matches items rated at least four stars.
+    Rating(u8),
+
+    /// "duration:>30" matches videos longer than a number of seconds.
+    LongerThan(u32),
+
+    /// "duration:<30" matches videos shorter than a number of seconds.
+    ShorterThan(u32),
+
+    /// Any term prefixed with a '-'. Such as "-is:selfie".
+    Not(Box<Term>),
+}
+
+/// A parsed search query. A visual item must match every term.
+///
+/// Terms are separated by whitespace and are either free text or a `key:value` pair.
+/// Values containing whitespace can be quoted, such as `camera:"Pixel 7"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pub terms: Vec<Term>,
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Query> {
+        let mut terms = Vec::new();
+
+        for token in tokenize(text) {
+            let term = match token.key {
+                Some(ref key) => to_term(key, &token.value)?,
+                None => Term::Text(token.value),
+            };
+
+            if token.is_negated {
+                terms.push(Term::Not(Box::new(term)));
+            } else {
+                terms.push(term);
+            }
+        }
+
+        Ok(Query { terms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Query::parse(s)
+    }
+}
+
+/// Formats a query as text that parses back to the same query,
+/// which is how smart albums are stored.
+impl Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let terms: Vec<String> = self.terms.iter().map(|t| t.to_string()).collect();
+        write!(f, "{}", terms.join(" "))
+    }
+}
+
+impl Display for Term {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Term::Text(text) => write!(f, "{}", quote(text)),
+            Term::Person(name) => write!(f, "person:{}", quote(name)),
+            Term::Year(year) => write!(f, "year:{}", year),
+            Term::Month(month) => write!(f, "month:{}", month),
+            Term::YearMonth(year, month) => write!(f, "month:{:04}-{:02}", year, month),
+            Term::After(date) => write!(f, "after:{}", date.format("%Y-%m-%d")),
+            Term::Before(date) => write!(f, "before:{}", date.format("%Y-%m-%d")),
+            Term::Folder(folder) => write!(f, "folder:{}", quote(folder)),
+            Term::Kind(Kind::Photo) => write!(f, "is:photo"),
+            Term::Kind(Kind::Video) => write!(f, "is:video"),
+            Term::Kind(Kind::Selfie) => write!(f, "is:selfie"),
+            Term::Kind(Kind::Motion) => write!(f, "is:motion"),
+            Term::Kind(Kind::Favourite) => write!(f, "is:favourite"),
+            Term::HasLocation => write!(f, "has:location"),
+            Term::Camera(camera) => write!(f, "camera:{}", quote(camera)),
+            Term::Lens(lens) => write!(f, "lens:{}", quote(lens)),
+            Term::Rating(stars) => write!(f, "rating:{}", stars),
+            Term::LongerThan(seconds) => write!(f, "duration:>{}", seconds),
+            Term::ShorterThan(seconds) => write!(f, "duration:<{}", seconds),
+            Term::Not(term) => write!(f, "-{}", term),
+        }
+    }
+}
+
+/// Quotes a value if it would otherwise be split or misread when parsed.
+/// Values can't contain quotes, so they are dropped.
+fn quote(value: &str) -> String {
+    let value = value.replace('"', "");
+    if value.is_empty() || value.contains(char::is_whitespace) || value.contains(':') || value.starts_with('-') {
+        format!("\"{}\"", value)
+    } else {
+        value
+    }
+}
+
+#[derive(Debug)]
+struct Token {
+    is_negated: bool,
+    key: Option<String>,
+    value: String,
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let is_negated = c == '-';
+        if is_negated {
+            chars.next();
+        }
+
+        let mut key = None;
+        let mut value = String::new();
+
+        if chars.peek() == Some(&'"') {
+            value = quoted(&mut chars);
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                chars.next();
+
+                if c == ':' && key.is_none() && !value.is_empty() {
+                    key = Some(std::mem::take(&mut value));
+                    if chars.peek() == Some(&'"') {
+                        value = quoted(&mut chars);
+                        break;
+                    }
+                } else {
+                    value.push(c);
+                }
+            }
+        }
+
+        // Ignore incomplete terms, such as "person:", which are common when
+        // searching as the user types.
+        if value.trim().is_empty() {
+            continue;
+        }
+
+        tokens.push(Token {
+            is_negated,
+            key,
+            value,
+        });
+    }
+
+    tokens
+}
+
+/// Reads a quoted value. An unterminated quote runs to the end of the text.
+fn quoted(chars: &mut Peekable<Chars>) -> String {
+    chars.next(); // opening quote
+    let mut value = String::new();
+    for c in chars.by_ref() {
+        if c == '"' {
+            break;
+        }
+        value.push(c);
+    }
+    value
+}
+
+fn to_term(key: &str, value: &str) -> Result<Term> {
+    let term = match key.to_lowercase().as_str() {
+        "person" => Term::Person(value.into()),
+        "folder" => Term::Folder(value.into()),
+        "camera" => Term::Camera(value.into()),
+        "lens" => Term::Lens(value.into()),
+        "year" => Term::Year(to_year(value)?),
+        "rating" => Term::Rating(to_rating(value)?),
+        "after" => Term::After(to_date(value)?),
+        "before" => Term::Before(to_date(value)?),
+        "duration" => {
+            if let Some(seconds) = value.strip_prefix('>') {
+                Term::LongerThan(to_seconds(seconds)?)
+            } else if let Some(seconds) = value.strip_prefix('<') {
+                Term::ShorterThan(to_seconds(seconds)?)
+            } else {
+                bail!("Duration must start with '>' or '<': {}", value);
+            }
+        }
+        "month" => {
+            if let Some((year, month)) = value.split_once('-') {
+                Term::YearMonth(to_year(year)?, to_month(month)?)
+            } else {
+                Term::Month(to_month(value)?)
+            }
+        }
+        "is" => match value.to_lowercase().as_str() {
+            "photo" | "photos" => Term::Kind(Kind::Photo),
+            "video" | "videos" => Term::Kind(Kind::Video),
+            "selfie" | "selfies" => Term::Kind(Kind::Selfie),
+            "motion" | "live" | "animated" => Term::Kind(Kind::Motion),
+            "favourite" | "favorite" | "favourites" | "favorites" => Term::Kind(Kind::Favourite),
+            _ => bail!("Unknown value for 'is': {}", value),
+        },
+        "has" => match value.to_lowercase().as_str() {
+            "location" | "gps" => Term::HasLocation,
+            _ => bail!("Unknown value for 'has': {}", value),
+        },
+        // Not a search key, so probably text that contains a colon, such as a time.
+        _ => Term::Text(format!("{}:{}", key, value)),
+    };
+    Ok(term)
+}
+
+fn to_year(value: &str) -> Result<i32> {
+    value
+        .parse::<i32>()
+        .map_err(|_| anyhow!("Invalid year: {}", value))
+}
+
+fn to_rating(value: &str) -> Result<u8> {
+    value
+        .parse::<u8>()
+        .ok()
+        .filter(|r| (1..=5).contains(r))
+        .ok_or_else(|| anyhow!("Invalid rating: {}", value))
+}
+
+fn to_date(value: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| anyhow!("Invalid date: {}", value))
+}
+
+/// Seconds, or minutes if suffixed with 'm', such as "90", "90s", or "2m".
+fn to_seconds(value: &str) -> Result<u32> {
+    let (number, multiplier) = if let Some(minutes) = value.strip_suffix('m') {
+        (minutes, 60)
+    } else {
+        (value.strip_suffix('s').unwrap_or(value), 1)
+    };
+
+    number
+        .parse::<u32>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| anyhow!("Invalid duration: {}", value))
+}
+
+fn to_month(value: &str) -> Result<u32> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|m| (1..=12).contains(m))
+        .ok_or_else(|| anyhow!("Invalid month: {}", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keys_and_text() {
+        let query =
+            Query::parse(r#"person:Alice year:2023 folder:Holidays is:video camera:"Pixel 7" beach"#)
+                .unwrap();
+
+        assert_eq!(
+            query.terms,
+            vec![
+                Term::Person("Alice".into()),
+                Term::Year(2023),
+                Term::Folder("Holidays".into()),
+                Term::Kind(Kind::Video),
+                Term::Camera("Pixel 7".into()),
+                Term::Text("beach".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_negated_and_quoted_text() {
+        let query = Query::parse(r#"-is:selfie "summer holiday" -sunset"#).unwrap();
+
+        assert_eq!(
+            query.terms,
+            vec![
+                Term::Not(Box::new(Term::Kind(Kind::Selfie))),
+                Term::Text("summer holiday".into()),
+                Term::Not(Box::new(Term::Text("sunset".into()))),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_months() {
+        let query = Query::parse("month:3 month:2023-12").unwrap();
+        assert_eq!(query.terms, vec![Term::Month(3), Term::YearMonth(2023, 12)]);
+
+        assert!(Query::parse("month:13").is_err());
+        assert!(Query::parse("year:twenty").is_err());
+        assert!(Query::parse("is:blurry").is_err());
+    }
+
+    #[test]
+    fn parse_favourites_and_ratings() {
+        let query = Query::parse("is:favorite rating:4").unwrap();
+        assert_eq!(query.terms, vec![Term::Kind(Kind::Favourite), Term::Rating(4)]);
+
+        assert!(Query::parse("rating:0").is_err());
+        assert!(Query::parse("rating:6").is_err());
+    }
+
+    #[test]
+    fn parse_dates_and_durations() {
+        let query = Query::parse("after:2023-01-01 before:2023-12-31 duration:>90 duration:<2m").unwrap();
+        assert_eq!(
+            query.terms,
+            vec![
+                Term::After(NaiveDate::from_ymd_opt(2023, 1, 1).unwrap()),
+                Term::Before(NaiveDate::from_ymd_opt(2023, 12, 31).unwrap()),
+                Term::LongerThan(90),
+                Term::ShorterThan(120),
+            ]
+        );
+
+        assert!(Query::parse("after:2023-13-01").is_err());
+        assert!(Query::parse("duration:30").is_err());
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let text = r#"person:"Alice Smith" folder:Holidays camera:"Pixel 7" lens:50mm after:2023-01-01 -is:selfie has:location duration:>30 month:2023-03 "12:30" beach"#;
+        let query = Query::parse(text).unwrap();
+        assert_eq!(query.to_string(), text);
+        assert_eq!(Query::parse(&query.to_string()).unwrap(), query);
+    }
+
+    #[test]
+    fn parse_incomplete_and_unknown_keys() {
+        let query = Query::parse("person: - 12:30 ").unwrap();
+        assert_eq!(query.terms, vec![Term::Text("12:30".into())]);
+
+        assert!(Query::parse("   ").unwrap().is_empty());
+    }
+}
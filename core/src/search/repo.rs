@@ -0,0 +1,340 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::query::{Kind, Query, Term};
+use crate::path_encoding;
+use crate::visual::VisualId;
+
+use anyhow::*;
+use rusqlite;
+use rusqlite::params;
+use rusqlite::types::Value;
+use rusqlite::Row;
+use std::path::{Component, Path, PathBuf};
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+use tracing::info;
+
+/// A visual item to add to the full-text index.
+struct IndexEntry {
+    visual_id: String,
+    picture_id: Option<i64>,
+    file_name: String,
+    folder_name: String,
+    person_names: Option<String>,
+    keywords: Option<String>,
+    description: Option<String>,
+}
+
+/// Repository for searching visual items.
+/// Repository is backed by a Sqlite database.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(con: Arc<Mutex<rusqlite::Connection>>) -> Result<Repository> {
+        let repo = Repository { con };
+        Ok(repo)
+    }
+
+    /// Rebuilds the full-text index of file names, folder names, person names, keywords,
+    /// and descriptions.
+    pub fn reindex(&mut self) -> Result<()> {
+        let start = std::time::Instant::now();
+
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let entries: Vec<IndexEntry> = {
+            let mut stmt = tx.prepare(
+                "SELECT
+                    visual.visual_id,
+                    visual.picture_id,
+                    visual.root_path_b64,
+                    visual.link_path_b64,
+                    visual.picture_path_b64,
+                    visual.video_path_b64,
+                    (
+                        SELECT GROUP_CONCAT(DISTINCT people.name)
+                        FROM pictures_faces
+                        INNER JOIN people USING (person_id)
+                        WHERE pictures_faces.picture_id = visual.picture_id
+                    ) AS person_names,
+                    (
+                        SELECT pictures.keywords
+                        FROM pictures
+                        WHERE pictures.picture_id = visual.picture_id
+                    ) AS keywords,
+                    COALESCE(
+                        (
+                            SELECT pictures.description
+                            FROM pictures
+                            WHERE pictures.picture_id = visual.picture_id
+                        ),
+                        (
+                            SELECT videos.description
+                            FROM videos
+                            WHERE videos.video_id = visual.video_id
+                        )
+                    ) AS description
+                FROM visual",
+            )?;
+
+            let result = stmt
+                .query_map([], Self::to_index_entry)?
+                .flatten()
+                .collect();
+            result
+        };
+
+        tx.execute("DELETE FROM visual_search", [])?;
+
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO visual_search (
+                    visual_id,
+                    picture_id,
+                    file_name,
+                    folder_name,
+                    person_names,
+                    keywords,
+                    description
+                ) VALUES (
+                    ?1, ?2, ?3, ?4, ?5, ?6, ?7
+                )",
+            )?;
+
+            for entry in entries.iter() {
+                stmt.execute(params![
+                    entry.visual_id,
+                    entry.picture_id,
+                    entry.file_name,
+                    entry.folder_name,
+                    entry.person_names,
+                    entry.keywords,
+                    entry.description,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+
+        info!(
+            "Indexed {} items for search in {} milliseconds.",
+            entries.len(),
+            start.elapsed().as_millis()
+        );
+
+        Ok(())
+    }
+
+    /// Updates person names in the full-text index.
+    /// Cheaper than a full reindex when people are renamed or deleted.
+    pub fn reindex_person_names(&mut self) -> Result<()> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+        tx.execute(
+            "UPDATE visual_search
+            SET person_names = (
+                SELECT GROUP_CONCAT(DISTINCT people.name)
+                FROM pictures_faces
+                INNER JOIN people USING (person_id)
+                WHERE pictures_faces.picture_id = visual_search.picture_id
+            )
+            WHERE picture_id IS NOT NULL",
+            [],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Finds IDs of visual items matching every term of a query, in ascending timestamp order.
+    pub fn search(&self, query: &Query) -> Result<Vec<VisualId>> {
+        let mut params: Vec<Value> = Vec::new();
+        let mut clauses: Vec<String> = Vec::new();
+
+        for term in query.terms.iter() {
+            clauses.push(to_clause(term, &mut params));
+        }
+
+        let mut sql = String::from("SELECT visual.visual_id FROM visual");
+        if !clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&clauses.join(" AND "));
+        }
+        sql.push_str(" ORDER BY visual.ordering_ts ASC");
+
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(&sql)?;
+
+        let result = stmt
+            .query_map(rusqlite::params_from_iter(params), |row| {
+                row.get("visual_id").map(VisualId::new)
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
+    fn to_index_entry(row: &Row<'_>) -> rusqlite::Result<IndexEntry> {
+        let visual_id: String = row.get("visual_id")?;
+        let picture_id: Option<i64> = row.get("picture_id")?;
+
+        let root_path: String = row.get("root_path_b64")?;
+        let root_path =
+            path_encoding::from_base64(&root_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+        let link_path: String = row.get("link_path_b64")?;
+        let link_path =
+            path_encoding::from_base64(&link_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+        let file_names: Vec<String> = ["picture_path_b64", "video_path_b64"]
+            .iter()
+            .filter_map(|column| row.get::<_, Option<String>>(*column).ok().flatten())
+            .filter_map(|x| path_encoding::from_base64(&x).ok())
+            .filter_map(|x| x.file_name().map(|n| n.to_string_lossy().to_string()))
+            .collect();
+
+        let person_names: Option<String> = row.get("person_names")?;
+        let keywords: Option<String> = row.get("keywords")?;
+        let description: Option<String> = row.get("description")?;
+
+        Ok(IndexEntry {
+            visual_id,
+            picture_id,
+            file_name: file_names.join(" "),
+            folder_name: folder_names(&root_path, &link_path),
+            person_names,
+            keywords,
+            description,
+        })
+    }
+}
+
+/// Names of the library root folder and every folder between the root and an item.
+/// The link path is relative to the root and has the same parent folder as the item.
+fn folder_names(root_path: &Path, link_path: &Path) -> String {
+    let folders = link_path
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_default();
+
+    root_path
+        .file_name()
+        .into_iter()
+        .chain(folders.components().filter_map(|c| match c {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        }))
+        .map(|x| x.to_string_lossy().to_string())
+        .collect::<Vec<String>>()
+        .join(" / ")
+}
+
+/// Converts a term to an SQL condition over the visual view, appending any parameters.
+fn to_clause(term: &Term, params: &mut Vec<Value>) -> String {
+    match term {
+        Term::Text(text) => {
+            params.push(Value::Text(to_fts_phrase(text)));
+            "visual.visual_id IN (SELECT visual_id FROM visual_search WHERE visual_search MATCH ?)"
+                .into()
+        }
+        Term::Folder(folder) => {
+            params.push(Value::Text(format!("folder_name : {}", to_fts_phrase(folder))));
+            "visual.visual_id IN (SELECT visual_id FROM visual_search WHERE visual_search MATCH ?)"
+                .into()
+        }
+        Term::Person(name) => {
+            params.push(Value::Text(to_like_pattern(name)));
+            "visual.picture_id IN (
+                SELECT pictures_faces.picture_id
+                FROM pictures_faces
+                INNER JOIN people USING (person_id)
+                WHERE people.name LIKE ? ESCAPE '\\'
+            )"
+            .into()
+        }
+        Term::Camera(camera) => {
+            params.push(Value::Text(to_like_pattern(camera)));
+            "visual.picture_id IN (
+                SELECT picture_id
+                FROM pictures_exif
+                WHERE COALESCE(camera_make, '') || ' ' || COALESCE(camera_model, '') LIKE ? ESCAPE '\\'
+            )"
+            .into()
+        }
+        Term::Lens(lens) => {
+            params.push(Value::Text(to_like_pattern(lens)));
+            "visual.picture_id IN (
+                SELECT picture_id
+                FROM pictures_exif
+                WHERE COALESCE(lens_make, '') || ' ' || COALESCE(lens_model, '') LIKE ? ESCAPE '\\'
+            )"
+            .into()
+        }
+        Term::Year(year) => {
+            params.push(Value::Integer(*year as i64));
+            "CAST(strftime('%Y', visual.ordering_ts) AS INTEGER) = ?".into()
+        }
+        Term::Month(month) => {
+            params.push(Value::Integer(*month as i64));
+            "CAST(strftime('%m', visual.ordering_ts) AS INTEGER) = ?".into()
+        }
+        Term::YearMonth(year, month) => {
+            params.push(Value::Text(format!("{:04}-{:02}", year, month)));
+            "strftime('%Y-%m', visual.ordering_ts) = ?".into()
+        }
+        Term::After(date) => {
+            params.push(Value::Text(date.format("%Y-%m-%d").to_string()));
+            "date(visual.ordering_ts) >= ?".into()
+        }
+        Term::Before(date) => {
+            params.push(Value::Text(date.format("%Y-%m-%d").to_string()));
+            "date(visual.ordering_ts) <= ?".into()
+        }
+        Term::Kind(Kind::Photo) => "visual.picture_id IS NOT NULL".into(),
+        Term::Kind(Kind::Video) => {
+            "(visual.video_id IS NOT NULL AND visual.picture_id IS NULL)".into()
+        }
+        Term::Kind(Kind::Selfie) => "visual.is_selfie IS TRUE".into(),
+        Term::Kind(Kind::Motion) => "visual.is_live_photo IS TRUE".into(),
+        Term::Kind(Kind::Favourite) => "visual.is_favourite IS TRUE".into(),
+        Term::Rating(stars) => {
+            params.push(Value::Integer(*stars as i64));
+            "visual.rating >= ?".into()
+        }
+        Term::LongerThan(seconds) => {
+            params.push(Value::Integer(*seconds as i64 * 1000));
+            "visual.duration_millis > ?".into()
+        }
+        Term::ShorterThan(seconds) => {
+            params.push(Value::Integer(*seconds as i64 * 1000));
+            "visual.duration_millis < ?".into()
+        }
+        Term::HasLocation => "visual.latitude IS NOT NULL".into(),
+        Term::Not(term) => {
+            // Conditions over nullable columns can be null, so coalesce to false before negating.
+            format!("NOT COALESCE(({}), FALSE)", to_clause(term, params))
+        }
+    }
+}
+
+/// Quotes text as an FTS5 prefix phrase so that punctuation and FTS5 keywords
+/// in the search text are matched literally.
+fn to_fts_phrase(text: &str) -> String {
+    format!("\"{}\"*", text.replace('"', "\"\""))
+}
+
+/// Case-insensitive pattern for matching text anywhere in a column.
+fn to_like_pattern(text: &str) -> String {
+    let text = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", text)
+}
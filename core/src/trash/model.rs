@@ -0,0 +1,48 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use crate::photo::PictureId;
+use crate::video::VideoId;
+use chrono::*;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// Database ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TrashId(i64);
+
+impl TrashId {
+    pub fn new(id: i64) -> Self {
+        Self(id)
+    }
+
+    /// FIXME replace this with a To/From SQL implementation.
+    pub fn id(&self) -> i64 {
+        self.0
+    }
+}
+
+impl Display for TrashId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A visual item moved to the trash from within Fotema.
+#[derive(Debug, Clone)]
+pub struct TrashedItem {
+    pub trash_id: TrashId,
+
+    pub picture_id: Option<PictureId>,
+
+    pub video_id: Option<VideoId>,
+
+    /// Paths of original files before they were moved to the trash.
+    pub original_paths: Vec<PathBuf>,
+
+    /// Thumbnail of picture, or of video if there is no picture.
+    pub thumbnail_path: Option<PathBuf>,
+
+    pub trashed_at: DateTime<Utc>,
+}
@@ -0,0 +1,527 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use super::model::{TrashId, TrashedItem};
+use crate::path_encoding;
+use crate::photo::PictureId;
+use crate::video::VideoId;
+use crate::visual::Visual;
+
+use anyhow::*;
+use chrono::*;
+use rusqlite;
+use rusqlite::params;
+use rusqlite::Row;
+use std::path::{Path, PathBuf};
+use std::result::Result::Ok;
+use std::sync::{Arc, Mutex};
+
+/// Repository of visual items moved to the trash from within Fotema.
+/// Repository is backed by a Sqlite database.
+///
+/// The original files are moved to the system trash by the caller. The database rows
+/// and derived files are kept until the item is deleted, so a restored item keeps
+/// its thumbnails, faces, ratings, and album membership.
+///
+/// A RAW file paired with a developed picture, such as a JPEG, is trashed,
+/// restored, and deleted along with that picture.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    /// Base path of cache directory
+    cache_dir_base_path: PathBuf,
+
+    /// Base path of data directory
+    data_dir_base_path: PathBuf,
+
+    /// Connection to backing Sqlite database.
+    con: Arc<Mutex<rusqlite::Connection>>,
+}
+
+impl Repository {
+    pub fn open(
+        cache_dir_base_path: &Path,
+        data_dir_base_path: &Path,
+        con: Arc<Mutex<rusqlite::Connection>>,
+    ) -> Result<Repository> {
+        let repo = Repository {
+            cache_dir_base_path: PathBuf::from(cache_dir_base_path),
+            data_dir_base_path: PathBuf::from(data_dir_base_path),
+            con,
+        };
+
+        Ok(repo)
+    }
+
+    /// Records that a visual item has been moved to the trash, along with the
+    /// cache and data files derived from it. The item is hidden from the library.
+    pub fn add(&mut self, visual: &Visual) -> Result<TrashId> {
+        let picture_id = visual.picture_id.map(|x| x.id());
+        let video_id = visual.video_id.map(|x| x.id());
+
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let trash_id = {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO trash (picture_id, video_id) VALUES (?1, ?2)",
+            )?;
+            stmt.execute(params![picture_id, video_id])?;
+            let trash_id = TrashId::new(tx.last_insert_rowid());
+
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR IGNORE INTO trash_raws (trash_id, picture_id)
+                SELECT ?1, raws.picture_id
+                FROM pictures AS raws
+                INNER JOIN pictures AS developed
+                    ON developed.library_root_id = raws.library_root_id
+                    AND developed.link_path_b64 = raws.link_path_b64
+                WHERE developed.picture_id = ?2
+                AND developed.is_raw IS FALSE
+                AND raws.is_raw IS TRUE",
+            )?;
+            stmt.execute(params![trash_id.id(), picture_id])?;
+
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO trash_files (trash_id, root_name, description, path)
+                SELECT ?1, root_name, description, path
+                FROM pictures_cleanup
+                WHERE (
+                    picture_id = ?2
+                    OR picture_id IN (SELECT picture_id FROM trash_raws WHERE trash_id = ?1)
+                )
+                AND path IS NOT NULL",
+            )?;
+            stmt.execute(params![trash_id.id(), picture_id])?;
+
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO trash_files (trash_id, root_name, description, path)
+                SELECT ?1, root_name, description, path
+                FROM videos_cleanup
+                WHERE video_id = ?2
+                AND path IS NOT NULL",
+            )?;
+            stmt.execute(params![trash_id.id(), video_id])?;
+
+            trash_id
+        };
+
+        tx.commit()?;
+        Ok(trash_id)
+    }
+
+    /// All items in the trash, most recently trashed first.
+    pub fn all(&self) -> Result<Vec<TrashedItem>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                trash.trash_id,
+                trash.picture_id,
+                trash.video_id,
+                trash.trashed_ts,
+                pictures.picture_path_b64,
+                pictures.thumbnail_path AS picture_thumbnail,
+                picture_roots.root_path_b64 AS picture_root_path_b64,
+                videos.video_path_b64,
+                videos.thumbnail_path AS video_thumbnail,
+                video_roots.root_path_b64 AS video_root_path_b64
+            FROM trash
+            LEFT OUTER JOIN pictures ON pictures.picture_id = trash.picture_id
+            LEFT OUTER JOIN library_roots AS picture_roots ON picture_roots.root_id = pictures.library_root_id
+            LEFT OUTER JOIN videos ON videos.video_id = trash.video_id
+            LEFT OUTER JOIN library_roots AS video_roots ON video_roots.root_id = videos.library_root_id
+            ORDER BY trash.trashed_ts DESC, trash.trash_id DESC",
+        )?;
+
+        let mut raws_stmt = con.prepare(
+            "SELECT
+                library_roots.root_path_b64,
+                pictures.picture_path_b64
+            FROM trash_raws
+            INNER JOIN pictures USING (picture_id)
+            INNER JOIN library_roots ON library_roots.root_id = pictures.library_root_id
+            WHERE trash_raws.trash_id = ?1
+            ORDER BY pictures.picture_id",
+        )?;
+
+        let items: Vec<TrashedItem> = stmt
+            .query_map([], |row| self.to_trashed_item(row))?
+            .flatten()
+            .collect();
+
+        let mut result = Vec::new();
+
+        for mut item in items {
+            let raw_paths = raws_stmt
+                .query_map([item.trash_id.id()], |row| {
+                    Ok(Self::original_path(
+                        row,
+                        "root_path_b64",
+                        "picture_path_b64",
+                    ))
+                })?
+                .flatten()
+                .flatten();
+
+            item.original_paths.extend(raw_paths);
+            result.push(item);
+        }
+
+        Ok(result)
+    }
+
+    /// Takes an item out of the trash so it is visible in the library again,
+    /// along with any RAW pictures trashed with it.
+    /// The caller must have restored the original files.
+    pub fn restore(&mut self, trash_id: TrashId) -> Result<()> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare_cached("DELETE FROM trash WHERE trash_id = ?1")?;
+        stmt.execute(params![trash_id.id()])?;
+        Ok(())
+    }
+
+    /// Permanently removes an item from the library.
+    /// Returns paths of derived cache and data files that the caller must delete.
+    pub fn delete(&mut self, trash_id: TrashId) -> Result<Vec<PathBuf>> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let paths = {
+            // Face thumbnails can be shared with a person, so must be kept.
+            let mut stmt = tx.prepare_cached(
+                "SELECT root_name, path
+                FROM trash_files
+                WHERE trash_id = ?1
+                AND path NOT IN (SELECT thumbnail_path FROM people)",
+            )?;
+
+            let paths: Vec<PathBuf> = stmt
+                .query_map(params![trash_id.id()], |row| self.to_cleanup_path(row))?
+                .flatten()
+                .collect();
+
+            let (picture_id, video_id): (Option<i64>, Option<i64>) = tx.query_row(
+                "SELECT picture_id, video_id FROM trash WHERE trash_id = ?1",
+                params![trash_id.id()],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+
+            // Deleting the picture or video cascades to the trash and everything else
+            // recorded against it.
+            tx.execute(
+                "DELETE FROM pictures
+                WHERE picture_id IN (SELECT picture_id FROM trash_raws WHERE trash_id = ?1)",
+                params![trash_id.id()],
+            )?;
+            tx.execute("DELETE FROM pictures WHERE picture_id = ?1", params![picture_id])?;
+            tx.execute("DELETE FROM videos WHERE video_id = ?1", params![video_id])?;
+
+            paths
+        };
+
+        tx.commit()?;
+        Ok(paths)
+    }
+
+    fn to_trashed_item(&self, row: &Row<'_>) -> rusqlite::Result<TrashedItem> {
+        let trash_id = row.get("trash_id").map(TrashId::new)?;
+        let picture_id: Option<PictureId> = row.get("picture_id").map(PictureId::new).ok();
+        let video_id: Option<VideoId> = row.get("video_id").map(VideoId::new).ok();
+        let trashed_at: DateTime<Utc> = row.get("trashed_ts")?;
+
+        let picture_path = Self::original_path(row, "picture_root_path_b64", "picture_path_b64");
+        let video_path = Self::original_path(row, "video_root_path_b64", "video_path_b64");
+        let original_paths = picture_path.into_iter().chain(video_path).collect();
+
+        let thumbnail_path = row
+            .get("picture_thumbnail")
+            .or_else(|_| row.get("video_thumbnail"))
+            .map(|x: String| self.cache_dir_base_path.join(x))
+            .ok();
+
+        Ok(TrashedItem {
+            trash_id,
+            picture_id,
+            video_id,
+            original_paths,
+            thumbnail_path,
+            trashed_at,
+        })
+    }
+
+    fn original_path(row: &Row<'_>, root_column: &str, path_column: &str) -> Option<PathBuf> {
+        let root_path: String = row.get(root_column).ok()?;
+        let root_path = path_encoding::from_base64(&root_path).ok()?;
+
+        let path: String = row.get(path_column).ok()?;
+        let path = path_encoding::from_base64(&path).ok()?;
+
+        Some(root_path.join(path))
+    }
+
+    fn to_cleanup_path(&self, row: &Row<'_>) -> rusqlite::Result<PathBuf> {
+        let root_name: String = row.get("root_name")?;
+
+        row.get("path")
+            .and_then(|p: String| match root_name.as_str() {
+                "cache" => std::result::Result::Ok(self.cache_dir_base_path.join(p)),
+                "data" => std::result::Result::Ok(self.data_dir_base_path.join(p)),
+                _ => Err(rusqlite::Error::InvalidPath(p.into())),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database;
+    use crate::library;
+    use crate::media::{MediaType, ScannedFile};
+    use crate::photo;
+    use crate::video;
+    use crate::visual;
+
+    fn scanned(path: PathBuf) -> ScannedFile {
+        ScannedFile {
+            path,
+            media_type: MediaType::Photo,
+            fs_created_at: None,
+            fs_modified_at: None,
+            fs_file_size_bytes: 1024,
+            fingerprint: None,
+        }
+    }
+
+    #[test]
+    fn trash_and_restore_raw_pair() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+
+        let mut roots = library::Repository::open(con.clone()).unwrap();
+        roots.add(Path::new("/pictures")).unwrap();
+        let root = roots.all().unwrap().remove(0);
+
+        let jpeg_path = root.path.join("IMG_0001.JPG");
+        let raw_path = root.path.join("IMG_0001.CR2");
+
+        let raw = ScannedFile {
+            media_type: MediaType::Raw,
+            fingerprint: Some("raw".into()),
+            ..scanned(raw_path.clone())
+        };
+
+        let mut photo_repo =
+            photo::Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+        photo_repo
+            .add_all(&root, &[scanned(jpeg_path.clone()), raw])
+            .unwrap();
+
+        let visual_repo = visual::Repository::open(Path::new("/cache"), con.clone()).unwrap();
+        let visuals = visual_repo.all().unwrap();
+        assert_eq!(visuals.len(), 1);
+
+        let mut repo =
+            Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+        let trash_id = repo.add(&visuals[0]).unwrap();
+
+        // Neither the developed picture nor its RAW file may be cleaned up while in the trash.
+        assert!(photo_repo.all().unwrap().is_empty());
+        assert_eq!(photo_repo.find_moved("raw").unwrap(), None);
+        assert!(visual_repo.all().unwrap().is_empty());
+
+        let items = repo.all().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(
+            items[0].original_paths,
+            vec![jpeg_path.clone(), raw_path.clone()]
+        );
+
+        repo.restore(trash_id).unwrap();
+
+        let paths: Vec<PathBuf> = photo_repo
+            .all()
+            .unwrap()
+            .into_iter()
+            .map(|x| x.path)
+            .collect();
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&jpeg_path));
+        assert!(paths.contains(&raw_path));
+        assert_eq!(visual_repo.all().unwrap().len(), 1);
+        assert!(repo.all().unwrap().is_empty());
+
+        let raws: i64 = con
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM trash_raws", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(raws, 0);
+    }
+
+    #[test]
+    fn trash_restore_and_delete_picture() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+
+        let mut roots = library::Repository::open(con.clone()).unwrap();
+        roots.add(Path::new("/pictures")).unwrap();
+        let root = roots.all().unwrap().remove(0);
+
+        let mut photo_repo =
+            photo::Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+        photo_repo
+            .add_all(&root, &[scanned(root.path.join("party.jpg"))])
+            .unwrap();
+        let picture_id = photo_repo.all().unwrap()[0].picture_id;
+        photo_repo
+            .add_thumbnail(&picture_id, Path::new("/cache/photo_thumbnails/1.avif"))
+            .unwrap();
+
+        {
+            let con = con.lock().unwrap();
+            con.execute(
+                "INSERT INTO people (person_id, thumbnail_path, name) VALUES (1, 'faces/1_thumb.png', 'Alice')",
+                [],
+            )
+            .unwrap();
+
+            let mut insert_face = con
+                .prepare(
+                    "INSERT INTO pictures_faces (
+                        face_id, model_name, picture_id, is_confirmed, person_id,
+                        thumbnail_path, bounds_path,
+                        bounds_x, bounds_y, bounds_width, bounds_height,
+                        right_eye_x, right_eye_y, left_eye_x, left_eye_y, nose_x, nose_y,
+                        right_mouth_corner_x, right_mouth_corner_y,
+                        left_mouth_corner_x, left_mouth_corner_y,
+                        confidence
+                    ) VALUES (
+                        ?1, 'test', ?2, ?3, ?4, ?5, ?6,
+                        0, 0, 10, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0.9
+                    )",
+                )
+                .unwrap();
+
+            insert_face
+                .execute(params![
+                    1,
+                    picture_id.id(),
+                    true,
+                    1,
+                    "faces/1_thumb.png",
+                    "faces/1_bounds.png"
+                ])
+                .unwrap();
+            insert_face
+                .execute(params![
+                    2,
+                    picture_id.id(),
+                    false,
+                    None::<i64>,
+                    "faces/2_thumb.png",
+                    "faces/2_bounds.png"
+                ])
+                .unwrap();
+        }
+
+        let visual_repo = visual::Repository::open(Path::new("/cache"), con.clone()).unwrap();
+        let visual = visual_repo.all().unwrap().remove(0);
+
+        let mut repo =
+            Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+
+        let trash_id = repo.add(&visual).unwrap();
+        assert!(visual_repo.all().unwrap().is_empty());
+
+        repo.restore(trash_id).unwrap();
+        assert_eq!(visual_repo.all().unwrap().len(), 1);
+        assert!(repo.all().unwrap().is_empty());
+
+        let trash_id = repo.add(&visual).unwrap();
+        let mut paths = repo.delete(trash_id).unwrap();
+        paths.sort();
+
+        // The thumbnail of the person's face is kept for the person.
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/cache/photo_thumbnails/1.avif"),
+                PathBuf::from("/data/faces/1_bounds.png"),
+                PathBuf::from("/data/faces/2_bounds.png"),
+                PathBuf::from("/data/faces/2_thumb.png"),
+            ]
+        );
+
+        assert!(photo_repo.all().unwrap().is_empty());
+        assert!(visual_repo.all().unwrap().is_empty());
+        assert!(repo.all().unwrap().is_empty());
+
+        let con = con.lock().unwrap();
+        for table in ["pictures", "pictures_faces", "trash", "trash_files"] {
+            let count: i64 = con
+                .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                    row.get(0)
+                })
+                .unwrap();
+            assert_eq!(count, 0, "{table}");
+        }
+
+        let people: i64 = con
+            .query_row("SELECT COUNT(*) FROM people", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(people, 1);
+    }
+
+    #[test]
+    fn delete_video() {
+        let con = Arc::new(Mutex::new(database::setup_in_memory().unwrap()));
+
+        let mut roots = library::Repository::open(con.clone()).unwrap();
+        roots.add(Path::new("/pictures")).unwrap();
+        let root = roots.all().unwrap().remove(0);
+
+        let vid = ScannedFile {
+            media_type: MediaType::Video,
+            ..scanned(root.path.join("party.mp4"))
+        };
+
+        let mut video_repo =
+            video::Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+        video_repo.add_all(&root, &[vid]).unwrap();
+        let video_id = video_repo.all().unwrap()[0].video_id;
+        video_repo
+            .add_thumbnail(&video_id, Path::new("/cache/video_thumbnails/1.avif"))
+            .unwrap();
+        video_repo
+            .add_transcode(video_id, Path::new("/cache/video_transcodes/1.mkv"))
+            .unwrap();
+
+        let visual_repo = visual::Repository::open(Path::new("/cache"), con.clone()).unwrap();
+        let visual = visual_repo.all().unwrap().remove(0);
+
+        let mut repo =
+            Repository::open(Path::new("/cache"), Path::new("/data"), con.clone()).unwrap();
+
+        let trash_id = repo.add(&visual).unwrap();
+        assert!(visual_repo.all().unwrap().is_empty());
+
+        let mut paths = repo.delete(trash_id).unwrap();
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/cache/video_thumbnails/1.avif"),
+                PathBuf::from("/cache/video_transcodes/1.mkv"),
+            ]
+        );
+
+        assert!(video_repo.all().unwrap().is_empty());
+        assert!(repo.all().unwrap().is_empty());
+
+        let files: i64 = con
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM trash_files", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(files, 0);
+    }
+}
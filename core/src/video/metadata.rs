@@ -3,6 +3,8 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use super::Metadata;
+use crate::import::takeout;
+use crate::photo::xmp;
 use anyhow::*;
 use chrono::{DateTime, TimeDelta};
 
@@ -13,12 +15,17 @@ use std::result::Result::Ok;
 
 use std::process::Command;
 
+use tracing::warn;
+
 /// This version number should be incremented each time metadata scanning has
 /// a bug fix or feature addition that changes the metadata produced.
 /// Each photo will be saved with a metadata scan version which will allow for
 /// easy selection of videos when there metadata can be updated.
-
-pub const VERSION: u32 = 2;
+///
+/// History:
+/// 3. XMP rating.
+/// 4. Google Photos Takeout date and description.
+pub const VERSION: u32 = 4;
 
 pub fn from_path(path: &Path) -> Result<Metadata> {
     let mut metadata = Metadata::default();
@@ -93,6 +100,23 @@ pub fn from_path(path: &Path) -> Result<Metadata> {
         metadata.audio_codec = Some(String::from(codec.id().name()));
     }
 
+    let takeout = takeout::read_sidecar(path).unwrap_or_else(|e| {
+        warn!("Failed reading Takeout sidecar for {:?}: {}", path, e);
+        None
+    });
+
+    if let Some(takeout) = takeout {
+        // Google Photos strips some metadata, so fill in what is missing.
+        metadata.created_at = metadata.created_at.or(takeout.taken_at);
+        metadata.description = takeout.description;
+    }
+
+    // Only sidecars are read as videos can be large and XMP can be anywhere in the file.
+    metadata.rating = xmp::read_rating(path, false).unwrap_or_else(|e| {
+        warn!("Failed reading XMP rating for {:?}: {}", path, e);
+        None
+    });
+
     Ok(metadata)
 }
 
@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use crate::library::LibraryRootId;
 use chrono::{DateTime, TimeDelta, Utc};
 use std::fmt::Display;
 use std::path::PathBuf;
@@ -36,6 +37,9 @@ pub struct Video {
     /// Database primary key for video
     pub video_id: VideoId,
 
+    /// Library root video was scanned from.
+    pub library_root_id: LibraryRootId,
+
     /// Full path to square preview image
     pub thumbnail_path: Option<PathBuf>,
 
@@ -52,19 +56,6 @@ pub struct Video {
     pub video_codec: Option<String>,
 }
 
-/// A video on the local file system that has been scanned.
-#[derive(Debug, Clone)]
-pub struct ScannedFile {
-    /// Full path to picture file.
-    pub path: PathBuf,
-
-    pub fs_created_at: Option<DateTime<Utc>>,
-
-    pub fs_modified_at: Option<DateTime<Utc>>,
-
-    pub fs_file_size_bytes: u64,
-}
-
 #[derive(Debug, Default, Clone)]
 pub struct Metadata {
     pub created_at: Option<DateTime<Utc>>,
@@ -86,4 +77,10 @@ pub struct Metadata {
     // Rotation of video in degrees.
     // Should be 90, 180, 270, or the negative of those.
     pub rotation: Option<i32>,
+
+    /// Star rating from xmp:Rating in a sidecar. Zero if explicitly unrated.
+    pub rating: Option<u8>,
+
+    /// Description from a Google Photos Takeout sidecar.
+    pub description: Option<String>,
 }
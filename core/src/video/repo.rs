@@ -4,23 +4,28 @@
 
 use super::metadata;
 use super::Metadata;
+use crate::import::icloud;
+use crate::library::{FileStamp, LibraryRoot, LibraryRootId};
+use crate::media::{ScannedFile, ScannedFileStore};
 use crate::path_encoding;
-use crate::video::model::{ScannedFile, Video, VideoId};
+use crate::video::model::{Video, VideoId};
 use anyhow::*;
 use chrono::*;
 use rusqlite;
 use rusqlite::params;
 use rusqlite::Row;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
+/// Longest video that might be the video of a live photo.
+/// Live photo videos are about three seconds long.
+const LIVE_PHOTO_MAX_DURATION_MILLIS: i64 = 4000;
+
 /// Repository of picture metadata.
 /// Repository is backed by a Sqlite database.
 #[derive(Debug, Clone)]
 pub struct Repository {
-    /// Base path to picture library on file system
-    library_base_path: PathBuf,
-
     /// Base path for thumbnails and transcoded videos
     cache_dir_base_path: PathBuf,
 
@@ -34,7 +39,6 @@ pub struct Repository {
 impl Repository {
     /// Builds a Repository and creates operational tables.
     pub fn open(
-        library_base_path: &Path,
         cache_dir_base_path: &Path,
         data_dir_base_path: &Path,
         con: Arc<Mutex<rusqlite::Connection>>,
@@ -42,7 +46,6 @@ impl Repository {
         std::fs::create_dir_all(cache_dir_base_path)?;
 
         let repo = Repository {
-            library_base_path: PathBuf::from(library_base_path),
             cache_dir_base_path: cache_dir_base_path.into(),
             data_dir_base_path: data_dir_base_path.into(),
             con,
@@ -134,10 +137,64 @@ impl Repository {
                     duration_millis = ?4,
                     video_codec = ?5,
                     content_id = ?6,
-                    rotation = ?7
+                    rotation = ?7,
+                    description = ?8
                 WHERE video_id = ?1",
             )?;
 
+            // Takeout and some iCloud downloads strip the content ID from the video
+            // of a live photo, so link it to a sibling picture by name instead.
+            let mut adopt_content_id = tx.prepare_cached(
+                "UPDATE videos
+                SET content_id = (
+                    SELECT pictures.content_id
+                    FROM pictures
+                    WHERE pictures.library_root_id = videos.library_root_id
+                    AND pictures.link_path_b64 = videos.link_path_b64
+                    AND pictures.content_id IS NOT NULL
+                    ORDER BY pictures.picture_id
+                    LIMIT 1
+                )
+                WHERE video_id = ?1
+                AND content_id IS NULL
+                AND duration_millis <= ?2",
+            )?;
+
+            // If neither has a content ID, then give both one made from the link path.
+            let mut share_content_id = tx.prepare_cached(
+                "UPDATE pictures
+                SET content_id = 'link:' || pictures.link_path_b64
+                WHERE content_id IS NULL
+                AND EXISTS (
+                    SELECT 1
+                    FROM videos
+                    WHERE videos.video_id = ?1
+                    AND videos.library_root_id = pictures.library_root_id
+                    AND videos.link_path_b64 = pictures.link_path_b64
+                    AND videos.content_id IS NULL
+                    AND videos.duration_millis <= ?2
+                )",
+            )?;
+
+            let mut set_shared_content_id = tx.prepare_cached(
+                "UPDATE videos
+                SET content_id = 'link:' || link_path_b64
+                WHERE video_id = ?1
+                AND content_id IS NULL",
+            )?;
+
+            // Embedded ratings are only imported once, so that rescanning doesn't
+            // overwrite ratings set by the user.
+            let mut import_rating = tx.prepare_cached(
+                "INSERT INTO videos_ratings (
+                    video_id,
+                    stars
+                ) VALUES (
+                    ?1, NULLIF(?2, 0)
+                ) ON CONFLICT (video_id) DO NOTHING
+                ",
+            )?;
+
             for (video_id, metadata) in vids {
                 stmt.execute(params![
                     video_id.id(),
@@ -147,7 +204,20 @@ impl Repository {
                     metadata.video_codec,
                     metadata.content_id,
                     metadata.rotation,
+                    metadata.description,
                 ])?;
+
+                adopt_content_id.execute(params![video_id.id(), LIVE_PHOTO_MAX_DURATION_MILLIS])?;
+
+                let shared = share_content_id
+                    .execute(params![video_id.id(), LIVE_PHOTO_MAX_DURATION_MILLIS])?;
+                if shared > 0 {
+                    set_shared_content_id.execute(params![video_id.id()])?;
+                }
+
+                if let Some(rating) = metadata.rating {
+                    import_rating.execute(params![video_id.id(), rating])?;
+                }
             }
         }
 
@@ -155,7 +225,9 @@ impl Repository {
         Ok(())
     }
 
-    pub fn add_all(&mut self, vids: &Vec<ScannedFile>) -> Result<()> {
+    /// Add all Videos received from a vector.
+    /// Videos must be under the given library root.
+    pub fn add_all(&mut self, root: &LibraryRoot, vids: &[ScannedFile]) -> Result<()> {
         let mut con = self.con.lock().unwrap();
         let tx = con.transaction()?;
 
@@ -168,27 +240,28 @@ impl Repository {
                         video_path_b64,
                         video_path_lossy,
                         link_path_b64,
-                        link_path_lossy
+                        link_path_lossy,
+                        library_root_id,
+                        fs_file_size_bytes,
+                        fingerprint
                     ) VALUES (
-                        ?1, ?2, ?3, ?4, ?5, ?6
-                    ) ON CONFLICT (video_path_b64) DO UPDATE SET
+                        ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9
+                    ) ON CONFLICT (library_root_id, video_path_b64) DO UPDATE SET
                         fs_created_ts = ?1,
-                        fs_modified_ts = ?2
+                        fs_modified_ts = ?2,
+                        link_path_b64 = ?5,
+                        link_path_lossy = ?6,
+                        fs_file_size_bytes = ?8,
+                        fingerprint = COALESCE(?9, fingerprint)
                     ",
             )?;
 
             for vid in vids {
                 // convert to relative path before saving to database
-                let video_path = vid.path.strip_prefix(&self.library_base_path)?;
+                let video_path = vid.path.strip_prefix(&root.path)?;
                 let video_path_b64 = path_encoding::to_base64(video_path);
 
-                // Path without suffix so sibling pictures and videos can be related
-                let link_path = video_path
-                    .file_stem()
-                    .and_then(|x| x.to_str())
-                    .expect("Must exist");
-
-                let link_path = video_path.with_file_name(link_path);
+                let link_path = Self::link_path(video_path);
                 let link_path_b64 = path_encoding::to_base64(&link_path);
 
                 vid_stmt.execute(params![
@@ -198,6 +271,9 @@ impl Repository {
                     video_path.to_string_lossy(),
                     link_path_b64,
                     link_path.to_string_lossy(),
+                    root.library_root_id.id(),
+                    vid.fs_file_size_bytes,
+                    vid.fingerprint,
                 ])?;
             }
         }
@@ -206,12 +282,203 @@ impl Repository {
         Ok(())
     }
 
+    /// Invalidates derived state of videos that have changed on the file system since
+    /// they were last scanned, so that they are processed again as if they were new.
+    /// File system timestamps and sizes are updated.
+    /// Returns paths of derived files that should be deleted.
+    pub fn invalidate(&mut self, root: &LibraryRoot, vids: &[ScannedFile]) -> Result<Vec<PathBuf>> {
+        let mut con = self.con.lock().unwrap();
+        let tx = con.transaction()?;
+
+        let mut cleanup_paths = Vec::new();
+
+        {
+            let mut find_video_id = tx.prepare_cached(
+                "SELECT video_id
+                FROM videos
+                WHERE library_root_id = ?1
+                AND video_path_b64 = ?2",
+            )?;
+
+            let mut find_cleanup = tx.prepare_cached(
+                "SELECT root_name, path
+                FROM videos_cleanup
+                WHERE video_id = ?1
+                AND path IS NOT NULL",
+            )?;
+
+            let mut update_video = tx.prepare_cached(
+                "UPDATE videos
+                SET
+                    fs_created_ts = ?2,
+                    fs_modified_ts = ?3,
+                    fs_file_size_bytes = ?4,
+                    fingerprint = ?5,
+                    thumbnail_path = NULL,
+                    transcoded_path = NULL,
+                    metadata_version = 0,
+                    is_broken = NULL
+                WHERE video_id = ?1",
+            )?;
+
+            for vid in vids {
+                let video_path = vid.path.strip_prefix(&root.path)?;
+                let video_path_b64 = path_encoding::to_base64(video_path);
+
+                let video_id: i64 = find_video_id.query_row(
+                    params![root.library_root_id.id(), video_path_b64],
+                    |row| row.get(0),
+                )?;
+
+                let paths: Vec<PathBuf> = find_cleanup
+                    .query_map([video_id], |row| self.to_cleanup_path(row))?
+                    .flatten()
+                    .collect();
+                cleanup_paths.extend(paths);
+
+                update_video.execute(params![
+                    video_id,
+                    vid.fs_created_at,
+                    vid.fs_modified_at,
+                    vid.fs_file_size_bytes,
+                    vid.fingerprint,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(cleanup_paths)
+    }
+
+    /// Finds a video with the given fingerprint whose file is missing, which implies the
+    /// file has been moved or renamed.
+    /// videos under unavailable library roots are ignored, as their files are only
+    /// missing because the root is unmounted. Videos in the trash are also ignored.
+    pub fn find_moved(&self, fingerprint: &str) -> Result<Option<VideoId>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    videos.video_id,
+                    videos.video_path_b64,
+                    library_roots.root_path_b64
+                FROM videos
+                INNER JOIN library_roots ON library_roots.root_id = videos.library_root_id
+                WHERE videos.fingerprint = ?1
+                AND library_roots.is_enabled IS TRUE
+                AND videos.video_id NOT IN (SELECT video_id FROM trash WHERE video_id IS NOT NULL)",
+        )?;
+
+        let candidates: Vec<(VideoId, PathBuf, PathBuf)> = stmt
+            .query_map([fingerprint], |row| {
+                let video_id = row.get("video_id").map(VideoId::new)?;
+
+                let video_path: String = row.get("video_path_b64")?;
+                let video_path = path_encoding::from_base64(&video_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+                let root_path: String = row.get("root_path_b64")?;
+                let root_path = path_encoding::from_base64(&root_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+                std::result::Result::Ok((video_id, root_path.join(&video_path), root_path))
+            })?
+            .flatten()
+            .collect();
+
+        let moved = candidates
+            .into_iter()
+            .find(|(_, path, root_path)| root_path.is_dir() && !path.exists())
+            .map(|(video_id, _, _)| video_id);
+
+        Ok(moved)
+    }
+
+    /// Updates the path of a video that has moved, keeping everything derived from it.
+    /// The new path must be under the given library root.
+    pub fn relocate(&mut self, video_id: VideoId, root: &LibraryRoot, vid: &ScannedFile) -> Result<()> {
+        let video_path = vid.path.strip_prefix(&root.path)?;
+        let video_path_b64 = path_encoding::to_base64(video_path);
+
+        let link_path = Self::link_path(video_path);
+        let link_path_b64 = path_encoding::to_base64(&link_path);
+
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "UPDATE videos
+            SET
+                library_root_id = ?2,
+                video_path_b64 = ?3,
+                video_path_lossy = ?4,
+                link_path_b64 = ?5,
+                link_path_lossy = ?6,
+                fs_created_ts = ?7,
+                fs_modified_ts = ?8,
+                fs_file_size_bytes = ?9
+            WHERE video_id = ?1",
+        )?;
+
+        stmt.execute(params![
+            video_id.id(),
+            root.library_root_id.id(),
+            video_path_b64,
+            video_path.to_string_lossy(),
+            link_path_b64,
+            link_path.to_string_lossy(),
+            vid.fs_created_at,
+            vid.fs_modified_at,
+            vid.fs_file_size_bytes,
+        ])?;
+
+        Ok(())
+    }
+
+    /// Path without suffix so sibling pictures and videos can be related.
+    fn link_path(video_path: &Path) -> PathBuf {
+        let link_path = icloud::video_link_stem(video_path).expect("Must exist");
+        video_path.with_file_name(link_path)
+    }
+
+    /// Gets file system modification times and sizes of all videos under a library root,
+    /// keyed by full path.
+    pub fn find_file_stamps(&self, root: &LibraryRoot) -> Result<HashMap<PathBuf, FileStamp>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    video_path_b64,
+                    fs_modified_ts,
+                    fs_file_size_bytes
+                FROM videos
+                WHERE library_root_id = ?1",
+        )?;
+
+        let result = stmt
+            .query_map([root.library_root_id.id()], |row| {
+                let video_path: String = row.get("video_path_b64")?;
+                let video_path = path_encoding::from_base64(&video_path)
+                    .map_err(|_| rusqlite::Error::InvalidQuery)?;
+
+                let stamp = FileStamp {
+                    fs_modified_at: row.get("fs_modified_ts")?,
+                    fs_file_size_bytes: row.get("fs_file_size_bytes")?,
+                };
+
+                std::result::Result::Ok((root.path.join(video_path), stamp))
+            })?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
     /// Gets all videos in the repository, in ascending order of modification timestamp.
+    /// Videos in the trash are excluded, as their files are missing until restored.
     pub fn all(&self) -> Result<Vec<Video>> {
         let con = self.con.lock().unwrap();
         let mut stmt = con.prepare(
             "SELECT
                     video_id,
+                    library_root_id,
+                    library_roots.root_path_b64,
                     video_path_b64,
                     thumbnail_path,
                     COALESCE(
@@ -224,7 +491,10 @@ impl Repository {
                     video_codec,
                     transcoded_path
                 FROM videos
+                INNER JOIN library_roots ON library_roots.root_id = videos.library_root_id
                 WHERE COALESCE(is_broken, FALSE) IS FALSE
+                AND library_roots.is_enabled IS TRUE
+                AND videos.video_id NOT IN (SELECT video_id FROM trash WHERE video_id IS NOT NULL)
                 ORDER BY ordering_ts ASC",
         )?;
 
@@ -239,6 +509,8 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     video_id,
+                    library_root_id,
+                    library_roots.root_path_b64,
                     video_path_b64,
                     thumbnail_path,
                     COALESCE(
@@ -251,8 +523,10 @@ impl Repository {
                     video_codec,
                     transcoded_path
                 FROM videos
+                INNER JOIN library_roots ON library_roots.root_id = videos.library_root_id
                 WHERE metadata_version < ?1
                 AND COALESCE(is_broken, FALSE) IS FALSE
+                AND library_roots.is_enabled IS TRUE
                 ORDER BY ordering_ts ASC",
         )?;
 
@@ -261,6 +535,25 @@ impl Repository {
         Ok(result)
     }
 
+    /// Gets all videos whose library root has been removed.
+    pub fn find_orphaned(&self) -> Result<Vec<VideoId>> {
+        let con = self.con.lock().unwrap();
+        let mut stmt = con.prepare(
+            "SELECT
+                    videos.video_id
+                FROM videos
+                LEFT OUTER JOIN library_roots ON library_roots.root_id = videos.library_root_id
+                WHERE library_roots.root_id IS NULL",
+        )?;
+
+        let result = stmt
+            .query_map([], |row| row.get("video_id").map(VideoId::new))?
+            .flatten()
+            .collect();
+
+        Ok(result)
+    }
+
     /// Gets paths of files to delete when a video is no longer present.
     pub fn find_files_to_cleanup(&self, video_id: VideoId) -> Result<Vec<PathBuf>> {
         let con = self.con.lock().unwrap();
@@ -278,10 +571,16 @@ impl Repository {
     fn to_video(&self, row: &Row<'_>) -> rusqlite::Result<Video> {
         let video_id = row.get("video_id").map(VideoId::new)?;
 
+        let library_root_id = row.get("library_root_id").map(LibraryRootId::new)?;
+
+        let root_path: String = row.get("root_path_b64")?;
+        let root_path =
+            path_encoding::from_base64(&root_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
+
         let video_path: String = row.get("video_path_b64")?;
         let video_path =
             path_encoding::from_base64(&video_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
-        let video_path = self.library_base_path.join(video_path);
+        let video_path = root_path.join(video_path);
 
         let thumbnail_path = row
             .get("thumbnail_path")
@@ -304,6 +603,7 @@ impl Repository {
 
         std::result::Result::Ok(Video {
             video_id,
+            library_root_id,
             path: video_path,
             thumbnail_path,
             ordering_ts,
@@ -333,3 +633,27 @@ impl Repository {
         Ok(())
     }
 }
+
+impl ScannedFileStore for Repository {
+    type Id = VideoId;
+
+    fn find_file_stamps(&self, root: &LibraryRoot) -> Result<HashMap<PathBuf, FileStamp>> {
+        Repository::find_file_stamps(self, root)
+    }
+
+    fn find_moved(&self, fingerprint: &str) -> Result<Option<VideoId>> {
+        Repository::find_moved(self, fingerprint)
+    }
+
+    fn relocate(&mut self, id: VideoId, root: &LibraryRoot, file: &ScannedFile) -> Result<()> {
+        Repository::relocate(self, id, root, file)
+    }
+
+    fn add_all(&mut self, root: &LibraryRoot, files: &[ScannedFile]) -> Result<()> {
+        Repository::add_all(self, root, files)
+    }
+
+    fn invalidate(&mut self, root: &LibraryRoot, files: &[ScannedFile]) -> Result<Vec<PathBuf>> {
+        Repository::invalidate(self, root, files)
+    }
+}
@@ -15,7 +15,7 @@ use h3o::LatLng;
 pub use crate::photo::model::Orientation as PictureOrientation;
 
 /// Database ID of a visual item
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct VisualId(String);
 
 impl VisualId {
@@ -66,8 +66,20 @@ pub struct Visual {
 
     pub picture_orientation: Option<Orientation>,
 
+    /// RAW file the picture was developed from, such as when a camera saves RAW+JPEG.
+    pub raw_path: Option<PathBuf>,
+
     pub motion_photo_video_path: Option<PathBuf>,
 
+    /// Number of frames of an animated picture, such as a GIF. None for a still picture.
+    pub frame_count: Option<u32>,
+
+    /// Name of camera a picture was taken with. Such as "Google Pixel 7".
+    pub camera: Option<String>,
+
+    /// Name of lens a picture was taken with.
+    pub lens: Option<String>,
+
     /// Best candidate for ordering visual items. With a final fallback of the current timestamp.
     pub ordering_ts: DateTime<Utc>,
 
@@ -82,6 +94,12 @@ pub struct Visual {
 
     // Where photo was taken
     pub location: Option<LatLng>,
+
+    /// Has the user marked this as a favourite?
+    pub is_favourite: bool,
+
+    /// Star rating from 1 to 5. None if unrated.
+    pub rating: Option<u8>,
 }
 
 impl Visual {
@@ -89,6 +107,18 @@ impl Visual {
         self.picture_path.as_ref().or(self.video_path.as_ref())
     }
 
+    /// Paths of the original picture and video files. A live photo has both,
+    /// and a picture developed from a RAW file includes the RAW file.
+    /// Excludes derived files, such as thumbnails and extracted motion photo videos.
+    pub fn original_paths(&self) -> Vec<PathBuf> {
+        self.picture_path
+            .iter()
+            .chain(self.raw_path.iter())
+            .chain(self.video_path.iter())
+            .cloned()
+            .collect()
+    }
+
     pub fn is_selfie(&self) -> bool {
         self.is_selfie.is_some_and(|x| x)
     }
@@ -97,6 +127,11 @@ impl Visual {
         self.is_live_photo
     }
 
+    /// Is this an animated picture, such as a GIF?
+    pub fn is_animated(&self) -> bool {
+        self.frame_count.is_some_and(|x| x > 1)
+    }
+
     pub fn is_photo_only(&self) -> bool {
         self.picture_id.is_some() && self.video_id.is_none() && !self.is_live_photo
     }
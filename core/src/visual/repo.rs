@@ -2,6 +2,7 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use crate::camera;
 use crate::photo::PictureId;
 use crate::video::VideoId;
 use crate::visual::model::{PictureOrientation, Visual, VisualId};
@@ -21,9 +22,6 @@ use std::sync::{Arc, Mutex};
 /// Repository is backed by a Sqlite database.
 #[derive(Debug, Clone)]
 pub struct Repository {
-    /// Base path to picture library on file system
-    library_base_path: path::PathBuf,
-
     /// Base path for thumbnails and transcoded videos
     cache_dir_base_path: path::PathBuf,
 
@@ -34,12 +32,10 @@ pub struct Repository {
 impl Repository {
     /// Builds a Repository and creates operational tables.
     pub fn open(
-        library_base_path: &path::Path,
         cache_dir_base_path: &path::Path,
         con: Arc<Mutex<rusqlite::Connection>>,
     ) -> Result<Repository> {
         let repo = Repository {
-            library_base_path: path::PathBuf::from(library_base_path),
             cache_dir_base_path: path::PathBuf::from(cache_dir_base_path),
             con,
         };
@@ -52,6 +48,7 @@ impl Repository {
         let mut stmt = con.prepare(
             "SELECT
                     visual_id,
+                    root_path_b64,
                     link_path_b64,
 
                     picture_id,
@@ -59,12 +56,19 @@ impl Repository {
                     picture_thumbnail,
                     picture_orientation,
                     is_selfie,
+                    raw_picture_path_b64,
 
                     video_id,
                     video_path_b64,
                     video_thumbnail,
 
                     motion_photo_video_path,
+                    frame_count,
+
+                    camera_make,
+                    camera_model,
+                    lens_make,
+                    lens_model,
 
                     ordering_ts,
                     is_live_photo,
@@ -75,7 +79,10 @@ impl Repository {
                     video_rotation,
 
                     latitude,
-                    longitude
+                    longitude,
+
+                    is_favourite,
+                    rating
                 FROM visual
                 ORDER BY ordering_ts ASC",
         )?;
@@ -91,10 +98,14 @@ impl Repository {
             .map(VisualId::new)
             .expect("Must have visual_id");
 
+        let root_path: String = row.get("root_path_b64")?;
+        let root_path =
+            path_encoding::from_base64(&root_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
+
         let link_path: String = row.get("link_path_b64")?;
         let link_path =
             path_encoding::from_base64(&link_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
-        let link_path = self.library_base_path.join(link_path);
+        let link_path = root_path.join(link_path);
 
         let picture_id: Option<PictureId> = row.get("picture_id").map(PictureId::new).ok();
 
@@ -103,7 +114,7 @@ impl Repository {
             .ok()
             .and_then(|x: String| path_encoding::from_base64(&x).ok());
 
-        let picture_path = picture_path.map(|x| self.library_base_path.join(x));
+        let picture_path = picture_path.map(|x| root_path.join(x));
 
         let picture_thumbnail: Option<PathBuf> = row
             .get("picture_thumbnail")
@@ -118,6 +129,12 @@ impl Repository {
 
         let is_selfie: Option<bool> = row.get("is_selfie").ok();
 
+        let raw_path: Option<PathBuf> = row
+            .get("raw_picture_path_b64")
+            .ok()
+            .and_then(|x: String| path_encoding::from_base64(&x).ok())
+            .map(|x| root_path.join(x));
+
         let video_id: Option<VideoId> = row.get("video_id").map(VideoId::new).ok();
 
         let video_path: Option<PathBuf> = row
@@ -125,7 +142,7 @@ impl Repository {
             .ok()
             .and_then(|x: String| path_encoding::from_base64(&x).ok());
 
-        let video_path = video_path.map(|x| self.library_base_path.join(x));
+        let video_path = video_path.map(|x| root_path.join(x));
 
         let video_thumbnail: Option<PathBuf> = row
             .get("video_thumbnail")
@@ -146,6 +163,16 @@ impl Repository {
             .map(|x| self.cache_dir_base_path.join(x))
             .ok();
 
+        let frame_count: Option<u32> = row.get("frame_count").ok();
+
+        let camera_make: Option<String> = row.get("camera_make").ok();
+        let camera_model: Option<String> = row.get("camera_model").ok();
+        let camera = camera::model::camera_name(camera_make.as_deref(), camera_model.as_deref());
+
+        let lens_make: Option<String> = row.get("lens_make").ok();
+        let lens_model: Option<String> = row.get("lens_model").ok();
+        let lens = camera::model::camera_name(lens_make.as_deref(), lens_model.as_deref());
+
         let ordering_ts: DateTime<Utc> = row.get("ordering_ts").expect("Must have ordering_ts");
 
         let is_live_photo: Option<bool> = row.get("is_live_photo").ok();
@@ -174,6 +201,11 @@ impl Repository {
             None
         };
 
+        let is_favourite: Option<bool> = row.get("is_favourite").ok();
+        let is_favourite = is_favourite.is_some_and(|x| x);
+
+        let rating: Option<u8> = row.get("rating").ok();
+
         let v = Visual {
             visual_id,
             parent_path: link_path.parent().map(PathBuf::from).expect("Parent path"),
@@ -181,6 +213,10 @@ impl Repository {
             picture_id,
             picture_path,
             picture_orientation,
+            raw_path,
+            frame_count,
+            camera,
+            lens,
             video_id,
             video_path,
             ordering_ts,
@@ -192,6 +228,8 @@ impl Repository {
             video_duration,
             motion_photo_video_path,
             location,
+            is_favourite,
+            rating,
         };
         Ok(v)
     }
@@ -21,6 +21,9 @@ all-album = Day
 # Title for video album.
 videos-album = Videos
 
+# Title for album of favourite photos and videos.
+favourites-album = Favourites
+
 # Title for album of selfies.
 selfies-album = Selfies
 
@@ -33,9 +36,23 @@ folders-album = Folders
 # Title for album showing contents of one folder.
 folder-album = Folder
 
+# Title for page listing albums created by the user.
+user-albums = Albums
+
 # Title for places page which shows photos overlayed onto a map.
 places-page = Places
 
+# Title for album showing all cameras and lenses pictures were taken with.
+cameras-album = Cameras
+
+# Number of items taken with a camera and lens, shown under the cover in the cameras album.
+# Variables:
+#   $count - number of items.
+cameras-album-item-count = { $count ->
+    [one] 1 item
+   *[other] { $count } items
+}
+
 # Title for people page which shows an album of faces.
 people-page = People
 
@@ -52,6 +69,85 @@ people-page-status-no-people =
   .description = { -app-name } will look for faces in new photos when launched.
   Name the people in your photos so { -app-name } can make an album for each person.
 
+# Title for album of duplicate photos.
+duplicates-album = Duplicates
+
+# Status page shown for duplicates album when no duplicates are found.
+duplicates-album-status-none =
+  .title = No duplicates found
+  .description = { -app-name } looks for copies of photos after generating thumbnails.
+
+# Heading for a group of duplicate photos.
+# Attributes:
+#  .exact - all photos are identical files.
+#  .similar - photos look the same, but might have been resized or edited.
+duplicates-album-group =
+  .exact = Identical copies
+  .similar = Similar photos
+
+# Button to keep the checked photos in a group and move the others to the trash.
+# Attributes:
+#  .tooltip - Tooltip on mouse hover.
+duplicates-album-keep-selected = Keep Selected
+  .tooltip = Move photos that are not checked to the trash
+
+# Title for page of items moved to the trash from within Fotema.
+recently-deleted-page = Recently Deleted
+
+# Status page shown for recently deleted page when nothing has been moved to the trash.
+recently-deleted-status-none =
+  .title = No recently deleted items
+  .description = Photos and videos moved to the trash from { -app-name } can be restored from here.
+
+# Button to restore checked items to their original folders.
+# Attributes:
+#  .tooltip - Tooltip on mouse hover.
+recently-deleted-restore = Restore
+  .tooltip = Move checked items back to their original folders
+
+# Button to permanently delete checked items.
+# Attributes:
+#  .tooltip - Tooltip on mouse hover.
+recently-deleted-delete = Delete
+  .tooltip = Permanently delete checked items
+
+# Button to permanently delete every item in the recently deleted page.
+# Attributes:
+#  .tooltip - Tooltip on mouse hover.
+recently-deleted-empty = Empty Trash
+  .tooltip = Permanently delete all items
+
+# Heading of dialog for confirming permanent deletion.
+# Variables:
+#   $count - number of items to delete.
+recently-deleted-delete-heading = { $count ->
+    [one] Permanently delete item?
+   *[other] Permanently delete { $count } items?
+}
+
+recently-deleted-delete-dialog =
+  .body = Deleted items cannot be restored.
+  .cancel-button = Cancel
+  .delete-button = Delete
+
+# Title for search page.
+# Attributes:
+#  .placeholder - Placeholder text in empty search entry.
+#  .tooltip - Tooltip on mouse hover. Explains the search keywords, which should not be translated.
+search-page = Search
+  .placeholder = Search photos and videos
+  .tooltip = Search file, folder, and person names. Narrow results with person:, year:, month:, folder:, camera:, lens:, rating:, after:YYYY-MM-DD, before:YYYY-MM-DD, duration:>SECONDS, is:photo, is:video, is:selfie, is:motion, is:favourite, or has:location. Prefix with - to exclude.
+
+# Status page shown for search page before anything is searched for.
+search-page-status-empty =
+  .title = Search your library
+  .description = Try a name, or a search like person:Alice year:2023
+
+# Status page shown for search page when nothing matches.
+search-page-status-no-results =
+  .title = No results found
+  .description = Try a different search.
+
 ## Thumbnail decorations
 
 # Label on month album thumbnails.
@@ -90,6 +186,39 @@ about-translator-credits =
 # Tooltip for (i) button to show photo/video information sidebar
 viewer-info-tooltip = Show properties
 
+# Button to add or remove the shown item from favourites.
+viewer-favourite =
+    .tooltip = Favourite
+
+# Menu button for rating the shown item.
+viewer-rating =
+    .tooltip = Rating
+    .clear = Clear rating
+
+# Menu button for adding the shown item to an album.
+viewer-albums =
+    .tooltip = Add to album
+    .empty = No albums
+    .remove = Remove from this album
+    .cover = Use as album cover
+
+# Menu item to add the shown item to an album.
+viewer-albums-add = Add to { $name }
+
+# Button to move the shown item to the trash.
+viewer-trash =
+    .tooltip = Move to trash
+
+# Button to open the shown photo in the photo editor.
+viewer-edit =
+    .tooltip = Edit
+
+# Tooltip for a button that rates the shown item.
+viewer-rating-stars = { $count ->
+    [one] One star
+   *[other] { $count } stars
+}
+
 viewer-faces-menu =
   .tooltip = Faces menu
   .restore-ignored = Restore all ignored faces
@@ -152,6 +281,96 @@ viewer-error-missing-file = Cannot display file because it is missing:
 # be raised.
 viewer-error-missing-path = File path not present in database
 
+## Photo Editor
+
+# Title of the photo editor dialog.
+photo-editor-title = Edit Photo
+
+# Button to close the editor without saving.
+photo-editor-cancel = Cancel
+
+# Button to save edits and close the editor.
+photo-editor-done = Done
+
+# Tooltip for the photo being edited, which can be dragged over to crop it.
+photo-editor-crop =
+    .tooltip = Drag to crop
+
+photo-editor-rotate-left =
+    .tooltip = Rotate left
+
+photo-editor-rotate-right =
+    .tooltip = Rotate right
+
+photo-editor-flip-horizontal =
+    .tooltip = Flip horizontally
+
+photo-editor-flip-vertical =
+    .tooltip = Flip vertically
+
+# Label and tooltip for slider that levels a tilted photo.
+photo-editor-straighten = Straighten
+    .tooltip = Rotate to level the horizon
+
+photo-editor-clear-crop = Clear Crop
+    .tooltip = Show the whole photo
+
+# Heading above buttons that apply ready-made colour adjustments.
+photo-editor-presets = Presets
+
+photo-editor-preset =
+    .vivid = Vivid
+    .warm = Warm
+    .cool = Cool
+    .mono = Mono
+    .fade = Fade
+    .dramatic = Dramatic
+
+# Heading above sliders for colour and exposure adjustments.
+photo-editor-adjustments = Adjustments
+
+photo-editor-adjustment =
+    .exposure = Exposure
+    .contrast = Contrast
+    .highlights = Highlights
+    .shadows = Shadows
+    .saturation = Saturation
+    .temperature = Temperature
+    .tint = Tint
+
+photo-editor-revert = Revert
+    .tooltip = Discard all edits and show the original photo
+
+# Button to save the edited photo as a new file. The original file is never changed.
+photo-editor-save-copy = Save a Copy…
+    .tooltip = Save the edited photo as a new file
+    .dialog-title = Save a Copy
+    .saved = Copy saved
+    .failed = Failed to save copy
+
+# Suggested file name for an edited copy. Variables:
+#  $name - file name of the original photo without extension.
+photo-editor-copy-name = { $name } (edited)
+
+# Options for the file format and size of an edited copy.
+photo-editor-copy-format = Format
+
+photo-editor-copy-size = Size
+
+photo-editor-copy-size-full = Full size
+
+# Size option for an edited copy scaled down. Variables:
+#  $edge - length of the longest edge of the copy in pixels.
+photo-editor-copy-size-edge = { $edge } pixels
+
+photo-editor-copy-cancel = Cancel
+
+photo-editor-copy-save = Save…
+
+photo-editor-load-failed = Failed to load photo for editing.
+
+photo-editor-save-failed = Failed to save edits.
+
 ## Photo/Video Information Sidebar
 
 # Name of containing folder of photo or video being viewed.
@@ -196,6 +415,32 @@ infobar-audio-codec = Audio Codec
 # Width and height of photo or video.
 infobar-dimensions = Dimensions
 
+# Make and model of camera a photo was taken with, such as "Google Pixel 7".
+infobar-camera = Camera
+
+# Lens a photo was taken with.
+infobar-lens = Lens
+
+# Focal length of lens, with the equivalent focal length of a 35mm film camera.
+infobar-focal-length = Focal Length
+
+# Aperture of lens, such as "ƒ/1.8".
+infobar-aperture = Aperture
+
+# Exposure time, such as "1/250 s".
+infobar-exposure-time = Exposure Time
+
+# ISO speed, such as "ISO 100".
+infobar-iso = ISO
+
+# If the camera flash fired when a photo was taken.
+# Attributes:
+#  .fired - the flash fired.
+#  .not-fired - the flash didn't fire.
+infobar-flash = Flash
+  .fired = Fired
+  .not-fired = Did not fire
+
 ## Faces and People
 
 # Menu item to mark a face as the most import face for a person
@@ -233,12 +478,61 @@ prefs-ui-section = UI
 prefs-ui-selfies = Selfies
   .subtitle = Shows a separate album for selfies taken on iOS devices. Restart {-app-name} to apply.
 
+# Write XMP sidecars enabled or disabled.
+# Attributes:
+#   .subtitle - Description of toggle button action action.
+prefs-ui-write-xmp = Write XMP Sidecars
+  .subtitle = Save ratings, people, keywords, dates, and locations to .xmp files beside photos and videos so other apps, such as darktable and digiKam, can read them.
+
 # Album sort drop-down menu
 prefs-ui-chronological-album-sort = Sort Order
   .subtitle = Chronological sort order for albums.
   .ascending = Ascending
   .descending = Descending
 
+# Section of preferences for folders that make up the library.
+prefs-library-section = Library Folders
+  .description = Folders to scan for photos and videos.
+
+# Button to add a folder to the library.
+# Attributes:
+#   .tooltip - Tooltip for button
+#   .dialog-title - Title of folder chooser dialog
+prefs-library-add-folder = Add Folder
+  .tooltip = Add a folder to the library.
+  .dialog-title = Choose Library Folder
+
+# Button to remove a folder from the library.
+prefs-library-remove-folder = Remove Folder
+  .tooltip = Remove folder and its photos and videos from the library. Files are not deleted.
+
+# Toggle to enable or disable a library folder.
+prefs-library-enable-folder = Enable Folder
+  .tooltip = Show photos and videos from this folder.
+
+# Error message shown when a folder cannot be added to the library,
+# such as when it is inside a folder that is already in the library.
+prefs-library-add-folder-failed = Folder could not be added to the library.
+
+# Comma separated glob patterns of files to scan in a library folder.
+# If empty, all files are scanned.
+prefs-library-include-globs = Only Include Patterns, such as DCIM/**
+
+# Comma separated glob patterns of files and folders not to scan in a library folder.
+prefs-library-exclude-globs = Exclude Patterns, such as Screenshots
+
+# Toggle to skip folders whose names start with a dot.
+prefs-library-skip-hidden = Skip Hidden Folders
+  .subtitle = Folders containing a .nomedia or .fotemaignore file are always skipped.
+
+# Minimum size in kilobytes of files to scan.
+prefs-library-min-file-size = Minimum File Size
+  .subtitle = Skip files smaller than this many kilobytes.
+
+# Minimum width and height in pixels of pictures to scan.
+prefs-library-min-dimension = Minimum Picture Size
+  .subtitle = Skip pictures narrower or shorter than this many pixels.
+
 # Preferences related to machine learning, such as face detection.
 # Machine learning is CPU intensive so capabilities can be turned on or off by
 # the user
@@ -275,6 +569,15 @@ progress-detect-faces-photos = Detecting faces in photos.
 # Recognize faces in photos as known people
 progress-recognize-faces-photos = Recognizing people in photos.
 
+# Computing hashes to find duplicate photos
+progress-duplicates-photos = Looking for duplicate photos.
+
+# Saving people, keywords, dates, and locations to XMP sidecar files
+progress-sidecars-photos = Writing XMP sidecars.
+
+# Writing copies of photos and videos to a folder or zip archive
+progress-export-photos = Exporting.
+
 # Not doing any background work
 progress-idle = Idle.
 
@@ -282,11 +585,8 @@ progress-idle = Idle.
 
 # Similar to the progress bar, but allows for longer messages.
 
-# Scanning file system for new photos
-banner-scan-photos = Scanning file system for photos.
-
-# Scanning file system for new videos
-banner-scan-videos = Scanning file system for videos.
+# Scanning file system for new photos and videos
+banner-scan-library = Scanning file system for photos and videos.
 
 # Processing new photos to extract metadata from EXIF tags.
 banner-metadata-photos = Processing photo metadata.
@@ -318,6 +618,15 @@ banner-recognize-faces-photos = Recognizing people in photos. This will take a w
 # Transcoding videos to a compatible format
 banner-convert-videos = Converting videos.
 
+# Computing hashes to find duplicate photos
+banner-duplicates-photos = Looking for duplicate photos.
+
+# Saving people, keywords, dates, and locations to XMP sidecar files
+banner-sidecars-photos = Writing XMP sidecars.
+
+# Writing copies of photos and videos to a folder or zip archive
+banner-export-photos = Exporting photos and videos.
+
 # Button to stop all tasks doing background processing.
 banner-button-stop =
   .label = Stop
@@ -330,6 +639,9 @@ banner-stopping = Stopping tasks...
 
 # The "hamburger" menu on the main app navigation sidebar.
 
+# Menu item to show dialog for importing from cameras, phones, and memory cards
+primary-menu-import = Import…
+
 # Menu item to show preferences dialog
 primary-menu-preferences = Preferences
 
@@ -357,3 +669,325 @@ person-rename-dialog =
   .placeholder = New name
   .cancel-button = Cancel
   .rename-button = Rename
+
+## User Albums
+
+# Button to create a new album, and the heading of the dialog for naming it.
+user-albums-new = New Album
+  .tooltip = Create a new album
+  .create-button = Create
+
+# Button to create a new smart album, and the heading of the dialog for its rules.
+user-albums-new-smart = New Smart Album
+  .tooltip = Create an album that automatically includes everything matching some rules
+
+# Dialog for the name and rules of a smart album.
+smart-album-rules =
+  .name = Album name
+  .after = Taken on or after (YYYY-MM-DD)
+  .before = Taken on or before (YYYY-MM-DD)
+  .folder = Folder name
+  .person = Person name
+  .camera = Camera
+  .has-location = Has location
+  .videos-only = Videos only
+  .selfies-only = Selfies only
+  .min-duration = Minimum video length
+  .min-duration-subtitle = In seconds. Zero for any length.
+  .other = Other search terms
+  .cancel-button = Cancel
+  .save-button = Save
+
+# Number of items in an album, shown under the album cover.
+user-albums-item-count = { $count ->
+    [one] 1 item
+   *[other] { $count } items
+}
+
+# Status page shown when no albums have been created.
+user-albums-status-empty =
+  .title = No Albums
+  .description = Create an album, then add photos and videos to it from the viewer.
+
+# Menu item to edit the rules of a smart album
+user-album-menu-edit-rules = Edit rules
+
+# Menu item to rename an album
+user-album-menu-rename = Rename album
+
+# Menu item to delete an album
+user-album-menu-delete = Delete album
+
+# Dialog for naming a new album or renaming an album.
+user-album-name-dialog =
+  .rename-heading = Rename album?
+  .placeholder = Album name
+  .cancel-button = Cancel
+  .rename-button = Rename
+
+# Album delete dialog
+user-album-delete-dialog =
+  .heading = Delete album?
+  .body = No pictures or videos will be deleted.
+  .cancel-button = Cancel
+  .delete-button = Delete
+
+## Selecting items in albums
+
+# Button to leave selection mode.
+album-selection-done =
+  .tooltip = Stop selecting
+
+# Number of selected items, shown on the button for changing the selection.
+album-selection-count = { $count ->
+    [one] 1 selected
+   *[other] { $count } selected
+}
+
+# Menus for changing the selection and for actions on selected items.
+album-selection-menu =
+  .tooltip = More actions
+  .select-all = Select all
+  .select-none = Select none
+  .select-months = Select whole months
+  .select-years = Select whole years
+  .copy-to-folder = Copy to folder…
+  .move-to-folder = Move to folder…
+  .assign-person = Assign person…
+  .adjust-date = Adjust date and time…
+  .export = Export…
+  .open-with = Open with…
+
+# Button to move selected items to the trash.
+album-selection-trash =
+  .tooltip = Move to trash
+
+# Button to add selected items to an album.
+album-selection-add-to-album =
+  .tooltip = Add to album
+
+## Actions on selected items
+
+# Button on notifications to reverse an action.
+bulk-actions-undo = Undo
+
+# Button to dismiss a dialog for choosing an album, person, or application.
+bulk-actions-cancel-button = Cancel
+
+# Button to confirm a dialog for adjusting dates.
+bulk-actions-apply-button = Apply
+
+# Title of dialogs for choosing a folder.
+bulk-actions-folder-dialog =
+  .copy-title = Copy to folder
+  .move-title = Move to folder
+
+# Headings of dialogs for choosing an album, person, or application.
+bulk-actions-choose-album = Add to album
+bulk-actions-choose-person = Assign person
+bulk-actions-choose-app = Open with
+
+# Notifications when there is nothing to choose from.
+bulk-actions-no-albums = Create an album first
+bulk-actions-no-people = Name a person in the viewer first
+bulk-actions-no-apps = No applications can open these items
+
+# Notifications after actions on selected items.
+# Variables:
+#   $count - number of files or items changed.
+bulk-actions-copied = { $count ->
+    [one] Copied 1 file
+   *[other] Copied { $count } files
+}
+
+bulk-actions-moved = { $count ->
+    [one] Moved 1 file
+   *[other] Moved { $count } files
+}
+
+bulk-actions-trashed = { $count ->
+    [one] Moved 1 item to trash
+   *[other] Moved { $count } items to trash
+}
+
+bulk-actions-added-to-album = { $count ->
+    [one] Added 1 item to album
+   *[other] Added { $count } items to album
+}
+
+# Only pictures with exactly one unknown face can be assigned to a person.
+# Variables:
+#   $count - number of pictures assigned to person.
+#   $total - number of selected items.
+bulk-actions-assigned-person = Assigned { $count } of { $total } to person
+
+# Variables:
+#   $count - number of items with adjusted dates.
+bulk-actions-adjusted-dates = { $count ->
+    [one] Adjusted date of 1 item
+   *[other] Adjusted dates of { $count } items
+}
+
+# Dialog for correcting the date and time of selected items.
+# Variables:
+#   $count - number of selected items.
+bulk-actions-choose-date = Adjust date and time
+bulk-actions-choose-date-body = { $count ->
+    [one] Correct when 1 item was taken.
+   *[other] Correct when { $count } items were taken.
+}
+
+# Kinds of date correction.
+bulk-actions-date-mode = Change
+  .set = Set date and time
+  .shift = Shift by an amount
+  .time-zone = Set time zone
+  .revert = Revert to original
+
+# Local date and time, entered as year-month-day hour:minute:second.
+bulk-actions-date-set = Date and time (YYYY-MM-DD HH:MM:SS)
+
+# Amounts to shift dates by. Negative amounts shift dates earlier.
+bulk-actions-date-shift =
+  .days = Days
+  .hours = Hours
+  .minutes = Minutes
+
+bulk-actions-date-time-zone = Time zone
+
+# Variables:
+#   $offset - offset from UTC, such as +10:30.
+bulk-actions-date-time-zone-name = UTC{ $offset }
+
+# Notification when the entered date can't be understood.
+bulk-actions-invalid-date = Enter a date such as 2024-06-30 14:05
+
+## Import dialog
+
+# Title of dialog for importing from cameras, phones, and memory cards.
+import-title = Import
+
+# Button to copy or move new items into the library.
+import-button = Import
+
+# Connected cameras, phones, and memory cards.
+import-sources-section = Devices
+  .description = Connected cameras, phones, and memory cards.
+
+# Button to import from a folder that isn't on a connected device.
+import-choose-folder =
+  .tooltip = Import from folder
+  .dialog-title = Import from folder
+
+# Subtitle of a connected device that must be mounted before importing.
+import-source-not-mounted = Select to open device
+
+import-no-sources = No devices found
+
+# Notification when a device couldn't be opened.
+import-mount-failed = Couldn't open device
+
+# Where imported items are copied to.
+# Keep "{year}" and other placeholders in the description untranslated.
+import-destination-section = Destination
+  .description = Folders are named with {"{"}year{"}"}, {"{"}month{"}"}, and {"{"}day{"}"}, and files with {"{"}original_name{"}"}, {"{"}stem{"}"}, and {"{"}extension{"}"}.
+
+# Library folder to import items into.
+import-library-root = Library folder
+
+# Path of imported files within the library folder.
+import-template = Folder and file name
+
+import-delete-originals = Delete from device
+  .subtitle = Remove items from the device after importing.
+
+# Items on the device that aren't already in the library.
+import-preview-section = New items
+
+import-preview-no-source = Choose a device or folder to import from.
+
+import-preview-searching = Looking for new items…
+
+# Variables:
+#   $count - number of items not already in the library.
+#   $size - total size of the items, such as "1.2 GB".
+import-preview-count = { $count ->
+    [one] 1 new item, { $size }
+   *[other] { $count } new items, { $size }
+}
+
+# Progress of importing items.
+# Variables:
+#   $count - number of items imported so far.
+#   $total - number of items to import.
+import-progress = Importing { $count } of { $total }
+
+# Notifications after importing.
+# Variables:
+#   $count - number of files imported.
+#   $failed - number of files that couldn't be imported.
+import-completed = { $count ->
+    [one] Imported 1 item
+   *[other] Imported { $count } items
+}
+
+import-completed-with-failures = Imported { $count } items. { $failed } couldn't be imported.
+
+## Export dialog
+
+# Title of dialog for exporting copies of photos and videos, such as for sharing.
+export-title = Export
+
+# Title of export dialog when items have been chosen.
+# Variables:
+#   $count - number of items to export.
+export-title-count = { $count ->
+    [one] Export 1 item
+   *[other] Export { $count } items
+}
+
+# Button to choose where to export to and then start exporting.
+export-button = Export…
+
+export-pictures-section = Pictures
+
+export-format = Format
+
+export-size = Size
+
+export-size-full = Full size
+
+# Size option for exported pictures scaled down. Variables:
+#  $edge - length of the longest edge of the picture in pixels.
+export-size-edge = { $edge } pixels
+
+export-quality = Quality
+    .subtitle = Higher quality makes larger files. Only for JPEG and AVIF.
+
+export-metadata-section = Metadata
+
+export-keep-metadata = Keep metadata
+    .subtitle = Camera details and dates. Only JPEG and PNG files can keep metadata.
+
+export-keep-location = Keep location
+
+export-apply-orientation = Rotate pictures
+    .subtitle = Rotate pictures to match their orientation, for apps that ignore it.
+
+export-files-section = Files
+    .description = Videos are exported as they are.
+
+export-include-motion-videos = Include live photo videos
+    .subtitle = Export the video of live photos and motion photos alongside the picture.
+
+# Template for names of exported files. Placeholders are in English and must not be translated.
+export-template = File name, such as {"{"}year{"}"}-{"{"}month{"}"}-{"{"}day{"}"} {"{"}original_name{"}"}
+
+export-zip = Zip archive
+    .subtitle = Export to a single zip file instead of a folder.
+
+# Titles of file choosers for where to export to.
+export-choose-destination =
+    .folder-title = Export to folder
+    .zip-title = Export to zip archive
+    .zip-name = Export.zip
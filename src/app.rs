@@ -26,10 +26,19 @@ use crate::config::{APP_ID, PROFILE};
 use crate::adaptive;
 use crate::fl;
 
+use fotema_core::album;
+use fotema_core::camera;
 use fotema_core::database;
+use fotema_core::duplicates;
+use fotema_core::edit;
+use fotema_core::library;
+use fotema_core::Visual;
 use fotema_core::VisualId;
 use fotema_core::PictureId;
 use fotema_core::people;
+use fotema_core::rating;
+use fotema_core::search;
+use fotema_core::trash;
 
 use h3o::CellIndex;
 
@@ -51,20 +60,30 @@ use self::components::{
         album::{Album, AlbumInput, AlbumOutput},
         album_filter::AlbumFilter,
         album_sort::AlbumSort,
+        cameras_album::{CamerasAlbum, CamerasAlbumInput, CamerasAlbumOutput},
+        duplicates_album::{DuplicatesAlbum, DuplicatesAlbumInput, DuplicatesAlbumOutput},
         folders_album::{FoldersAlbum, FoldersAlbumInput, FoldersAlbumOutput},
         people_album::{PeopleAlbum, PeopleAlbumInput, PeopleAlbumOutput},
         person_album::{PersonAlbum, PersonAlbumInput, PersonAlbumOutput},
         places_album::{PlacesAlbum, PlacesAlbumInput, PlacesAlbumOutput},
+        recently_deleted::{RecentlyDeleted, RecentlyDeletedInput, RecentlyDeletedOutput},
+        search_album::{SearchAlbum, SearchAlbumInput, SearchAlbumOutput},
+        user_album::{UserAlbum, UserAlbumInput, UserAlbumOutput},
+        user_albums::{UserAlbums, UserAlbumsInput, UserAlbumsOutput},
     },
+    bulk_actions::{BulkAction, BulkActions, BulkActionsInput, BulkActionsOutput},
+    export_dialog::{ExportDialog, ExportDialogInput, ExportDialogOutput},
+    import_dialog::{ImportDialog, ImportDialogInput, ImportDialogOutput},
     library::{Library, LibraryInput, LibraryOutput},
     viewer::view_nav::{ViewNav, ViewNavInput, ViewNavOutput},
-    preferences::{PreferencesDialog, PreferencesInput},
+    preferences::{PreferencesDialog, PreferencesInput, PreferencesOutput},
 };
 
 mod background;
 
 use self::background::{
     bootstrap::{Bootstrap, BootstrapInput, BootstrapOutput, TaskName, MediaType},
+    library_watcher::LibraryWatcher,
 };
 
 use self::components::progress_monitor::ProgressMonitor;
@@ -86,7 +105,14 @@ pub enum ViewName {
     People,
     Person,
     Places,
+    Cameras,
     Selfies,
+    Duplicates,
+    RecentlyDeleted,
+    Search,
+    Favourites,
+    Albums,
+    UserAlbum,
 }
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, EnumString, AsRefStr, FromRepr)]
@@ -113,6 +139,9 @@ pub struct Settings {
     /// Sorting for albums.
     /// NOTE: doesn't include folder's album.
     pub album_sort: AlbumSort,
+
+    /// Write changes, such as ratings, to XMP sidecar files so other apps can see them.
+    pub write_xmp_sidecars: bool,
 }
 
 /// Active settings
@@ -134,16 +163,25 @@ pub(super) struct App {
 
     about_dialog: Controller<AboutDialog>,
     preferences_dialog: Controller<PreferencesDialog>,
+    import_dialog: Controller<ImportDialog>,
+    export_dialog: Controller<ExportDialog>,
 
     bootstrap: WorkerController<Bootstrap>,
 
+    // Queues scans when library files change on disk.
+    library_watcher: LibraryWatcher,
+
     library: Controller<Library>,
 
     view_nav: AsyncController<ViewNav>,
 
+    // Actions for items selected in albums. Shows toasts for undoing actions.
+    bulk_actions: Controller<BulkActions>,
+
     show_selfies: bool,
     selfies_page: Controller<Album>,
     videos_page: Controller<Album>,
+    favourites_page: Controller<Album>,
     motion_page: Controller<Album>,
 
     /// Album with photos overlayed onto a map
@@ -158,9 +196,27 @@ pub(super) struct App {
     // Grid of folders of photos
     folders_album: Controller<FoldersAlbum>,
 
+    // Grid of cameras and lenses pictures were taken with
+    cameras_album: Controller<CamerasAlbum>,
+
+    // Groups of duplicate photos
+    duplicates_page: Controller<DuplicatesAlbum>,
+
+    // Items moved to the trash from within Fotema
+    recently_deleted: Controller<RecentlyDeleted>,
+
+    // Search results
+    search_page: Controller<SearchAlbum>,
+
     // Folder album currently being viewed
     folder_album: Controller<Album>,
 
+    // Grid of albums created by the user
+    user_albums: Controller<UserAlbums>,
+
+    // User album currently being viewed
+    user_album: Controller<UserAlbum>,
+
     // Main navigation. Parent of library stack.
     main_navigation: adw::OverlaySplitView,
 
@@ -197,6 +253,9 @@ pub(super) enum AppMsg {
     // A sidebar item has been clicked
     SwitchView,
 
+    // Show search page
+    ShowSearch,
+
     // Show item.
     View(VisualId, AlbumFilter),
 
@@ -207,8 +266,17 @@ pub(super) enum AppMsg {
 
     ViewGeographicArea(CellIndex),
 
+    ViewCamera(String, Option<String>),
+
     ViewPerson(people::Person),
 
+    ViewUserAlbum(album::Album),
+
+    // A user album has been created, renamed, reordered, or had items added or removed.
+    UserAlbumsChanged,
+
+    UserAlbumDeleted,
+
     PersonDeleted,
 
     PersonRenamed,
@@ -224,6 +292,39 @@ pub(super) enum AppMsg {
     ScanPictureForFaces(PictureId),
     ScanPicturesForFaces,
 
+    // An item has been added to or removed from favourites, or rated.
+    RatingChanged,
+
+    // Library roots have been added, removed, enabled, or disabled.
+    LibraryRootsChanged,
+
+    // People, or other metadata saved to XMP sidecars, might have changed.
+    WriteSidecars,
+
+    // User has chosen an action for items selected in an album.
+    Bulk(BulkAction, Vec<Arc<Visual>>),
+
+    // User has chosen export settings and a destination for items.
+    Export(Vec<Arc<Visual>>, fotema_core::export::ExportSettings),
+
+    // Files have been copied, moved, or trashed by a bulk action, or imported.
+    FilesChanged(Vec<PathBuf>),
+
+    // Pictures have been assigned to or unassigned from a person by a bulk action.
+    PeopleChanged,
+
+    // Items have been moved to, restored from, or deleted from the trash.
+    TrashChanged,
+
+    // Dates of items have been corrected by a bulk action.
+    DatesChanged,
+
+    // A photo has been edited, so its thumbnail is out of date.
+    PhotoEdited,
+
+    // Viewer has nothing left to show.
+    CloseViewer,
+
     // Stop all background tasks
     StopBackgroundTasks,
 
@@ -236,7 +337,9 @@ pub(super) enum AppMsg {
 
 relm4::new_action_group!(pub(super) WindowActionGroup, "win");
 relm4::new_stateless_action!(PreferencesAction, WindowActionGroup, "preferences");
+relm4::new_stateless_action!(ImportAction, WindowActionGroup, "import");
 relm4::new_stateless_action!(AboutAction, WindowActionGroup, "about");
+relm4::new_stateless_action!(SearchAction, WindowActionGroup, "search");
 
 #[relm4::component(pub)]
 impl SimpleComponent for App {
@@ -248,6 +351,7 @@ impl SimpleComponent for App {
     menu! {
         primary_menu: {
             section! {
+                &fl!("primary-menu-import") => ImportAction,
                 &fl!("primary-menu-preferences") => PreferencesAction,
                 &fl!("primary-menu-about") => AboutAction,
             }
@@ -289,185 +393,255 @@ impl SimpleComponent for App {
                 connect_unapply => AppMsg::Adapt(adaptive::Layout::Wide),
             },
 
-            // Top-level navigation view containing:
-            // 1. Navigation view containing stack of pages.
-            // 2. Page for displaying a single photo.
+            // Toasts for undoing bulk actions are shown over every page.
             #[local_ref]
-            picture_navigation_view -> adw::NavigationView {
-                set_pop_on_escape: true,
-                connect_popped[sender] => move |_,_| sender.input(AppMsg::ViewHidden),
+            toast_overlay -> adw::ToastOverlay {
+                // Top-level navigation view containing:
+                // 1. Navigation view containing stack of pages.
+                // 2. Page for displaying a single photo.
+                #[local_ref]
+                picture_navigation_view -> adw::NavigationView {
+                    set_pop_on_escape: true,
+                    connect_popped[sender] => move |_,_| sender.input(AppMsg::ViewHidden),
 
-                // Page for showing main navigation. Such as "Library", "Selfies", etc.
-                adw::NavigationPage {
+                    // Page for showing main navigation. Such as "Library", "Selfies", etc.
+                    adw::NavigationPage {
+                        set_tag: Some("main"),
 
-                    #[local_ref]
-                    main_navigation -> adw::OverlaySplitView {
 
-                        set_max_sidebar_width: 200.0,
+                        #[local_ref]
+                        main_navigation -> adw::OverlaySplitView {
 
-                        #[wrap(Some)]
-                        set_sidebar = &adw::NavigationPage {
-                            adw::ToolbarView {
-                                add_top_bar = &adw::HeaderBar {
-                                    pack_end = &gtk::MenuButton {
-                                        set_icon_name: "open-menu-symbolic",
-                                        set_menu_model: Some(&primary_menu),
-                                    }
-                                },
-                                #[wrap(Some)]
-                                set_content = &gtk::Box {
-                                    set_orientation: gtk::Orientation::Vertical,
-                                    gtk::StackSidebar {
-                                        set_stack: &main_stack,
-                                        set_vexpand: true,
+                            set_max_sidebar_width: 200.0,
+
+                            #[wrap(Some)]
+                            set_sidebar = &adw::NavigationPage {
+                                adw::ToolbarView {
+                                    add_top_bar = &adw::HeaderBar {
+                                        pack_end = &gtk::MenuButton {
+                                            set_icon_name: "open-menu-symbolic",
+                                            set_menu_model: Some(&primary_menu),
+                                        }
                                     },
+                                    #[wrap(Some)]
+                                    set_content = &gtk::Box {
+                                        set_orientation: gtk::Orientation::Vertical,
+                                        gtk::StackSidebar {
+                                            set_stack: &main_stack,
+                                            set_vexpand: true,
+                                        },
 
-                                    model.bootstrap_progress.widget(),
+                                        model.bootstrap_progress.widget(),
+                                    }
                                 }
-                            }
-                        },
+                            },
+
+                            #[wrap(Some)]
+                            set_content = &adw::NavigationPage {
+                                adw::ToolbarView {
+                                    #[local_ref]
+                                    add_top_bar = &header_bar -> adw::HeaderBar {
+                                        set_hexpand: true,
+                                        pack_start = &gtk::Button {
+                                            set_icon_name: "dock-left-symbolic",
+                                            connect_clicked => AppMsg::ToggleSidebar,
+                                        },
 
-                        #[wrap(Some)]
-                        set_content = &adw::NavigationPage {
-                            adw::ToolbarView {
-                                #[local_ref]
-                                add_top_bar = &header_bar -> adw::HeaderBar {
-                                    set_hexpand: true,
-                                    pack_start = &gtk::Button {
-                                        set_icon_name: "dock-left-symbolic",
-                                        connect_clicked => AppMsg::ToggleSidebar,
+                                        #[local_ref]
+                                        pack_end = &spinner -> gtk::Spinner,
                                     },
 
-                                    #[local_ref]
-                                    pack_end = &spinner -> gtk::Spinner,
-                                },
+                                    // NOTE I would like this to be an adw::ViewStack
+                                    // so that I could use a adw::ViewSwitcher in the sidebar
+                                    // that would show icons.
+                                    // However, adw::ViewSwitch can't display vertically.
+                                    #[wrap(Some)]
+                                    set_content = &gtk::Box {
+                                        set_orientation: gtk::Orientation::Vertical,
+
+                                        #[local_ref]
+                                        banner -> adw::Banner {
+                                            connect_button_clicked => AppMsg::StopBackgroundTasks,
+                                        },
 
-                                // NOTE I would like this to be an adw::ViewStack
-                                // so that I could use a adw::ViewSwitcher in the sidebar
-                                // that would show icons.
-                                // However, adw::ViewSwitch can't display vertically.
-                                #[wrap(Some)]
-                                set_content = &gtk::Box {
-                                    set_orientation: gtk::Orientation::Vertical,
+                                        #[local_ref]
+                                        main_stack -> gtk::Stack {
+                                            connect_visible_child_notify => AppMsg::SwitchView,
 
-                                    #[local_ref]
-                                    banner -> adw::Banner {
-                                        connect_button_clicked => AppMsg::StopBackgroundTasks,
-                                    },
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.library.widget(),
 
-                                    #[local_ref]
-                                    main_stack -> gtk::Stack {
-                                        connect_visible_child_notify => AppMsg::SwitchView,
+                                                #[name(switcher_bar)]
+                                                adw::ViewSwitcherBar {
+                                                    set_stack: Some(model.library.widget()),
+                                                },
+                                            } -> {
+                                                set_title: &fl!("library-page"),
+                                                set_name: ViewName::Library.into(),
 
-                                        add_child = &gtk::Box {
-                                            set_orientation: gtk::Orientation::Vertical,
-                                            container_add: model.library.widget(),
+                                                // NOTE gtk::StackSidebar doesn't show icon :-/
+                                                set_icon_name: "image-alt-symbolic",
+                                            },
 
-                                            #[name(switcher_bar)]
-                                            adw::ViewSwitcherBar {
-                                                set_stack: Some(model.library.widget()),
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.search_page.widget(),
+                                            } -> {
+                                                set_title: &fl!("search-page"),
+                                                set_name: ViewName::Search.into(),
+                                                // NOTE gtk::StackSidebar doesn't show icon :-/
+                                                set_icon_name: "system-search-symbolic",
                                             },
-                                        } -> {
-                                            set_title: &fl!("library-page"),
-                                            set_name: ViewName::Library.into(),
 
-                                            // NOTE gtk::StackSidebar doesn't show icon :-/
-                                            set_icon_name: "image-alt-symbolic",
-                                        },
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.favourites_page.widget(),
+                                            } -> {
+                                                set_title: &fl!("favourites-album"),
+                                                set_name: ViewName::Favourites.into(),
+                                                // NOTE gtk::StackSidebar doesn't show icon :-/
+                                                set_icon_name: "emblem-favorite-symbolic",
+                                            },
 
-                                        add_child = &gtk::Box {
-                                            set_orientation: gtk::Orientation::Vertical,
-                                            container_add: model.videos_page.widget(),
-                                        } -> {
-                                            set_title: &fl!("videos-album"),
-                                            set_name: ViewName::Videos.into(),
-                                            // NOTE gtk::StackSidebar doesn't show icon :-/
-                                            set_icon_name: "video-reel-symbolic",
-                                        },
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.user_albums.widget(),
+                                            } -> {
+                                                set_title: &fl!("user-albums"),
+                                                set_name: ViewName::Albums.into(),
+                                                // NOTE gtk::StackSidebar doesn't show icon :-/
+                                                set_icon_name: "folder-pictures-symbolic",
+                                            },
 
-                                        add_child = &gtk::Box {
-                                            set_orientation: gtk::Orientation::Vertical,
-                                            container_add: model.motion_page.widget(),
-                                        } -> {
-                                            set_title: &fl!("animated-album"),
-                                            set_name: ViewName::Animated.into(),
-                                            // NOTE gtk::StackSidebar doesn't show icon :-/
-                                            set_icon_name: "sonar-symbolic",
-                                        },
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.videos_page.widget(),
+                                            } -> {
+                                                set_title: &fl!("videos-album"),
+                                                set_name: ViewName::Videos.into(),
+                                                // NOTE gtk::StackSidebar doesn't show icon :-/
+                                                set_icon_name: "video-reel-symbolic",
+                                            },
 
-                                        add_child = &gtk::Box {
-                                            set_orientation: gtk::Orientation::Vertical,
-                                            container_add: model.people_page.widget(),
-                                        } -> {
-                                            set_title: &fl!("people-page"),
-                                            set_name: ViewName::People.into(),
-                                        },
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.motion_page.widget(),
+                                            } -> {
+                                                set_title: &fl!("animated-album"),
+                                                set_name: ViewName::Animated.into(),
+                                                // NOTE gtk::StackSidebar doesn't show icon :-/
+                                                set_icon_name: "sonar-symbolic",
+                                            },
 
-                                        add_child = &gtk::Box {
-                                            set_orientation: gtk::Orientation::Vertical,
-                                            container_add: model.places_page.widget(),
-                                        } -> {
-                                            set_title: &fl!("places-page"),
-                                            set_name: ViewName::Places.into(),
-                                        },
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.people_page.widget(),
+                                            } -> {
+                                                set_title: &fl!("people-page"),
+                                                set_name: ViewName::People.into(),
+                                            },
 
-                                        add_child = &gtk::Box {
-                                            set_orientation: gtk::Orientation::Vertical,
-                                            container_add: model.selfies_page.widget(),
-                                        } -> {
-                                            set_visible: model.show_selfies,
-                                            set_title: &fl!("selfies-album"),
-                                            set_name: ViewName::Selfies.into(),
-                                            // NOTE gtk::StackSidebar doesn't show icon :-/
-                                            set_icon_name: "sentiment-very-satisfied-symbolic",
-                                        },
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.places_page.widget(),
+                                            } -> {
+                                                set_title: &fl!("places-page"),
+                                                set_name: ViewName::Places.into(),
+                                            },
+
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.selfies_page.widget(),
+                                            } -> {
+                                                set_visible: model.show_selfies,
+                                                set_title: &fl!("selfies-album"),
+                                                set_name: ViewName::Selfies.into(),
+                                                // NOTE gtk::StackSidebar doesn't show icon :-/
+                                                set_icon_name: "sentiment-very-satisfied-symbolic",
+                                            },
+
+                                            add_child = &adw::NavigationView {
+                                                set_pop_on_escape: true,
+
+                                                adw::NavigationPage {
+                                                    //set_tag: Some("folders"),
+                                                    //set_title: "Folder",
+                                                    model.folders_album.widget(),
+                                                },
+                                            } -> {
+                                                set_title: &fl!("folders-album"),
+                                                set_name: ViewName::Folders.into(),
+                                                // NOTE gtk::StackSidebar doesn't show icon :-/
+                                                set_icon_name: "folder-symbolic",
+                                            },
+
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.cameras_album.widget(),
+                                            } -> {
+                                                set_title: &fl!("cameras-album"),
+                                                set_name: ViewName::Cameras.into(),
+                                                // NOTE gtk::StackSidebar doesn't show icon :-/
+                                                set_icon_name: "camera-photo-symbolic",
+                                            },
 
-                                        add_child = &adw::NavigationView {
-                                            set_pop_on_escape: true,
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.duplicates_page.widget(),
+                                            } -> {
+                                                set_title: &fl!("duplicates-album"),
+                                                set_name: ViewName::Duplicates.into(),
+                                                // NOTE gtk::StackSidebar doesn't show icon :-/
+                                                set_icon_name: "edit-copy-symbolic",
+                                            },
 
-                                            adw::NavigationPage {
-                                                //set_tag: Some("folders"),
-                                                //set_title: "Folder",
-                                                model.folders_album.widget(),
+                                            add_child = &gtk::Box {
+                                                set_orientation: gtk::Orientation::Vertical,
+                                                container_add: model.recently_deleted.widget(),
+                                            } -> {
+                                                set_title: &fl!("recently-deleted-page"),
+                                                set_name: ViewName::RecentlyDeleted.into(),
+                                                set_icon_name: "user-trash-symbolic",
                                             },
-                                        } -> {
-                                            set_title: &fl!("folders-album"),
-                                            set_name: ViewName::Folders.into(),
-                                            // NOTE gtk::StackSidebar doesn't show icon :-/
-                                            set_icon_name: "folder-symbolic",
                                         },
                                     },
                                 },
                             },
                         },
                     },
-                },
 
-                adw::NavigationPage {
-                    set_tag: Some("album"),
-                    adw::ToolbarView {
-                        add_top_bar = &adw::HeaderBar {
+                    adw::NavigationPage {
+                        set_tag: Some("album"),
+                        adw::ToolbarView {
+                            add_top_bar = &adw::HeaderBar {
+                                #[wrap(Some)]
+                                set_title_widget = &gtk::Label {
+                                    set_label: &fl!("folder-album"),
+                                    add_css_class: "title",
+                                }
+                            },
+
                             #[wrap(Some)]
-                            set_title_widget = &gtk::Label {
-                                set_label: &fl!("folder-album"),
-                                add_css_class: "title",
-                            }
-                        },
+                            set_content = model.folder_album.widget(),
+                        }
+                    },
 
-                        #[wrap(Some)]
-                        set_content = model.folder_album.widget(),
-                    }
-                },
+                    adw::NavigationPage {
+                        set_tag: Some("person_album"),
+                        model.person_album.widget(),
+                    },
 
-                adw::NavigationPage {
-                    set_tag: Some("person_album"),
-                    model.person_album.widget(),
-                },
+                    adw::NavigationPage {
+                        set_tag: Some("user_album"),
+                        model.user_album.widget(),
+                    },
 
-                // Page for showing a single photo.
-                adw::NavigationPage {
-                    set_tag: Some("picture"),
-                    model.view_nav.widget(),
+                    // Page for showing a single photo.
+                    adw::NavigationPage {
+                        set_tag: Some("picture"),
+                        model.view_nav.widget(),
+                    },
                 },
             },
         }
@@ -494,12 +668,33 @@ impl SimpleComponent for App {
         let con = database::setup(&db_path).expect("Must be able to open database");
         let con = Arc::new(Mutex::new(con));
 
+        let mut library_repo = library::Repository::open(con.clone()).unwrap();
+
+        // The XDG pictures directory is the only library root on first run.
+        if let Err(e) = library_repo.add_default_if_empty(&pic_base_dir) {
+            error!("Failed adding default library root: {}", e);
+        }
+
         let people_repo = people::Repository::open(
-            &pic_base_dir,
             &data_dir,
             con.clone(),
         ).unwrap();
 
+        let duplicates_repo = duplicates::Repository::open(&cache_dir, con.clone()).unwrap();
+
+        let search_repo = search::Repository::open(con.clone()).unwrap();
+
+        let rating_repo = rating::Repository::open(con.clone()).unwrap();
+
+        let album_repo = album::Repository::open(con.clone()).unwrap();
+
+        let trash_repo = trash::Repository::open(&cache_dir, &data_dir, con.clone()).unwrap();
+        let dates_repo = fotema_core::dates::Repository::open(con.clone()).unwrap();
+
+        let edit_repo = edit::Repository::open(con.clone()).unwrap();
+
+        let camera_repo = camera::Repository::open(con.clone()).unwrap();
+
         let state = SharedState::new(relm4::SharedState::new());
         let active_view = ActiveView::new(relm4::SharedState::new());
         let adaptive_layout = Arc::new(adaptive::LayoutState::new());
@@ -529,19 +724,38 @@ impl SimpleComponent for App {
                 BootstrapOutput::Completed => AppMsg::BootstrapCompleted,
             });
 
+        let mut library_watcher = LibraryWatcher::new(library_repo.clone(), bootstrap.sender().clone());
+        library_watcher.watch();
+
         let library = Library::builder()
             .launch((state.clone(), active_view.clone(), adaptive_layout.clone()))
             .forward(sender.input_sender(), |msg| match msg {
                 LibraryOutput::View(id) => AppMsg::View(id, AlbumFilter::All),
+                LibraryOutput::Bulk(action, visuals) => AppMsg::Bulk(action, visuals),
             });
 
         settings_state.subscribe(library.sender(), |settings| LibraryInput::Sort(settings.album_sort));
 
         let view_nav = ViewNav::builder()
-            .launch((state.clone(), bootstrap_progress_monitor, adaptive_layout.clone(), people_repo.clone()))
+            .launch((
+                state.clone(),
+                bootstrap_progress_monitor,
+                adaptive_layout.clone(),
+                people_repo.clone(),
+                rating_repo,
+                album_repo.clone(),
+                edit_repo,
+                camera_repo,
+                settings_state.clone(),
+            ))
             .forward(sender.input_sender(), |msg| match msg {
                 ViewNavOutput::TranscodeAll => AppMsg::TranscodeAll,
                 ViewNavOutput::ScanForFaces(picture_id) => AppMsg::ScanPictureForFaces(picture_id),
+                ViewNavOutput::RatingChanged => AppMsg::RatingChanged,
+                ViewNavOutput::AlbumsChanged => AppMsg::UserAlbumsChanged,
+                ViewNavOutput::Trash(visual) => AppMsg::Bulk(BulkAction::Trash, vec![visual]),
+                ViewNavOutput::Close => AppMsg::CloseViewer,
+                ViewNavOutput::Edited => AppMsg::PhotoEdited,
             });
 
         let selfies_page = Album::builder()
@@ -549,6 +763,8 @@ impl SimpleComponent for App {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::Moved(_, _) => AppMsg::Ignore,
+                AlbumOutput::Bulk(action, visuals) => AppMsg::Bulk(action, visuals),
             });
 
         state.subscribe(selfies_page.sender(), |_| AlbumInput::Refresh);
@@ -562,6 +778,8 @@ impl SimpleComponent for App {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::Moved(_, _) => AppMsg::Ignore,
+                AlbumOutput::Bulk(action, visuals) => AppMsg::Bulk(action, visuals),
             });
 
         state.subscribe(motion_page.sender(), |_| AlbumInput::Refresh);
@@ -573,12 +791,49 @@ impl SimpleComponent for App {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::Moved(_, _) => AppMsg::Ignore,
+                AlbumOutput::Bulk(action, visuals) => AppMsg::Bulk(action, visuals),
             });
 
         state.subscribe(videos_page.sender(), |_| AlbumInput::Refresh);
         adaptive_layout.subscribe(videos_page.sender(), |layout| AlbumInput::Adapt(*layout));
         settings_state.subscribe(videos_page.sender(), |settings| AlbumInput::Sort(settings.album_sort));
 
+        let favourites_page = Album::builder()
+            .launch((state.clone(), active_view.clone(), ViewName::Favourites, AlbumFilter::Favourites))
+            .forward(sender.input_sender(), |msg| match msg {
+                AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::Moved(_, _) => AppMsg::Ignore,
+                AlbumOutput::Bulk(action, visuals) => AppMsg::Bulk(action, visuals),
+            });
+
+        state.subscribe(favourites_page.sender(), |_| AlbumInput::Refresh);
+        adaptive_layout.subscribe(favourites_page.sender(), |layout| AlbumInput::Adapt(*layout));
+        settings_state.subscribe(favourites_page.sender(), |settings| AlbumInput::Sort(settings.album_sort));
+
+        let user_albums = UserAlbums::builder()
+            .launch((state.clone(), album_repo.clone(), active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                UserAlbumsOutput::AlbumSelected(user_album) => AppMsg::ViewUserAlbum(user_album),
+            });
+
+        state.subscribe(user_albums.sender(), |_| UserAlbumsInput::Refresh);
+        adaptive_layout.subscribe(user_albums.sender(), |layout| UserAlbumsInput::Adapt(*layout));
+
+        let user_album = UserAlbum::builder()
+            .launch((state.clone(), album_repo.clone(), active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                UserAlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                UserAlbumOutput::Changed => AppMsg::UserAlbumsChanged,
+                UserAlbumOutput::Deleted => AppMsg::UserAlbumDeleted,
+                UserAlbumOutput::Bulk(action, visuals) => AppMsg::Bulk(action, visuals),
+            });
+
+        state.subscribe(user_album.sender(), |_| UserAlbumInput::Refresh);
+        adaptive_layout.subscribe(user_album.sender(), |layout| UserAlbumInput::Adapt(*layout));
+        settings_state.subscribe(user_album.sender(), |settings| UserAlbumInput::Sort(settings.album_sort));
+
         let people_page = PeopleAlbum::builder()
             .launch((people_repo.clone(), active_view.clone(), settings_state.clone()))
             .forward(
@@ -597,6 +852,7 @@ impl SimpleComponent for App {
                 PersonAlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 PersonAlbumOutput::Deleted => AppMsg::PersonDeleted,
                 PersonAlbumOutput::Renamed => AppMsg::PersonRenamed,
+                PersonAlbumOutput::Bulk(action, visuals) => AppMsg::Bulk(action, visuals),
             });
 
         state.subscribe(person_album.sender(), |_| PersonAlbumInput::Refresh);
@@ -613,6 +869,33 @@ impl SimpleComponent for App {
         state.subscribe(places_page.sender(), |_| PlacesAlbumInput::Refresh);
         adaptive_layout.subscribe(places_page.sender(), |layout| PlacesAlbumInput::Adapt(*layout));
 
+        let duplicates_page = DuplicatesAlbum::builder()
+            .launch((duplicates_repo, trash_repo.clone(), state.clone(), active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                DuplicatesAlbumOutput::Trashed => AppMsg::TrashChanged,
+            });
+
+        state.subscribe(duplicates_page.sender(), |_| DuplicatesAlbumInput::Refresh);
+
+        let recently_deleted = RecentlyDeleted::builder()
+            .launch((trash_repo.clone(), active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                RecentlyDeletedOutput::Changed => AppMsg::TrashChanged,
+            });
+
+        state.subscribe(recently_deleted.sender(), |_| RecentlyDeletedInput::Refresh);
+
+        let search_page = SearchAlbum::builder()
+            .launch((state.clone(), search_repo, active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                SearchAlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
+                SearchAlbumOutput::Bulk(action, visuals) => AppMsg::Bulk(action, visuals),
+            });
+
+        state.subscribe(search_page.sender(), |_| SearchAlbumInput::Refresh);
+        adaptive_layout.subscribe(search_page.sender(), |layout| SearchAlbumInput::Adapt(*layout));
+        settings_state.subscribe(search_page.sender(), |settings| SearchAlbumInput::Sort(settings.album_sort));
+
         let folders_album = FoldersAlbum::builder()
             .launch((state.clone(), active_view.clone()))
             .forward(
@@ -625,11 +908,22 @@ impl SimpleComponent for App {
         state.subscribe(folders_album.sender(), |_| FoldersAlbumInput::Refresh);
         adaptive_layout.subscribe(folders_album.sender(), |layout| FoldersAlbumInput::Adapt(*layout));
 
+        let cameras_album = CamerasAlbum::builder()
+            .launch((state.clone(), active_view.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                CamerasAlbumOutput::CameraSelected(camera, lens) => AppMsg::ViewCamera(camera, lens),
+            });
+
+        state.subscribe(cameras_album.sender(), |_| CamerasAlbumInput::Refresh);
+        adaptive_layout.subscribe(cameras_album.sender(), |layout| CamerasAlbumInput::Adapt(*layout));
+
         let folder_album = Album::builder()
             .launch((state.clone(), active_view.clone(), ViewName::Folder, AlbumFilter::None))
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, filter) => AppMsg::View(id, filter),
                 AlbumOutput::ScrollOffset(_) => AppMsg::Ignore,
+                AlbumOutput::Moved(_, _) => AppMsg::Ignore,
+                AlbumOutput::Bulk(action, visuals) => AppMsg::Bulk(action, visuals),
             });
 
         state.subscribe(folder_album.sender(), |_| AlbumInput::Refresh);
@@ -638,9 +932,34 @@ impl SimpleComponent for App {
 
         let about_dialog = AboutDialog::builder().launch(root.clone()).detach();
 
+        let bulk_actions = BulkActions::builder()
+            .launch((root.clone(), album_repo.clone(), people_repo.clone(), trash_repo, dates_repo))
+            .forward(sender.input_sender(), |msg| match msg {
+                BulkActionsOutput::FilesChanged(paths) => AppMsg::FilesChanged(paths),
+                BulkActionsOutput::AlbumsChanged => AppMsg::UserAlbumsChanged,
+                BulkActionsOutput::PeopleChanged => AppMsg::PeopleChanged,
+                BulkActionsOutput::TrashChanged => AppMsg::TrashChanged,
+                BulkActionsOutput::DatesChanged => AppMsg::DatesChanged,
+            });
+
         let preferences_dialog = PreferencesDialog::builder()
-            .launch((settings_state.clone(), root.clone()))
-            .detach();
+            .launch((settings_state.clone(), library_repo.clone(), root.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                PreferencesOutput::LibraryRootsChanged => AppMsg::LibraryRootsChanged,
+                PreferencesOutput::WriteXmpSidecarsEnabled => AppMsg::WriteSidecars,
+            });
+
+        let import_dialog = ImportDialog::builder()
+            .launch((library_repo.clone(), root.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                ImportDialogOutput::FilesChanged(paths) => AppMsg::FilesChanged(paths),
+            });
+
+        let export_dialog = ExportDialog::builder()
+            .launch(root.clone())
+            .forward(sender.input_sender(), |msg| match msg {
+                ExportDialogOutput::Export(visuals, settings) => AppMsg::Export(visuals, settings),
+            });
 
         let picture_navigation_view = adw::NavigationView::builder().build();
 
@@ -660,22 +979,33 @@ impl SimpleComponent for App {
         let model = Self {
             adaptive_layout,
             bootstrap,
+            library_watcher,
 
             about_dialog,
             preferences_dialog,
+            import_dialog,
+            export_dialog,
 
             library,
 
             view_nav,
+            bulk_actions,
             motion_page,
             videos_page,
+            favourites_page,
             people_page,
             person_album,
             places_page,
             selfies_page,
             show_selfies,
             folders_album,
+            cameras_album,
+            user_albums,
+            user_album,
             folder_album,
+            duplicates_page,
+            recently_deleted,
+            search_page,
 
             main_navigation: main_navigation.clone(),
             main_stack: main_stack.clone(),
@@ -689,6 +1019,8 @@ impl SimpleComponent for App {
             banner: banner.clone(),
         };
 
+        let toast_overlay = model.bulk_actions.widget();
+
         let widgets = view_output!();
 
         let mut actions = RelmActionGroup::<WindowActionGroup>::new();
@@ -707,11 +1039,29 @@ impl SimpleComponent for App {
             })
         };
 
+        let import_action = {
+            let sender = model.import_dialog.sender().clone();
+            RelmAction::<ImportAction>::new_stateless(move |_| {
+                sender.send(ImportDialogInput::Present).unwrap();
+            })
+        };
+
+        let search_action = {
+            let sender = sender.clone();
+            RelmAction::<SearchAction>::new_stateless(move |_| {
+                sender.input(AppMsg::ShowSearch);
+            })
+        };
+
         actions.add_action(about_action);
         actions.add_action(preferences_action);
+        actions.add_action(import_action);
+        actions.add_action(search_action);
 
         actions.register_for_widget(&widgets.main_window);
 
+        main_application().set_accelerators_for_action::<SearchAction>(&["<Control>f"]);
+
         widgets.load_window_size();
 
         // Get startup window size and propagate so all components have correct narrow/wide layout.
@@ -789,9 +1139,27 @@ impl SimpleComponent for App {
                     ViewName::People => self.people_page.emit(PeopleAlbumInput::Activate),
                     ViewName::Person => self.person_album.emit(PersonAlbumInput::Activate),
                     ViewName::Places => self.places_page.emit(PlacesAlbumInput::Activate),
+                    ViewName::Cameras => self.cameras_album.emit(CamerasAlbumInput::Activate),
+                    ViewName::Duplicates => self.duplicates_page.emit(DuplicatesAlbumInput::Activate),
+                    ViewName::RecentlyDeleted => self.recently_deleted.emit(RecentlyDeletedInput::Activate),
+                    ViewName::Search => self.search_page.emit(SearchAlbumInput::Activate),
+                    ViewName::Favourites => self.favourites_page.emit(AlbumInput::Activate),
+                    ViewName::Albums => self.user_albums.emit(UserAlbumsInput::Activate),
+                    ViewName::UserAlbum => self.user_album.emit(UserAlbumInput::Activate),
                     ViewName::Nothing => event!(Level::WARN, "Nothing activated... which should not happen"),
                 }
             },
+            AppMsg::ShowSearch => {
+                self.picture_navigation_view.pop_to_tag("main");
+                let search: &str = ViewName::Search.into();
+                if self.main_stack.visible_child_name().is_some_and(|x| x == search) {
+                    // Already visible, so just focus search entry.
+                    self.search_page.emit(SearchAlbumInput::Activate);
+                } else {
+                    // Switching view will activate search page and focus search entry.
+                    self.main_stack.set_visible_child_name(search);
+                }
+            },
             AppMsg::View(visual_id, filter) => {
                 // Send message to show image
                 self.view_nav.emit(ViewNavInput::View(visual_id, filter));
@@ -813,6 +1181,11 @@ impl SimpleComponent for App {
                 self.picture_navigation_view.push_by_tag("album");
 
             },
+            AppMsg::ViewCamera(camera, lens) => {
+                self.folder_album.emit(AlbumInput::Activate);
+                self.folder_album.emit(AlbumInput::Filter(AlbumFilter::Camera(camera, lens)));
+                self.picture_navigation_view.push_by_tag("album");
+            },
             AppMsg::ViewPerson(person) => {
                 //info!("picture_ids = {:?}", picture_ids);
                 info!("Viewing person: {}", person.person_id);
@@ -820,12 +1193,34 @@ impl SimpleComponent for App {
                 self.person_album.emit(PersonAlbumInput::View(person));
                 self.picture_navigation_view.push_by_tag("person_album");
             },
+            AppMsg::ViewUserAlbum(user_album) => {
+                info!("Viewing user album: {}", user_album.album_id);
+                self.user_album.emit(UserAlbumInput::View(user_album));
+                self.picture_navigation_view.push_by_tag("user_album");
+            },
+            AppMsg::UserAlbumsChanged => {
+                self.user_albums.emit(UserAlbumsInput::Refresh);
+                self.user_album.emit(UserAlbumInput::Refresh);
+            },
+            AppMsg::UserAlbumDeleted => {
+                self.picture_navigation_view.pop();
+                self.user_albums.emit(UserAlbumsInput::Refresh);
+            },
             AppMsg::PersonDeleted => {
                 self.picture_navigation_view.pop();
                 self.people_page.emit(PeopleAlbumInput::Refresh);
+                self.search_page.emit(SearchAlbumInput::PeopleChanged);
+                self.bootstrap.emit(BootstrapInput::WriteSidecars);
+            },
+            AppMsg::RatingChanged => {
+                // Favourites and search results for ratings depend on the changed item.
+                self.favourites_page.emit(AlbumInput::Refresh);
+                self.search_page.emit(SearchAlbumInput::Refresh);
             },
             AppMsg::PersonRenamed => {
                 self.people_page.emit(PeopleAlbumInput::Refresh);
+                self.search_page.emit(SearchAlbumInput::PeopleChanged);
+                self.bootstrap.emit(BootstrapInput::WriteSidecars);
             },
             AppMsg::TaskStarted(task_name) => {
                 self.spinner.start();
@@ -834,11 +1229,8 @@ impl SimpleComponent for App {
                 self.banner.set_button_label(Some(&fl!("banner-button-stop", "label")));
 
                 match task_name {
-                    TaskName::Scan(MediaType::Photo) => {
-                        self.banner.set_title(&fl!("banner-scan-photos"));
-                    },
-                    TaskName::Scan(MediaType::Video) => {
-                        self.banner.set_title(&fl!("banner-scan-videos"));
+                    TaskName::Scan => {
+                        self.banner.set_title(&fl!("banner-scan-library"));
                     },
                     TaskName::Enrich(MediaType::Photo) => {
                         self.banner.set_title(&fl!("banner-metadata-photos"));
@@ -870,6 +1262,15 @@ impl SimpleComponent for App {
                     TaskName::Transcode => {
                         self.banner.set_title(&fl!("banner-convert-videos"));
                     },
+                    TaskName::Duplicates => {
+                        self.banner.set_title(&fl!("banner-duplicates-photos"));
+                    },
+                    TaskName::Sidecars => {
+                        self.banner.set_title(&fl!("banner-sidecars-photos"));
+                    },
+                    TaskName::Export => {
+                        self.banner.set_title(&fl!("banner-export-photos"));
+                    },
                 };
             },
             AppMsg::BootstrapCompleted => {
@@ -889,6 +1290,54 @@ impl SimpleComponent for App {
                 info!("Scan pictures for faces");
                 self.bootstrap.emit(BootstrapInput::ScanPicturesForFaces);
             },
+            AppMsg::LibraryRootsChanged => {
+                info!("Library roots changed");
+                self.library_watcher.watch();
+                self.bootstrap.emit(BootstrapInput::ScanLibrary);
+            },
+            AppMsg::Bulk(BulkAction::Export, visuals) => {
+                self.export_dialog.emit(ExportDialogInput::Present(visuals));
+            },
+            AppMsg::Bulk(action, visuals) => {
+                self.bulk_actions.emit(BulkActionsInput::Run(action, visuals));
+            },
+            AppMsg::Export(visuals, settings) => {
+                info!("Export {} items", visuals.len());
+                self.bootstrap.emit(BootstrapInput::Export(visuals, settings));
+            },
+            AppMsg::FilesChanged(paths) => {
+                info!("Files changed by bulk action or import: {}", paths.len());
+                self.bootstrap.emit(BootstrapInput::ScanPaths(paths));
+            },
+            AppMsg::PeopleChanged => {
+                self.people_page.emit(PeopleAlbumInput::Refresh);
+                self.person_album.emit(PersonAlbumInput::Refresh);
+                self.search_page.emit(SearchAlbumInput::PeopleChanged);
+                self.bootstrap.emit(BootstrapInput::WriteSidecars);
+            },
+            AppMsg::TrashChanged => {
+                // Items in the trash are hidden from the library, so it must be reloaded.
+                // Albums and the recently deleted view refresh after the library reloads.
+                self.bootstrap.emit(BootstrapInput::RefreshLibrary);
+            },
+            AppMsg::DatesChanged => {
+                // Items are ordered by date, so the library must be reloaded.
+                self.bootstrap.emit(BootstrapInput::RefreshLibrary);
+                self.bootstrap.emit(BootstrapInput::WriteSidecars);
+            },
+            AppMsg::PhotoEdited => {
+                // The library reloads once the thumbnail is regenerated.
+                self.bootstrap.emit(BootstrapInput::RegenerateThumbnails);
+            },
+            AppMsg::CloseViewer => {
+                self.picture_navigation_view.pop();
+
+                // Faces might have been named or removed in the viewer.
+                self.bootstrap.emit(BootstrapInput::WriteSidecars);
+            },
+            AppMsg::WriteSidecars => {
+                self.bootstrap.emit(BootstrapInput::WriteSidecars);
+            },
             AppMsg::StopBackgroundTasks => {
                 info!("Stop all background tasks");
                 self.banner.set_button_label(None);
@@ -928,6 +1377,7 @@ impl App {
                 .unwrap_or(FaceDetectionMode::Off),
             album_sort: AlbumSort::from_str(&gio_settings.string("album-sort"))
                 .unwrap_or(AlbumSort::Ascending),
+            write_xmp_sidecars: gio_settings.boolean("write-xmp-sidecars"),
         })
     }
 
@@ -937,6 +1387,7 @@ impl App {
         gio_settings.set_boolean("show-selfies", settings.show_selfies)?;
         gio_settings.set_string("face-detection-mode", settings.face_detection_mode.as_ref())?;
         gio_settings.set_string("album-sort", settings.album_sort.as_ref())?;
+        gio_settings.set_boolean("write-xmp-sidecars", settings.write_xmp_sidecars)?;
         Ok(())
     }
 }
@@ -7,16 +7,23 @@ use relm4::{
 
 use crate::config::APP_ID;
 use fotema_core::database;
+use fotema_core::duplicates;
+use fotema_core::edit;
+use fotema_core::export::ExportSettings;
+use fotema_core::library;
 use fotema_core::people;
 use fotema_core::photo;
+use fotema_core::search;
 use fotema_core::video;
 use fotema_core::visual;
+use fotema_core::visual::Visual;
 use fotema_core::PictureId;
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use std::collections::VecDeque;
+use std::path::PathBuf;
 use std::time::Instant;
 
 use tracing::info;
@@ -24,21 +31,25 @@ use tracing::info;
 use thread_priority::*;
 
 use super::{
+    library_scan::{LibraryScan, LibraryScanInput, LibraryScanOutput},
     load_library::{LoadLibrary, LoadLibraryInput},
     photo_clean::{PhotoClean, PhotoCleanInput, PhotoCleanOutput},
     photo_detect_faces::{PhotoDetectFaces, PhotoDetectFacesInput, PhotoDetectFacesOutput},
     photo_enrich::{PhotoEnrich, PhotoEnrichInput, PhotoEnrichOutput},
     photo_extract_motion::{PhotoExtractMotion, PhotoExtractMotionInput, PhotoExtractMotionOutput},
+    photo_hash::{PhotoHash, PhotoHashInput, PhotoHashOutput},
     photo_recognize_faces::{
         PhotoRecognizeFaces, PhotoRecognizeFacesInput, PhotoRecognizeFacesOutput,
     },
-    photo_scan::{PhotoScan, PhotoScanInput, PhotoScanOutput},
     photo_thumbnail::{PhotoThumbnail, PhotoThumbnailInput, PhotoThumbnailOutput},
+    photo_write_sidecars::{
+        PhotoWriteSidecars, PhotoWriteSidecarsInput, PhotoWriteSidecarsOutput,
+    },
     video_clean::{VideoClean, VideoCleanInput, VideoCleanOutput},
     video_enrich::{VideoEnrich, VideoEnrichInput, VideoEnrichOutput},
-    video_scan::{VideoScan, VideoScanInput, VideoScanOutput},
     video_thumbnail::{VideoThumbnail, VideoThumbnailInput, VideoThumbnailOutput},
     video_transcode::{VideoTranscode, VideoTranscodeInput, VideoTranscodeOutput},
+    visual_export::{VisualExport, VisualExportInput, VisualExportOutput},
 };
 
 use crate::app::FaceDetectionMode;
@@ -58,7 +69,7 @@ pub enum MediaType {
 /// Any thoughts about this fact?
 #[derive(Debug)]
 pub enum TaskName {
-    Scan(MediaType),
+    Scan,
     Enrich(MediaType),
     MotionPhoto,
     Thumbnail(MediaType),
@@ -66,6 +77,9 @@ pub enum TaskName {
     DetectFaces,
     RecognizeFaces,
     Transcode,
+    Duplicates,
+    Sidecars,
+    Export,
 }
 
 #[derive(Debug)]
@@ -73,6 +87,19 @@ pub enum BootstrapInput {
     /// Start the initial background processes for setting up Fotema.
     Start,
 
+    /// Queue tasks for scanning the library, such as after library roots have changed.
+    ScanLibrary,
+
+    /// Queue tasks for scanning files and directories that have changed on disk.
+    ScanPaths(Vec<PathBuf>),
+
+    /// Reload the library because items have been hidden or shown without files changing,
+    /// such as when moved to or restored from the trash.
+    RefreshLibrary,
+
+    /// Queue task for regenerating photo thumbnails, such as after a picture has been edited.
+    RegenerateThumbnails,
+
     /// Queue task for scanning picture for more faces.
     ScanPictureForFaces(PictureId),
     ScanPicturesForFaces,
@@ -80,6 +107,12 @@ pub enum BootstrapInput {
     // Queue task for transcoding videos
     TranscodeAll,
 
+    /// Queue task for writing XMP sidecars, such as after people have been named.
+    WriteSidecars,
+
+    /// Queue task for exporting items to a folder or zip archive.
+    Export(Vec<Arc<Visual>>, ExportSettings),
+
     /// A background task has started.
     TaskStarted(TaskName),
 
@@ -113,8 +146,7 @@ pub struct Bootstrap {
 
     load_library: Arc<WorkerController<LoadLibrary>>,
 
-    photo_scan: Arc<WorkerController<PhotoScan>>,
-    video_scan: Arc<WorkerController<VideoScan>>,
+    library_scan: Arc<WorkerController<LibraryScan>>,
 
     photo_enrich: Arc<WorkerController<PhotoEnrich>>,
     video_enrich: Arc<WorkerController<VideoEnrich>>,
@@ -127,11 +159,17 @@ pub struct Bootstrap {
 
     photo_extract_motion: Arc<WorkerController<PhotoExtractMotion>>,
 
+    photo_hash: Arc<WorkerController<PhotoHash>>,
+
     photo_detect_faces: Arc<WorkerController<PhotoDetectFaces>>,
     photo_recognize_faces: Arc<WorkerController<PhotoRecognizeFaces>>,
 
     video_transcode: Arc<WorkerController<VideoTranscode>>,
 
+    photo_write_sidecars: Arc<WorkerController<PhotoWriteSidecars>>,
+
+    visual_export: Arc<WorkerController<VisualExport>>,
+
     /// Pending ordered tasks to process
     /// Wow... figuring out a type signature that would compile was a nightmare.
     pending_tasks: Arc<Mutex<VecDeque<Box<Task>>>>,
@@ -143,14 +181,14 @@ pub struct Bootstrap {
 type Task = dyn Fn() + Send + Sync;
 
 impl Bootstrap {
-    fn add_task_photo_scan(&mut self) {
-        let sender = self.photo_scan.sender().clone();
-        self.enqueue(Box::new(move || sender.emit(PhotoScanInput::Start)));
+    fn add_task_library_scan(&mut self) {
+        let sender = self.library_scan.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(LibraryScanInput::Start)));
     }
 
-    fn add_task_video_scan(&mut self) {
-        let sender = self.video_scan.sender().clone();
-        self.enqueue(Box::new(move || sender.emit(VideoScanInput::Start)));
+    fn add_task_library_scan_paths(&mut self, paths: Vec<PathBuf>) {
+        let sender = self.library_scan.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(LibraryScanInput::ScanPaths(paths.clone()))));
     }
 
     fn add_task_photo_enrich(&mut self) {
@@ -183,6 +221,16 @@ impl Bootstrap {
         self.enqueue(Box::new(move || sender.emit(VideoCleanInput::Start)));
     }
 
+    fn add_task_photo_clean_paths(&mut self, paths: Vec<PathBuf>) {
+        let sender = self.photo_clean.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(PhotoCleanInput::CleanPaths(paths.clone()))));
+    }
+
+    fn add_task_video_clean_paths(&mut self, paths: Vec<PathBuf>) {
+        let sender = self.video_clean.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(VideoCleanInput::CleanPaths(paths.clone()))));
+    }
+
     fn add_task_photo_extract_motion(&mut self) {
         let sender = self.photo_extract_motion.sender().clone();
         self.enqueue(Box::new(move || {
@@ -190,6 +238,11 @@ impl Bootstrap {
         }));
     }
 
+    fn add_task_photo_hash(&mut self) {
+        let sender = self.photo_hash.sender().clone();
+        self.enqueue(Box::new(move || sender.emit(PhotoHashInput::Start)));
+    }
+
     fn add_task_photo_detect_faces(&mut self) {
         let sender = self.photo_detect_faces.sender().clone();
         let mode = self.settings_state.read().face_detection_mode;
@@ -229,6 +282,23 @@ impl Bootstrap {
         };
     }
 
+    fn add_task_photo_write_sidecars(&mut self) {
+        if !self.settings_state.read().write_xmp_sidecars {
+            return;
+        }
+        let sender = self.photo_write_sidecars.sender().clone();
+        self.enqueue(Box::new(move || {
+            sender.emit(PhotoWriteSidecarsInput::Start)
+        }));
+    }
+
+    fn add_task_visual_export(&mut self, visuals: Vec<Arc<Visual>>, settings: ExportSettings) {
+        let sender = self.visual_export.sender().clone();
+        self.enqueue(Box::new(move || {
+            sender.emit(VisualExportInput::Start(visuals.clone(), settings.clone()))
+        }));
+    }
+
     fn add_task_video_transcode(&mut self) {
         let sender = self.video_transcode.sender().clone();
         self.enqueue(Box::new(move || sender.emit(VideoTranscodeInput::Start)));
@@ -287,54 +357,42 @@ impl Worker for Bootstrap {
         let cache_dir = glib::user_cache_dir().join(APP_ID);
         let _ = std::fs::create_dir_all(&cache_dir);
 
-        let pic_base_dir = glib::user_special_dir(glib::enums::UserDirectory::Pictures)
-            .expect("Expect XDG_PICTURES_DIR");
-
-        let photo_scanner = photo::Scanner::build(&pic_base_dir).unwrap();
+        let library_repo = library::Repository::open(con.clone()).unwrap();
 
-        let photo_repo =
-            photo::Repository::open(&pic_base_dir, &cache_dir, &data_dir, con.clone()).unwrap();
+        let photo_repo = photo::Repository::open(&cache_dir, &data_dir, con.clone()).unwrap();
 
         let photo_thumbnailer = photo::Thumbnailer::build(&cache_dir).unwrap();
 
-        let video_scanner = video::Scanner::build(&pic_base_dir).unwrap();
-
-        let video_repo =
-            { video::Repository::open(&pic_base_dir, &cache_dir, &data_dir, con.clone()).unwrap() };
+        let video_repo = video::Repository::open(&cache_dir, &data_dir, con.clone()).unwrap();
 
         let video_thumbnailer = video::Thumbnailer::build(&cache_dir).unwrap();
 
         let motion_photo_extractor = photo::MotionPhotoExtractor::build(&cache_dir).unwrap();
 
-        let visual_repo = visual::Repository::open(&pic_base_dir, &cache_dir, con.clone()).unwrap();
+        let visual_repo = visual::Repository::open(&cache_dir, con.clone()).unwrap();
+
+        let people_repo = people::Repository::open(&data_dir, con.clone()).unwrap();
+
+        let duplicates_repo = duplicates::Repository::open(&cache_dir, con.clone()).unwrap();
 
-        let people_repo = people::Repository::open(&pic_base_dir, &data_dir, con.clone()).unwrap();
+        let search_repo = search::Repository::open(con.clone()).unwrap();
+
+        let edit_repo = edit::Repository::open(con.clone()).unwrap();
 
         let stop = Arc::new(AtomicBool::new(false));
 
         let load_library = LoadLibrary::builder()
-            .detach_worker((visual_repo.clone(), state.clone()))
+            .detach_worker((visual_repo.clone(), search_repo, state.clone()))
             .detach();
 
-        let photo_scan = PhotoScan::builder()
-            .detach_worker((photo_scanner.clone(), photo_repo.clone()))
+        let library_scan = LibraryScan::builder()
+            .detach_worker((library_repo.clone(), photo_repo.clone(), video_repo.clone()))
             .forward(sender.input_sender(), |msg| match msg {
-                PhotoScanOutput::Started => {
-                    BootstrapInput::TaskStarted(TaskName::Scan(MediaType::Photo))
+                LibraryScanOutput::Started => {
+                    BootstrapInput::TaskStarted(TaskName::Scan)
                 }
-                PhotoScanOutput::Completed => {
-                    BootstrapInput::TaskCompleted(TaskName::Scan(MediaType::Photo), None)
-                }
-            });
-
-        let video_scan = VideoScan::builder()
-            .detach_worker((video_scanner.clone(), video_repo.clone()))
-            .forward(sender.input_sender(), |msg| match msg {
-                VideoScanOutput::Started => {
-                    BootstrapInput::TaskStarted(TaskName::Scan(MediaType::Video))
-                }
-                VideoScanOutput::Completed => {
-                    BootstrapInput::TaskCompleted(TaskName::Scan(MediaType::Video), None)
+                LibraryScanOutput::Completed(summary) => {
+                    BootstrapInput::TaskCompleted(TaskName::Scan, Some(summary.total()))
                 }
             });
 
@@ -376,11 +434,23 @@ impl Worker for Bootstrap {
                 }
             });
 
+        let photo_hash = PhotoHash::builder()
+            .detach_worker((stop.clone(), duplicates_repo, progress_monitor.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                PhotoHashOutput::Started => {
+                    BootstrapInput::TaskStarted(TaskName::Duplicates)
+                }
+                PhotoHashOutput::Completed(_) => {
+                    BootstrapInput::TaskCompleted(TaskName::Duplicates, None)
+                }
+            });
+
         let photo_thumbnail = PhotoThumbnail::builder()
             .detach_worker((
                 stop.clone(),
                 photo_thumbnailer.clone(),
                 photo_repo.clone(),
+                edit_repo.clone(),
                 progress_monitor.clone(),
             ))
             .forward(sender.input_sender(), |msg| match msg {
@@ -428,7 +498,7 @@ impl Worker for Bootstrap {
             });
 
         let photo_clean = PhotoClean::builder()
-            .detach_worker((stop.clone(), photo_repo.clone()))
+            .detach_worker((stop.clone(), library_repo.clone(), photo_repo.clone()))
             .forward(sender.input_sender(), |msg| match msg {
                 PhotoCleanOutput::Started => {
                     BootstrapInput::TaskStarted(TaskName::Clean(MediaType::Photo))
@@ -439,7 +509,7 @@ impl Worker for Bootstrap {
             });
 
         let video_clean = VideoClean::builder()
-            .detach_worker((stop.clone(), video_repo.clone()))
+            .detach_worker((stop.clone(), library_repo.clone(), video_repo.clone()))
             .forward(sender.input_sender(), |msg| match msg {
                 VideoCleanOutput::Started => {
                     BootstrapInput::TaskStarted(TaskName::Clean(MediaType::Video))
@@ -481,17 +551,37 @@ impl Worker for Bootstrap {
                 }
             });
 
+        let photo_write_sidecars = PhotoWriteSidecars::builder()
+            .detach_worker((stop.clone(), photo_repo.clone(), progress_monitor.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                PhotoWriteSidecarsOutput::Started => {
+                    BootstrapInput::TaskStarted(TaskName::Sidecars)
+                }
+                PhotoWriteSidecarsOutput::Completed(count) => {
+                    BootstrapInput::TaskCompleted(TaskName::Sidecars, Some(count))
+                }
+            });
+
+        let visual_export = VisualExport::builder()
+            .detach_worker((stop.clone(), edit_repo, progress_monitor.clone()))
+            .forward(sender.input_sender(), |msg| match msg {
+                VisualExportOutput::Started => BootstrapInput::TaskStarted(TaskName::Export),
+                VisualExportOutput::Completed(count) => {
+                    BootstrapInput::TaskCompleted(TaskName::Export, Some(count))
+                }
+            });
+
         let mut bootstrap = Self {
             started_at: None,
             stop,
             settings_state,
             library_stale: false,
             load_library: Arc::new(load_library),
-            photo_scan: Arc::new(photo_scan),
-            video_scan: Arc::new(video_scan),
+            library_scan: Arc::new(library_scan),
             photo_enrich: Arc::new(photo_enrich),
             video_enrich: Arc::new(video_enrich),
             photo_extract_motion: Arc::new(photo_extract_motion),
+            photo_hash: Arc::new(photo_hash),
             photo_clean: Arc::new(photo_clean),
             video_clean: Arc::new(video_clean),
             photo_thumbnail: Arc::new(photo_thumbnail),
@@ -499,13 +589,14 @@ impl Worker for Bootstrap {
             photo_detect_faces: Arc::new(photo_detect_faces),
             photo_recognize_faces: Arc::new(photo_recognize_faces),
             video_transcode: Arc::new(video_transcode),
+            photo_write_sidecars: Arc::new(photo_write_sidecars),
+            visual_export: Arc::new(visual_export),
             pending_tasks: Arc::new(Mutex::new(VecDeque::new())),
             is_running: false,
         };
 
         // Tasks will execute in the order added.
-        bootstrap.add_task_photo_scan();
-        bootstrap.add_task_video_scan();
+        bootstrap.add_task_library_scan();
         bootstrap.add_task_photo_enrich();
         bootstrap.add_task_video_enrich();
         bootstrap.add_task_photo_thumbnail();
@@ -513,8 +604,10 @@ impl Worker for Bootstrap {
         bootstrap.add_task_photo_clean();
         bootstrap.add_task_video_clean();
         bootstrap.add_task_photo_extract_motion();
+        bootstrap.add_task_photo_hash();
         bootstrap.add_task_photo_detect_faces();
         bootstrap.add_task_photo_recognize_faces();
+        bootstrap.add_task_photo_write_sidecars();
 
         bootstrap
     }
@@ -540,6 +633,59 @@ impl Worker for Bootstrap {
                     }
                 }
             }
+            BootstrapInput::ScanLibrary => {
+                info!("Queueing tasks to scan library");
+                self.add_task_library_scan();
+                self.add_task_photo_enrich();
+                self.add_task_video_enrich();
+                self.add_task_photo_thumbnail();
+                self.add_task_video_thumbnail();
+                self.add_task_photo_clean();
+                self.add_task_video_clean();
+                self.add_task_photo_extract_motion();
+                self.add_task_photo_hash();
+                self.add_task_photo_detect_faces();
+                self.add_task_photo_recognize_faces();
+                self.add_task_photo_write_sidecars();
+
+                // Library roots might have been disabled or removed, which changes visible
+                // library items even if no files are processed.
+                self.library_stale = true;
+                self.run_if_idle();
+            }
+            BootstrapInput::ScanPaths(paths) => {
+                info!("Queueing tasks to scan {} changed paths", paths.len());
+                self.add_task_library_scan_paths(paths.clone());
+
+                // Later tasks only process items that need it, so will only
+                // process the files found by scanning the changed paths.
+                // Cleaning checks every file still exists, so is limited to the changed paths.
+                self.add_task_photo_enrich();
+                self.add_task_video_enrich();
+                self.add_task_photo_thumbnail();
+                self.add_task_video_thumbnail();
+                self.add_task_photo_clean_paths(paths.clone());
+                self.add_task_video_clean_paths(paths);
+                self.add_task_photo_extract_motion();
+                self.add_task_photo_hash();
+                self.add_task_photo_detect_faces();
+                self.add_task_photo_recognize_faces();
+                self.add_task_photo_write_sidecars();
+                self.run_if_idle();
+            }
+            BootstrapInput::RefreshLibrary => {
+                if self.is_running {
+                    // Library will be reloaded when the running tasks complete.
+                    self.library_stale = true;
+                } else {
+                    self.load_library.emit(LoadLibraryInput::Refresh);
+                }
+            }
+            BootstrapInput::RegenerateThumbnails => {
+                info!("Queueing task to regenerate photo thumbnails");
+                self.add_task_photo_thumbnail();
+                self.run_if_idle();
+            }
             BootstrapInput::ScanPictureForFaces(picture_id) => {
                 info!("Queueing task to scan picture {} for faces", picture_id);
                 self.add_task_photo_detect_faces_for_one(picture_id);
@@ -557,6 +703,16 @@ impl Worker for Bootstrap {
                 self.add_task_video_transcode();
                 self.run_if_idle();
             }
+            BootstrapInput::WriteSidecars => {
+                info!("Queueing task to write XMP sidecars");
+                self.add_task_photo_write_sidecars();
+                self.run_if_idle();
+            }
+            BootstrapInput::Export(visuals, settings) => {
+                info!("Queueing task to export {} items", visuals.len());
+                self.add_task_visual_export(visuals, settings);
+                self.run_if_idle();
+            }
             BootstrapInput::TaskStarted(task_name) => {
                 info!("Task started: {:?}", task_name);
                 let _ = sender.output(BootstrapOutput::TaskStarted(task_name));
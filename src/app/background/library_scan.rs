@@ -0,0 +1,275 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use tracing::{debug, error, info};
+
+use fotema_core::library::{self, FileStamp, LibraryRoot, ScanDiff, ScanSummary};
+use fotema_core::media::{self, MediaType, ScannedFile, ScannedFileStore};
+use fotema_core::photo;
+use fotema_core::photo::xmp;
+use fotema_core::video;
+
+#[derive(Debug)]
+pub enum LibraryScanInput {
+    Start,
+
+    /// Scan only the given files and directories, such as after they have changed on disk.
+    ScanPaths(Vec<PathBuf>),
+}
+
+#[derive(Debug)]
+pub enum LibraryScanOutput {
+    Started,
+
+    // Counts of pictures and videos added, changed, and removed since the previous scan.
+    Completed(ScanSummary),
+}
+
+/// Scans library roots for pictures and videos in a single pass of the file system.
+pub struct LibraryScan {
+    library_repo: library::Repository,
+    photo_repo: photo::Repository,
+    video_repo: video::Repository,
+}
+
+impl Worker for LibraryScan {
+    type Init = (library::Repository, photo::Repository, video::Repository);
+    type Input = LibraryScanInput;
+    type Output = LibraryScanOutput;
+
+    fn init((library_repo, photo_repo, video_repo): Self::Init, _sender: ComponentSender<Self>) -> Self {
+        Self { library_repo, photo_repo, video_repo }
+    }
+
+    fn update(&mut self, msg: LibraryScanInput, sender: ComponentSender<Self>) {
+        match msg {
+            LibraryScanInput::Start => {
+                let result = self.scan_and_add(sender);
+                if let Err(e) = result {
+                    error!("Failed scan with: {}", e);
+                }
+            }
+            LibraryScanInput::ScanPaths(paths) => {
+                let result = self.scan_paths_and_add(paths, sender);
+                if let Err(e) = result {
+                    error!("Failed scan with: {}", e);
+                }
+            }
+        };
+    }
+}
+
+impl LibraryScan {
+    fn scan_and_add(&mut self, sender: ComponentSender<Self>) -> std::result::Result<(), String> {
+
+        sender.output(LibraryScanOutput::Started)
+            .map_err(|e| format!("{:?}", e))?;
+
+        let roots = self.library_repo.all_available().map_err(|e| e.to_string())?;
+
+        let mut summary = ScanSummary::default();
+
+        for root in roots {
+            info!("Scanning {:?} for pictures and videos...", root.path);
+
+            let scan = media::Scanner::build(&root.path).map_err(|e| e.to_string())?
+                .with_rules(root.rules.clone());
+            let scanned = scan.scan_all().map_err(|e| e.to_string())?;
+
+            summary += self.apply_all(&root, scanned, None).map_err(|e| e.to_string())?;
+        }
+
+        sender.output(LibraryScanOutput::Completed(summary))
+            .map_err(|e| format!("{:?}", e))
+
+    }
+
+    fn scan_paths_and_add(&mut self, paths: Vec<PathBuf>, sender: ComponentSender<Self>) -> std::result::Result<(), String> {
+
+        sender.output(LibraryScanOutput::Started)
+            .map_err(|e| format!("{:?}", e))?;
+
+        let roots = self.library_repo.all_available().map_err(|e| e.to_string())?;
+
+        let mut summary = ScanSummary::default();
+
+        for root in roots {
+            let paths: Vec<PathBuf> = paths.iter()
+                .filter(|path| path.starts_with(&root.path))
+                .cloned()
+                .collect();
+
+            if paths.is_empty() {
+                continue;
+            }
+
+            info!("Scanning {} changed paths in {:?} for pictures and videos...", paths.len(), root.path);
+
+            let scan = media::Scanner::build(&root.path).map_err(|e| e.to_string())?
+                .with_rules(root.rules.clone());
+            let scanned = scan.scan_paths(&paths).map_err(|e| e.to_string())?;
+
+            summary += self.apply_all(&root, scanned, Some(&paths)).map_err(|e| e.to_string())?;
+        }
+
+        sender.output(LibraryScanOutput::Completed(summary))
+            .map_err(|e| format!("{:?}", e))
+    }
+
+    /// Split scanned files by media type and store each in its repository.
+    /// If only some paths were scanned, then only stored files under those paths
+    /// are compared, otherwise everything else in the library would look removed.
+    fn apply_all(
+        &mut self,
+        root: &LibraryRoot,
+        scanned: Vec<ScannedFile>,
+        scanned_paths: Option<&[PathBuf]>,
+    ) -> anyhow::Result<ScanSummary> {
+        // Sidecars belong to pictures and videos, so aren't library items themselves.
+        let (sidecars, scanned): (Vec<ScannedFile>, Vec<ScannedFile>) = scanned
+            .into_iter()
+            .partition(|file| file.media_type == MediaType::Sidecar);
+
+        let (pictures, videos): (Vec<ScannedFile>, Vec<ScannedFile>) = scanned
+            .into_iter()
+            .filter(|file| file.media_type.is_picture() || file.media_type.is_video())
+            .partition(|file| file.media_type.is_picture());
+
+        let mut summary = Self::apply(&mut self.photo_repo, root, pictures, scanned_paths)?;
+        info!("Found {:?} picture changes in {:?}", summary, root.path);
+
+        // After pictures are stored, so sidecars of new pictures are recorded too.
+        let sidecar_count = self.apply_sidecars(root, sidecars, scanned_paths)?;
+        info!("Found {} pictures with sidecar changes in {:?}", sidecar_count, root.path);
+
+        let video_summary = Self::apply(&mut self.video_repo, root, videos, scanned_paths)?;
+        info!("Found {:?} video changes in {:?}", video_summary, root.path);
+
+        summary += video_summary;
+        Ok(summary)
+    }
+
+    /// Metadata such as ratings and keywords is read from XMP sidecars, so a picture
+    /// must be scanned again when its sidecar is added, changed, or removed.
+    /// Returns the count of pictures whose sidecar has changed.
+    fn apply_sidecars(
+        &mut self,
+        root: &LibraryRoot,
+        sidecars: Vec<ScannedFile>,
+        scanned_paths: Option<&[PathBuf]>,
+    ) -> anyhow::Result<usize> {
+        let sidecars: HashMap<PathBuf, FileStamp> = sidecars
+            .into_iter()
+            .map(|file| (file.path.clone(), file.file_stamp()))
+            .collect();
+
+        let changes: Vec<(PathBuf, Option<FileStamp>)> = self.photo_repo
+            .find_sidecar_stamps(root)?
+            .into_iter()
+            .filter_map(|(picture_path, stored)| {
+                let sidecar_paths = xmp::sidecar_paths(&picture_path);
+
+                // If only some paths were scanned, then sidecars elsewhere are unknown.
+                let is_scanned = match scanned_paths {
+                    Some(paths) => sidecar_paths.iter().any(|x| paths.iter().any(|p| x.starts_with(p))),
+                    None => true,
+                };
+
+                if !is_scanned {
+                    return None;
+                }
+
+                // Same order of preference as when the sidecar is read.
+                let scanned = sidecar_paths.iter().find_map(|x| sidecars.get(x)).copied();
+
+                let is_changed = match (stored, scanned) {
+                    (Some(stored), Some(scanned)) => stored.is_changed(&scanned),
+                    (None, None) => false,
+                    _ => true,
+                };
+
+                is_changed.then_some((picture_path, scanned))
+            })
+            .collect();
+
+        self.photo_repo.invalidate_sidecars(root, &changes)?;
+        Ok(changes.len())
+    }
+
+    /// Add new and changed files to the database.
+    /// Removed files are deleted from the database by the clean task.
+    fn apply<R: ScannedFileStore>(
+        repo: &mut R,
+        root: &LibraryRoot,
+        scanned: Vec<ScannedFile>,
+        scanned_paths: Option<&[PathBuf]>,
+    ) -> anyhow::Result<ScanSummary> {
+        let stored: HashMap<PathBuf, FileStamp> = repo.find_file_stamps(root)?
+            .into_iter()
+            .filter(|(path, _)| match scanned_paths {
+                Some(paths) => paths.iter().any(|x| path.starts_with(x)),
+                None => true,
+            })
+            .collect();
+
+        let mut diff = ScanDiff::build(&stored, scanned, |file| (file.path.clone(), file.file_stamp()));
+
+        // Fingerprint files with new contents, or that have never been fingerprinted.
+        for file in diff.added.iter_mut()
+            .chain(diff.changed.iter_mut())
+            .chain(diff.refreshed.iter_mut()) {
+            file.fingerprint = library::fingerprint(&file.path)
+                .inspect_err(|e| error!("Failed fingerprinting {:?}: {}", file.path, e))
+                .ok();
+        }
+
+        // A new file with the same fingerprint as a missing file has been moved or renamed,
+        // so keep the existing database entry and everything derived from it.
+        let mut moved = 0;
+        let mut added = Vec::with_capacity(diff.added.len());
+        for file in std::mem::take(&mut diff.added) {
+            let moved_id = match file.fingerprint {
+                Some(ref fingerprint) => repo.find_moved(fingerprint)?,
+                None => None,
+            };
+
+            if let Some(moved_id) = moved_id {
+                debug!("Moved {} to {:?}", moved_id, file.path);
+                repo.relocate(moved_id, root, &file)?;
+                moved += 1;
+            } else {
+                added.push(file);
+            }
+        }
+        diff.added = added;
+
+        // Moved files are no longer stored at their old paths, so have not been removed.
+        if moved > 0 {
+            let stored = repo.find_file_stamps(root)?;
+            diff.removed.retain(|path| stored.contains_key(path));
+        }
+
+        let mut summary = diff.summary();
+        summary.moved = moved;
+
+        repo.add_all(root, &diff.added)?;
+        repo.add_all(root, &diff.refreshed)?;
+
+        // Changed files must be processed again, so delete anything derived from them.
+        let paths = repo.invalidate(root, &diff.changed)?;
+        for path in paths {
+            debug!("Deleting {:?}", path);
+            if let Err(e) = std::fs::remove_file(&path) {
+                error!("Failed deleting {:?} with {}", path, e);
+            }
+        }
+
+        Ok(summary)
+    }
+}
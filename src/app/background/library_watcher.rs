@@ -0,0 +1,370 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::gtk::{gio, glib};
+use relm4::gtk::prelude::*;
+
+use fotema_core::library;
+use fotema_core::library::{LibraryRoot, ScanRules};
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use tracing::{debug, error, info, warn};
+use walkdir::WalkDir;
+
+use super::bootstrap::BootstrapInput;
+
+/// How long to wait after the last file system event before scanning.
+/// Copying many photos generates a burst of events that should be scanned together.
+const DEBOUNCE: Duration = Duration::from_secs(3);
+
+/// Linux limits how many directories can be watched, so watching stops at the limit.
+const MAX_USER_WATCHES_PATH: &str = "/proc/sys/fs/inotify/max_user_watches";
+
+/// Watches library roots for file system changes and queues scans of changed paths.
+///
+/// File monitors deliver events on the main loop, so unlike the background workers
+/// this must be owned by the main thread. Directories to watch are found on a
+/// background thread.
+pub struct LibraryWatcher {
+    library_repo: library::Repository,
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    bootstrap: relm4::Sender<BootstrapInput>,
+
+    /// Roots being watched, for the scan rules of new directories.
+    roots: RefCell<Vec<LibraryRoot>>,
+
+    /// Incremented when roots are watched again, so that directories found for
+    /// the previous roots are not watched.
+    generation: Cell<u64>,
+
+    /// Directory monitors are not recursive, so every directory is watched.
+    monitors: RefCell<HashMap<PathBuf, gio::FileMonitor>>,
+
+    /// Maximum number of directories that can be watched, if known.
+    max_watches: Option<usize>,
+
+    /// Set when no more directories can be watched, so the warning is only logged once.
+    is_watch_limit_reached: Cell<bool>,
+
+    /// Paths changed since the last scan was queued.
+    pending: RefCell<PendingChanges>,
+
+    /// Timer for queuing a scan of pending paths.
+    debounce: RefCell<Option<glib::SourceId>>,
+}
+
+/// Paths changed on disk, collected until file system events have settled.
+#[derive(Debug, Default)]
+struct PendingChanges {
+    paths: HashSet<PathBuf>,
+
+    /// Time of the most recent change.
+    last_changed_at: Option<Instant>,
+}
+
+impl PendingChanges {
+    fn add(&mut self, path: PathBuf, now: Instant) {
+        self.paths.insert(path);
+        self.last_changed_at = Some(now);
+    }
+
+    /// How long until changes will have settled. None if nothing has changed.
+    fn settles_in(&self, now: Instant) -> Option<Duration> {
+        self.last_changed_at
+            .map(|x| DEBOUNCE.saturating_sub(now.saturating_duration_since(x)))
+    }
+
+    /// Takes the changed paths, in order, if nothing has changed for the debounce period.
+    fn take_settled(&mut self, now: Instant) -> Option<Vec<PathBuf>> {
+        if !self.settles_in(now)?.is_zero() {
+            return None;
+        }
+
+        self.last_changed_at = None;
+        let mut paths: Vec<PathBuf> = self.paths.drain().collect();
+        paths.sort();
+        Some(paths)
+    }
+}
+
+impl LibraryWatcher {
+    pub fn new(library_repo: library::Repository, bootstrap: relm4::Sender<BootstrapInput>) -> Self {
+        let max_watches = std::fs::read_to_string(MAX_USER_WATCHES_PATH)
+            .ok()
+            .and_then(|x| x.trim().parse().ok());
+
+        let inner = Inner {
+            bootstrap,
+            roots: RefCell::new(Vec::new()),
+            generation: Cell::new(0),
+            monitors: RefCell::new(HashMap::new()),
+            max_watches,
+            is_watch_limit_reached: Cell::new(false),
+            pending: RefCell::new(PendingChanges::default()),
+            debounce: RefCell::new(None),
+        };
+
+        Self {
+            library_repo,
+            inner: Rc::new(inner),
+        }
+    }
+
+    /// Watch all available library roots, replacing any existing watches.
+    pub fn watch(&mut self) {
+        for (_, monitor) in self.inner.monitors.borrow_mut().drain() {
+            monitor.cancel();
+        }
+
+        self.inner.generation.set(self.inner.generation.get() + 1);
+        self.inner.is_watch_limit_reached.set(false);
+
+        let roots = match self.library_repo.all_available() {
+            Ok(roots) => roots,
+            Err(e) => {
+                error!("Failed loading library roots to watch: {}", e);
+                return;
+            }
+        };
+
+        for root in &roots {
+            info!("Watching {:?} for changes", root.path);
+            self.inner.watch_tree(root, &root.path);
+        }
+
+        *self.inner.roots.borrow_mut() = roots;
+    }
+}
+
+impl Inner {
+    /// Watch a directory and all directories below it that aren't ignored by the
+    /// root's scan rules. Walking the directories happens on a background thread.
+    fn watch_tree(self: &Rc<Self>, root: &LibraryRoot, base: &Path) {
+        let root_path = root.path.clone();
+        let rules = root.rules.clone();
+        let base = base.to_path_buf();
+
+        let generation = self.generation.get();
+        let inner = Rc::downgrade(self);
+
+        relm4::spawn_local(async move {
+            let dirs = relm4::spawn_blocking(move || find_dirs(&root_path, &rules, &base)).await;
+
+            let Some(inner) = inner.upgrade() else {
+                return;
+            };
+
+            if inner.generation.get() != generation {
+                debug!("Library roots watched again, so skipping stale directories");
+                return;
+            }
+
+            match dirs {
+                Ok(dirs) => dirs.iter().for_each(|x| inner.watch_dir(x)),
+                Err(e) => error!("Failed finding directories to watch: {}", e),
+            }
+        });
+    }
+
+    fn watch_dir(self: &Rc<Self>, dir: &Path) {
+        if self.is_watch_limit_reached.get() || self.monitors.borrow().contains_key(dir) {
+            return;
+        }
+
+        let count = self.monitors.borrow().len();
+        if self.max_watches.is_some_and(|max| count >= max) {
+            self.warn_watch_limit_reached();
+            return;
+        }
+
+        let file = gio::File::for_path(dir);
+        let monitor = match file.monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE) {
+            Ok(monitor) => monitor,
+            Err(e) if e.matches(gio::IOErrorEnum::NoSpace) || e.matches(gio::IOErrorEnum::TooManyOpenFiles) => {
+                self.warn_watch_limit_reached();
+                return;
+            }
+            Err(e) => {
+                error!("Failed watching {:?}: {}", dir, e);
+                return;
+            }
+        };
+
+        let inner = Rc::downgrade(self);
+        monitor.connect_changed(move |_, file, other_file, event| {
+            if let Some(inner) = inner.upgrade() {
+                inner.on_changed(file, other_file, event);
+            }
+        });
+
+        self.monitors.borrow_mut().insert(dir.to_path_buf(), monitor);
+    }
+
+    fn warn_watch_limit_reached(&self) {
+        self.is_watch_limit_reached.set(true);
+        warn!(
+            "Stopped watching library for changes after {} folders because the inotify watch limit was reached. \
+            Changes in other folders will be found by the next library scan. \
+            Raise fs.inotify.max_user_watches to watch more folders.",
+            self.monitors.borrow().len()
+        );
+    }
+
+    /// Stop watching a directory and all directories below it.
+    fn unwatch(&self, base: &Path) {
+        self.monitors.borrow_mut().retain(|dir, monitor| {
+            if dir.starts_with(base) {
+                monitor.cancel();
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    fn on_changed(self: &Rc<Self>, file: &gio::File, other_file: Option<&gio::File>, event: gio::FileMonitorEvent) {
+        let paths = match event {
+            gio::FileMonitorEvent::Created
+            | gio::FileMonitorEvent::ChangesDoneHint
+            | gio::FileMonitorEvent::Deleted
+            | gio::FileMonitorEvent::MovedIn
+            | gio::FileMonitorEvent::MovedOut => vec![file.path()],
+            gio::FileMonitorEvent::Renamed => vec![file.path(), other_file.and_then(|x| x.path())],
+            _ => return,
+        };
+
+        for path in paths.into_iter().flatten() {
+            debug!("File system event {:?} for {:?}", event, path);
+
+            if path.is_dir() {
+                let root = self.roots.borrow().iter().find(|x| path.starts_with(&x.path)).cloned();
+                if let Some(root) = root {
+                    self.watch_tree(&root, &path);
+                }
+            } else if !path.exists() {
+                self.unwatch(&path);
+            }
+
+            self.pending.borrow_mut().add(path, Instant::now());
+        }
+
+        self.schedule_scan();
+    }
+
+    /// Queue a scan of pending paths once file system events have settled.
+    fn schedule_scan(self: &Rc<Self>) {
+        // A timer is already waiting for changes to settle.
+        if self.debounce.borrow().is_some() {
+            return;
+        }
+
+        let Some(delay) = self.pending.borrow().settles_in(Instant::now()) else {
+            return;
+        };
+
+        let inner = Rc::downgrade(self);
+        let source_id = glib::timeout_add_local_once(delay, move || {
+            let Some(inner) = inner.upgrade() else {
+                return;
+            };
+
+            // Timer has fired, so another can be scheduled.
+            let _ = inner.debounce.borrow_mut().take();
+
+            let paths = inner.pending.borrow_mut().take_settled(Instant::now());
+            match paths {
+                Some(paths) => {
+                    info!("Library changed on disk: {} paths", paths.len());
+                    inner.bootstrap.emit(BootstrapInput::ScanPaths(paths));
+                }
+                // Changed again since the timer was scheduled, so wait for that to settle too.
+                None => inner.schedule_scan(),
+            }
+        });
+
+        *self.debounce.borrow_mut() = Some(source_id);
+    }
+}
+
+/// Find a directory and all directories below it, skipping directories ignored by scan rules.
+fn find_dirs(root: &Path, rules: &ScanRules, base: &Path) -> Vec<PathBuf> {
+    WalkDir::new(base)
+        .into_iter()
+        .filter_entry(|x| !x.file_type().is_dir() || !rules.is_ignored_dir(root, x.path()))
+        .flatten()
+        .filter(|x| x.file_type().is_dir())
+        .map(|x| x.into_path())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_pending_changes_coalesced_until_settled() {
+        let start = Instant::now();
+        let mut pending = PendingChanges::default();
+        assert_eq!(pending.settles_in(start), None);
+
+        pending.add(PathBuf::from("/pictures/b.jpg"), start);
+        pending.add(PathBuf::from("/pictures/a.jpg"), start + Duration::from_secs(1));
+        pending.add(PathBuf::from("/pictures/b.jpg"), start + Duration::from_secs(2));
+
+        // Waits for the debounce period after the last change, not the first.
+        let now = start + DEBOUNCE;
+        assert_eq!(pending.take_settled(now), None);
+        assert_eq!(pending.settles_in(now), Some(Duration::from_secs(2)));
+
+        let now = start + Duration::from_secs(2) + DEBOUNCE;
+        assert_eq!(
+            pending.take_settled(now),
+            Some(vec![PathBuf::from("/pictures/a.jpg"), PathBuf::from("/pictures/b.jpg")])
+        );
+
+        assert_eq!(pending.settles_in(now), None);
+        assert_eq!(pending.take_settled(now + DEBOUNCE), None);
+    }
+
+    #[test]
+    fn test_find_dirs_skips_ignored_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+
+        fs::create_dir_all(root.join("Camera/2024")).unwrap();
+        fs::create_dir_all(root.join(".cache/thumbnails")).unwrap();
+        fs::create_dir_all(root.join("WhatsApp/Media")).unwrap();
+        fs::create_dir_all(root.join("Screenshots")).unwrap();
+        fs::write(root.join("WhatsApp/.nomedia"), b"").unwrap();
+        fs::write(root.join("Camera/a.jpg"), b"not really a picture").unwrap();
+
+        let rules = ScanRules {
+            exclude_globs: vec!["Screenshots".into()],
+            ..ScanRules::default()
+        };
+
+        let mut dirs = find_dirs(root, &rules, root);
+        dirs.sort();
+        assert_eq!(
+            dirs,
+            vec![root.to_path_buf(), root.join("Camera"), root.join("Camera/2024")]
+        );
+
+        // New directories below the root are found with the same rules.
+        let mut dirs = find_dirs(root, &rules, &root.join("Camera"));
+        dirs.sort();
+        assert_eq!(dirs, vec![root.join("Camera"), root.join("Camera/2024")]);
+
+        assert!(find_dirs(root, &rules, &root.join("WhatsApp")).is_empty());
+    }
+}
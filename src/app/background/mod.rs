@@ -3,18 +3,22 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 pub mod bootstrap;
+pub mod library_scan;
+pub mod library_watcher;
 pub mod load_library;
 
 pub mod photo_clean;
 pub mod photo_detect_faces;
 pub mod photo_enrich;
 pub mod photo_extract_motion;
+pub mod photo_hash;
 pub mod photo_recognize_faces;
-pub mod photo_scan;
 pub mod photo_thumbnail;
+pub mod photo_write_sidecars;
 
 pub mod video_clean;
 pub mod video_enrich;
-pub mod video_scan;
 pub mod video_thumbnail;
 pub mod video_transcode;
+
+pub mod visual_export;
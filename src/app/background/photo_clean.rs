@@ -7,6 +7,8 @@ use relm4::Worker;
 use rayon::prelude::*;
 use anyhow::Result;
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -15,6 +17,9 @@ use tracing::{debug, error, info};
 #[derive(Debug)]
 pub enum PhotoCleanInput {
     Start,
+
+    /// Clean only pictures under the given files and directories, such as after they have changed on disk.
+    CleanPaths(Vec<PathBuf>),
 }
 
 #[derive(Debug)]
@@ -31,22 +36,63 @@ pub struct PhotoClean {
     // Stop flag
     stop: Arc<AtomicBool>,
 
+    library_repo: fotema_core::library::Repository,
+
     // Danger! Don't hold the repo mutex for too long as it blocks viewing images.
     repo: fotema_core::photo::Repository,
 }
 
 impl PhotoClean {
 
-    fn cleanup(&mut self, sender: &ComponentSender<Self>) -> Result<()> {
+    fn cleanup(&mut self, paths: Option<&[PathBuf]>, sender: &ComponentSender<Self>) -> Result<()> {
 
         let start = std::time::Instant::now();
 
+        // Only clean pictures under library roots that are present on the file system,
+        // otherwise an unmounted drive would look like all of its pictures had been deleted.
+        let available_roots: HashMap<fotema_core::LibraryRootId, fotema_core::library::LibraryRoot> = self.library_repo
+            .all_available()?
+            .into_iter()
+            .map(|root| (root.library_root_id, root))
+            .collect();
+
         // Scrub pics from database if they no longer exist on the file system.
-        let pics: Vec<fotema_core::photo::model::Picture> = self.repo.all()?;
+        let pics: Vec<fotema_core::photo::model::Picture> = self.repo.all()?
+            .into_iter()
+            .filter(|p| available_roots.contains_key(&p.library_root_id))
+            .filter(|p| match paths {
+                Some(paths) => paths.iter().any(|x| p.path.starts_with(x)),
+                None => true,
+            })
+            .collect();
 
         info!("Found {} photos as candidates for cleaning", pics.len());
 
-        let count = pics.par_iter().filter(|p| !p.path.exists()).count();
+        // Pictures under a library root that has been removed must also be scrubbed.
+        // Library roots aren't removed by changes on disk, so only look when cleaning everything.
+        let orphaned = match paths {
+            Some(_) => Vec::new(),
+            None => self.repo.find_orphaned()?,
+        };
+
+        info!("Found {} photos under removed library roots", orphaned.len());
+
+        // Pictures that are now excluded by the scan rules of their library root
+        // are scrubbed as if they had been deleted.
+        let picture_ids: Vec<fotema_core::PictureId> = pics.par_iter()
+            .filter(|p| {
+                let root = &available_roots[&p.library_root_id];
+                !p.path.exists()
+                    || root.rules.is_excluded(&root.path, &p.path)
+                    || root.rules.is_too_small(&p.path)
+            })
+            .map(|p| p.picture_id)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .chain(orphaned)
+            .collect();
+
+        let count = picture_ids.len();
 
         // Short-circuit before sending progress messages to stop
         // banner from appearing and disappearing.
@@ -59,26 +105,24 @@ impl PhotoClean {
             error!("Failed sending cleanup started: {:?}", e);
         }
 
-        pics.par_iter()
+        picture_ids.par_iter()
             .take_any_while(|_| !self.stop.load(Ordering::Relaxed))
-            .for_each(|pic| {
-                if !pic.path.exists() {
-                    let mut repo = self.repo.clone();
-                    if let Ok(paths) = repo.find_files_to_cleanup(pic.picture_id) {
-                        for path in paths {
-                            debug!("Deleting {:?}", path);
-                            if let Err(e) = std::fs::remove_file(&path) {
-                                error!("Failed deleting {:?} with {}", path, e);
-                            }
+            .for_each(|picture_id| {
+                let mut repo = self.repo.clone();
+                if let Ok(paths) = repo.find_files_to_cleanup(*picture_id) {
+                    for path in paths {
+                        debug!("Deleting {:?}", path);
+                        if let Err(e) = std::fs::remove_file(&path) {
+                            error!("Failed deleting {:?} with {}", path, e);
                         }
                     }
+                }
 
-                    let result = repo.remove(pic.picture_id);
-                    if let Err(e) = result {
-                        error!("Failed remove {}: {:?}", pic.picture_id, e);
-                    } else {
-                        info!("Removed {}", pic.picture_id);
-                    }
+                let result = repo.remove(*picture_id);
+                if let Err(e) = result {
+                    error!("Failed remove {}: {:?}", picture_id, e);
+                } else {
+                    info!("Removed {}", picture_id);
                 }
             });
 
@@ -93,12 +137,12 @@ impl PhotoClean {
 }
 
 impl Worker for PhotoClean {
-    type Init = (Arc<AtomicBool>, fotema_core::photo::Repository);
+    type Init = (Arc<AtomicBool>, fotema_core::library::Repository, fotema_core::photo::Repository);
     type Input = PhotoCleanInput;
     type Output = PhotoCleanOutput;
 
-    fn init((stop, repo): Self::Init, _sender: ComponentSender<Self>) -> Self {
-        Self { stop, repo }
+    fn init((stop, library_repo, repo): Self::Init, _sender: ComponentSender<Self>) -> Self {
+        Self { stop, library_repo, repo }
     }
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
@@ -106,7 +150,14 @@ impl Worker for PhotoClean {
             PhotoCleanInput::Start => {
                 info!("Cleaning photos...");
 
-                if let Err(e) = self.cleanup(&sender) {
+                if let Err(e) = self.cleanup(None, &sender) {
+                    error!("Failed to clean photos: {}", e);
+                }
+            }
+            PhotoCleanInput::CleanPaths(paths) => {
+                info!("Cleaning photos under {} changed paths...", paths.len());
+
+                if let Err(e) = self.cleanup(Some(&paths), &sender) {
                     error!("Failed to clean photos: {}", e);
                 }
             }
@@ -19,6 +19,7 @@ use futures::executor::block_on;
 use fotema_core::machine_learning::face_extractor::FaceExtractor;
 use fotema_core::people;
 use fotema_core::photo::PictureId;
+use fotema_core::photo::xmp;
 
 use crate::app::components::progress_monitor::{
     ProgressMonitor,
@@ -64,7 +65,10 @@ impl PhotoDetectFaces {
         let result = self.repo.get_file_to_scan(picture_id)?;
         if let Some(picture_path) = result {
             let unprocessed = vec![(picture_id, picture_path)];
-            self.detect(sender, unprocessed)
+
+            // Scanning one picture is asked for by the user, so detect faces even if
+            // the picture has face regions from another tool.
+            self.detect(sender, unprocessed, false)
         } else {
             Err(anyhow!("No file to scan"))
         }
@@ -77,10 +81,12 @@ impl PhotoDetectFaces {
             .filter(|(_, path)| path.exists())
             .collect();
 
-        self.detect(sender, unprocessed)
+        self.detect(sender, unprocessed, true)
     }
 
-    fn detect(&self, sender: ComponentSender<Self>, unprocessed: Vec<(PictureId, PathBuf)>) -> Result<()> {
+    /// Detect faces in pictures. If import_regions is true, then faces named in face regions
+    /// written by other photo management tools are imported instead of detecting faces.
+    fn detect(&self, sender: ComponentSender<Self>, unprocessed: Vec<(PictureId, PathBuf)>, import_regions: bool) -> Result<()> {
         let start = std::time::Instant::now();
 
         let count = unprocessed.len();
@@ -113,9 +119,24 @@ impl PhotoDetectFaces {
 
                 // Careful! panic::catch_unwind returns Ok(Err) if the evaluated expression returns
                 // an error but doesn't panic.
-                let result = block_on(async {
+                let regions = if import_regions {
+                    xmp::read_face_regions(path).unwrap_or_else(|e| {
+                        error!("Failed reading face regions: Photo path: {:?}. Error: {:?}", path, e);
+                        Vec::new()
+                    })
+                } else {
+                    Vec::new()
+                };
+
+                let result = if regions.is_empty() {
+                    block_on(async {
                         extractor.extract_faces(picture_id, path).await
-                    }).and_then(|faces| repo.clone().add_face_scans(picture_id, &faces));
+                    }).and_then(|faces| repo.clone().add_face_scans(picture_id, &faces))
+                } else {
+                    block_on(async {
+                        extractor.import_faces(picture_id, path, &regions).await
+                    }).and_then(|faces| repo.clone().add_imported_faces(picture_id, &faces))
+                };
 
                 if result.is_err() {
                     error!("Failed detecting faces: Photo path: {:?}. Error: {:?}", path, result);
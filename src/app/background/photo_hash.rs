@@ -0,0 +1,138 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use relm4::Reducer;
+use rayon::prelude::*;
+use anyhow::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{error, info};
+
+use fotema_core::duplicates;
+use fotema_core::duplicates::PictureHashes;
+
+use crate::app::components::progress_monitor::{
+    ProgressMonitor,
+    ProgressMonitorInput,
+    TaskName,
+};
+
+#[derive(Debug)]
+pub enum PhotoHashInput {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum PhotoHashOutput {
+    // Hashing started.
+    Started,
+
+    // Hashing completed
+    Completed(usize),
+}
+
+/// Compute content and perceptual hashes of photos for finding duplicates.
+pub struct PhotoHash {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    repo: duplicates::Repository,
+
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl PhotoHash {
+
+    fn hash(
+        stop: Arc<AtomicBool>,
+        mut repo: duplicates::Repository,
+        progress_monitor: Arc<Reducer<ProgressMonitor>>,
+        sender: &ComponentSender<PhotoHash>) -> Result<()>
+    {
+        let start = std::time::Instant::now();
+
+        let unprocessed = repo.find_need_hashes()?;
+
+        let count = unprocessed.len();
+        info!("Found {} photos to hash", count);
+
+        // Short-circuit before sending progress messages to stop
+        // banner from appearing and disappearing.
+        if count == 0 {
+            let _ = sender.output(PhotoHashOutput::Completed(count));
+            return Ok(());
+        }
+
+        let _ = sender.output(PhotoHashOutput::Started);
+
+        progress_monitor.emit(ProgressMonitorInput::Start(TaskName::Duplicates, count));
+
+        let hashes: Vec<PictureHashes> = unprocessed
+            .par_iter()
+            .take_any_while(|_| !stop.load(Ordering::Relaxed))
+            .flat_map(|(picture_id, path, thumbnail_path)| {
+                let result = duplicates::hash::content_hash(path).and_then(|content_hash| {
+                    let perceptual_hash = duplicates::hash::perceptual_hash(thumbnail_path)?;
+                    Ok(PictureHashes {
+                        picture_id: *picture_id,
+                        content_hash,
+                        perceptual_hash,
+                    })
+                });
+
+                if let Err(ref e) = result {
+                    error!("Failed hashing {:?}: {}", path, e);
+                }
+
+                progress_monitor.emit(ProgressMonitorInput::Advance);
+                result
+            })
+            .collect();
+
+        repo.add_hashes(hashes)?;
+
+        info!("Hashed {} photos in {} seconds.", count, start.elapsed().as_secs());
+
+        progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        if let Err(e) = sender.output(PhotoHashOutput::Completed(count)) {
+            error!("Failed sending PhotoHashOutput::Completed: {:?}", e);
+        }
+
+        Ok(())
+    }
+}
+
+impl Worker for PhotoHash {
+    type Init = (Arc<AtomicBool>, duplicates::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Input = PhotoHashInput;
+    type Output = PhotoHashOutput;
+
+    fn init((stop, repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+        PhotoHash {
+            stop,
+            repo,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            PhotoHashInput::Start => {
+                info!("Hashing photos...");
+                let stop = self.stop.clone();
+                let repo = self.repo.clone();
+                let progress_monitor = self.progress_monitor.clone();
+
+                rayon::spawn(move || {
+                    if let Err(e) = PhotoHash::hash(stop, repo, progress_monitor, &sender) {
+                        error!("Failed to hash photos: {}", e);
+                    }
+                });
+            }
+        };
+    }
+}
@@ -47,6 +47,8 @@ pub struct PhotoThumbnail {
     // Danger! Don't hold the repo mutex for too long as it blocks viewing images.
     repo: fotema_core::photo::Repository,
 
+    edit_repo: fotema_core::edit::Repository,
+
     progress_monitor: Arc<Reducer<ProgressMonitor>>,
 }
 
@@ -55,6 +57,7 @@ impl PhotoThumbnail {
     fn enrich(
         stop: Arc<AtomicBool>,
         repo: fotema_core::photo::Repository,
+        edit_repo: fotema_core::edit::Repository,
         thumbnailer: fotema_core::photo::Thumbnailer,
         progress_monitor: Arc<Reducer<ProgressMonitor>>,
         sender: ComponentSender<Self>) -> Result<()>
@@ -71,6 +74,8 @@ impl PhotoThumbnail {
         // should be ascending time order from database, so reverse to process newest items first
         unprocessed.reverse();
 
+        let edits = edit_repo.all()?;
+
         let count = unprocessed.len();
          info!("Found {} photos to generate thumbnails for", count);
 
@@ -96,7 +101,8 @@ impl PhotoThumbnail {
                 // Careful! panic::catch_unwind returns Ok(Err) if the evaluated expression returns
                 // an error but doesn't panic.
                 let result = panic::catch_unwind(|| {
-                    block_on(async {thumbnailer.thumbnail(&pic.picture_id, &pic.path).await})
+                    let edit = edits.get(&pic.picture_id).cloned().unwrap_or_default();
+                    block_on(async {thumbnailer.thumbnail(&pic.picture_id, &pic.path, &edit).await})
                         .and_then(|thumbnail_path| repo.clone().add_thumbnail(&pic.picture_id, &thumbnail_path))
                 });
 
@@ -124,15 +130,16 @@ impl PhotoThumbnail {
 }
 
 impl Worker for PhotoThumbnail {
-    type Init = (Arc<AtomicBool>, fotema_core::photo::Thumbnailer, fotema_core::photo::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Init = (Arc<AtomicBool>, fotema_core::photo::Thumbnailer, fotema_core::photo::Repository, fotema_core::edit::Repository, Arc<Reducer<ProgressMonitor>>);
     type Input = PhotoThumbnailInput;
     type Output = PhotoThumbnailOutput;
 
-    fn init((stop, thumbnailer, repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+    fn init((stop, thumbnailer, repo, edit_repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
         PhotoThumbnail {
             stop,
             thumbnailer,
             repo,
+            edit_repo,
             progress_monitor,
         }
     }
@@ -144,12 +151,13 @@ impl Worker for PhotoThumbnail {
                 info!("Generating photo thumbnails...");
                 let stop = self.stop.clone();
                 let repo = self.repo.clone();
+                let edit_repo = self.edit_repo.clone();
                 let thumbnailer = self.thumbnailer.clone();
                 let progress_monitor = self.progress_monitor.clone();
 
                 // Avoid runtime panic from calling block_on
                 rayon::spawn(move || {
-                    if let Err(e) = PhotoThumbnail::enrich(stop, repo, thumbnailer, progress_monitor, sender) {
+                    if let Err(e) = PhotoThumbnail::enrich(stop, repo, edit_repo, thumbnailer, progress_monitor, sender) {
                         error!("Failed to update previews: {}", e);
                     }
                 });
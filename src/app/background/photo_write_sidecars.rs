@@ -0,0 +1,130 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use relm4::Reducer;
+use anyhow::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{error, info};
+
+use fotema_core::photo;
+use fotema_core::photo::xmp;
+
+use crate::app::components::progress_monitor::{
+    ProgressMonitor,
+    ProgressMonitorInput,
+    TaskName,
+};
+
+#[derive(Debug)]
+pub enum PhotoWriteSidecarsInput {
+    Start,
+}
+
+#[derive(Debug)]
+pub enum PhotoWriteSidecarsOutput {
+    // Writing started.
+    Started,
+
+    // Writing completed
+    Completed(usize),
+}
+
+/// Write metadata, such as the names of people, to XMP sidecars so that it isn't
+/// lost when switching to another photo management tool.
+pub struct PhotoWriteSidecars {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    repo: photo::Repository,
+
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl PhotoWriteSidecars {
+
+    fn write(
+        stop: Arc<AtomicBool>,
+        mut repo: photo::Repository,
+        progress_monitor: Arc<Reducer<ProgressMonitor>>,
+        sender: &ComponentSender<PhotoWriteSidecars>) -> Result<()>
+    {
+        let start = std::time::Instant::now();
+
+        let unprocessed = repo.find_need_sidecar_write()?;
+
+        let count = unprocessed.len();
+        info!("Found {} photos with sidecars to write", count);
+
+        // Short-circuit before sending progress messages to stop
+        // banner from appearing and disappearing.
+        if count == 0 {
+            let _ = sender.output(PhotoWriteSidecarsOutput::Completed(count));
+            return Ok(());
+        }
+
+        let _ = sender.output(PhotoWriteSidecarsOutput::Started);
+
+        progress_monitor.emit(ProgressMonitorInput::Start(TaskName::Sidecars, count));
+
+        // Sidecars are small, so write them one at a time rather than contending
+        // for the file system.
+        for sidecar in unprocessed.iter().take_while(|_| !stop.load(Ordering::Relaxed)) {
+            match xmp::write_sidecar(sidecar) {
+                Ok(_) => {
+                    if let Err(e) = repo.mark_sidecar_written(sidecar.picture_id, &sidecar.digest()) {
+                        error!("Failed marking sidecar written for {:?}: {}", sidecar.path, e);
+                    }
+                },
+                Err(e) => error!("Failed writing sidecar for {:?}: {}", sidecar.path, e),
+            }
+
+            progress_monitor.emit(ProgressMonitorInput::Advance);
+        }
+
+        info!("Wrote {} sidecars in {} seconds.", count, start.elapsed().as_secs());
+
+        progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        // Writing sidecars doesn't change the library, so report nothing updated.
+        if let Err(e) = sender.output(PhotoWriteSidecarsOutput::Completed(0)) {
+            error!("Failed sending PhotoWriteSidecarsOutput::Completed: {:?}", e);
+        }
+
+        Ok(())
+    }
+}
+
+impl Worker for PhotoWriteSidecars {
+    type Init = (Arc<AtomicBool>, photo::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Input = PhotoWriteSidecarsInput;
+    type Output = PhotoWriteSidecarsOutput;
+
+    fn init((stop, repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+        PhotoWriteSidecars {
+            stop,
+            repo,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            PhotoWriteSidecarsInput::Start => {
+                info!("Writing sidecars...");
+                let stop = self.stop.clone();
+                let repo = self.repo.clone();
+                let progress_monitor = self.progress_monitor.clone();
+
+                rayon::spawn(move || {
+                    if let Err(e) = PhotoWriteSidecars::write(stop, repo, progress_monitor, &sender) {
+                        error!("Failed to write sidecars: {}", e);
+                    }
+                });
+            }
+        };
+    }
+}
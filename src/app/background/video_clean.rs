@@ -7,6 +7,8 @@ use relm4::Worker;
 use rayon::prelude::*;
 use anyhow::Result;
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -15,6 +17,9 @@ use tracing::{debug, error, info};
 #[derive(Debug)]
 pub enum VideoCleanInput {
     Start,
+
+    /// Clean only videos under the given files and directories, such as after they have changed on disk.
+    CleanPaths(Vec<PathBuf>),
 }
 
 #[derive(Debug)]
@@ -31,22 +36,61 @@ pub struct VideoClean {
     // Stop flag
     stop: Arc<AtomicBool>,
 
+    library_repo: fotema_core::library::Repository,
+
     // Danger! Don't hold the repo mutex for too long as it blocks viewing images.
     repo: fotema_core::video::Repository,
 }
 
 impl VideoClean {
 
-    fn cleanup(&mut self, sender: &ComponentSender<Self>) -> Result<()> {
+    fn cleanup(&mut self, paths: Option<&[PathBuf]>, sender: &ComponentSender<Self>) -> Result<()> {
 
         let start = std::time::Instant::now();
 
+        // Only clean videos under library roots that are present on the file system,
+        // otherwise an unmounted drive would look like all of its videos had been deleted.
+        let available_roots: HashMap<fotema_core::LibraryRootId, fotema_core::library::LibraryRoot> = self.library_repo
+            .all_available()?
+            .into_iter()
+            .map(|root| (root.library_root_id, root))
+            .collect();
+
         // Scrub vids from database if they no longer exist on the file system.
-        let vids: Vec<fotema_core::video::model::Video> = self.repo.all()?;
+        let vids: Vec<fotema_core::video::model::Video> = self.repo.all()?
+            .into_iter()
+            .filter(|v| available_roots.contains_key(&v.library_root_id))
+            .filter(|v| match paths {
+                Some(paths) => paths.iter().any(|x| v.path.starts_with(x)),
+                None => true,
+            })
+            .collect();
 
         info!("Found {} videos as candidates for cleaning", vids.len());
 
-        let count = vids.par_iter().filter(|v| !v.path.exists()).count();
+        // Videos under a library root that has been removed must also be scrubbed.
+        // Library roots aren't removed by changes on disk, so only look when cleaning everything.
+        let orphaned = match paths {
+            Some(_) => Vec::new(),
+            None => self.repo.find_orphaned()?,
+        };
+
+        info!("Found {} videos under removed library roots", orphaned.len());
+
+        // Videos that are now excluded by the scan rules of their library root
+        // are scrubbed as if they had been deleted.
+        let video_ids: Vec<fotema_core::VideoId> = vids.par_iter()
+            .filter(|v| {
+                let root = &available_roots[&v.library_root_id];
+                !v.path.exists() || root.rules.is_excluded(&root.path, &v.path)
+            })
+            .map(|v| v.video_id)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .chain(orphaned)
+            .collect();
+
+        let count = video_ids.len();
 
         // Short-circuit before sending progress messages to stop
         // banner from appearing and disappearing.
@@ -59,26 +103,24 @@ impl VideoClean {
             error!("Failed sending cleanup started: {:?}", e);
         }
 
-        vids.par_iter()
+        video_ids.par_iter()
             .take_any_while(|_| !self.stop.load(Ordering::Relaxed))
-            .for_each(|vid| {
-                if !vid.path.exists() {
-                    let mut repo = self.repo.clone();
-                    if let Ok(paths) = repo.find_files_to_cleanup(vid.video_id) {
-                        for path in paths {
-                            debug!("Deleting {:?}", path);
-                            if let Err(e) = std::fs::remove_file(&path) {
-                                error!("Failed deleting {:?} with {}", path, e);
-                            }
+            .for_each(|video_id| {
+                let mut repo = self.repo.clone();
+                if let Ok(paths) = repo.find_files_to_cleanup(*video_id) {
+                    for path in paths {
+                        debug!("Deleting {:?}", path);
+                        if let Err(e) = std::fs::remove_file(&path) {
+                            error!("Failed deleting {:?} with {}", path, e);
                         }
                     }
+                }
 
-                    let result = repo.remove(vid.video_id);
-                    if let Err(e) = result {
-                        error!("Failed remove {}: {:?}", vid.video_id, e);
-                    } else {
-                        info!("Removed {}", vid.video_id);
-                    }
+                let result = repo.remove(*video_id);
+                if let Err(e) = result {
+                    error!("Failed remove {}: {:?}", video_id, e);
+                } else {
+                    info!("Removed {}", video_id);
                 }
             });
 
@@ -93,12 +135,12 @@ impl VideoClean {
 }
 
 impl Worker for VideoClean {
-    type Init = (Arc<AtomicBool>, fotema_core::video::Repository);
+    type Init = (Arc<AtomicBool>, fotema_core::library::Repository, fotema_core::video::Repository);
     type Input = VideoCleanInput;
     type Output = VideoCleanOutput;
 
-    fn init((stop, repo): Self::Init, _sender: ComponentSender<Self>) -> Self {
-        Self { stop, repo }
+    fn init((stop, library_repo, repo): Self::Init, _sender: ComponentSender<Self>) -> Self {
+        Self { stop, library_repo, repo }
     }
 
     fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
@@ -106,7 +148,14 @@ impl Worker for VideoClean {
             VideoCleanInput::Start => {
                 info!("Cleaning videos...");
 
-                if let Err(e) = self.cleanup(&sender) {
+                if let Err(e) = self.cleanup(None, &sender) {
+                    error!("Failed to clean videos: {}", e);
+                }
+            }
+            VideoCleanInput::CleanPaths(paths) => {
+                info!("Cleaning videos under {} changed paths...", paths.len());
+
+                if let Err(e) = self.cleanup(Some(&paths), &sender) {
                     error!("Failed to clean videos: {}", e);
                 }
             }
@@ -0,0 +1,124 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::prelude::*;
+use relm4::Worker;
+use relm4::Reducer;
+use futures::executor::block_on;
+use anyhow::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{error, info};
+
+use fotema_core::edit;
+use fotema_core::export::{Exporter, ExportSettings};
+use fotema_core::visual::Visual;
+
+use crate::app::components::progress_monitor::{
+    ProgressMonitor,
+    ProgressMonitorInput,
+    TaskName,
+};
+
+#[derive(Debug)]
+pub enum VisualExportInput {
+    /// Export items with settings.
+    Start(Vec<Arc<Visual>>, ExportSettings),
+}
+
+#[derive(Debug)]
+pub enum VisualExportOutput {
+    // Export started.
+    Started,
+
+    // Export completed
+    Completed(usize),
+}
+
+/// Export pictures and videos to a folder or zip archive, such as for sharing.
+pub struct VisualExport {
+    // Stop flag
+    stop: Arc<AtomicBool>,
+
+    edit_repo: edit::Repository,
+
+    progress_monitor: Arc<Reducer<ProgressMonitor>>,
+}
+
+impl VisualExport {
+
+    fn export(
+        stop: Arc<AtomicBool>,
+        edit_repo: edit::Repository,
+        visuals: Vec<Arc<Visual>>,
+        settings: ExportSettings,
+        progress_monitor: Arc<Reducer<ProgressMonitor>>,
+        sender: &ComponentSender<VisualExport>) -> Result<()>
+    {
+        let start = std::time::Instant::now();
+
+        let count = visuals.len();
+        info!("Exporting {} items to {:?}", count, settings.destination);
+
+        let _ = sender.output(VisualExportOutput::Started);
+
+        progress_monitor.emit(ProgressMonitorInput::Start(TaskName::Export, count));
+
+        let result = Exporter::create(edit_repo, settings).and_then(|mut exporter| {
+            // Items are exported one at a time so that file names are claimed in order
+            // and a zip archive is written sequentially.
+            for visual in visuals.iter().take_while(|_| !stop.load(Ordering::Relaxed)) {
+                if let Err(e) = block_on(async { exporter.export(visual).await }) {
+                    error!("Failed exporting {:?}: {}", visual.path(), e);
+                }
+
+                progress_monitor.emit(ProgressMonitorInput::Advance);
+            }
+            exporter.finish()
+        });
+
+        info!("Exported {} items in {} seconds.", count, start.elapsed().as_secs());
+
+        progress_monitor.emit(ProgressMonitorInput::Complete);
+
+        // Exporting doesn't change the library, so report nothing updated.
+        if let Err(e) = sender.output(VisualExportOutput::Completed(0)) {
+            error!("Failed sending VisualExportOutput::Completed: {:?}", e);
+        }
+
+        result
+    }
+}
+
+impl Worker for VisualExport {
+    type Init = (Arc<AtomicBool>, edit::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Input = VisualExportInput;
+    type Output = VisualExportOutput;
+
+    fn init((stop, edit_repo, progress_monitor): Self::Init, _sender: ComponentSender<Self>) -> Self  {
+        VisualExport {
+            stop,
+            edit_repo,
+            progress_monitor,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            VisualExportInput::Start(visuals, settings) => {
+                info!("Exporting...");
+                let stop = self.stop.clone();
+                let edit_repo = self.edit_repo.clone();
+                let progress_monitor = self.progress_monitor.clone();
+
+                // Avoid runtime panic from calling block_on
+                rayon::spawn(move || {
+                    if let Err(e) = VisualExport::export(stop, edit_repo, visuals, settings, progress_monitor, &sender) {
+                        error!("Failed to export: {}", e);
+                    }
+                });
+            }
+        };
+    }
+}
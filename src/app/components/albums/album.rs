@@ -3,25 +3,33 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use gtk::prelude::OrientableExt;
+use fotema_core::Visual;
 use fotema_core::VisualId;
 use fotema_core::YearMonth;
 use fotema_core::visual::model::PictureOrientation;
 use strum::IntoEnumIterator;
 use relm4::gtk;
 use relm4::gtk::gdk;
+use relm4::gtk::gio;
 use relm4::gtk::prelude::*;
 use relm4::gtk::prelude::AdjustmentExt;
 use relm4::gtk::gdk_pixbuf;
 use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
 use relm4::*;
+use relm4::actions::{RelmAction, RelmActionGroup};
 use relm4::binding::*;
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::path::Path;
+use std::rc::Rc;
 use std::sync::Arc;
 
 use crate::app::adaptive;
 use crate::app::SharedState;
 use crate::app::ActiveView;
 use crate::app::ViewName;
+use crate::app::components::bulk_actions::BulkAction;
+use crate::fl;
 use super::album_filter::AlbumFilter;
 use super::album_sort::AlbumSort;
 
@@ -30,6 +38,22 @@ use tracing::{debug, info};
 const NARROW_EDGE_LENGTH: i32 = 112;
 const WIDE_EDGE_LENGTH: i32 = 200;
 
+relm4::new_action_group!(AlbumActionGroup, "album");
+
+// Change selection
+relm4::new_stateless_action!(SelectAllAction, AlbumActionGroup, "select_all");
+relm4::new_stateless_action!(SelectNoneAction, AlbumActionGroup, "select_none");
+relm4::new_stateless_action!(SelectMonthsAction, AlbumActionGroup, "select_months");
+relm4::new_stateless_action!(SelectYearsAction, AlbumActionGroup, "select_years");
+
+// Bulk actions for selection that don't have their own button
+relm4::new_stateless_action!(CopyToFolderAction, AlbumActionGroup, "copy_to_folder");
+relm4::new_stateless_action!(MoveToFolderAction, AlbumActionGroup, "move_to_folder");
+relm4::new_stateless_action!(AssignPersonAction, AlbumActionGroup, "assign_person");
+relm4::new_stateless_action!(AdjustDateAction, AlbumActionGroup, "adjust_date");
+relm4::new_stateless_action!(ExportAction, AlbumActionGroup, "export");
+relm4::new_stateless_action!(OpenWithAction, AlbumActionGroup, "open_with");
+
 #[derive(Debug)]
 pub enum AlbumInput {
 
@@ -61,7 +85,33 @@ pub enum AlbumInput {
     ScrollOffset(f64),
 
     // Scroll to top of photo grid, regardless of sort order
-    ScrollToTop
+    ScrollToTop,
+
+    // First item has been dragged and dropped onto second item.
+    Move(VisualId, VisualId),
+
+    /// Enter or leave selection mode.
+    SelectionMode(bool),
+
+    /// Enter selection mode with an item selected, such as by right-clicking
+    /// or long-pressing an item.
+    StartSelection(VisualId),
+
+    /// Items have been selected or unselected.
+    SelectionChanged,
+
+    SelectAll,
+
+    SelectNone,
+
+    /// Select every item from the same months as the selected items.
+    SelectMonths,
+
+    /// Select every item from the same years as the selected items.
+    SelectYears,
+
+    /// Apply an action to every selected item.
+    Bulk(BulkAction),
 }
 
 #[derive(Debug)]
@@ -71,6 +121,12 @@ pub enum AlbumOutput {
 
     // Scroll offset, in pixels.
     ScrollOffset(f64),
+
+    /// User has dragged first item onto second item to reorder a user album.
+    Moved(VisualId, VisualId),
+
+    /// User has chosen an action for the selected items.
+    Bulk(BulkAction, Vec<Arc<Visual>>),
 }
 
 #[derive(Debug)]
@@ -79,8 +135,23 @@ struct PhotoGridItem {
 
     // Length of thumbnail edge to allow for resizing when layout changes.
     edge_length: I32Binding,
+
+    // Sender for drag-and-drop reordering and starting selection mode.
+    sender: relm4::Sender<AlbumInput>,
+
+    // Can items be reordered with drag-and-drop? Only for albums in a manual order.
+    is_reorderable: bool,
 }
 
+/// Item bound to grid item widgets, for drag-and-drop reordering and starting selection mode.
+struct BoundItem {
+    visual_id: VisualId,
+    sender: relm4::Sender<AlbumInput>,
+    is_reorderable: bool,
+}
+
+type SharedBoundItem = Rc<RefCell<Option<BoundItem>>>;
+
 struct PhotoGridItemWidgets {
     picture: gtk::Picture,
     status_overlay: gtk::Frame,
@@ -88,6 +159,8 @@ struct PhotoGridItemWidgets {
     duration_overlay: gtk::Frame,
     duration_label: gtk::Label,
 
+    bound_item: SharedBoundItem,
+
     // If the gtk::Picture has been bound to edge_length.
     is_bound: bool,
 }
@@ -143,12 +216,71 @@ impl RelmGridItem for PhotoGridItem {
             }
         }
 
+        let bound_item = SharedBoundItem::default();
+
+        let drag_source = gtk::DragSource::new();
+        drag_source.set_actions(gdk::DragAction::MOVE);
+        {
+            let bound_item = bound_item.clone();
+            drag_source.connect_prepare(move |_, _, _| {
+                bound_item
+                    .borrow()
+                    .as_ref()
+                    .filter(|item| item.is_reorderable)
+                    .map(|item| gdk::ContentProvider::for_value(&item.visual_id.id().to_value()))
+            });
+        }
+        root.add_controller(drag_source);
+
+        let drop_target = gtk::DropTarget::new(String::static_type(), gdk::DragAction::MOVE);
+        {
+            let bound_item = bound_item.clone();
+            drop_target.connect_drop(move |_, value, _, _| {
+                let Ok(source) = value.get::<String>() else {
+                    return false;
+                };
+                let bound_item = bound_item.borrow();
+                let Some(target) = bound_item.as_ref().filter(|item| item.is_reorderable) else {
+                    return false;
+                };
+                target.sender.emit(AlbumInput::Move(VisualId::new(source), target.visual_id.clone()));
+                true
+            });
+        }
+        root.add_controller(drop_target);
+
+        // Right-click with a mouse, or long-press on a touch screen, to start selecting items.
+        let start_selection = {
+            let bound_item = bound_item.clone();
+            move || {
+                if let Some(item) = bound_item.borrow().as_ref() {
+                    item.sender.emit(AlbumInput::StartSelection(item.visual_id.clone()));
+                }
+            }
+        };
+
+        let secondary_click = gtk::GestureClick::builder()
+            .button(gdk::BUTTON_SECONDARY)
+            .build();
+        {
+            let start_selection = start_selection.clone();
+            secondary_click.connect_pressed(move |_, _, _, _| start_selection());
+        }
+        root.add_controller(secondary_click);
+
+        let long_press = gtk::GestureLongPress::builder()
+            .touch_only(true)
+            .build();
+        long_press.connect_pressed(move |_, _, _| start_selection());
+        root.add_controller(long_press);
+
         let widgets = PhotoGridItemWidgets {
             picture,
             status_overlay,
             motion_type_icon,
             duration_overlay,
             duration_label,
+            bound_item,
             is_bound: false,
         };
 
@@ -170,6 +302,12 @@ impl RelmGridItem for PhotoGridItem {
             widgets.is_bound = true;
         }
 
+        *widgets.bound_item.borrow_mut() = Some(BoundItem {
+            visual_id: self.visual.visual_id.clone(),
+            sender: self.sender.clone(),
+            is_reorderable: self.is_reorderable,
+        });
+
         if self.visual.thumbnail_path.as_ref().is_some_and(|x| x.exists()) {
             widgets.picture.set_filename(self.visual.thumbnail_path.clone());
         } else {
@@ -200,6 +338,11 @@ impl RelmGridItem for PhotoGridItem {
             widgets.status_overlay.set_visible(true);
             widgets.duration_overlay.set_visible(false);
             widgets.motion_type_icon.set_icon_name(Some("play-symbolic"));
+        } else if self.visual.is_animated() {
+            widgets.status_overlay.set_visible(true);
+            widgets.duration_overlay.set_visible(false);
+            widgets.duration_label.set_label("");
+            widgets.motion_type_icon.set_icon_name(Some("playlist-infinite-symbolic"));
         } else { // is_photo_only()
             widgets.status_overlay.set_visible(false);
             widgets.motion_type_icon.set_icon_name(None);
@@ -209,6 +352,7 @@ impl RelmGridItem for PhotoGridItem {
     }
 
     fn unbind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        *widgets.bound_item.borrow_mut() = None;
         widgets.picture.set_filename(None::<&Path>);
         widgets.motion_type_icon.set_icon_name(None);
         widgets.status_overlay.set_visible(false);
@@ -226,10 +370,17 @@ pub struct Album {
     state: SharedState,
     active_view: ActiveView,
     view_name: ViewName,
-    photo_grid: TypedGridView<PhotoGridItem, gtk::SingleSelection>,
+    photo_grid: TypedGridView<PhotoGridItem, gtk::MultiSelection>,
     filter: AlbumFilter,
     sort: AlbumSort,
     edge_length: I32Binding,
+    input_sender: relm4::Sender<AlbumInput>,
+
+    // In selection mode clicking an item selects it instead of viewing it.
+    selection_mode: bool,
+
+    // Count of selected items, shown in the selection action bar.
+    selected_count: u32,
 }
 
 #[relm4::component(pub)]
@@ -238,28 +389,100 @@ impl SimpleComponent for Album {
     type Input = AlbumInput;
     type Output = AlbumOutput;
 
-    view! {
-        gtk::ScrolledWindow {
-            set_vexpand: true,
+    menu! {
+        select_menu: {
+            section! {
+                &fl!("album-selection-menu", "select-all") => SelectAllAction,
+                &fl!("album-selection-menu", "select-none") => SelectNoneAction,
+            },
+            section! {
+                &fl!("album-selection-menu", "select-months") => SelectMonthsAction,
+                &fl!("album-selection-menu", "select-years") => SelectYearsAction,
+            }
+        },
+        bulk_menu: {
+            section! {
+                &fl!("album-selection-menu", "copy-to-folder") => CopyToFolderAction,
+                &fl!("album-selection-menu", "move-to-folder") => MoveToFolderAction,
+            },
+            section! {
+                &fl!("album-selection-menu", "assign-person") => AssignPersonAction,
+                &fl!("album-selection-menu", "adjust-date") => AdjustDateAction,
+                &fl!("album-selection-menu", "export") => ExportAction,
+                &fl!("album-selection-menu", "open-with") => OpenWithAction,
+            }
+        }
+    }
 
-            #[local_ref]
-            grid_view -> gtk::GridView {
-                set_orientation: gtk::Orientation::Vertical,
-                set_single_click_activate: true,
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            gtk::ScrolledWindow {
+                set_vexpand: true,
+
+                #[local_ref]
+                grid_view -> gtk::GridView {
+                    set_orientation: gtk::Orientation::Vertical,
+                    #[watch]
+                    set_single_click_activate: !model.selection_mode,
+                    #[watch]
+                    set_enable_rubberband: model.selection_mode,
+
+                    connect_activate[sender] => move |_, idx| {
+                        sender.input(AlbumInput::Selected(idx))
+                    },
+                },
 
-                connect_activate[sender] => move |_, idx| {
-                    sender.input(AlbumInput::Selected(idx))
+                #[wrap(Some)]
+                set_vadjustment = &gtk::Adjustment {
+                    // Emit scroll events so PersonAlbum can determine when to hide avatar.
+                    // FIXME maybe just emit one event at a boundary, instead of emitting an
+                    // event for every scroll?
+                    connect_value_changed[sender] => move |v| sender.input(AlbumInput::ScrollOffset(v.value())),
                 },
             },
 
-            #[wrap(Some)]
-            set_vadjustment = &gtk::Adjustment {
-                // Emit scroll events so PersonAlbum can determine when to hide avatar.
-                // FIXME maybe just emit one event at a boundary, instead of emitting an
-                // event for every scroll?
-                connect_value_changed[sender] => move |v| sender.input(AlbumInput::ScrollOffset(v.value())),
-            },
+            gtk::ActionBar {
+                #[watch]
+                set_revealed: model.selection_mode,
+
+                pack_start = &gtk::Button {
+                    set_icon_name: "window-close-symbolic",
+                    set_tooltip_text: Some(&fl!("album-selection-done", "tooltip")),
+                    connect_clicked => AlbumInput::SelectionMode(false),
+                },
 
+                pack_start = &gtk::MenuButton {
+                    #[watch]
+                    set_label: &fl!("album-selection-count", count = model.selected_count),
+                    set_menu_model: Some(&select_menu),
+                },
+
+                pack_end = &gtk::MenuButton {
+                    set_icon_name: "view-more-symbolic",
+                    set_tooltip_text: Some(&fl!("album-selection-menu", "tooltip")),
+                    set_menu_model: Some(&bulk_menu),
+                    #[watch]
+                    set_sensitive: model.selected_count > 0,
+                },
+
+                pack_end = &gtk::Button {
+                    set_icon_name: "user-trash-symbolic",
+                    set_tooltip_text: Some(&fl!("album-selection-trash", "tooltip")),
+                    connect_clicked => AlbumInput::Bulk(BulkAction::Trash),
+                    #[watch]
+                    set_sensitive: model.selected_count > 0,
+                },
+
+                pack_end = &gtk::Button {
+                    set_icon_name: "folder-pictures-symbolic",
+                    set_tooltip_text: Some(&fl!("album-selection-add-to-album", "tooltip")),
+                    connect_clicked => AlbumInput::Bulk(BulkAction::AddToAlbum),
+                    #[watch]
+                    set_sensitive: model.selected_count > 0,
+                },
+            },
         }
     }
 
@@ -271,6 +494,13 @@ impl SimpleComponent for Album {
         let photo_grid = TypedGridView::new();
         let grid_view = &photo_grid.view.clone();
 
+        {
+            let sender = sender.clone();
+            photo_grid.selection_model.connect_selection_changed(move |_, _, _| {
+                sender.input(AlbumInput::SelectionChanged);
+            });
+        }
+
         let mut model = Album {
             state,
             active_view,
@@ -279,11 +509,35 @@ impl SimpleComponent for Album {
             filter,
             sort: AlbumSort::default(),
             edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
+            input_sender: sender.input_sender().clone(),
+            selection_mode: false,
+            selected_count: 0,
         };
 
         model.update_filter();
 
         let widgets = view_output!();
+
+        let mut actions = RelmActionGroup::<AlbumActionGroup>::new();
+
+        let input_action = |input: fn() -> AlbumInput| {
+            let sender = sender.clone();
+            move |_: &gio::SimpleAction| sender.input(input())
+        };
+
+        actions.add_action(RelmAction::<SelectAllAction>::new_stateless(input_action(|| AlbumInput::SelectAll)));
+        actions.add_action(RelmAction::<SelectNoneAction>::new_stateless(input_action(|| AlbumInput::SelectNone)));
+        actions.add_action(RelmAction::<SelectMonthsAction>::new_stateless(input_action(|| AlbumInput::SelectMonths)));
+        actions.add_action(RelmAction::<SelectYearsAction>::new_stateless(input_action(|| AlbumInput::SelectYears)));
+        actions.add_action(RelmAction::<CopyToFolderAction>::new_stateless(input_action(|| AlbumInput::Bulk(BulkAction::CopyToFolder))));
+        actions.add_action(RelmAction::<MoveToFolderAction>::new_stateless(input_action(|| AlbumInput::Bulk(BulkAction::MoveToFolder))));
+        actions.add_action(RelmAction::<AssignPersonAction>::new_stateless(input_action(|| AlbumInput::Bulk(BulkAction::AssignPerson))));
+        actions.add_action(RelmAction::<AdjustDateAction>::new_stateless(input_action(|| AlbumInput::Bulk(BulkAction::AdjustDate))));
+        actions.add_action(RelmAction::<ExportAction>::new_stateless(input_action(|| AlbumInput::Bulk(BulkAction::Export))));
+        actions.add_action(RelmAction::<OpenWithAction>::new_stateless(input_action(|| AlbumInput::Bulk(BulkAction::OpenWith))));
+
+        actions.register_for_widget(&root);
+
         ComponentParts { model, widgets }
     }
 
@@ -317,6 +571,11 @@ impl SimpleComponent for Album {
                 }
             }
             AlbumInput::Selected(index) => {
+                // Activating an item in selection mode shouldn't leave the selection.
+                if self.selection_mode {
+                    return;
+                }
+
                 // Albums are filters so must use get_visible(...) over get(...), otherwise
                 // wrong photo is displayed.
                 if let Some(item) = self.photo_grid.get_visible(index) {
@@ -354,6 +613,67 @@ impl SimpleComponent for Album {
             AlbumInput::ScrollOffset(offset) => {
                 let _ = sender.output(AlbumOutput::ScrollOffset(offset));
             },
+            AlbumInput::Move(visual_id, target) => {
+                if visual_id != target {
+                    let _ = sender.output(AlbumOutput::Moved(visual_id, target));
+                }
+            },
+            AlbumInput::SelectionMode(selection_mode) => {
+                info!("{:?} selection mode: {}", self.view_name, selection_mode);
+                self.selection_mode = selection_mode;
+                self.photo_grid.selection_model.unselect_all();
+            },
+            AlbumInput::StartSelection(visual_id) => {
+                if !self.selection_mode {
+                    self.selection_mode = true;
+                    self.photo_grid.selection_model.unselect_all();
+                }
+
+                if let Some(index) = self.find_visible(|item| item.visual.visual_id == visual_id) {
+                    self.photo_grid.selection_model.select_item(index, false);
+                }
+            },
+            AlbumInput::SelectionChanged => {
+                self.selected_count = self.photo_grid.selection_model.selection().size() as u32;
+
+                // Outside of selection mode, hovering selects a single item. Selecting more
+                // with control-click or shift-click starts selection mode.
+                if !self.selection_mode && self.selected_count > 1 {
+                    self.selection_mode = true;
+                }
+            },
+            AlbumInput::SelectAll => {
+                self.photo_grid.selection_model.select_all();
+            },
+            AlbumInput::SelectNone => {
+                self.photo_grid.selection_model.unselect_all();
+            },
+            AlbumInput::SelectMonths => {
+                let months: HashSet<YearMonth> = self.selected()
+                    .iter()
+                    .map(|visual| visual.year_month())
+                    .collect();
+
+                self.select_where(|visual| months.contains(&visual.year_month()));
+            },
+            AlbumInput::SelectYears => {
+                let years: HashSet<u32> = self.selected()
+                    .iter()
+                    .map(|visual| visual.year())
+                    .collect();
+
+                self.select_where(|visual| years.contains(&visual.year()));
+            },
+            AlbumInput::Bulk(action) => {
+                let visuals = self.selected();
+                if visuals.is_empty() {
+                    return;
+                }
+
+                info!("{:?} for {} selected items", action, visuals.len());
+                let _ = sender.output(AlbumOutput::Bulk(action, visuals));
+                sender.input(AlbumInput::SelectionMode(false));
+            },
         }
     }
 }
@@ -361,6 +681,14 @@ impl SimpleComponent for Album {
 impl Album {
 
     fn refresh(&mut self) {
+        // User albums are in the order chosen by the user, which can be changed by dragging.
+        let positions = match self.filter {
+            AlbumFilter::UserAlbum(_, ref positions) => Some(positions.clone()),
+            _ => None,
+        };
+
+        let is_reorderable = positions.is_some();
+
         let mut all = {
             let data = self.state.read();
             data
@@ -368,10 +696,20 @@ impl Album {
                 .map(|visual| PhotoGridItem {
                     visual: visual.clone(),
                     edge_length: self.edge_length.clone(),
+                    sender: self.input_sender.clone(),
+                    is_reorderable,
                 })
                 .collect::<Vec<PhotoGridItem>>()
         };
 
+        if let Some(positions) = positions {
+            all.sort_by_key(|item| positions.get(&item.visual.visual_id).copied().unwrap_or(usize::MAX));
+            self.photo_grid.clear();
+            self.photo_grid.extend_from_iter(all);
+            info!("{} items added to album in album order", self.photo_grid.len());
+            return;
+        }
+
         // State is always in ascending time order
         self.sort.sort(&mut all);
 
@@ -387,6 +725,47 @@ impl Album {
         self.sort.scroll_to_end(&mut self.photo_grid);
     }
 
+    /// Selected visual items, in grid order.
+    fn selected(&self) -> Vec<Arc<Visual>> {
+        let selection_model = &self.photo_grid.selection_model;
+        (0..selection_model.n_items())
+            .filter(|index| selection_model.is_selected(*index))
+            .filter_map(|index| self.photo_grid.get_visible(index))
+            .map(|item| item.borrow().visual.clone())
+            .collect()
+    }
+
+    /// Adds visible items matching a predicate to the selection.
+    fn select_where<F>(&self, predicate: F)
+    where
+        F: Fn(&Visual) -> bool,
+    {
+        let selected = gtk::Bitset::new_empty();
+        for index in 0..self.photo_grid.selection_model.n_items() {
+            if let Some(item) = self.photo_grid.get_visible(index) {
+                if predicate(&item.borrow().visual) {
+                    selected.add(index);
+                }
+            }
+        }
+
+        // Mask only includes the newly selected items, so existing selection is kept.
+        self.photo_grid.selection_model.set_selection(&selected, &selected);
+    }
+
+    /// Index of first visible item matching a predicate.
+    fn find_visible<F>(&self, predicate: F) -> Option<u32>
+    where
+        F: Fn(&PhotoGridItem) -> bool,
+    {
+        (0..self.photo_grid.selection_model.n_items())
+            .find(|index| {
+                self.photo_grid
+                    .get_visible(*index)
+                    .is_some_and(|item| predicate(&item.borrow()))
+            })
+    }
+
     fn update_filter(&mut self) {
         self.photo_grid.clear_filters();
         let filter = self.filter.clone();
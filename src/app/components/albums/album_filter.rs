@@ -2,9 +2,12 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use fotema_core::Visual;
+use fotema_core::album::AlbumId;
 use h3o::CellIndex;
 use fotema_core::VisualId;
 use fotema_core::PictureId;
@@ -28,18 +31,37 @@ pub enum AlbumFilter {
     // Show only videos
     Videos,
 
-    // Show only motion photos (live photos)
+    // Show only motion photos (live photos) and animated pictures
     Motion,
 
+    // Show only favourites
+    Favourites,
+
     // Show photos only for folder
     Folder(PathBuf),
 
     // Show photos in a geographic area
     GeographicArea(CellIndex),
 
+    /// Show pictures taken with a camera and lens. A lens of None shows pictures
+    /// taken with the camera that have no lens recorded.
+    Camera(String, Option<String>),
+
     /// Show photos who's picture_id is in a set. Used for person filtering.
     /// FIXME should probably be a Set of some kind... but that mucks up PartialEq and Eq.
-    Any(Vec<PictureId>)
+    Any(Vec<PictureId>),
+
+    /// Show visual items found by a search query.
+    /// Shared because the filter is cloned for every item in an album.
+    Search(Arc<HashSet<VisualId>>),
+
+    /// Show visual items in a user album, with the position of each item in the album.
+    /// Shared because the filter is cloned for every item in an album.
+    UserAlbum(AlbumId, Arc<HashMap<VisualId, usize>>),
+
+    /// Show visual items matching the query of a smart album, as evaluated by fotema_core.
+    /// Shared because the filter is cloned for every item in an album.
+    SmartAlbum(AlbumId, Arc<HashSet<VisualId>>),
 }
 
 impl AlbumFilter {
@@ -49,7 +71,8 @@ impl AlbumFilter {
             AlbumFilter::One(visual_id) => v.visual_id == visual_id,
             AlbumFilter::All => true,
             AlbumFilter::Folder(path) => v.parent_path == path,
-            AlbumFilter::Motion => v.is_motion_photo(),
+            AlbumFilter::Motion => v.is_motion_photo() || v.is_animated(),
+            AlbumFilter::Favourites => v.is_favourite,
             AlbumFilter::Selfies => v.is_selfie(),
             AlbumFilter::Videos => v.is_video_only() && !v.is_motion_photo(),
             AlbumFilter::GeographicArea(cell_index) => {
@@ -60,7 +83,11 @@ impl AlbumFilter {
                     false
                 }
             },
+            AlbumFilter::Camera(camera, lens) => v.camera.as_ref() == Some(&camera) && v.lens == lens,
             AlbumFilter::Any(picture_ids) => v.picture_id.is_some_and(|id| picture_ids.contains(&id)),
+            AlbumFilter::Search(visual_ids) => visual_ids.contains(&v.visual_id),
+            AlbumFilter::UserAlbum(_, positions) => positions.contains_key(&v.visual_id),
+            AlbumFilter::SmartAlbum(_, visual_ids) => visual_ids.contains(&v.visual_id),
         }
     }
 }
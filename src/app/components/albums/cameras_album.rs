@@ -0,0 +1,293 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+
+use fotema_core::visual::model::PictureOrientation;
+use strum::IntoEnumIterator;
+
+use itertools::Itertools;
+
+use relm4::gtk;
+use relm4::gtk::gdk;
+use relm4::gtk::gdk_pixbuf;
+use relm4::gtk::prelude::WidgetExt;
+use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
+use relm4::*;
+use relm4::binding::*;
+
+use std::path;
+use std::sync::Arc;
+
+use crate::adaptive;
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::fl;
+
+use tracing::{event, Level, info};
+
+const NARROW_EDGE_LENGTH: i32 = 170;
+const WIDE_EDGE_LENGTH: i32 = 200;
+
+#[derive(Debug)]
+struct PhotoGridItem {
+    camera: String,
+
+    lens: Option<String>,
+
+    // Number of items taken with camera and lens.
+    item_count: usize,
+
+    // Camera album cover
+    picture: Arc<fotema_core::visual::Visual>,
+
+    // Length of thumbnail edge to allow for resizing when layout changes.
+    edge_length: I32Binding,
+}
+
+struct Widgets {
+    picture: gtk::Picture,
+    label: gtk::Label,
+    lens_label: gtk::Label,
+    count_label: gtk::Label,
+
+    // If the gtk::Picture has been bound to edge_length.
+    is_bound: bool,
+}
+
+#[derive(Debug)]
+pub enum CamerasAlbumInput {
+    Activate,
+
+    // Reload photos from database
+    Refresh,
+
+    CameraSelected(u32), // Index into photo grid vector
+
+    // Adapt to layout
+    Adapt(adaptive::Layout),
+}
+
+#[derive(Debug)]
+pub enum CamerasAlbumOutput {
+    // Camera and lens selected. Lens is None for pictures without a lens model.
+    CameraSelected(String, Option<String>),
+}
+
+impl RelmGridItem for PhotoGridItem {
+    type Root = gtk::Box;
+    type Widgets = Widgets;
+
+    fn setup(_item: &gtk::ListItem) -> (gtk::Box, Widgets) {
+        relm4::view! {
+           my_box = gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                gtk::AspectFrame {
+                    gtk::Frame {
+                        #[name(picture)]
+                        gtk::Picture {
+                            set_can_shrink: true,
+                            set_width_request: NARROW_EDGE_LENGTH,
+                            set_height_request: NARROW_EDGE_LENGTH,
+                        }
+                    }
+                },
+
+                #[name(label)]
+                gtk::Label {
+                    add_css_class: "caption-heading",
+                    set_margin_top: 4,
+                },
+
+                #[name(lens_label)]
+                gtk::Label {
+                    add_css_class: "caption",
+                    set_ellipsize: gtk::pango::EllipsizeMode::End,
+                    set_max_width_chars: 24,
+                },
+
+                #[name(count_label)]
+                gtk::Label {
+                    add_css_class: "caption",
+                    add_css_class: "dim-label",
+                    set_margin_bottom: 12,
+                },
+            }
+        }
+
+        let widgets = Widgets {
+            picture,
+            label,
+            lens_label,
+            count_label,
+            is_bound: false,
+        };
+
+        (my_box, widgets)
+    }
+
+    fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        widgets.label.set_text(&self.camera);
+
+        widgets.lens_label.set_text(self.lens.as_deref().unwrap_or_default());
+        widgets.lens_label.set_tooltip_text(self.lens.as_deref());
+        widgets.lens_label.set_visible(self.lens.is_some());
+
+        widgets.count_label.set_text(&fl!("cameras-album-item-count", count = self.item_count));
+
+        // If we repeatedly bind, then Fotema will die with the following error:
+        // (fotema:2): GLib-GObject-CRITICAL **: 13:26:14.297: Too many GWeakRef registered
+        // GLib-GObject:ERROR:../gobject/gbinding.c:805:g_binding_constructed: assertion failed: (source != NULL)
+        // Bail out! GLib-GObject:ERROR:../gobject/gbinding.c:805:g_binding_constructed: assertion failed: (source != NULL)
+        if !widgets.is_bound {
+            widgets.picture.add_write_only_binding(&self.edge_length, "width-request");
+            widgets.picture.add_write_only_binding(&self.edge_length, "height-request");
+            widgets.is_bound = true;
+        }
+
+        if self.picture.thumbnail_path.as_ref().is_some_and(|x| x.exists())
+        {
+            widgets
+                .picture
+                .set_filename(self.picture.thumbnail_path.clone());
+        } else {
+            let pb = gdk_pixbuf::Pixbuf::from_resource_at_scale(
+                "/app/fotema/Fotema/icons/scalable/actions/image-missing-symbolic.svg",
+                200, 200, true
+            ).unwrap();
+            let img = gdk::Texture::for_pixbuf(&pb);
+            widgets.picture.set_paintable(Some(&img));
+        }
+    }
+
+    fn unbind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        widgets.picture.set_filename(None::<&path::Path>);
+        // clear orientation transformation css classes
+        for orient in PictureOrientation::iter() {
+            widgets.picture.remove_css_class(orient.as_ref());
+        }
+    }
+}
+
+/// Grid of every camera and lens pictures were taken with, with a count of pictures for each.
+pub struct CamerasAlbum {
+    state: SharedState,
+    active_view: ActiveView,
+    photo_grid: TypedGridView<PhotoGridItem, gtk::SingleSelection>,
+    edge_length: I32Binding,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for CamerasAlbum {
+    type Init = (SharedState, ActiveView);
+    type Input = CamerasAlbumInput;
+    type Output = CamerasAlbumOutput;
+
+    view! {
+        gtk::ScrolledWindow {
+            set_vexpand: true,
+
+            #[local_ref]
+            pictures_box -> gtk::GridView {
+                set_orientation: gtk::Orientation::Vertical,
+                set_single_click_activate: true,
+
+                connect_activate[sender] => move |_, idx| {
+                    sender.input(CamerasAlbumInput::CameraSelected(idx))
+                }
+            }
+        }
+    }
+
+    fn init(
+        (state, active_view): Self::Init,
+        _root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let photo_grid = TypedGridView::new();
+
+        let model = CamerasAlbum {
+            state,
+            active_view,
+            photo_grid,
+            edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
+        };
+
+        let pictures_box = &model.photo_grid.view;
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            CamerasAlbumInput::Activate => {
+                *self.active_view.write() = ViewName::Cameras;
+                if self.photo_grid.is_empty() {
+                    self.refresh();
+                }
+            },
+            CamerasAlbumInput::Refresh => {
+                if *self.active_view.read() == ViewName::Cameras {
+                    info!("Cameras view is active so refreshing");
+                    self.refresh();
+                } else {
+                    info!("Cameras view is inactive so clearing");
+                    self.photo_grid.clear();
+                }
+            },
+            CamerasAlbumInput::CameraSelected(index) => {
+                event!(Level::DEBUG, "Camera selected index: {}", index);
+                if let Some(item) = self.photo_grid.get_visible(index) {
+                    let item = item.borrow();
+                    event!(Level::DEBUG, "Camera selected item: {} {:?}", item.camera, item.lens);
+
+                    let _ = sender
+                        .output(CamerasAlbumOutput::CameraSelected(item.camera.clone(), item.lens.clone()));
+                }
+            },
+            CamerasAlbumInput::Adapt(adaptive::Layout::Narrow) => {
+                self.edge_length.set_value(NARROW_EDGE_LENGTH);
+            },
+            CamerasAlbumInput::Adapt(adaptive::Layout::Wide) => {
+                self.edge_length.set_value(WIDE_EDGE_LENGTH);
+            },
+        }
+    }
+}
+
+impl CamerasAlbum {
+    fn refresh(&mut self) {
+        let all = {
+            let data = self.state.read();
+            data.iter()
+                .filter_map(|vis| vis.camera.clone().map(|camera| ((camera, vis.lens.clone()), vis.clone())))
+                .into_group_map()
+        };
+
+        let mut cameras = Vec::new();
+
+        for ((camera, lens), group) in all {
+            // Items are in order of when they were taken, so the most recent is the cover.
+            let last = group.last().expect("Groups can't be empty");
+            let album = PhotoGridItem {
+                camera,
+                lens,
+                item_count: group.len(),
+                picture: last.clone(),
+                edge_length: self.edge_length.clone(),
+            };
+            cameras.push(album);
+        }
+
+        cameras.sort_by(|a, b| a.camera.cmp(&b.camera).then_with(|| a.lens.cmp(&b.lens)));
+
+        self.photo_grid.clear();
+        self.photo_grid.extend_from_iter(cameras);
+
+        // NOTE cameras view is not sorted by a timestamp, so don't scroll to end.
+    }
+}
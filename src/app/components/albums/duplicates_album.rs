@@ -0,0 +1,332 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use fotema_core::duplicates;
+use fotema_core::duplicates::group::DEFAULT_THRESHOLD;
+use fotema_core::duplicates::DuplicateGroup;
+use fotema_core::trash;
+use fotema_core::PictureId;
+
+use relm4::adw;
+use relm4::adw::prelude::*;
+use relm4::gtk;
+use relm4::*;
+
+use std::path::PathBuf;
+
+use crate::app::components::system_trash;
+use crate::app::ActiveView;
+use crate::app::SharedState;
+use crate::app::ViewName;
+use crate::fl;
+
+use tracing::{error, info};
+
+const EDGE_LENGTH: i32 = 112;
+
+#[derive(Debug)]
+pub enum DuplicatesAlbumInput {
+    Activate,
+
+    // Reload duplicates from database
+    Refresh,
+
+    // Keep checked pictures in group and move the others to the trash.
+    // Index into vector of groups.
+    KeepSelected(usize),
+}
+
+#[derive(Debug)]
+pub enum DuplicatesAlbumCommand {
+    // Groups of duplicates found in the background.
+    Found(Vec<DuplicateGroup>),
+}
+
+#[derive(Debug)]
+pub enum DuplicatesAlbumOutput {
+    // Pictures have been moved to the trash.
+    Trashed,
+}
+
+/// Widgets for a group of duplicate pictures.
+struct GroupRow {
+    group: adw::PreferencesGroup,
+
+    // ID and path of each picture and the check button for keeping it.
+    pictures: Vec<(PictureId, PathBuf, gtk::CheckButton)>,
+}
+
+pub struct DuplicatesAlbum {
+    repo: duplicates::Repository,
+    trash_repo: trash::Repository,
+    state: SharedState,
+    active_view: ActiveView,
+    groups_box: gtk::Box,
+    scrolled: gtk::ScrolledWindow,
+    status: adw::StatusPage,
+
+    // Rows currently shown. None if the group has been resolved.
+    rows: Vec<Option<GroupRow>>,
+
+    // Looking for duplicates in the background.
+    is_refreshing: bool,
+
+    // Another refresh was requested while looking for duplicates.
+    is_refresh_pending: bool,
+}
+
+#[relm4::component(pub)]
+impl Component for DuplicatesAlbum {
+    type Init = (duplicates::Repository, trash::Repository, SharedState, ActiveView);
+    type Input = DuplicatesAlbumInput;
+    type Output = DuplicatesAlbumOutput;
+    type CommandOutput = DuplicatesAlbumCommand;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            #[local_ref]
+            scrolled -> gtk::ScrolledWindow {
+                set_vexpand: true,
+
+                adw::Clamp {
+                    set_maximum_size: 800,
+
+                    #[local_ref]
+                    groups_box -> gtk::Box {
+                        set_orientation: gtk::Orientation::Vertical,
+                        set_spacing: 24,
+                        set_margin_all: 12,
+                    },
+                },
+            },
+
+            #[local_ref]
+            status -> adw::StatusPage {
+                set_valign: gtk::Align::Start,
+                set_vexpand: true,
+                set_visible: false,
+                set_icon_name: Some("edit-copy-symbolic"),
+                set_title: &fl!("duplicates-album-status-none", "title"),
+                set_description: Some(&fl!("duplicates-album-status-none", "description")),
+            },
+        },
+    }
+
+    fn init(
+        (repo, trash_repo, state, active_view): Self::Init,
+        _root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+
+        let groups_box = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        let scrolled = gtk::ScrolledWindow::new();
+        let status = adw::StatusPage::new();
+
+        let model = DuplicatesAlbum {
+            repo,
+            trash_repo,
+            state,
+            active_view,
+            groups_box: groups_box.clone(),
+            scrolled: scrolled.clone(),
+            status: status.clone(),
+            rows: Vec::new(),
+            is_refreshing: false,
+            is_refresh_pending: false,
+        };
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
+        match msg {
+            DuplicatesAlbumInput::Activate => {
+                info!("Activating duplicates view");
+                *self.active_view.write() = ViewName::Duplicates;
+                self.refresh(&sender);
+            },
+            DuplicatesAlbumInput::Refresh => {
+                // Grouping is slow for large libraries, so only do it when visible.
+                if *self.active_view.read() == ViewName::Duplicates {
+                    self.refresh(&sender);
+                }
+            },
+            DuplicatesAlbumInput::KeepSelected(index) => {
+                let Some(row) = self.rows.get_mut(index).and_then(|x| x.take()) else {
+                    return;
+                };
+
+                // Never trash every copy.
+                if !row.pictures.iter().any(|(_, _, keep)| keep.is_active()) {
+                    info!("No pictures selected to keep, so not trashing any");
+                    self.rows[index] = Some(row);
+                    return;
+                }
+
+                // Trash duplicates like any other item, so they can be restored from
+                // recently deleted and are hidden from the library straight away.
+                let mut trashed_count = 0;
+                for (picture_id, path, keep) in row.pictures.iter() {
+                    if keep.is_active() {
+                        continue;
+                    }
+
+                    let visual = self.state.read()
+                        .iter()
+                        .find(|x| x.picture_id == Some(*picture_id))
+                        .cloned();
+
+                    let Some(visual) = visual else {
+                        error!("Duplicate not in library, so not trashing: {:?}", path);
+                        continue;
+                    };
+
+                    info!("Moving duplicate to trash: {:?}", path);
+                    if system_trash::trash_item(&mut self.trash_repo, &visual).is_some() {
+                        trashed_count += 1;
+                    }
+                }
+
+                self.groups_box.remove(&row.group);
+                self.update_status();
+
+                if trashed_count > 0 {
+                    let _ = sender.output(DuplicatesAlbumOutput::Trashed);
+                }
+            },
+        }
+    }
+
+    fn update_cmd(&mut self, msg: Self::CommandOutput, sender: ComponentSender<Self>, _root: &Self::Root) {
+        match msg {
+            DuplicatesAlbumCommand::Found(groups) => {
+                info!("Found {} groups of duplicates", groups.len());
+                self.is_refreshing = false;
+
+                for row in self.rows.drain(..).flatten() {
+                    self.groups_box.remove(&row.group);
+                }
+
+                for (index, group) in groups.into_iter().enumerate() {
+                    let row = Self::group_row(index, group, &sender);
+                    self.groups_box.append(&row.group);
+                    self.rows.push(Some(row));
+                }
+
+                self.update_status();
+
+                if self.is_refresh_pending {
+                    self.is_refresh_pending = false;
+                    self.refresh(&sender);
+                }
+            },
+        }
+    }
+}
+
+impl DuplicatesAlbum {
+    /// Finds duplicates in the background. Groups currently shown are replaced when done.
+    fn refresh(&mut self, sender: &ComponentSender<Self>) {
+        if self.is_refreshing {
+            self.is_refresh_pending = true;
+            return;
+        }
+
+        self.is_refreshing = true;
+
+        let repo = self.repo.clone();
+        sender.spawn_oneshot_command(move || {
+            let groups = repo.find_duplicates(DEFAULT_THRESHOLD).unwrap_or_else(|e| {
+                error!("Failed finding duplicates: {}", e);
+                Vec::new()
+            });
+            DuplicatesAlbumCommand::Found(groups)
+        });
+    }
+
+    fn update_status(&self) {
+        let is_empty = self.rows.iter().all(|x| x.is_none());
+        self.status.set_visible(is_empty);
+        self.scrolled.set_visible(!is_empty);
+    }
+
+    fn group_row(index: usize, group: DuplicateGroup, sender: &ComponentSender<Self>) -> GroupRow {
+        let title = if group.is_exact {
+            fl!("duplicates-album-group", "exact")
+        } else {
+            fl!("duplicates-album-group", "similar")
+        };
+
+        let keep_button = gtk::Button::builder()
+            .label(fl!("duplicates-album-keep-selected"))
+            .tooltip_text(fl!("duplicates-album-keep-selected", "tooltip"))
+            .valign(gtk::Align::Center)
+            .css_classes(["destructive-action"])
+            .build();
+
+        {
+            let sender = sender.clone();
+            keep_button.connect_clicked(move |_| {
+                sender.input(DuplicatesAlbumInput::KeepSelected(index));
+            });
+        }
+
+        let preferences_group = adw::PreferencesGroup::builder()
+            .title(title)
+            .header_suffix(&keep_button)
+            .build();
+
+        let flow_box = gtk::FlowBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .homogeneous(true)
+            .column_spacing(12)
+            .row_spacing(12)
+            .build();
+
+        let mut pictures = Vec::with_capacity(group.pictures.len());
+
+        for (i, pic) in group.pictures.into_iter().enumerate() {
+            let thumbnail = gtk::Picture::builder()
+                .can_shrink(true)
+                .width_request(EDGE_LENGTH)
+                .height_request(EDGE_LENGTH)
+                .content_fit(gtk::ContentFit::Cover)
+                .build();
+
+            if let Some(ref thumbnail_path) = pic.thumbnail_path {
+                thumbnail.set_filename(Some(thumbnail_path));
+            }
+
+            let file_name = pic.path.file_name()
+                .map(|x| x.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            // Largest picture is first and kept by default, as it is most likely the original.
+            let keep = gtk::CheckButton::builder()
+                .label(file_name)
+                .tooltip_text(pic.path.to_string_lossy())
+                .active(i == 0)
+                .build();
+
+            let item = gtk::Box::new(gtk::Orientation::Vertical, 6);
+            item.append(&gtk::Frame::builder().child(&thumbnail).build());
+            item.append(&keep);
+
+            flow_box.insert(&item, -1);
+
+            pictures.push((pic.picture_id, pic.path, keep));
+        }
+
+        preferences_group.add(&flow_box);
+
+        GroupRow {
+            group: preferences_group,
+            pictures,
+        }
+    }
+}
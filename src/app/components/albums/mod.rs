@@ -5,9 +5,16 @@
 pub mod album;
 pub mod album_filter;
 pub mod album_sort;
+pub mod cameras_album;
+pub mod duplicates_album;
 pub mod folders_album;
 pub mod months_album;
 pub mod people_album;
 pub mod person_album;
 pub mod places_album;
+pub mod recently_deleted;
+pub mod search_album;
+pub mod smart_album_rules;
+pub mod user_album;
+pub mod user_albums;
 pub mod years_album;
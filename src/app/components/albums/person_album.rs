@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use gtk::prelude::OrientableExt;
+use fotema_core::Visual;
 use fotema_core::VisualId;
 use relm4::gtk;
 use relm4::gtk::prelude::*;
@@ -13,6 +14,8 @@ use relm4::adw::prelude::*;
 use relm4::gtk::gdk;
 use relm4::actions::{RelmAction, RelmActionGroup};
 
+use std::sync::Arc;
+
 use crate::app::adaptive;
 use crate::app::SharedState;
 use crate::app::ActiveView;
@@ -25,6 +28,7 @@ use crate::app::components::albums:: {
 
 use fotema_core::people;
 use fotema_core::PictureId;
+use crate::app::components::bulk_actions::BulkAction;
 use crate::fl;
 
 use tracing::{error, info};
@@ -74,6 +78,12 @@ pub enum PersonAlbumInput {
     Delete,
 
     Sort(AlbumSort),
+
+    /// User has chosen an action for the selected items.
+    Bulk(BulkAction, Vec<Arc<Visual>>),
+
+    /// Ignore event
+    Ignore,
 }
 
 #[derive(Debug)]
@@ -86,6 +96,9 @@ pub enum PersonAlbumOutput {
 
     /// Person renamed.
     Renamed,
+
+    /// User has chosen an action for the selected items.
+    Bulk(BulkAction, Vec<Arc<Visual>>),
 }
 
 pub struct PersonAlbum {
@@ -158,7 +171,9 @@ impl SimpleComponent for PersonAlbum {
             .launch((state.clone(), active_view.clone(), ViewName::Person, AlbumFilter::None))
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, _) => PersonAlbumInput::Selected(id),
+                AlbumOutput::Bulk(action, visuals) => PersonAlbumInput::Bulk(action, visuals),
                 AlbumOutput::ScrollOffset(offset) => PersonAlbumInput::ScrollOffset(offset),
+                AlbumOutput::Moved(_, _) => PersonAlbumInput::Ignore,
             });
 
         let title = gtk::Label::builder()
@@ -373,6 +388,10 @@ impl SimpleComponent for PersonAlbum {
                 self.picture_ids.clear();
                 let _ = sender.output(PersonAlbumOutput::Deleted);
             },
+            PersonAlbumInput::Bulk(action, visuals) => {
+                let _ = sender.output(PersonAlbumOutput::Bulk(action, visuals));
+            },
+            PersonAlbumInput::Ignore => {},
         }
     }
 }
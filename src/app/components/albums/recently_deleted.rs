@@ -0,0 +1,408 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use fotema_core::photo::xmp;
+use fotema_core::trash;
+use fotema_core::trash::{TrashId, TrashedItem};
+
+use relm4::adw;
+use relm4::adw::prelude::*;
+use relm4::gtk;
+use relm4::*;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::app::components::system_trash;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::fl;
+
+use tracing::{debug, error, info};
+
+const EDGE_LENGTH: i32 = 112;
+
+#[derive(Debug)]
+pub enum RecentlyDeletedInput {
+    Activate,
+
+    // Reload trashed items from database
+    Refresh,
+
+    // An item has been checked or unchecked.
+    SelectionChanged,
+
+    // Restore checked items to their original folders.
+    RestoreSelected,
+
+    // Ask before permanently deleting checked items.
+    DeleteSelected,
+
+    // Ask before permanently deleting every item.
+    EmptyTrash,
+
+    // Permanently delete items the user has confirmed deleting.
+    Delete(Vec<TrashId>),
+}
+
+#[derive(Debug)]
+pub enum RecentlyDeletedOutput {
+    // Items have been restored to the library or permanently deleted.
+    Changed,
+}
+
+pub struct RecentlyDeleted {
+    repo: trash::Repository,
+    active_view: ActiveView,
+    items_box: gtk::FlowBox,
+    scrolled: gtk::ScrolledWindow,
+    status: adw::StatusPage,
+    restore_button: gtk::Button,
+    delete_button: gtk::Button,
+    empty_button: gtk::Button,
+
+    // Items currently shown and the check button for selecting each one.
+    items: Vec<(TrashedItem, gtk::CheckButton)>,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for RecentlyDeleted {
+    type Init = (trash::Repository, ActiveView);
+    type Input = RecentlyDeletedInput;
+    type Output = RecentlyDeletedOutput;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            #[local_ref]
+            scrolled -> gtk::ScrolledWindow {
+                set_vexpand: true,
+
+                #[local_ref]
+                items_box -> gtk::FlowBox {
+                    set_valign: gtk::Align::Start,
+                    set_selection_mode: gtk::SelectionMode::None,
+                    set_homogeneous: true,
+                    set_column_spacing: 12,
+                    set_row_spacing: 12,
+                    set_margin_all: 12,
+                },
+            },
+
+            #[local_ref]
+            status -> adw::StatusPage {
+                set_valign: gtk::Align::Start,
+                set_vexpand: true,
+                set_visible: false,
+                set_icon_name: Some("user-trash-symbolic"),
+                set_title: &fl!("recently-deleted-status-none", "title"),
+                set_description: Some(&fl!("recently-deleted-status-none", "description")),
+            },
+
+            gtk::ActionBar {
+                #[local_ref]
+                pack_start = &restore_button -> gtk::Button {
+                    set_label: &fl!("recently-deleted-restore"),
+                    set_tooltip_text: Some(&fl!("recently-deleted-restore", "tooltip")),
+                    connect_clicked => RecentlyDeletedInput::RestoreSelected,
+                },
+
+                #[local_ref]
+                pack_end = &empty_button -> gtk::Button {
+                    set_label: &fl!("recently-deleted-empty"),
+                    set_tooltip_text: Some(&fl!("recently-deleted-empty", "tooltip")),
+                    add_css_class: "destructive-action",
+                    connect_clicked => RecentlyDeletedInput::EmptyTrash,
+                },
+
+                #[local_ref]
+                pack_end = &delete_button -> gtk::Button {
+                    set_label: &fl!("recently-deleted-delete"),
+                    set_tooltip_text: Some(&fl!("recently-deleted-delete", "tooltip")),
+                    connect_clicked => RecentlyDeletedInput::DeleteSelected,
+                },
+            },
+        },
+    }
+
+    fn init(
+        (repo, active_view): Self::Init,
+        _root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+
+        let items_box = gtk::FlowBox::new();
+        let scrolled = gtk::ScrolledWindow::new();
+        let status = adw::StatusPage::new();
+        let restore_button = gtk::Button::new();
+        let delete_button = gtk::Button::new();
+        let empty_button = gtk::Button::new();
+
+        let mut model = RecentlyDeleted {
+            repo,
+            active_view,
+            items_box: items_box.clone(),
+            scrolled: scrolled.clone(),
+            status: status.clone(),
+            restore_button: restore_button.clone(),
+            delete_button: delete_button.clone(),
+            empty_button: empty_button.clone(),
+            items: Vec::new(),
+        };
+
+        // Items might have been restored or deleted by a file manager while Fotema wasn't
+        // running, which must be reconciled before the library is first loaded.
+        model.refresh(&sender);
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            RecentlyDeletedInput::Activate => {
+                info!("Activating recently deleted view");
+                *self.active_view.write() = ViewName::RecentlyDeleted;
+                self.refresh(&sender);
+            },
+            RecentlyDeletedInput::Refresh => {
+                // Listing the system trash can be slow, so only do it when visible.
+                if *self.active_view.read() == ViewName::RecentlyDeleted {
+                    self.refresh(&sender);
+                }
+            },
+            RecentlyDeletedInput::SelectionChanged => {
+                self.update_status();
+            },
+            RecentlyDeletedInput::RestoreSelected => {
+                let mut count = 0;
+
+                for item in self.selected() {
+                    info!("Restoring {} from trash", item.trash_id);
+                    system_trash::restore_from_trash(with_sidecars(&item.original_paths));
+
+                    // Leave item in the trash if its files could not be put back, such as
+                    // if the original folder has been deleted.
+                    if !item.original_paths.iter().any(|path| path.exists()) {
+                        error!("Failed restoring files of {} from trash", item.trash_id);
+                        continue;
+                    }
+
+                    if let Err(e) = self.repo.restore(item.trash_id) {
+                        error!("Failed restoring {} from trash: {}", item.trash_id, e);
+                    } else {
+                        count += 1;
+                    }
+                }
+
+                self.refresh(&sender);
+
+                if count > 0 {
+                    let _ = sender.output(RecentlyDeletedOutput::Changed);
+                }
+            },
+            RecentlyDeletedInput::DeleteSelected => {
+                let trash_ids: Vec<TrashId> = self.selected().iter().map(|x| x.trash_id).collect();
+                self.confirm_delete(trash_ids, &sender);
+            },
+            RecentlyDeletedInput::EmptyTrash => {
+                let trash_ids: Vec<TrashId> = self.items.iter().map(|(x, _)| x.trash_id).collect();
+                self.confirm_delete(trash_ids, &sender);
+            },
+            RecentlyDeletedInput::Delete(trash_ids) => {
+                let trash_ids: HashSet<TrashId> = trash_ids.into_iter().collect();
+
+                let items: Vec<TrashedItem> = self.items
+                    .iter()
+                    .map(|(item, _)| item)
+                    .filter(|item| trash_ids.contains(&item.trash_id))
+                    .cloned()
+                    .collect();
+
+                for item in items {
+                    info!("Permanently deleting {}", item.trash_id);
+                    system_trash::delete_from_trash(with_sidecars(&item.original_paths));
+                    self.delete_item(&item);
+                }
+
+                self.refresh(&sender);
+                let _ = sender.output(RecentlyDeletedOutput::Changed);
+            },
+        }
+    }
+}
+
+impl RecentlyDeleted {
+    fn refresh(&mut self, sender: &ComponentSender<Self>) {
+        self.items.clear();
+        while let Some(child) = self.items_box.first_child() {
+            self.items_box.remove(&child);
+        }
+
+        let items = self.repo.all().unwrap_or_else(|e| {
+            error!("Failed loading trashed items: {}", e);
+            Vec::new()
+        });
+
+        let in_trash = system_trash::trashed_paths();
+        let mut is_changed = false;
+
+        for item in items {
+            if item.original_paths.iter().all(|path| path.exists()) {
+                // Restored by another application, such as a file manager.
+                info!("Files of {} have been restored outside of Fotema", item.trash_id);
+                if let Err(e) = self.repo.restore(item.trash_id) {
+                    error!("Failed restoring {} from trash: {}", item.trash_id, e);
+                }
+                is_changed = true;
+            } else if is_purged(&item, &in_trash) {
+                // Trash has been emptied by another application.
+                info!("Files of {} have been deleted outside of Fotema", item.trash_id);
+                self.delete_item(&item);
+                is_changed = true;
+            } else {
+                let check = self.add_item(&item, sender);
+                self.items.push((item, check));
+            }
+        }
+
+        info!("Found {} items in trash", self.items.len());
+
+        self.update_status();
+
+        if is_changed {
+            let _ = sender.output(RecentlyDeletedOutput::Changed);
+        }
+    }
+
+    fn add_item(&self, item: &TrashedItem, sender: &ComponentSender<Self>) -> gtk::CheckButton {
+        let thumbnail = gtk::Picture::builder()
+            .can_shrink(true)
+            .width_request(EDGE_LENGTH)
+            .height_request(EDGE_LENGTH)
+            .content_fit(gtk::ContentFit::Cover)
+            .build();
+
+        if let Some(ref thumbnail_path) = item.thumbnail_path {
+            thumbnail.set_filename(Some(thumbnail_path));
+        }
+
+        let original_path = item.original_paths.first().cloned().unwrap_or_default();
+
+        let file_name = original_path.file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let check = gtk::CheckButton::builder()
+            .label(file_name)
+            .tooltip_text(original_path.to_string_lossy())
+            .build();
+
+        {
+            let sender = sender.clone();
+            check.connect_toggled(move |_| sender.input(RecentlyDeletedInput::SelectionChanged));
+        }
+
+        let child = gtk::Box::new(gtk::Orientation::Vertical, 6);
+        child.append(&gtk::Frame::builder().child(&thumbnail).build());
+        child.append(&check);
+
+        self.items_box.insert(&child, -1);
+
+        check
+    }
+
+    /// Checked items.
+    fn selected(&self) -> Vec<TrashedItem> {
+        self.items
+            .iter()
+            .filter(|(_, check)| check.is_active())
+            .map(|(item, _)| item.clone())
+            .collect()
+    }
+
+    /// Removes item from database and deletes files derived from it.
+    /// The original files must already be gone from the system trash.
+    fn delete_item(&mut self, item: &TrashedItem) {
+        let paths = match self.repo.delete(item.trash_id) {
+            Ok(paths) => paths,
+            Err(e) => {
+                error!("Failed deleting {}: {}", item.trash_id, e);
+                return;
+            },
+        };
+
+        for path in paths {
+            debug!("Deleting {:?}", path);
+            if let Err(e) = std::fs::remove_file(&path) {
+                error!("Failed deleting {:?} with {}", path, e);
+            }
+        }
+    }
+
+    fn confirm_delete(&self, trash_ids: Vec<TrashId>, sender: &ComponentSender<Self>) {
+        if trash_ids.is_empty() {
+            return;
+        }
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(fl!("recently-deleted-delete-heading", count = trash_ids.len()))
+            .body(fl!("recently-deleted-delete-dialog", "body"))
+            .close_response("cancel")
+            .default_response("delete")
+            .build();
+
+        dialog.add_response("cancel", &fl!("recently-deleted-delete-dialog", "cancel-button"));
+        dialog.set_default_response(Some("cancel"));
+        dialog.set_close_response("cancel");
+
+        dialog.add_response("delete", &fl!("recently-deleted-delete-dialog", "delete-button"));
+        dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+
+        let sender = sender.clone();
+        dialog.connect_response(None, move |_, response| {
+            if response == "delete" {
+                sender.input(RecentlyDeletedInput::Delete(trash_ids.clone()));
+            }
+        });
+
+        if let Some(root) = self.items_box.root() {
+            dialog.present(Some(&root));
+        } else {
+            error!("Couldn't get root widget!");
+        }
+    }
+
+    fn update_status(&self) {
+        let is_empty = self.items.is_empty();
+        self.status.set_visible(is_empty);
+        self.scrolled.set_visible(!is_empty);
+
+        let is_selected = self.items.iter().any(|(_, check)| check.is_active());
+        self.restore_button.set_sensitive(is_selected);
+        self.delete_button.set_sensitive(is_selected);
+        self.empty_button.set_sensitive(!is_empty);
+    }
+}
+
+/// Original files of an item along with any XMP sidecars trashed with them.
+fn with_sidecars(paths: &[PathBuf]) -> Vec<PathBuf> {
+    paths
+        .iter()
+        .flat_map(|path| std::iter::once(path.clone()).chain(xmp::sidecar_paths(path)))
+        .collect()
+}
+
+/// Whether the original files of an item are gone from both the library and the system trash.
+/// Files are only considered gone if their folder exists, otherwise they might be on an
+/// unmounted drive.
+fn is_purged(item: &TrashedItem, in_trash: &HashSet<PathBuf>) -> bool {
+    item.original_paths.iter().all(|path| {
+        !path.exists()
+            && !in_trash.contains(path)
+            && path.parent().is_some_and(|folder| folder.is_dir())
+    })
+}
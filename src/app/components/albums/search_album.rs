@@ -0,0 +1,250 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+use fotema_core::search;
+use fotema_core::Visual;
+use fotema_core::VisualId;
+use relm4::gtk;
+use relm4::gtk::prelude::*;
+use relm4::*;
+use relm4::adw;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::app::adaptive;
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::app::components::albums:: {
+    album::{Album, AlbumInput, AlbumOutput},
+    album_filter::AlbumFilter,
+    album_sort::AlbumSort,
+};
+use crate::app::components::bulk_actions::BulkAction;
+use crate::fl;
+
+use tracing::{error, info};
+
+#[derive(Debug)]
+pub enum SearchAlbumInput {
+    /// Album is visible
+    Activate,
+
+    // State has been updated
+    Refresh,
+
+    /// Search text has changed
+    Search(String),
+
+    /// People have been renamed or deleted
+    PeopleChanged,
+
+    /// Adapt to layout
+    Adapt(adaptive::Layout),
+
+    /// Picture selected in underlying album
+    Selected(VisualId),
+
+    Sort(AlbumSort),
+
+    /// User has chosen an action for the selected items.
+    Bulk(BulkAction, Vec<Arc<Visual>>),
+
+    /// Ignore event
+    Ignore,
+}
+
+#[derive(Debug)]
+pub enum SearchAlbumOutput {
+    /// User has selected photo or video in grid view
+    Selected(VisualId, AlbumFilter),
+
+    /// User has chosen an action for the selected items.
+    Bulk(BulkAction, Vec<Arc<Visual>>),
+}
+
+pub struct SearchAlbum {
+    repo: search::Repository,
+    album: Controller<Album>,
+    active_view: ActiveView,
+    search_entry: gtk::SearchEntry,
+    status: adw::StatusPage,
+
+    // Text of current search
+    text: String,
+
+    // Filter for the results of the current search
+    filter: AlbumFilter,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for SearchAlbum {
+    type Init = (SharedState, search::Repository, ActiveView);
+    type Input = SearchAlbumInput;
+    type Output = SearchAlbumOutput;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            adw::Clamp {
+                set_maximum_size: 600,
+                set_margin_all: 12,
+
+                #[local_ref]
+                search_entry -> gtk::SearchEntry {
+                    set_placeholder_text: Some(&fl!("search-page", "placeholder")),
+                    set_tooltip_text: Some(&fl!("search-page", "tooltip")),
+                    connect_search_changed[sender] => move |entry| {
+                        sender.input(SearchAlbumInput::Search(entry.text().into()));
+                    },
+                },
+            },
+
+            #[local_ref]
+            status -> adw::StatusPage {
+                set_valign: gtk::Align::Start,
+                set_vexpand: true,
+                set_icon_name: Some("system-search-symbolic"),
+            },
+
+            model.album.widget(),
+        }
+    }
+
+    fn init(
+        (state, repo, active_view): Self::Init,
+        _root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let album = Album::builder()
+            .launch((state, active_view.clone(), ViewName::Search, AlbumFilter::None))
+            .forward(sender.input_sender(), |msg| match msg {
+                AlbumOutput::Selected(id, _) => SearchAlbumInput::Selected(id),
+                AlbumOutput::Bulk(action, visuals) => SearchAlbumInput::Bulk(action, visuals),
+                AlbumOutput::ScrollOffset(_) => SearchAlbumInput::Ignore,
+                AlbumOutput::Moved(_, _) => SearchAlbumInput::Ignore,
+            });
+
+        let search_entry = gtk::SearchEntry::new();
+        let status = adw::StatusPage::new();
+
+        let model = SearchAlbum {
+            repo,
+            album,
+            active_view,
+            search_entry: search_entry.clone(),
+            status: status.clone(),
+            text: String::new(),
+            filter: AlbumFilter::None,
+        };
+
+        let widgets = view_output!();
+
+        model.update_status(None);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            SearchAlbumInput::Activate => {
+                *self.active_view.write() = ViewName::Search;
+                self.album.sender().emit(AlbumInput::Activate);
+                self.search_entry.grab_focus();
+            }
+            SearchAlbumInput::Refresh => {
+                // Library has been reloaded, so results might have changed.
+                self.search();
+                self.album.sender().emit(AlbumInput::Refresh);
+            }
+            SearchAlbumInput::Search(text) => {
+                if self.text != text {
+                    self.text = text;
+                    self.search();
+                    self.album.sender().emit(AlbumInput::ScrollToTop);
+                }
+            }
+            SearchAlbumInput::PeopleChanged => {
+                if let Err(e) = self.repo.reindex_person_names() {
+                    error!("Failed updating person names in search index: {}", e);
+                }
+                self.search();
+            }
+            SearchAlbumInput::Adapt(layout) => {
+                self.album.sender().emit(AlbumInput::Adapt(layout));
+            }
+            SearchAlbumInput::Sort(sort) => {
+                self.album.sender().emit(AlbumInput::Sort(sort));
+            }
+            SearchAlbumInput::Selected(visual_id) => {
+                let _ = sender.output(SearchAlbumOutput::Selected(visual_id, self.filter.clone()));
+            }
+            SearchAlbumInput::Bulk(action, visuals) => {
+                let _ = sender.output(SearchAlbumOutput::Bulk(action, visuals));
+            },
+            SearchAlbumInput::Ignore => {}
+        }
+    }
+}
+
+impl SearchAlbum {
+    fn search(&mut self) {
+        let query = match search::Query::parse(&self.text) {
+            Ok(query) => query,
+            Err(e) => {
+                // Keep showing previous results while the user corrects the query.
+                info!("Invalid search query: {}", e);
+                self.search_entry.add_css_class("error");
+                return;
+            }
+        };
+
+        self.search_entry.remove_css_class("error");
+
+        if query.is_empty() {
+            self.filter = AlbumFilter::None;
+            self.album.sender().emit(AlbumInput::Filter(AlbumFilter::None));
+            self.update_status(None);
+            return;
+        }
+
+        let visual_ids = self.repo.search(&query).unwrap_or_else(|e| {
+            error!("Failed searching: {}", e);
+            Vec::new()
+        });
+
+        info!("Search found {} items", visual_ids.len());
+
+        let count = visual_ids.len();
+        let visual_ids: HashSet<VisualId> = visual_ids.into_iter().collect();
+        self.filter = AlbumFilter::Search(Arc::new(visual_ids));
+        self.album.sender().emit(AlbumInput::Filter(self.filter.clone()));
+        self.update_status(Some(count));
+    }
+
+    /// Show status page if there is no query or no results.
+    fn update_status(&self, count: Option<usize>) {
+        match count {
+            None => {
+                self.status.set_title(&fl!("search-page-status-empty", "title"));
+                self.status.set_description(Some(&fl!("search-page-status-empty", "description")));
+                self.status.set_visible(true);
+                self.album.widget().set_visible(false);
+            }
+            Some(0) => {
+                self.status.set_title(&fl!("search-page-status-no-results", "title"));
+                self.status.set_description(Some(&fl!("search-page-status-no-results", "description")));
+                self.status.set_visible(true);
+                self.album.widget().set_visible(false);
+            }
+            Some(_) => {
+                self.status.set_visible(false);
+                self.album.widget().set_visible(true);
+            }
+        }
+    }
+}
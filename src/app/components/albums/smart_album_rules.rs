@@ -0,0 +1,234 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use fotema_core::search::query::{Kind, Query, Term};
+use relm4::adw;
+use relm4::adw::prelude::*;
+use relm4::gtk;
+
+use crate::fl;
+
+use tracing::error;
+
+/// Editor for the name and rules of a smart album.
+///
+/// Rules are stored as a search query, so each row adds a search term and any
+/// terms without a row are kept as text in the "other" row.
+#[derive(Clone)]
+pub struct SmartAlbumRules {
+    group: adw::PreferencesGroup,
+    name: adw::EntryRow,
+    after: adw::EntryRow,
+    before: adw::EntryRow,
+    folder: adw::EntryRow,
+    person: adw::EntryRow,
+    camera: adw::EntryRow,
+    has_location: adw::SwitchRow,
+    videos_only: adw::SwitchRow,
+    selfies_only: adw::SwitchRow,
+    min_duration: adw::SpinRow,
+    other: adw::EntryRow,
+}
+
+impl SmartAlbumRules {
+    pub fn new(name: &str, query: &Query) -> Self {
+        let entry = |title: String| {
+            adw::EntryRow::builder()
+                .title(title)
+                .build()
+        };
+
+        let switch = |title: String| {
+            adw::SwitchRow::builder()
+                .title(title)
+                .build()
+        };
+
+        let rules = SmartAlbumRules {
+            group: adw::PreferencesGroup::new(),
+            name: entry(fl!("smart-album-rules", "name")),
+            after: entry(fl!("smart-album-rules", "after")),
+            before: entry(fl!("smart-album-rules", "before")),
+            folder: entry(fl!("smart-album-rules", "folder")),
+            person: entry(fl!("smart-album-rules", "person")),
+            camera: entry(fl!("smart-album-rules", "camera")),
+            has_location: switch(fl!("smart-album-rules", "has-location")),
+            videos_only: switch(fl!("smart-album-rules", "videos-only")),
+            selfies_only: switch(fl!("smart-album-rules", "selfies-only")),
+            min_duration: adw::SpinRow::builder()
+                .title(fl!("smart-album-rules", "min-duration"))
+                .subtitle(fl!("smart-album-rules", "min-duration-subtitle"))
+                .adjustment(&gtk::Adjustment::new(0.0, 0.0, 86400.0, 1.0, 10.0, 0.0))
+                .build(),
+            other: entry(fl!("smart-album-rules", "other")),
+        };
+
+        rules.name.set_text(name);
+
+        let mut other = Query::default();
+
+        for term in query.terms.iter() {
+            match term {
+                Term::After(date) if rules.after.text().is_empty() => {
+                    rules.after.set_text(&date.format("%Y-%m-%d").to_string());
+                }
+                Term::Before(date) if rules.before.text().is_empty() => {
+                    rules.before.set_text(&date.format("%Y-%m-%d").to_string());
+                }
+                Term::Folder(folder) if rules.folder.text().is_empty() => {
+                    rules.folder.set_text(folder);
+                }
+                Term::Person(person) if rules.person.text().is_empty() => {
+                    rules.person.set_text(person);
+                }
+                Term::Camera(camera) if rules.camera.text().is_empty() => {
+                    rules.camera.set_text(camera);
+                }
+                Term::HasLocation => rules.has_location.set_active(true),
+                Term::Kind(Kind::Video) => rules.videos_only.set_active(true),
+                Term::Kind(Kind::Selfie) => rules.selfies_only.set_active(true),
+                Term::LongerThan(seconds) if rules.min_duration.value() == 0.0 => {
+                    rules.min_duration.set_value(*seconds as f64);
+                }
+                term => other.terms.push(term.clone()),
+            }
+        }
+
+        rules.other.set_text(&other.to_string());
+
+        rules.group.add(&rules.name);
+        rules.group.add(&rules.after);
+        rules.group.add(&rules.before);
+        rules.group.add(&rules.folder);
+        rules.group.add(&rules.person);
+        rules.group.add(&rules.camera);
+        rules.group.add(&rules.has_location);
+        rules.group.add(&rules.videos_only);
+        rules.group.add(&rules.selfies_only);
+        rules.group.add(&rules.min_duration);
+        rules.group.add(&rules.other);
+
+        rules
+    }
+
+    pub fn name(&self) -> String {
+        self.name.text().trim().to_string()
+    }
+
+    /// Query for rules. An error if any rule is invalid, such as a malformed date.
+    pub fn query(&self) -> anyhow::Result<Query> {
+        let mut terms: Vec<String> = Vec::new();
+
+        let after = self.after.text();
+        if !after.trim().is_empty() {
+            terms.push(format!("after:{}", after.trim()));
+        }
+
+        let before = self.before.text();
+        if !before.trim().is_empty() {
+            terms.push(format!("before:{}", before.trim()));
+        }
+
+        let folder = self.folder.text();
+        if !folder.trim().is_empty() {
+            terms.push(Term::Folder(folder.trim().into()).to_string());
+        }
+
+        let person = self.person.text();
+        if !person.trim().is_empty() {
+            terms.push(Term::Person(person.trim().into()).to_string());
+        }
+
+        let camera = self.camera.text();
+        if !camera.trim().is_empty() {
+            terms.push(Term::Camera(camera.trim().into()).to_string());
+        }
+
+        if self.has_location.is_active() {
+            terms.push(Term::HasLocation.to_string());
+        }
+
+        if self.videos_only.is_active() {
+            terms.push(Term::Kind(Kind::Video).to_string());
+        }
+
+        if self.selfies_only.is_active() {
+            terms.push(Term::Kind(Kind::Selfie).to_string());
+        }
+
+        let min_duration = self.min_duration.value() as u32;
+        if min_duration > 0 {
+            terms.push(Term::LongerThan(min_duration).to_string());
+        }
+
+        terms.push(self.other.text().to_string());
+
+        Query::parse(&terms.join(" "))
+    }
+
+    /// A smart album must have a name and at least one valid rule.
+    pub fn is_valid(&self) -> bool {
+        !self.name().is_empty() && self.query().is_ok_and(|q| !q.is_empty())
+    }
+
+    /// Present a dialog for editing rules. The save callback is only called with valid rules.
+    pub fn present<F>(self, parent: &impl IsA<gtk::Widget>, heading: &str, on_save: F)
+    where
+        F: Fn(String, Query) + 'static,
+    {
+        let dialog = adw::AlertDialog::builder()
+            .heading(heading)
+            .close_response("cancel")
+            .default_response("save")
+            .extra_child(&self.group)
+            .build();
+
+        dialog.add_response("cancel", &fl!("smart-album-rules", "cancel-button"));
+        dialog.add_response("save", &fl!("smart-album-rules", "save-button"));
+        dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+        dialog.set_response_enabled("save", self.is_valid());
+
+        // Only allow saving valid rules.
+        let validate = {
+            let rules = self.clone();
+            let dialog = dialog.clone();
+            move || dialog.set_response_enabled("save", rules.is_valid())
+        };
+
+        for row in [&self.name, &self.after, &self.before, &self.folder, &self.person, &self.camera, &self.other] {
+            let validate = validate.clone();
+            row.connect_changed(move |_| validate());
+        }
+
+        for row in [&self.has_location, &self.videos_only, &self.selfies_only] {
+            let validate = validate.clone();
+            row.connect_active_notify(move |_| validate());
+        }
+
+        {
+            let validate = validate.clone();
+            self.min_duration.connect_value_notify(move |_| validate());
+        }
+
+        {
+            let rules = self.clone();
+            dialog.connect_response(None, move |_, response| {
+                if response != "save" {
+                    return;
+                }
+                match rules.query() {
+                    Ok(query) => on_save(rules.name(), query),
+                    Err(e) => error!("Invalid smart album rules: {}", e),
+                }
+            });
+        }
+
+        if let Some(root) = gtk::Widget::root(parent.as_ref()) {
+            dialog.present(Some(&root));
+            self.name.grab_focus();
+        } else {
+            error!("Couldn't get root widget!");
+        }
+    }
+}
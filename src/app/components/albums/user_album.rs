@@ -0,0 +1,472 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+use fotema_core::album;
+use fotema_core::search;
+use fotema_core::Visual;
+use fotema_core::VisualId;
+use relm4::gtk;
+use relm4::gtk::gio;
+use relm4::gtk::prelude::*;
+use relm4::*;
+use relm4::adw;
+use relm4::adw::prelude::*;
+use relm4::actions::{RelmAction, RelmActionGroup};
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::app::adaptive;
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::app::components::albums:: {
+    album::{Album, AlbumInput, AlbumOutput},
+    album_filter::AlbumFilter,
+    album_sort::AlbumSort,
+    smart_album_rules::SmartAlbumRules,
+};
+use crate::app::components::bulk_actions::BulkAction;
+use crate::fl;
+
+use tracing::{error, info};
+
+relm4::new_action_group!(UserAlbumActionGroup, "user_album");
+
+// Rename an album
+relm4::new_stateless_action!(RenameAction, UserAlbumActionGroup, "rename");
+
+// Delete an album
+relm4::new_stateless_action!(DeleteAction, UserAlbumActionGroup, "delete");
+
+// Edit rules of a smart album
+relm4::new_stateless_action!(EditRulesAction, UserAlbumActionGroup, "edit_rules");
+
+#[derive(Debug)]
+pub enum UserAlbumInput {
+    /// Album is visible
+    Activate,
+
+    // State has been updated, or items have been added to or removed from album.
+    Refresh,
+
+    /// View a user album
+    View(album::Album),
+
+    /// Adapt to layout
+    Adapt(adaptive::Layout),
+
+    /// Picture selected in underlying album
+    Selected(VisualId),
+
+    /// First item dragged onto second item in underlying album
+    Moved(VisualId, VisualId),
+
+    /// Start rename album flow
+    RenameDialog,
+
+    /// Actually rename album
+    Rename(String),
+
+    /// Start delete album flow.
+    DeleteDialog,
+
+    /// Actually delete album.
+    Delete,
+
+    /// Start edit smart album rules flow.
+    EditRulesDialog,
+
+    /// Actually change name and rules of smart album.
+    SetRules(String, search::Query),
+
+    Sort(AlbumSort),
+
+    /// User has chosen an action for the selected items.
+    Bulk(BulkAction, Vec<Arc<Visual>>),
+
+    /// Ignore event
+    Ignore,
+}
+
+#[derive(Debug)]
+pub enum UserAlbumOutput {
+    /// User has selected photo or video in grid view
+    Selected(VisualId, AlbumFilter),
+
+    /// Album renamed, reordered, or smart album rules changed.
+    Changed,
+
+    /// Album deleted.
+    Deleted,
+
+    /// User has chosen an action for the selected items.
+    Bulk(BulkAction, Vec<Arc<Visual>>),
+}
+
+pub struct UserAlbum {
+    repo: album::Repository,
+    user_album: Option<album::Album>,
+    album: Controller<Album>,
+    title: gtk::Label,
+    active_view: ActiveView,
+
+    // Filter for items in album, in album order
+    filter: AlbumFilter,
+
+    // Only smart albums have rules to edit
+    edit_rules_action: gio::SimpleAction,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for UserAlbum {
+    type Init = (SharedState, album::Repository, ActiveView);
+    type Input = UserAlbumInput;
+    type Output = UserAlbumOutput;
+
+    menu! {
+        primary_menu: {
+            section! {
+                &fl!("user-album-menu-edit-rules") => EditRulesAction,
+                &fl!("user-album-menu-rename") => RenameAction,
+                &fl!("user-album-menu-delete") => DeleteAction,
+            }
+        }
+    }
+
+    view! {
+        adw::ToolbarView {
+            add_top_bar = &adw::HeaderBar {
+                #[wrap(Some)]
+                #[local_ref]
+                set_title_widget = &title -> gtk::Label {
+                    add_css_class: "title",
+                },
+
+                pack_end = &gtk::MenuButton {
+                    set_icon_name: "open-menu-symbolic",
+                    set_menu_model: Some(&primary_menu),
+                },
+            },
+
+            #[wrap(Some)]
+            set_content = &gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_vexpand: true,
+
+                model.album.widget(),
+            }
+        }
+    }
+
+    fn init(
+        (state, repo, active_view): Self::Init,
+        root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let album = Album::builder()
+            .launch((state, active_view.clone(), ViewName::UserAlbum, AlbumFilter::None))
+            .forward(sender.input_sender(), |msg| match msg {
+                AlbumOutput::Selected(id, _) => UserAlbumInput::Selected(id),
+                AlbumOutput::Bulk(action, visuals) => UserAlbumInput::Bulk(action, visuals),
+                AlbumOutput::Moved(id, target) => UserAlbumInput::Moved(id, target),
+                AlbumOutput::ScrollOffset(_) => UserAlbumInput::Ignore,
+            });
+
+        let title = gtk::Label::builder()
+            .build();
+
+        let edit_rules_action = {
+            let sender = sender.clone();
+            RelmAction::<EditRulesAction>::new_stateless(move |_| {
+                sender.input(UserAlbumInput::EditRulesDialog);
+            })
+        };
+
+        let model = UserAlbum {
+            repo,
+            user_album: None,
+            album,
+            title: title.clone(),
+            active_view,
+            filter: AlbumFilter::None,
+            edit_rules_action: edit_rules_action.gio_action().clone(),
+        };
+
+        let widgets = view_output!();
+
+        let mut actions = RelmActionGroup::<UserAlbumActionGroup>::new();
+
+        let rename_action = {
+            let sender = sender.clone();
+            RelmAction::<RenameAction>::new_stateless(move |_| {
+                sender.input(UserAlbumInput::RenameDialog);
+            })
+        };
+
+        let delete_action = {
+            let sender = sender.clone();
+            RelmAction::<DeleteAction>::new_stateless(move |_| {
+                sender.input(UserAlbumInput::DeleteDialog);
+            })
+        };
+
+        actions.add_action(edit_rules_action);
+        actions.add_action(rename_action);
+        actions.add_action(delete_action);
+        actions.register_for_widget(&root);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            UserAlbumInput::Activate => {
+                *self.active_view.write() = ViewName::UserAlbum;
+                self.album.sender().emit(AlbumInput::Activate);
+            }
+            UserAlbumInput::Refresh => {
+                self.update_filter();
+                self.album.sender().emit(AlbumInput::Refresh);
+            }
+            UserAlbumInput::View(user_album) => {
+                info!("Viewing user album: {}", user_album.album_id);
+                self.title.set_label(&user_album.name);
+                self.edit_rules_action.set_enabled(user_album.is_smart());
+                self.user_album = Some(user_album);
+                self.update_filter();
+
+                // Always refresh, as activating only refreshes an empty album and the
+                // previously viewed album would still be in the grid in its own order.
+                *self.active_view.write() = ViewName::UserAlbum;
+                self.album.sender().emit(AlbumInput::Refresh);
+                self.album.sender().emit(AlbumInput::ScrollToTop);
+            }
+            UserAlbumInput::Selected(visual_id) => {
+                let _ = sender.output(UserAlbumOutput::Selected(visual_id, self.filter.clone()));
+            },
+            UserAlbumInput::Moved(visual_id, target) => {
+                let Some(ref user_album) = self.user_album else {
+                    return;
+                };
+
+                info!("Moving {} to {} in album {}", visual_id, target, user_album.album_id);
+                if let Err(e) = self.repo.move_item(user_album.album_id, &visual_id, &target) {
+                    error!("Failed to reorder album: {}", e);
+                    return;
+                }
+
+                sender.input(UserAlbumInput::Refresh);
+                let _ = sender.output(UserAlbumOutput::Changed);
+            },
+            UserAlbumInput::Adapt(layout) => {
+                self.album.sender().emit(AlbumInput::Adapt(layout));
+            },
+            UserAlbumInput::RenameDialog => {
+                let Some(ref user_album) = self.user_album else {
+                    info!("Asked to rename album, but no album to rename");
+                    return;
+                };
+                info!("Renaming album {}", user_album.album_id);
+
+                let album_name = gtk::Entry::builder()
+                    .placeholder_text(fl!("user-album-name-dialog", "placeholder"))
+                    .text(&user_album.name)
+                    .build();
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("user-album-name-dialog", "rename-heading"))
+                    .close_response("cancel")
+                    .default_response("rename")
+                    .extra_child(&album_name)
+                    .build();
+
+                dialog.add_response("cancel", &fl!("user-album-name-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("rename", &fl!("user-album-name-dialog", "rename-button"));
+                dialog.set_response_appearance("rename", adw::ResponseAppearance::Suggested);
+
+                {
+                    let album_name = album_name.clone();
+                    let sender = sender.clone();
+                    dialog.connect_response(None, move |_, response| {
+                        if response == "rename" {
+                            sender.input(UserAlbumInput::Rename(album_name.text().into()));
+                        }
+                    });
+                }
+
+                {
+                    let album_name = album_name.clone();
+                    let sender = sender.clone();
+                    let dialog = dialog.clone();
+                    album_name.clone().connect_activate(move |_| {
+                        dialog.close();
+                        sender.input(UserAlbumInput::Rename(album_name.text().into()));
+                    });
+                }
+
+                if let Some(root) = gtk::Widget::root(self.title.widget_ref()) {
+                    dialog.present(Some(&root));
+                    album_name.grab_focus();
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            UserAlbumInput::Rename(name) => {
+                let Some(ref mut user_album) = self.user_album else {
+                    info!("Asked to rename album, but no album to rename");
+                    return;
+                };
+
+                let name = name.trim();
+                if name.is_empty() {
+                    return;
+                }
+
+                info!("Renaming {} to {}", user_album.name, name);
+
+                if let Err(e) = self.repo.rename(user_album.album_id, name) {
+                    error!("Failed to rename album: {}", e);
+                    return;
+                }
+                self.title.set_label(name);
+                user_album.name = name.to_string();
+                let _ = sender.output(UserAlbumOutput::Changed);
+            },
+            UserAlbumInput::DeleteDialog => {
+                let Some(ref user_album) = self.user_album else {
+                    info!("Asked to delete album, but no album to delete");
+                    return;
+                };
+                info!("Starting delete flow for album: {}", user_album.album_id);
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("user-album-delete-dialog", "heading"))
+                    .body(fl!("user-album-delete-dialog", "body"))
+                    .close_response("cancel")
+                    .default_response("delete")
+                    .build();
+
+                dialog.add_response("cancel", &fl!("user-album-delete-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("delete", &fl!("user-album-delete-dialog", "delete-button"));
+                dialog.set_response_appearance("delete", adw::ResponseAppearance::Destructive);
+
+                dialog.connect_response(None, move |_, response| {
+                    if response == "delete" {
+                       sender.input(UserAlbumInput::Delete);
+                    }
+                });
+
+                if let Some(root) = gtk::Widget::root(self.title.widget_ref()) {
+                    dialog.present(Some(&root));
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            UserAlbumInput::Delete => {
+                let Some(ref user_album) = self.user_album else {
+                    info!("Asked to delete album, but no album to delete");
+                    return;
+                };
+                info!("Deleting album: {}", user_album.album_id);
+                if let Err(e) = self.repo.delete(user_album.album_id) {
+                    error!("Failed to delete album: {}", e);
+                    return;
+                }
+                self.user_album = None;
+                self.filter = AlbumFilter::None;
+                let _ = sender.output(UserAlbumOutput::Deleted);
+            },
+            UserAlbumInput::EditRulesDialog => {
+                let Some(ref user_album) = self.user_album else {
+                    info!("Asked to edit rules, but no album to edit");
+                    return;
+                };
+
+                let Some(ref query) = user_album.query else {
+                    info!("Asked to edit rules, but album {} is not a smart album", user_album.album_id);
+                    return;
+                };
+
+                let query = search::Query::parse(query).unwrap_or_else(|e| {
+                    error!("Invalid smart album query: {}", e);
+                    search::Query::default()
+                });
+
+                let rules = SmartAlbumRules::new(&user_album.name, &query);
+                rules.present(&self.title, &fl!("user-album-menu-edit-rules"), move |name, query| {
+                    sender.input(UserAlbumInput::SetRules(name, query));
+                });
+            },
+            UserAlbumInput::SetRules(name, query) => {
+                let Some(ref mut user_album) = self.user_album else {
+                    info!("Asked to set rules, but no album to change");
+                    return;
+                };
+
+                info!("Setting rules of album {} to {}", user_album.album_id, query);
+
+                if let Err(e) = self.repo.rename(user_album.album_id, &name)
+                    .and_then(|_| self.repo.set_query(user_album.album_id, &query)) {
+                    error!("Failed to set smart album rules: {}", e);
+                    return;
+                }
+
+                self.title.set_label(&name);
+                user_album.name = name;
+                user_album.query = Some(query.to_string());
+
+                sender.input(UserAlbumInput::Refresh);
+                let _ = sender.output(UserAlbumOutput::Changed);
+            },
+            UserAlbumInput::Sort(sort) => {
+                // Ignored by album for curated albums, which are in the order chosen by the user.
+                self.album.sender().emit(AlbumInput::Sort(sort));
+            },
+            UserAlbumInput::Bulk(action, visuals) => {
+                let _ = sender.output(UserAlbumOutput::Bulk(action, visuals));
+            },
+            UserAlbumInput::Ignore => {},
+        }
+    }
+}
+
+impl UserAlbum {
+    /// Reload items of album from database so that additions, removals, and ordering are shown.
+    fn update_filter(&mut self) {
+        let Some(ref user_album) = self.user_album else {
+            return;
+        };
+
+        let visual_ids = self.repo.visual_ids(user_album.album_id).unwrap_or_else(|e| {
+            error!("Failed loading items for album: {}", e);
+            Vec::new()
+        });
+
+        info!("Album {} has {} items to view.", user_album.album_id, visual_ids.len());
+
+        if user_album.is_smart() {
+            let visual_ids: HashSet<VisualId> = visual_ids.into_iter().collect();
+            self.filter = AlbumFilter::SmartAlbum(user_album.album_id, Arc::new(visual_ids));
+            self.album.sender().emit(AlbumInput::Filter(self.filter.clone()));
+            return;
+        }
+
+        let positions: HashMap<VisualId, usize> = visual_ids
+            .into_iter()
+            .enumerate()
+            .map(|(position, visual_id)| (visual_id, position))
+            .collect();
+
+        self.filter = AlbumFilter::UserAlbum(user_album.album_id, Arc::new(positions));
+        self.album.sender().emit(AlbumInput::Filter(self.filter.clone()));
+    }
+}
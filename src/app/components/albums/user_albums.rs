@@ -0,0 +1,413 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use gtk::prelude::OrientableExt;
+
+use fotema_core::album;
+use fotema_core::search;
+use fotema_core::visual::model::PictureOrientation;
+use fotema_core::Visual;
+use strum::IntoEnumIterator;
+
+use relm4::gtk;
+use relm4::gtk::gdk;
+use relm4::gtk::gdk_pixbuf;
+use relm4::gtk::prelude::*;
+use relm4::typed_view::grid::{RelmGridItem, TypedGridView};
+use relm4::*;
+use relm4::adw;
+use relm4::adw::prelude::*;
+use relm4::binding::*;
+
+use std::path;
+use std::sync::Arc;
+
+use crate::adaptive;
+use crate::app::SharedState;
+use crate::app::ActiveView;
+use crate::app::ViewName;
+use crate::app::components::albums::smart_album_rules::SmartAlbumRules;
+use crate::fl;
+
+use tracing::{debug, error, info};
+
+const NARROW_EDGE_LENGTH: i32 = 170;
+const WIDE_EDGE_LENGTH: i32 = 200;
+
+#[derive(Debug)]
+struct PhotoGridItem {
+    album: album::Album,
+
+    // Album cover
+    cover: Option<Arc<Visual>>,
+
+    // Length of thumbnail edge to allow for resizing when layout changes.
+    edge_length: I32Binding,
+}
+
+struct Widgets {
+    picture: gtk::Picture,
+    label: gtk::Label,
+    count_label: gtk::Label,
+
+    // If the gtk::Picture has been bound to edge_length.
+    is_bound: bool,
+}
+
+#[derive(Debug)]
+pub enum UserAlbumsInput {
+    Activate,
+
+    // Reload albums from database
+    Refresh,
+
+    AlbumSelected(u32), // Index into photo grid vector
+
+    // Adapt to layout
+    Adapt(adaptive::Layout),
+
+    /// Start create album flow
+    CreateDialog,
+
+    /// Actually create album
+    Create(String),
+
+    /// Start create smart album flow
+    CreateSmartDialog,
+
+    /// Actually create smart album
+    CreateSmart(String, search::Query),
+}
+
+#[derive(Debug)]
+pub enum UserAlbumsOutput {
+    AlbumSelected(album::Album),
+}
+
+impl RelmGridItem for PhotoGridItem {
+    type Root = gtk::Box;
+    type Widgets = Widgets;
+
+    fn setup(_item: &gtk::ListItem) -> (gtk::Box, Widgets) {
+        relm4::view! {
+           my_box = gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                gtk::AspectFrame {
+                    gtk::Frame {
+                        #[name(picture)]
+                        gtk::Picture {
+                            set_can_shrink: true,
+                            set_width_request: NARROW_EDGE_LENGTH,
+                            set_height_request: NARROW_EDGE_LENGTH,
+                        }
+                    }
+                },
+
+                #[name(label)]
+                gtk::Label {
+                    add_css_class: "caption-heading",
+                    set_margin_top: 4,
+                },
+
+                #[name(count_label)]
+                gtk::Label {
+                    add_css_class: "caption",
+                    add_css_class: "dim-label",
+                    set_margin_bottom: 12,
+                },
+            }
+        }
+
+        let widgets = Widgets {
+            picture,
+            label,
+            count_label,
+            is_bound: false,
+        };
+
+        (my_box, widgets)
+    }
+
+    fn bind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        widgets.label.set_text(&self.album.name);
+        widgets.count_label.set_text(&fl!("user-albums-item-count", count = self.album.item_count));
+
+        // If we repeatedly bind, then Fotema will die with the following error:
+        // (fotema:2): GLib-GObject-CRITICAL **: 13:26:14.297: Too many GWeakRef registered
+        // GLib-GObject:ERROR:../gobject/gbinding.c:805:g_binding_constructed: assertion failed: (source != NULL)
+        // Bail out! GLib-GObject:ERROR:../gobject/gbinding.c:805:g_binding_constructed: assertion failed: (source != NULL)
+        if !widgets.is_bound {
+            widgets.picture.add_write_only_binding(&self.edge_length, "width-request");
+            widgets.picture.add_write_only_binding(&self.edge_length, "height-request");
+            widgets.is_bound = true;
+        }
+
+        let thumbnail_path = self.cover
+            .as_ref()
+            .and_then(|x| x.thumbnail_path.clone())
+            .filter(|x| x.exists());
+
+        if thumbnail_path.is_some() {
+            widgets.picture.set_filename(thumbnail_path);
+        } else {
+            let pb = gdk_pixbuf::Pixbuf::from_resource_at_scale(
+                "/app/fotema/Fotema/icons/scalable/actions/image-missing-symbolic.svg",
+                200, 200, true
+            ).unwrap();
+            let img = gdk::Texture::for_pixbuf(&pb);
+            widgets.picture.set_paintable(Some(&img));
+        }
+    }
+
+    fn unbind(&mut self, widgets: &mut Self::Widgets, _root: &mut Self::Root) {
+        widgets.picture.set_filename(None::<&path::Path>);
+        // clear orientation transformation css classes
+        for orient in PictureOrientation::iter() {
+            widgets.picture.remove_css_class(orient.as_ref());
+        }
+    }
+}
+
+pub struct UserAlbums {
+    repo: album::Repository,
+    state: SharedState,
+    active_view: ActiveView,
+    photo_grid: TypedGridView<PhotoGridItem, gtk::SingleSelection>,
+    albums: gtk::ScrolledWindow,
+    status: adw::StatusPage,
+    edge_length: I32Binding,
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for UserAlbums {
+    type Init = (SharedState, album::Repository, ActiveView);
+    type Input = UserAlbumsInput;
+    type Output = UserAlbumsOutput;
+
+    view! {
+        gtk::Box {
+            set_orientation: gtk::Orientation::Vertical,
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_halign: gtk::Align::End,
+                set_margin_all: 12,
+                set_spacing: 6,
+
+                gtk::Button {
+                    set_tooltip_text: Some(&fl!("user-albums-new-smart", "tooltip")),
+
+                    #[wrap(Some)]
+                    set_child = &adw::ButtonContent {
+                        set_icon_name: "system-search-symbolic",
+                        set_label: &fl!("user-albums-new-smart"),
+                    },
+
+                    connect_clicked => UserAlbumsInput::CreateSmartDialog,
+                },
+
+                gtk::Button {
+                    set_tooltip_text: Some(&fl!("user-albums-new", "tooltip")),
+
+                    #[wrap(Some)]
+                    set_child = &adw::ButtonContent {
+                        set_icon_name: "list-add-symbolic",
+                        set_label: &fl!("user-albums-new"),
+                    },
+
+                    connect_clicked => UserAlbumsInput::CreateDialog,
+                },
+            },
+
+            #[local_ref]
+            albums -> gtk::ScrolledWindow {
+                set_vexpand: true,
+
+                #[local_ref]
+                pictures_box -> gtk::GridView {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_single_click_activate: true,
+
+                    connect_activate[sender] => move |_, idx| {
+                        sender.input(UserAlbumsInput::AlbumSelected(idx))
+                    }
+                }
+            },
+
+            #[local_ref]
+            status -> adw::StatusPage {
+                set_valign: gtk::Align::Start,
+                set_vexpand: true,
+                set_visible: false,
+                set_icon_name: Some("folder-pictures-symbolic"),
+                set_title: &fl!("user-albums-status-empty", "title"),
+                set_description: Some(&fl!("user-albums-status-empty", "description")),
+            },
+        }
+    }
+
+    fn init(
+        (state, repo, active_view): Self::Init,
+        _root: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let photo_grid = TypedGridView::new();
+
+        let status = adw::StatusPage::new();
+
+        let albums = gtk::ScrolledWindow::builder().build();
+
+        let model = UserAlbums {
+            repo,
+            state,
+            active_view,
+            photo_grid,
+            albums: albums.clone(),
+            status: status.clone(),
+            edge_length: I32Binding::new(NARROW_EDGE_LENGTH),
+        };
+
+        let pictures_box = &model.photo_grid.view;
+
+        let widgets = view_output!();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
+        match msg {
+            UserAlbumsInput::Activate => {
+                *self.active_view.write() = ViewName::Albums;
+                self.refresh();
+            },
+            UserAlbumsInput::Refresh => {
+                // Always refresh, as albums can change while a user album is being viewed
+                // and there is no activation when navigating back to this view.
+                self.refresh();
+            },
+            UserAlbumsInput::AlbumSelected(index) => {
+                debug!("Album selected index: {}", index);
+                if let Some(item) = self.photo_grid.get_visible(index) {
+                    let item = item.borrow();
+                    debug!("Album selected item: {}", item.album.album_id);
+                    let _ = sender.output(UserAlbumsOutput::AlbumSelected(item.album.clone()));
+                }
+            },
+            UserAlbumsInput::Adapt(adaptive::Layout::Narrow) => {
+                self.edge_length.set_value(NARROW_EDGE_LENGTH);
+            },
+            UserAlbumsInput::Adapt(adaptive::Layout::Wide) => {
+                self.edge_length.set_value(WIDE_EDGE_LENGTH);
+            },
+            UserAlbumsInput::CreateDialog => {
+                let album_name = gtk::Entry::builder()
+                    .placeholder_text(fl!("user-album-name-dialog", "placeholder"))
+                    .build();
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("user-albums-new"))
+                    .close_response("cancel")
+                    .default_response("create")
+                    .extra_child(&album_name)
+                    .build();
+
+                dialog.add_response("cancel", &fl!("user-album-name-dialog", "cancel-button"));
+                dialog.set_default_response(Some("cancel"));
+                dialog.set_close_response("cancel");
+
+                dialog.add_response("create", &fl!("user-albums-new", "create-button"));
+                dialog.set_response_appearance("create", adw::ResponseAppearance::Suggested);
+
+                {
+                    let album_name = album_name.clone();
+                    let sender = sender.clone();
+                    dialog.connect_response(None, move |_, response| {
+                        if response == "create" {
+                            sender.input(UserAlbumsInput::Create(album_name.text().into()));
+                        }
+                    });
+                }
+
+                {
+                    let album_name = album_name.clone();
+                    let sender = sender.clone();
+                    let dialog = dialog.clone();
+                    album_name.clone().connect_activate(move |_| {
+                        dialog.close();
+                        sender.input(UserAlbumsInput::Create(album_name.text().into()));
+                    });
+                }
+
+                if let Some(root) = gtk::Widget::root(self.albums.widget_ref()) {
+                    dialog.present(Some(&root));
+                    album_name.grab_focus();
+                } else {
+                    error!("Couldn't get root widget!");
+                }
+            },
+            UserAlbumsInput::Create(name) => {
+                let name = name.trim();
+                if name.is_empty() {
+                    return;
+                }
+
+                info!("Creating album {}", name);
+                if let Err(e) = self.repo.create(name) {
+                    error!("Failed to create album: {}", e);
+                    return;
+                }
+                self.refresh();
+            },
+            UserAlbumsInput::CreateSmartDialog => {
+                let rules = SmartAlbumRules::new("", &search::Query::default());
+                rules.present(&self.albums, &fl!("user-albums-new-smart"), move |name, query| {
+                    sender.input(UserAlbumsInput::CreateSmart(name, query));
+                });
+            },
+            UserAlbumsInput::CreateSmart(name, query) => {
+                info!("Creating smart album {} with query {}", name, query);
+                if let Err(e) = self.repo.create_smart(&name, &query) {
+                    error!("Failed to create smart album: {}", e);
+                    return;
+                }
+                self.refresh();
+            },
+        }
+    }
+}
+
+impl UserAlbums {
+    fn refresh(&mut self) {
+        let albums = self.repo.all().unwrap_or_else(|e| {
+            error!("Failed loading albums: {}", e);
+            Vec::new()
+        });
+
+        let items: Vec<PhotoGridItem> = {
+            let data = self.state.read();
+            albums
+                .into_iter()
+                .map(|album| {
+                    let cover = album
+                        .cover
+                        .as_ref()
+                        .and_then(|visual_id| data.iter().find(|v| v.visual_id == *visual_id))
+                        .cloned();
+
+                    PhotoGridItem {
+                        album,
+                        cover,
+                        edge_length: self.edge_length.clone(),
+                    }
+                })
+                .collect()
+        };
+
+        self.status.set_visible(items.is_empty());
+        self.albums.set_visible(!items.is_empty());
+
+        self.photo_grid.clear();
+        self.photo_grid.extend_from_iter(items);
+    }
+}
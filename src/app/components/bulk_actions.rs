@@ -0,0 +1,742 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::adw::{self, prelude::*};
+use relm4::gtk::{self, gio, glib};
+use relm4::*;
+
+use fotema_core::album;
+use fotema_core::dates::{self, DateChange, DateOverride};
+use fotema_core::file_ops;
+use fotema_core::people;
+use fotema_core::trash;
+use fotema_core::FaceId;
+use fotema_core::PersonId;
+use fotema_core::Visual;
+use fotema_core::VisualId;
+
+use chrono::{FixedOffset, Local, Offset, TimeDelta};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::app::components::system_trash;
+use crate::fl;
+
+use tracing::{error, info};
+
+/// Action to apply to every item selected in an album.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkAction {
+    /// Copy original files to a folder.
+    CopyToFolder,
+
+    /// Move original files to a folder.
+    MoveToFolder,
+
+    /// Move original files to the trash.
+    Trash,
+
+    /// Add items to a user album.
+    AddToAlbum,
+
+    /// Mark the only unknown face of each picture as a person.
+    AssignPerson,
+
+    /// Correct the date and time of items, such as for a camera with a wrong clock.
+    AdjustDate,
+
+    /// Save converted copies outside of the library. Handled by the export dialog.
+    Export,
+
+    /// Open original files with another application.
+    OpenWith,
+}
+
+/// A completed bulk action that can be reversed.
+#[derive(Debug)]
+enum Undo {
+    /// Copies to delete.
+    Copied(Vec<PathBuf>),
+
+    /// Original and new paths of moved files.
+    Moved(Vec<(PathBuf, PathBuf)>),
+
+    /// Items in the trash, with original paths of their trashed files.
+    Trashed(Vec<(trash::TrashId, Vec<PathBuf>)>),
+
+    /// Items added to an album that weren't already in the album.
+    AddedToAlbum(album::AlbumId, Vec<VisualId>),
+
+    /// Faces marked as a person.
+    AssignedPerson(Vec<FaceId>),
+
+    /// Items with corrected dates, with their previous corrections.
+    AdjustedDates(Vec<(Arc<Visual>, DateOverride)>),
+}
+
+#[derive(Debug)]
+pub enum BulkActionsInput {
+    /// Start an action for some items. Might present a dialog for choosing
+    /// a folder, album, person, or application.
+    Run(BulkAction, Vec<Arc<Visual>>),
+
+    /// Folder chosen for copy or move.
+    FolderChosen(BulkAction, PathBuf),
+
+    /// Album chosen for adding items to.
+    AlbumChosen(album::AlbumId),
+
+    /// Person chosen for assigning to pictures.
+    PersonChosen(PersonId),
+
+    /// Date correction chosen for items.
+    DateChosen(DateChange),
+
+    /// Reverse the last action.
+    Undo,
+}
+
+#[derive(Debug)]
+pub enum BulkActionsOutput {
+    /// Files have been added, moved, or removed, so must be scanned.
+    FilesChanged(Vec<PathBuf>),
+
+    /// Items have been added to or removed from an album.
+    AlbumsChanged,
+
+    /// Pictures have been assigned to or unassigned from a person.
+    PeopleChanged,
+
+    /// Items have been moved to or restored from the trash.
+    TrashChanged,
+
+    /// Dates of items have been corrected or reverted.
+    DatesChanged,
+}
+
+/// Result of file operations that run in the background.
+#[derive(Debug)]
+pub enum BulkActionsCommand {
+    /// Paths of new copies.
+    Copied(Vec<PathBuf>),
+
+    /// Original and new paths of moved files.
+    Moved(Vec<(PathBuf, PathBuf)>),
+
+    /// Items moved to the trash, with original paths of their trashed files.
+    Trashed(Vec<(trash::TrashId, Vec<PathBuf>)>),
+
+    /// Paths changed by undoing a file operation.
+    Undone(Vec<PathBuf>),
+
+    /// Items have been restored from the trash.
+    Restored,
+}
+
+pub struct BulkActions {
+    parent: adw::ApplicationWindow,
+
+    toast_overlay: adw::ToastOverlay,
+
+    album_repo: album::Repository,
+
+    people_repo: people::Repository,
+
+    trash_repo: trash::Repository,
+
+    dates_repo: dates::Repository,
+
+    /// Items waiting on the user to choose a folder, album, or person.
+    pending: Vec<Arc<Visual>>,
+
+    /// Last reversible action.
+    undo: Option<Undo>,
+}
+
+impl Component for BulkActions {
+    type Init = (
+        adw::ApplicationWindow,
+        album::Repository,
+        people::Repository,
+        trash::Repository,
+        dates::Repository,
+    );
+    type Input = BulkActionsInput;
+    type Output = BulkActionsOutput;
+    type CommandOutput = BulkActionsCommand;
+    type Root = adw::ToastOverlay;
+    type Widgets = ();
+
+    fn init_root() -> Self::Root {
+        adw::ToastOverlay::new()
+    }
+
+    fn init(
+        (parent, album_repo, people_repo, trash_repo, dates_repo): Self::Init,
+        root: Self::Root,
+        _sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = BulkActions {
+            parent,
+            toast_overlay: root,
+            album_repo,
+            people_repo,
+            trash_repo,
+            dates_repo,
+            pending: Vec::new(),
+            undo: None,
+        };
+
+        ComponentParts { model, widgets: () }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
+        match msg {
+            BulkActionsInput::Run(action, visuals) => {
+                info!("{:?} for {} items", action, visuals.len());
+                self.pending = visuals;
+
+                match action {
+                    BulkAction::CopyToFolder | BulkAction::MoveToFolder => {
+                        self.choose_folder(action, &sender);
+                    },
+                    BulkAction::Trash => {
+                        let visuals: Vec<Arc<Visual>> = self.pending.drain(..).collect();
+                        let mut trash_repo = self.trash_repo.clone();
+
+                        sender.spawn_oneshot_command(move || {
+                            let trashed = visuals
+                                .iter()
+                                .filter_map(|visual| system_trash::trash_item(&mut trash_repo, visual))
+                                .collect();
+                            BulkActionsCommand::Trashed(trashed)
+                        });
+                    },
+                    BulkAction::AddToAlbum => self.choose_album(&sender),
+                    BulkAction::AssignPerson => self.choose_person(&sender),
+                    BulkAction::AdjustDate => self.choose_date(&sender),
+                    BulkAction::OpenWith => self.choose_app(),
+                    BulkAction::Export => error!("Export should be handled by the export dialog"),
+                }
+            },
+            BulkActionsInput::FolderChosen(action, folder) => {
+                let files: Vec<Vec<PathBuf>> = self.pending
+                    .drain(..)
+                    .map(|visual| system_trash::original_files(&visual))
+                    .collect();
+
+                match action {
+                    BulkAction::CopyToFolder => {
+                        sender.spawn_oneshot_command(move || BulkActionsCommand::Copied(file_ops::copy_files(&folder, files, copy_file)));
+                    },
+                    BulkAction::MoveToFolder => {
+                        sender.spawn_oneshot_command(move || BulkActionsCommand::Moved(file_ops::move_files(&folder, files, move_file)));
+                    },
+                    _ => error!("Folder chosen for {:?}, which doesn't need a folder", action),
+                }
+            },
+            BulkActionsInput::AlbumChosen(album_id) => {
+                let existing: HashSet<VisualId> = self.album_repo
+                    .visual_ids(album_id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .collect();
+
+                let added: Vec<VisualId> = self.pending
+                    .drain(..)
+                    .map(|visual| visual.visual_id.clone())
+                    .filter(|visual_id| !existing.contains(visual_id))
+                    .collect();
+
+                if let Err(e) = self.album_repo.add(album_id, &added) {
+                    error!("Failed adding items to album {}: {}", album_id, e);
+                    return;
+                }
+
+                self.toast(&fl!("bulk-actions-added-to-album", count = added.len()), &sender);
+                self.undo = Some(Undo::AddedToAlbum(album_id, added));
+                let _ = sender.output(BulkActionsOutput::AlbumsChanged);
+            },
+            BulkActionsInput::PersonChosen(person_id) => {
+                let total = self.pending.len();
+
+                let face_ids: Vec<FaceId> = self.pending
+                    .drain(..)
+                    .filter_map(|visual| visual.picture_id)
+                    .filter_map(|picture_id| {
+                        self.people_repo.assign_person(picture_id, person_id)
+                            .inspect_err(|e| error!("Failed assigning person to picture {}: {}", picture_id, e))
+                            .ok()
+                            .flatten()
+                    })
+                    .collect();
+
+                self.toast(&fl!("bulk-actions-assigned-person", count = face_ids.len(), total = total), &sender);
+                self.undo = Some(Undo::AssignedPerson(face_ids));
+                let _ = sender.output(BulkActionsOutput::PeopleChanged);
+            },
+            BulkActionsInput::DateChosen(change) => {
+                info!("Adjusting dates with {:?}", change);
+
+                let previous: Vec<(Arc<Visual>, DateOverride)> = self.pending
+                    .drain(..)
+                    .filter_map(|visual| {
+                        self.dates_repo.apply(&visual, change)
+                            .inspect_err(|e| error!("Failed adjusting date of {}: {}", visual.visual_id, e))
+                            .ok()
+                            .map(|previous| (visual, previous))
+                    })
+                    .collect();
+
+                self.toast(&fl!("bulk-actions-adjusted-dates", count = previous.len()), &sender);
+                self.undo = Some(Undo::AdjustedDates(previous));
+                let _ = sender.output(BulkActionsOutput::DatesChanged);
+            },
+            BulkActionsInput::Undo => {
+                let Some(undo) = self.undo.take() else {
+                    info!("Nothing to undo");
+                    return;
+                };
+
+                info!("Undoing {:?}", undo);
+
+                match undo {
+                    Undo::Copied(copies) => {
+                        sender.spawn_oneshot_command(move || BulkActionsCommand::Undone(delete_files(copies)));
+                    },
+                    Undo::Moved(moves) => {
+                        let moves = moves.into_iter().map(|(from, to)| (to, from)).collect();
+                        sender.spawn_oneshot_command(move || {
+                            BulkActionsCommand::Undone(
+                                file_ops::move_back(moves, move_file)
+                                    .into_iter()
+                                    .flat_map(|(from, to)| [from, to])
+                                    .collect()
+                            )
+                        });
+                    },
+                    Undo::Trashed(items) => {
+                        let mut trash_repo = self.trash_repo.clone();
+                        sender.spawn_oneshot_command(move || {
+                            for (trash_id, paths) in items {
+                                system_trash::restore_from_trash(paths);
+                                if let Err(e) = trash_repo.restore(trash_id) {
+                                    error!("Failed restoring {} from trash: {}", trash_id, e);
+                                }
+                            }
+                            BulkActionsCommand::Restored
+                        });
+                    },
+                    Undo::AddedToAlbum(album_id, visual_ids) => {
+                        if let Err(e) = self.album_repo.remove(album_id, &visual_ids) {
+                            error!("Failed removing items from album {}: {}", album_id, e);
+                        }
+                        let _ = sender.output(BulkActionsOutput::AlbumsChanged);
+                    },
+                    Undo::AssignedPerson(face_ids) => {
+                        for face_id in face_ids {
+                            if let Err(e) = self.people_repo.mark_not_person(face_id) {
+                                error!("Failed unassigning person from face {}: {}", face_id, e);
+                            }
+                        }
+                        let _ = sender.output(BulkActionsOutput::PeopleChanged);
+                    },
+                    Undo::AdjustedDates(items) => {
+                        for (visual, previous) in items {
+                            if let Err(e) = self.dates_repo.set(&visual, &previous) {
+                                error!("Failed restoring date of {}: {}", visual.visual_id, e);
+                            }
+                        }
+                        let _ = sender.output(BulkActionsOutput::DatesChanged);
+                    },
+                }
+            },
+        }
+    }
+
+    fn update_cmd(&mut self, msg: Self::CommandOutput, sender: ComponentSender<Self>, _root: &Self::Root) {
+        match msg {
+            BulkActionsCommand::Copied(copies) => {
+                self.toast(&fl!("bulk-actions-copied", count = copies.len()), &sender);
+                let _ = sender.output(BulkActionsOutput::FilesChanged(copies.clone()));
+                self.undo = Some(Undo::Copied(copies));
+            },
+            BulkActionsCommand::Moved(moves) => {
+                self.toast(&fl!("bulk-actions-moved", count = moves.len()), &sender);
+                let changed = moves.iter().flat_map(|(from, to)| [from.clone(), to.clone()]).collect();
+                let _ = sender.output(BulkActionsOutput::FilesChanged(changed));
+                self.undo = Some(Undo::Moved(moves));
+            },
+            BulkActionsCommand::Trashed(items) => {
+                self.toast(&fl!("bulk-actions-trashed", count = items.len()), &sender);
+                let _ = sender.output(BulkActionsOutput::TrashChanged);
+                self.undo = Some(Undo::Trashed(items));
+            },
+            BulkActionsCommand::Undone(paths) => {
+                let _ = sender.output(BulkActionsOutput::FilesChanged(paths));
+            },
+            BulkActionsCommand::Restored => {
+                let _ = sender.output(BulkActionsOutput::TrashChanged);
+            },
+        }
+    }
+}
+
+impl BulkActions {
+    /// Shows a message with a button for undoing the last action.
+    fn toast(&self, title: &str, sender: &ComponentSender<Self>) {
+        let toast = adw::Toast::builder()
+            .title(title)
+            .button_label(fl!("bulk-actions-undo"))
+            .priority(adw::ToastPriority::High)
+            .build();
+
+        let sender = sender.clone();
+        toast.connect_button_clicked(move |_| sender.input(BulkActionsInput::Undo));
+
+        self.toast_overlay.add_toast(toast);
+    }
+
+    fn choose_folder(&self, action: BulkAction, sender: &ComponentSender<Self>) {
+        let title = match action {
+            BulkAction::MoveToFolder => fl!("bulk-actions-folder-dialog", "move-title"),
+            _ => fl!("bulk-actions-folder-dialog", "copy-title"),
+        };
+
+        let file_dialog = gtk::FileDialog::builder()
+            .title(title)
+            .modal(true)
+            .build();
+
+        let sender = sender.clone();
+        file_dialog.select_folder(Some(&self.parent), gio::Cancellable::NONE, move |result| {
+            if let Some(path) = result.ok().and_then(|folder| folder.path()) {
+                sender.input(BulkActionsInput::FolderChosen(action, path));
+            }
+        });
+    }
+
+    fn choose_album(&self, sender: &ComponentSender<Self>) {
+        // Smart albums contain whatever matches their rules, so items can't be added.
+        let albums: Vec<album::Album> = self.album_repo
+            .all()
+            .unwrap_or_else(|e| {
+                error!("Failed loading albums: {}", e);
+                Vec::new()
+            })
+            .into_iter()
+            .filter(|album| !album.is_smart())
+            .collect();
+
+        if albums.is_empty() {
+            self.toast_overlay.add_toast(adw::Toast::new(&fl!("bulk-actions-no-albums")));
+            return;
+        }
+
+        let rows = albums
+            .iter()
+            .map(|album| {
+                adw::ActionRow::builder()
+                    .title(album.name.clone())
+                    .subtitle(fl!("user-albums-item-count", count = album.item_count))
+                    .build()
+            })
+            .collect();
+
+        let sender = sender.clone();
+        self.choose(&fl!("bulk-actions-choose-album"), rows, move |index| {
+            sender.input(BulkActionsInput::AlbumChosen(albums[index].album_id));
+        });
+    }
+
+    fn choose_person(&self, sender: &ComponentSender<Self>) {
+        let people = self.people_repo.all_people().unwrap_or_else(|e| {
+            error!("Failed loading people: {}", e);
+            Vec::new()
+        });
+
+        if people.is_empty() {
+            self.toast_overlay.add_toast(adw::Toast::new(&fl!("bulk-actions-no-people")));
+            return;
+        }
+
+        let rows = people
+            .iter()
+            .map(|person| {
+                let avatar = adw::Avatar::builder()
+                    .size(32)
+                    .text(person.name.clone())
+                    .show_initials(true)
+                    .build();
+
+                if let Ok(image) = gtk::gdk::Texture::from_filename(&person.thumbnail_path) {
+                    avatar.set_custom_image(Some(&image));
+                }
+
+                let row = adw::ActionRow::builder()
+                    .title(person.name.clone())
+                    .build();
+                row.add_prefix(&avatar);
+                row
+            })
+            .collect();
+
+        let sender = sender.clone();
+        self.choose(&fl!("bulk-actions-choose-person"), rows, move |index| {
+            sender.input(BulkActionsInput::PersonChosen(people[index].person_id));
+        });
+    }
+
+    fn choose_date(&self, sender: &ComponentSender<Self>) {
+        let modes = [
+            fl!("bulk-actions-date-mode", "set"),
+            fl!("bulk-actions-date-mode", "shift"),
+            fl!("bulk-actions-date-mode", "time-zone"),
+            fl!("bulk-actions-date-mode", "revert"),
+        ];
+        let modes: Vec<&str> = modes.iter().map(|x| x.as_str()).collect();
+
+        let mode_row = adw::ComboRow::builder()
+            .title(fl!("bulk-actions-date-mode"))
+            .model(&gtk::StringList::new(&modes))
+            .build();
+
+        // Start from the date of the first item, which is all that is needed for one item.
+        let initial = self.pending
+            .first()
+            .map(|visual| visual.ordering_ts.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+
+        let date_row = adw::EntryRow::builder()
+            .title(fl!("bulk-actions-date-set"))
+            .text(initial)
+            .build();
+
+        let spin_row = |title: String, limit: f64| {
+            adw::SpinRow::builder()
+                .title(title)
+                .adjustment(&gtk::Adjustment::new(0.0, -limit, limit, 1.0, 10.0, 0.0))
+                .build()
+        };
+
+        let days_row = spin_row(fl!("bulk-actions-date-shift", "days"), 36500.0);
+        let hours_row = spin_row(fl!("bulk-actions-date-shift", "hours"), 23.0);
+        let minutes_row = spin_row(fl!("bulk-actions-date-shift", "minutes"), 59.0);
+
+        let offsets = utc_offsets();
+        let offset_names: Vec<String> = offsets
+            .iter()
+            .map(|offset| fl!("bulk-actions-date-time-zone-name", offset = offset.to_string()))
+            .collect();
+        let offset_names: Vec<&str> = offset_names.iter().map(|x| x.as_str()).collect();
+
+        let local_offset = Local::now().offset().fix();
+        let time_zone_row = adw::ComboRow::builder()
+            .title(fl!("bulk-actions-date-time-zone"))
+            .model(&gtk::StringList::new(&offset_names))
+            .selected(offsets.iter().position(|x| *x == local_offset).unwrap_or(0) as u32)
+            .build();
+
+        let group = adw::PreferencesGroup::new();
+        group.add(&mode_row);
+        group.add(&date_row);
+        group.add(&days_row);
+        group.add(&hours_row);
+        group.add(&minutes_row);
+        group.add(&time_zone_row);
+
+        // Only show the rows for the chosen kind of change.
+        let show_rows = {
+            let date_row = date_row.clone();
+            let shift_rows = [days_row.clone(), hours_row.clone(), minutes_row.clone()];
+            let time_zone_row = time_zone_row.clone();
+            move |mode: u32| {
+                date_row.set_visible(mode == 0);
+                for row in shift_rows.iter() {
+                    row.set_visible(mode == 1);
+                }
+                time_zone_row.set_visible(mode == 2);
+            }
+        };
+
+        show_rows(0);
+        mode_row.connect_selected_notify(move |row| show_rows(row.selected()));
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(fl!("bulk-actions-choose-date"))
+            .body(fl!("bulk-actions-choose-date-body", count = self.pending.len()))
+            .extra_child(&group)
+            .build();
+
+        dialog.add_responses(&[
+            ("cancel", &fl!("bulk-actions-cancel-button")),
+            ("apply", &fl!("bulk-actions-apply-button")),
+        ]);
+        dialog.set_response_appearance("apply", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("apply"));
+        dialog.set_close_response("cancel");
+
+        let sender = sender.clone();
+        let toast_overlay = self.toast_overlay.clone();
+
+        dialog.connect_response(None, move |_, response| {
+            if response != "apply" {
+                return;
+            }
+
+            let change = match mode_row.selected() {
+                0 => match dates::model::parse_local(&date_row.text()) {
+                    Some(local) => DateChange::Set(local, None),
+                    None => {
+                        toast_overlay.add_toast(adw::Toast::new(&fl!("bulk-actions-invalid-date")));
+                        return;
+                    },
+                },
+                1 => DateChange::Shift(
+                    TimeDelta::days(days_row.value() as i64)
+                        + TimeDelta::hours(hours_row.value() as i64)
+                        + TimeDelta::minutes(minutes_row.value() as i64),
+                ),
+                2 => match offsets.get(time_zone_row.selected() as usize) {
+                    Some(offset) => DateChange::TimeZone(*offset),
+                    None => return,
+                },
+                _ => DateChange::Revert,
+            };
+
+            sender.input(BulkActionsInput::DateChosen(change));
+        });
+
+        dialog.present(Some(&self.parent));
+    }
+
+    fn choose_app(&mut self) {
+        let files: Vec<gio::File> = self.pending
+            .drain(..)
+            .filter_map(|visual| visual.path().cloned())
+            .map(gio::File::for_path)
+            .collect();
+
+        let Some(path) = files.first().and_then(|file| file.path()) else {
+            return;
+        };
+
+        // Applications for the first item. Mixed selections of photos and videos
+        // will only offer applications that can open the first item.
+        let (content_type, _) = gio::content_type_guess(Some(&path), &[]);
+        let apps = gio::AppInfo::recommended_for_type(&content_type);
+
+        if apps.is_empty() {
+            self.toast_overlay.add_toast(adw::Toast::new(&fl!("bulk-actions-no-apps")));
+            return;
+        }
+
+        let rows = apps
+            .iter()
+            .map(|app| {
+                let row = adw::ActionRow::builder()
+                    .title(app.display_name())
+                    .build();
+
+                if let Some(icon) = app.icon() {
+                    row.add_prefix(&gtk::Image::from_gicon(&icon));
+                }
+                row
+            })
+            .collect();
+
+        let context = gtk::prelude::WidgetExt::display(&self.parent).app_launch_context();
+
+        self.choose(&fl!("bulk-actions-choose-app"), rows, move |index| {
+            if let Err(e) = apps[index].launch(&files, Some(&context)) {
+                error!("Failed launching {}: {}", apps[index].name(), e);
+            }
+        });
+    }
+
+    /// Presents a dialog for choosing one of a list of rows.
+    fn choose<F>(&self, heading: &str, rows: Vec<adw::ActionRow>, on_choose: F)
+    where
+        F: Fn(usize) + 'static,
+    {
+        let list = gtk::ListBox::builder()
+            .css_classes(["boxed-list"])
+            .selection_mode(gtk::SelectionMode::None)
+            .build();
+
+        for row in rows {
+            row.set_activatable(true);
+            list.append(&row);
+        }
+
+        let scrolled = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .propagate_natural_height(true)
+            .max_content_height(360)
+            .child(&list)
+            .build();
+
+        let dialog = adw::AlertDialog::builder()
+            .heading(heading)
+            .close_response("cancel")
+            .extra_child(&scrolled)
+            .build();
+
+        dialog.add_response("cancel", &fl!("bulk-actions-cancel-button"));
+
+        {
+            let dialog = dialog.clone();
+            list.connect_row_activated(move |_, row| {
+                dialog.close();
+                if let Ok(index) = usize::try_from(row.index()) {
+                    on_choose(index);
+                }
+            });
+        }
+
+        dialog.present(Some(&self.parent));
+    }
+}
+
+/// Offsets from UTC of time zones, in 15 minute steps from UTC-12:00 to UTC+14:00.
+fn utc_offsets() -> Vec<FixedOffset> {
+    (-12 * 4..=14 * 4)
+        .filter_map(|quarters| FixedOffset::east_opt(quarters * 15 * 60))
+        .collect()
+}
+
+/// Copies a file with GIO.
+fn copy_file(source: &Path, target: &Path) -> Result<(), glib::Error> {
+    gio::File::for_path(source).copy(
+        &gio::File::for_path(target),
+        gio::FileCopyFlags::NONE,
+        gio::Cancellable::NONE,
+        None,
+    )
+}
+
+/// Moves a file with GIO, which can move files between file systems.
+fn move_file(source: &Path, target: &Path) -> Result<(), glib::Error> {
+    gio::File::for_path(source).move_(
+        &gio::File::for_path(target),
+        gio::FileCopyFlags::NONE,
+        gio::Cancellable::NONE,
+        None,
+    )
+}
+
+/// Permanently deletes files, returning paths of deleted files.
+fn delete_files(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths
+        .into_iter()
+        .filter(|path| {
+            info!("Deleting {:?}", path);
+            gio::File::for_path(path)
+                .delete(gio::Cancellable::NONE)
+                .inspect_err(|e| error!("Failed deleting {:?}: {}", path, e))
+                .is_ok()
+        })
+        .collect()
+}
@@ -0,0 +1,321 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::adw::{self, prelude::*};
+use relm4::gtk::{self, gio};
+use relm4::*;
+
+use fotema_core::edit::{self, ExportFormat, ExportOptions};
+use fotema_core::export::{template, Destination, ExportSettings};
+use fotema_core::Visual;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use strum::IntoEnumIterator;
+use tracing::info;
+
+use crate::fl;
+
+/// Longest edges exported pictures can be scaled down to. None keeps the full size.
+const EXPORT_SIZES: [Option<u32>; 4] = [None, Some(3840), Some(2048), Some(1024)];
+
+#[derive(Debug)]
+pub enum ExportDialogInput {
+    /// Show export options for items.
+    Present(Vec<Arc<Visual>>),
+
+    /// Options chosen, so choose where to export to.
+    ChooseDestination,
+
+    /// Destination chosen, so start exporting.
+    DestinationChosen(Destination),
+}
+
+#[derive(Debug)]
+pub enum ExportDialogOutput {
+    /// Export items with settings.
+    Export(Vec<Arc<Visual>>, ExportSettings),
+}
+
+/// Dialog for exporting copies of pictures and videos, such as for sharing.
+pub struct ExportDialog {
+    parent: adw::ApplicationWindow,
+    dialog: adw::Dialog,
+
+    formats: Vec<ExportFormat>,
+    format_row: adw::ComboRow,
+    size_row: adw::ComboRow,
+    quality_row: adw::SpinRow,
+    keep_metadata_row: adw::SwitchRow,
+    keep_location_row: adw::SwitchRow,
+    apply_orientation_row: adw::SwitchRow,
+    include_motion_videos_row: adw::SwitchRow,
+    template_row: adw::EntryRow,
+    zip_row: adw::SwitchRow,
+
+    /// Items to export.
+    visuals: Vec<Arc<Visual>>,
+}
+
+impl Component for ExportDialog {
+    type Init = adw::ApplicationWindow;
+    type Input = ExportDialogInput;
+    type Output = ExportDialogOutput;
+    type CommandOutput = ();
+    type Root = adw::Dialog;
+    type Widgets = ();
+
+    fn init_root() -> Self::Root {
+        adw::Dialog::builder()
+            .title(fl!("export-title"))
+            .content_width(480)
+            .build()
+    }
+
+    fn init(
+        parent: Self::Init,
+        dialog: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let export_button = gtk::Button::builder()
+            .label(fl!("export-button"))
+            .css_classes(["suggested-action"])
+            .build();
+
+        {
+            let sender = sender.clone();
+            export_button.connect_clicked(move |_| sender.input(ExportDialogInput::ChooseDestination));
+        }
+
+        let header_bar = adw::HeaderBar::new();
+        header_bar.pack_end(&export_button);
+
+        let formats: Vec<ExportFormat> = ExportFormat::iter().collect();
+        let format_names: Vec<&str> = formats.iter().map(|f| format_name(*f)).collect();
+        let format_row = adw::ComboRow::builder()
+            .title(fl!("export-format"))
+            .model(&gtk::StringList::new(&format_names))
+            .build();
+
+        let size_names: Vec<String> = EXPORT_SIZES
+            .iter()
+            .map(|size| match size {
+                Some(edge) => fl!("export-size-edge", edge = *edge),
+                None => fl!("export-size-full"),
+            })
+            .collect();
+        let size_names: Vec<&str> = size_names.iter().map(|s| s.as_str()).collect();
+        let size_row = adw::ComboRow::builder()
+            .title(fl!("export-size"))
+            .model(&gtk::StringList::new(&size_names))
+            .build();
+
+        let quality_row = adw::SpinRow::builder()
+            .title(fl!("export-quality"))
+            .subtitle(fl!("export-quality", "subtitle"))
+            .adjustment(&gtk::Adjustment::new(edit::export::DEFAULT_QUALITY as f64, 1.0, 100.0, 1.0, 10.0, 0.0))
+            .build();
+
+        // Lossless formats have no quality to choose.
+        {
+            let quality_row = quality_row.clone();
+            let formats = formats.clone();
+            format_row.connect_selected_notify(move |row| {
+                let format = formats.get(row.selected() as usize);
+                quality_row.set_sensitive(matches!(format, Some(ExportFormat::Jpeg) | Some(ExportFormat::Avif)));
+            });
+        }
+
+        let pictures_group = adw::PreferencesGroup::builder()
+            .title(fl!("export-pictures-section"))
+            .build();
+
+        pictures_group.add(&format_row);
+        pictures_group.add(&size_row);
+        pictures_group.add(&quality_row);
+
+        let keep_metadata_row = adw::SwitchRow::builder()
+            .title(fl!("export-keep-metadata"))
+            .subtitle(fl!("export-keep-metadata", "subtitle"))
+            .active(true)
+            .build();
+
+        let keep_location_row = adw::SwitchRow::builder()
+            .title(fl!("export-keep-location"))
+            .build();
+
+        keep_metadata_row
+            .bind_property("active", &keep_location_row, "sensitive")
+            .sync_create()
+            .build();
+
+        let apply_orientation_row = adw::SwitchRow::builder()
+            .title(fl!("export-apply-orientation"))
+            .subtitle(fl!("export-apply-orientation", "subtitle"))
+            .active(true)
+            .build();
+
+        let metadata_group = adw::PreferencesGroup::builder()
+            .title(fl!("export-metadata-section"))
+            .build();
+
+        metadata_group.add(&keep_metadata_row);
+        metadata_group.add(&keep_location_row);
+        metadata_group.add(&apply_orientation_row);
+
+        let include_motion_videos_row = adw::SwitchRow::builder()
+            .title(fl!("export-include-motion-videos"))
+            .subtitle(fl!("export-include-motion-videos", "subtitle"))
+            .build();
+
+        let template_row = adw::EntryRow::builder()
+            .title(fl!("export-template"))
+            .text(template::DEFAULT_TEMPLATE)
+            .build();
+
+        let zip_row = adw::SwitchRow::builder()
+            .title(fl!("export-zip"))
+            .subtitle(fl!("export-zip", "subtitle"))
+            .build();
+
+        let files_group = adw::PreferencesGroup::builder()
+            .title(fl!("export-files-section"))
+            .description(fl!("export-files-section", "description"))
+            .build();
+
+        files_group.add(&include_motion_videos_row);
+        files_group.add(&template_row);
+        files_group.add(&zip_row);
+
+        let page = adw::PreferencesPage::new();
+        page.add(&pictures_group);
+        page.add(&metadata_group);
+        page.add(&files_group);
+
+        let toolbar_view = adw::ToolbarView::new();
+        toolbar_view.add_top_bar(&header_bar);
+        toolbar_view.set_content(Some(&page));
+
+        dialog.set_child(Some(&toolbar_view));
+
+        let model = ExportDialog {
+            parent,
+            dialog,
+            formats,
+            format_row,
+            size_row,
+            quality_row,
+            keep_metadata_row,
+            keep_location_row,
+            apply_orientation_row,
+            include_motion_videos_row,
+            template_row,
+            zip_row,
+            visuals: Vec::new(),
+        };
+
+        ComponentParts { model, widgets: () }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
+        match msg {
+            ExportDialogInput::Present(visuals) => {
+                self.dialog.set_title(&fl!("export-title-count", count = visuals.len()));
+                self.visuals = visuals;
+                self.dialog.present(Some(&self.parent));
+            },
+            ExportDialogInput::ChooseDestination => {
+                self.dialog.close();
+
+                let sender = sender.clone();
+
+                if self.zip_row.is_active() {
+                    let file_dialog = gtk::FileDialog::builder()
+                        .title(fl!("export-choose-destination", "zip-title"))
+                        .initial_name(fl!("export-choose-destination", "zip-name"))
+                        .modal(true)
+                        .build();
+
+                    file_dialog.save(Some(&self.parent), gio::Cancellable::NONE, move |result| {
+                        if let Some(path) = result.ok().and_then(|file| file.path()) {
+                            sender.input(ExportDialogInput::DestinationChosen(Destination::Zip(with_zip_extension(path))));
+                        }
+                    });
+                } else {
+                    let file_dialog = gtk::FileDialog::builder()
+                        .title(fl!("export-choose-destination", "folder-title"))
+                        .modal(true)
+                        .build();
+
+                    file_dialog.select_folder(Some(&self.parent), gio::Cancellable::NONE, move |result| {
+                        if let Some(path) = result.ok().and_then(|folder| folder.path()) {
+                            sender.input(ExportDialogInput::DestinationChosen(Destination::Folder(path)));
+                        }
+                    });
+                }
+            },
+            ExportDialogInput::DestinationChosen(destination) => {
+                let visuals: Vec<Arc<Visual>> = self.visuals.drain(..).collect();
+                if visuals.is_empty() {
+                    return;
+                }
+
+                let settings = self.settings(destination);
+                info!("Exporting {} items with {:?}", visuals.len(), settings);
+                let _ = sender.output(ExportDialogOutput::Export(visuals, settings));
+            },
+        }
+    }
+}
+
+impl ExportDialog {
+    fn settings(&self, destination: Destination) -> ExportSettings {
+        let format = self.formats
+            .get(self.format_row.selected() as usize)
+            .copied()
+            .unwrap_or(ExportFormat::Jpeg);
+
+        let image = ExportOptions {
+            format,
+            max_edge: EXPORT_SIZES.get(self.size_row.selected() as usize).copied().flatten(),
+            quality: self.quality_row.value().clamp(1.0, 100.0) as u8,
+        };
+
+        ExportSettings {
+            destination,
+            image,
+            keep_metadata: self.keep_metadata_row.is_active(),
+            keep_location: self.keep_metadata_row.is_active() && self.keep_location_row.is_active(),
+            apply_orientation: self.apply_orientation_row.is_active(),
+            include_motion_videos: self.include_motion_videos_row.is_active(),
+            file_name_template: self.template_row.text().trim().to_string(),
+        }
+    }
+}
+
+/// Name of a format shown to the user.
+fn format_name(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Jpeg => "JPEG",
+        ExportFormat::Png => "PNG",
+        ExportFormat::WebP => "WebP",
+        ExportFormat::Avif => "AVIF",
+    }
+}
+
+/// The save dialog doesn't add an extension, so make sure the archive has one.
+fn with_zip_extension(path: PathBuf) -> PathBuf {
+    let is_zip = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"));
+
+    if is_zip {
+        path
+    } else {
+        let mut name = path.into_os_string();
+        name.push(".zip");
+        PathBuf::from(name)
+    }
+}
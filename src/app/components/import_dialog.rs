@@ -0,0 +1,611 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::adw::{self, prelude::*};
+use relm4::gtk::{self, gio, glib};
+use relm4::*;
+
+use fotema_core::import::device::{self, ImportItem, ImportMode};
+use fotema_core::library::{self, LibraryRoot};
+
+use std::path::PathBuf;
+
+use crate::config::APP_ID;
+use crate::fl;
+
+use tracing::{error, info};
+
+/// Most items to list in the preview. Listing thousands of rows makes the dialog sluggish,
+/// and the count of new items is shown anyway.
+const PREVIEW_LIMIT: usize = 500;
+
+/// A folder to import from.
+#[derive(Debug, Clone)]
+struct Source {
+    /// Name of the device.
+    name: String,
+
+    /// Folder to look for new items in. Usually the DCIM folder of a device.
+    path: PathBuf,
+
+    icon: gio::Icon,
+}
+
+#[derive(Debug)]
+pub enum ImportDialogInput {
+    /// Show the import dialog.
+    Present,
+
+    /// A device has been connected or disconnected.
+    RefreshSources,
+
+    /// Show folder chooser for importing from a folder that isn't on a device.
+    ChooseFolder,
+
+    /// Look for new items in a folder.
+    SelectSource(PathBuf),
+
+    /// Mount a device, such as a phone, that is connected but not mounted.
+    Mount(gio::Volume),
+
+    /// Template for the paths of imported files has been edited.
+    UpdateTemplate(String),
+
+    /// Copy or move new items into the library.
+    Import,
+}
+
+#[derive(Debug)]
+pub enum ImportDialogOutput {
+    /// Files have been imported into the library, or moved from a library root.
+    FilesChanged(Vec<PathBuf>),
+}
+
+#[derive(Debug)]
+pub enum ImportDialogCommand {
+    /// New items found in a folder.
+    Found(PathBuf, Vec<ImportItem>),
+
+    /// An item has been imported, or has failed to import.
+    Advance,
+
+    /// Paths of imported files and count of items that failed to import.
+    Imported(Vec<PathBuf>, usize),
+}
+
+/// Dialog for importing pictures and videos from cameras, phones, and memory cards.
+pub struct ImportDialog {
+    parent: adw::ApplicationWindow,
+    dialog: adw::Dialog,
+    toast_overlay: adw::ToastOverlay,
+
+    library_repo: library::Repository,
+
+    // Kept so signals for connected and disconnected devices keep firing.
+    volume_monitor: gio::VolumeMonitor,
+
+    gio_settings: gio::Settings,
+
+    sources_group: adw::PreferencesGroup,
+    source_rows: Vec<adw::ActionRow>,
+
+    library_root_row: adw::ComboRow,
+    template_row: adw::EntryRow,
+    delete_originals_row: adw::SwitchRow,
+
+    preview_group: adw::PreferencesGroup,
+    preview_rows: Vec<adw::ActionRow>,
+
+    import_button: gtk::Button,
+    progress_bar: gtk::ProgressBar,
+
+    /// Library roots that can be imported into, in the order shown.
+    library_roots: Vec<LibraryRoot>,
+
+    /// Folder new items were found in.
+    source: Option<PathBuf>,
+
+    /// New items in the source folder.
+    items: Vec<ImportItem>,
+
+    /// Items imported so far, for showing progress.
+    imported_count: usize,
+
+    /// Looking for new items or importing.
+    is_busy: bool,
+}
+
+impl Component for ImportDialog {
+    type Init = (library::Repository, adw::ApplicationWindow);
+    type Input = ImportDialogInput;
+    type Output = ImportDialogOutput;
+    type CommandOutput = ImportDialogCommand;
+    type Root = adw::Dialog;
+    type Widgets = ();
+
+    fn init_root() -> Self::Root {
+        adw::Dialog::builder()
+            .title(fl!("import-title"))
+            .content_width(480)
+            .content_height(640)
+            .build()
+    }
+
+    fn init(
+        (library_repo, parent): Self::Init,
+        dialog: Self::Root,
+        sender: ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let gio_settings = gio::Settings::new(APP_ID);
+
+        let import_button = gtk::Button::builder()
+            .label(fl!("import-button"))
+            .css_classes(["suggested-action"])
+            .sensitive(false)
+            .build();
+
+        {
+            let sender = sender.clone();
+            import_button.connect_clicked(move |_| sender.input(ImportDialogInput::Import));
+        }
+
+        let header_bar = adw::HeaderBar::new();
+        header_bar.pack_end(&import_button);
+
+        let choose_folder_button = gtk::Button::builder()
+            .icon_name("folder-open-symbolic")
+            .valign(gtk::Align::Center)
+            .css_classes(["flat"])
+            .tooltip_text(fl!("import-choose-folder", "tooltip"))
+            .build();
+
+        {
+            let sender = sender.clone();
+            choose_folder_button.connect_clicked(move |_| sender.input(ImportDialogInput::ChooseFolder));
+        }
+
+        let sources_group = adw::PreferencesGroup::builder()
+            .title(fl!("import-sources-section"))
+            .description(fl!("import-sources-section", "description"))
+            .header_suffix(&choose_folder_button)
+            .build();
+
+        let library_root_row = adw::ComboRow::builder()
+            .title(fl!("import-library-root"))
+            .build();
+
+        let template_row = adw::EntryRow::builder()
+            .title(fl!("import-template"))
+            .text(gio_settings.string("import-template"))
+            .show_apply_button(true)
+            .build();
+
+        {
+            let sender = sender.clone();
+            template_row.connect_apply(move |row| {
+                sender.input(ImportDialogInput::UpdateTemplate(row.text().to_string()));
+            });
+        }
+
+        let delete_originals_row = adw::SwitchRow::builder()
+            .title(fl!("import-delete-originals"))
+            .subtitle(fl!("import-delete-originals", "subtitle"))
+            .build();
+
+        let destination_group = adw::PreferencesGroup::builder()
+            .title(fl!("import-destination-section"))
+            .description(fl!("import-destination-section", "description"))
+            .build();
+
+        destination_group.add(&library_root_row);
+        destination_group.add(&template_row);
+        destination_group.add(&delete_originals_row);
+
+        let preview_group = adw::PreferencesGroup::builder()
+            .title(fl!("import-preview-section"))
+            .build();
+
+        let page = adw::PreferencesPage::new();
+        page.add(&sources_group);
+        page.add(&destination_group);
+        page.add(&preview_group);
+
+        let progress_bar = gtk::ProgressBar::builder()
+            .show_text(true)
+            .visible(false)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_top(12)
+            .margin_bottom(12)
+            .build();
+
+        let toolbar_view = adw::ToolbarView::new();
+        toolbar_view.add_top_bar(&header_bar);
+        toolbar_view.set_content(Some(&page));
+        toolbar_view.add_bottom_bar(&progress_bar);
+
+        let toast_overlay = adw::ToastOverlay::new();
+        toast_overlay.set_child(Some(&toolbar_view));
+
+        dialog.set_child(Some(&toast_overlay));
+
+        let volume_monitor = gio::VolumeMonitor::get();
+
+        {
+            let sender = sender.clone();
+            volume_monitor.connect_mount_added(move |_, _| sender.input(ImportDialogInput::RefreshSources));
+        }
+        {
+            let sender = sender.clone();
+            volume_monitor.connect_mount_removed(move |_, _| sender.input(ImportDialogInput::RefreshSources));
+        }
+        {
+            let sender = sender.clone();
+            volume_monitor.connect_volume_added(move |_, _| sender.input(ImportDialogInput::RefreshSources));
+        }
+
+        let model = ImportDialog {
+            parent,
+            dialog,
+            toast_overlay,
+            library_repo,
+            volume_monitor,
+            gio_settings,
+            sources_group,
+            source_rows: Vec::new(),
+            library_root_row,
+            template_row,
+            delete_originals_row,
+            preview_group,
+            preview_rows: Vec::new(),
+            import_button,
+            progress_bar,
+            library_roots: Vec::new(),
+            source: None,
+            items: Vec::new(),
+            imported_count: 0,
+            is_busy: false,
+        };
+
+        ComponentParts { model, widgets: () }
+    }
+
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>, _root: &Self::Root) {
+        match msg {
+            ImportDialogInput::Present => {
+                self.refresh_library_roots();
+                self.refresh_sources(&sender);
+                self.dialog.present(Some(&self.parent));
+            },
+            ImportDialogInput::RefreshSources => {
+                self.refresh_sources(&sender);
+            },
+            ImportDialogInput::ChooseFolder => {
+                let file_dialog = gtk::FileDialog::builder()
+                    .title(fl!("import-choose-folder", "dialog-title"))
+                    .modal(true)
+                    .build();
+
+                let sender = sender.clone();
+                file_dialog.select_folder(Some(&self.parent), gio::Cancellable::NONE, move |result| {
+                    if let Some(path) = result.ok().and_then(|folder| folder.path()) {
+                        // A card reader or file manager might have opened the card rather than its DCIM folder.
+                        let path = device::find_dcim(&path).unwrap_or(path);
+                        sender.input(ImportDialogInput::SelectSource(path));
+                    }
+                });
+            },
+            ImportDialogInput::SelectSource(path) => {
+                if self.is_busy {
+                    return;
+                }
+
+                info!("Looking for new items in {:?}", path);
+                self.source = Some(path.clone());
+                self.items.clear();
+                self.set_busy(true);
+                self.refresh_preview();
+                self.preview_group.set_description(Some(&fl!("import-preview-searching")));
+
+                let library_repo = self.library_repo.clone();
+                sender.spawn_oneshot_command(move || {
+                    let known = library_repo.fingerprints().unwrap_or_else(|e| {
+                        error!("Failed loading fingerprints: {}", e);
+                        Default::default()
+                    });
+
+                    let items = device::find_new_items(&path, &known).unwrap_or_else(|e| {
+                        error!("Failed looking for new items in {:?}: {}", path, e);
+                        Vec::new()
+                    });
+
+                    ImportDialogCommand::Found(path, items)
+                });
+            },
+            ImportDialogInput::Mount(volume) => {
+                info!("Mounting {}", volume.name());
+                let operation = gtk::MountOperation::new(Some(&self.parent));
+                let toast_overlay = self.toast_overlay.clone();
+                volume.mount(
+                    gio::MountMountFlags::NONE,
+                    Some(&operation),
+                    gio::Cancellable::NONE,
+                    move |result| {
+                        // Sources are refreshed when the mount is added.
+                        if let Err(e) = result {
+                            error!("Failed mounting volume: {}", e);
+                            toast_overlay.add_toast(adw::Toast::new(&fl!("import-mount-failed")));
+                        }
+                    },
+                );
+            },
+            ImportDialogInput::UpdateTemplate(template) => {
+                let template = if template.trim().is_empty() {
+                    device::DEFAULT_TEMPLATE.to_string()
+                } else {
+                    template.trim().to_string()
+                };
+
+                info!("Update import template: {}", template);
+                self.template_row.set_text(&template);
+                if let Err(e) = self.gio_settings.set_string("import-template", &template) {
+                    error!("Failed saving import template: {}", e);
+                }
+                self.refresh_preview();
+            },
+            ImportDialogInput::Import => {
+                let Some(root) = self.selected_library_root() else {
+                    return;
+                };
+
+                if self.is_busy || self.items.is_empty() {
+                    return;
+                }
+
+                let items = self.items.clone();
+                let template = self.template();
+                let mode = if self.delete_originals_row.is_active() {
+                    ImportMode::Move
+                } else {
+                    ImportMode::Copy
+                };
+
+                info!("Importing {} items into {:?} with {:?}", items.len(), root.path, mode);
+
+                self.imported_count = 0;
+                self.set_busy(true);
+
+                sender.spawn_command(move |out| {
+                    let mut imported = Vec::new();
+                    let mut failed = 0;
+
+                    for item in items {
+                        match device::import_item(&root.path, &template, &item, mode) {
+                            Ok(Some(path)) => imported.push(path),
+                            Ok(None) => {},
+                            Err(e) => {
+                                error!("Failed importing {:?}: {}", item.path, e);
+                                failed += 1;
+                            },
+                        }
+                        let _ = out.send(ImportDialogCommand::Advance);
+                    }
+
+                    let _ = out.send(ImportDialogCommand::Imported(imported, failed));
+                });
+            },
+        }
+    }
+
+    fn update_cmd(&mut self, msg: Self::CommandOutput, sender: ComponentSender<Self>, _root: &Self::Root) {
+        match msg {
+            ImportDialogCommand::Found(path, items) => {
+                // Another source might have been chosen while looking.
+                if self.source.as_ref() != Some(&path) {
+                    return;
+                }
+
+                self.items = items;
+                self.set_busy(false);
+                self.refresh_preview();
+            },
+            ImportDialogCommand::Advance => {
+                self.imported_count += 1;
+                let total = self.items.len().max(1);
+                self.progress_bar.set_fraction(self.imported_count as f64 / total as f64);
+                self.progress_bar.set_text(Some(&fl!("import-progress", count = self.imported_count, total = self.items.len())));
+            },
+            ImportDialogCommand::Imported(paths, failed) => {
+                info!("Imported {} items, {} failed", paths.len(), failed);
+
+                let message = if failed == 0 {
+                    fl!("import-completed", count = paths.len())
+                } else {
+                    fl!("import-completed-with-failures", count = paths.len(), failed = failed)
+                };
+                self.toast_overlay.add_toast(adw::Toast::new(&message));
+
+                // Moving might have removed files from a folder within a library root.
+                let mut changed = paths;
+                if self.delete_originals_row.is_active() {
+                    changed.extend(self.items.iter().map(|item| item.path.clone()));
+                }
+
+                if !changed.is_empty() {
+                    let _ = sender.output(ImportDialogOutput::FilesChanged(changed));
+                }
+
+                self.items.clear();
+                self.set_busy(false);
+
+                // Look again so anything that failed is still offered.
+                if let Some(source) = self.source.clone() {
+                    sender.input(ImportDialogInput::SelectSource(source));
+                }
+            },
+        }
+    }
+}
+
+impl ImportDialog {
+    /// Template for paths of imported files, relative to the library root.
+    fn template(&self) -> String {
+        let template = self.gio_settings.string("import-template");
+        if template.trim().is_empty() {
+            device::DEFAULT_TEMPLATE.to_string()
+        } else {
+            template.to_string()
+        }
+    }
+
+    fn selected_library_root(&self) -> Option<LibraryRoot> {
+        let index = usize::try_from(self.library_root_row.selected()).ok()?;
+        self.library_roots.get(index).cloned()
+    }
+
+    fn set_busy(&mut self, is_busy: bool) {
+        self.is_busy = is_busy;
+        self.update_import_button();
+        self.progress_bar.set_visible(is_busy && !self.items.is_empty());
+        self.progress_bar.set_fraction(0.0);
+        self.progress_bar.set_text(None);
+    }
+
+    /// Importing needs new items, somewhere to put them, and nothing else happening.
+    fn update_import_button(&self) {
+        let can_import = !self.is_busy && !self.items.is_empty() && !self.library_roots.is_empty();
+        self.import_button.set_sensitive(can_import);
+    }
+
+    /// Rebuild choices of library root, keeping the current choice if possible.
+    fn refresh_library_roots(&mut self) {
+        let selected = self.selected_library_root().map(|root| root.library_root_id);
+
+        self.library_roots = self.library_repo.all_available().unwrap_or_else(|e| {
+            error!("Failed loading library roots: {}", e);
+            Vec::new()
+        });
+
+        let names: Vec<String> = self.library_roots
+            .iter()
+            .map(|root| root.path.to_string_lossy().to_string())
+            .collect();
+
+        let names: Vec<&str> = names.iter().map(|x| x.as_str()).collect();
+        self.library_root_row.set_model(Some(&gtk::StringList::new(&names)));
+
+        let index = self.library_roots
+            .iter()
+            .position(|root| Some(root.library_root_id) == selected)
+            .unwrap_or(0);
+
+        self.library_root_row.set_selected(index as u32);
+        self.update_import_button();
+    }
+
+    /// Rebuild rows for connected devices.
+    fn refresh_sources(&mut self, sender: &ComponentSender<Self>) {
+        for row in self.source_rows.drain(..) {
+            self.sources_group.remove(&row);
+        }
+
+        // Cameras and phones are mounted by GVfs, which makes them available as ordinary
+        // folders under /run/user. Mounts without a local path can't be read.
+        let sources = self.volume_monitor
+            .mounts()
+            .iter()
+            .filter_map(|mount| {
+                let path = mount.root().path()?;
+                Some(Source {
+                    name: mount.name().to_string(),
+                    path: device::find_dcim(&path)?,
+                    icon: mount.symbolic_icon(),
+                })
+            });
+
+        for source in sources {
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&source.name))
+                .subtitle(glib::markup_escape_text(&source.path.to_string_lossy()))
+                .activatable(true)
+                .build();
+
+            row.add_prefix(&gtk::Image::from_gicon(&source.icon));
+
+            if self.source.as_ref() == Some(&source.path) {
+                row.add_suffix(&gtk::Image::from_icon_name("object-select-symbolic"));
+            }
+
+            let sender = sender.clone();
+            row.connect_activated(move |_| sender.input(ImportDialogInput::SelectSource(source.path.clone())));
+
+            self.sources_group.add(&row);
+            self.source_rows.push(row);
+        }
+
+        // Phones connected over USB often appear as a volume that must be mounted first.
+        let unmounted = self.volume_monitor
+            .volumes()
+            .into_iter()
+            .filter(|volume| volume.get_mount().is_none() && volume.can_mount());
+
+        for volume in unmounted {
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&volume.name()))
+                .subtitle(fl!("import-source-not-mounted"))
+                .activatable(true)
+                .build();
+
+            row.add_prefix(&gtk::Image::from_gicon(&volume.symbolic_icon()));
+
+            let sender = sender.clone();
+            row.connect_activated(move |_| sender.input(ImportDialogInput::Mount(volume.clone())));
+
+            self.sources_group.add(&row);
+            self.source_rows.push(row);
+        }
+
+        if self.source_rows.is_empty() {
+            let row = adw::ActionRow::builder()
+                .title(fl!("import-no-sources"))
+                .build();
+            self.sources_group.add(&row);
+            self.source_rows.push(row);
+        }
+    }
+
+    /// Rebuild rows for new items, showing where each item will be imported to.
+    fn refresh_preview(&mut self) {
+        for row in self.preview_rows.drain(..) {
+            self.preview_group.remove(&row);
+        }
+
+        let description = if self.source.is_none() {
+            fl!("import-preview-no-source")
+        } else {
+            let size_bytes: u64 = self.items.iter().map(|item| item.size_bytes).sum();
+            let size = glib::format_size(size_bytes);
+            fl!("import-preview-count", count = self.items.len(), size = size.to_string())
+        };
+
+        self.preview_group.set_description(Some(&description));
+
+        let template = self.template();
+
+        for item in self.items.iter().take(PREVIEW_LIMIT) {
+            let name = item.path.file_name().unwrap_or_default().to_string_lossy();
+            let target = device::render_template(&template, item);
+
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&name))
+                .subtitle(glib::markup_escape_text(&target.to_string_lossy()))
+                .build();
+
+            self.preview_group.add(&row);
+            self.preview_rows.push(row);
+        }
+
+        self.update_import_button();
+    }
+}
@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use fotema_core::{VisualId, YearMonth};
+use fotema_core::{Visual, VisualId, YearMonth};
 
 use relm4::*;
 use relm4::adw;
@@ -17,6 +17,8 @@ use crate::app::ActiveView;
 use crate::app::ViewName;
 use crate::fl;
 
+use super::bulk_actions::BulkAction;
+
 use super::albums::album::{Album, AlbumInput, AlbumOutput};
 use super::albums::album_filter::AlbumFilter;
 use super::albums::album_sort::AlbumSort;
@@ -45,11 +47,17 @@ pub enum LibraryInput {
     View(VisualId),
 
     Sort(AlbumSort),
+
+    /// User has chosen an action for the selected items.
+    Bulk(BulkAction, Vec<Arc<Visual>>),
 }
 
 #[derive(Debug)]
 pub enum LibraryOutput {
     View(VisualId),
+
+    /// User has chosen an action for the selected items.
+    Bulk(BulkAction, Vec<Arc<Visual>>),
 }
 
 
@@ -97,6 +105,8 @@ impl SimpleComponent for Library {
             .forward(sender.input_sender(), |msg| match msg {
                 AlbumOutput::Selected(id, _) => LibraryInput::View(id),
                 AlbumOutput::ScrollOffset(_) => LibraryInput::Ignore,
+                AlbumOutput::Moved(_, _) => LibraryInput::Ignore,
+                AlbumOutput::Bulk(action, visuals) => LibraryInput::Bulk(action, visuals),
             });
 
         state.subscribe(all_album.sender(), |_| AlbumInput::Refresh);
@@ -171,6 +181,9 @@ impl SimpleComponent for Library {
                 self.months_album.emit(MonthsAlbumInput::Sort(sort));
                 self.years_album.emit(YearsAlbumInput::Sort(sort));
             },
+            LibraryInput::Bulk(action, visuals) => {
+                let _ = sender.output(LibraryOutput::Bulk(action, visuals));
+            },
         }
     }
 }
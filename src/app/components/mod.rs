@@ -5,7 +5,11 @@
 pub mod about;
 pub mod preferences;
 pub mod albums;
+pub mod bulk_actions;
+pub mod export_dialog;
+pub mod import_dialog;
 pub mod library;
 pub mod progress_monitor;
 pub mod progress_panel;
+pub mod system_trash;
 pub mod viewer;
@@ -5,8 +5,16 @@
 use relm4::{adw, ComponentParts, ComponentSender, SimpleComponent};
 use relm4::adw::prelude::*;
 use relm4::gtk;
+use relm4::gtk::gio;
 
-use tracing::info;
+use fotema_core::library;
+use fotema_core::LibraryRootId;
+use fotema_core::library::ScanRules;
+
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use tracing::{error, info};
 
 use crate::fl;
 use crate::app::{Settings, SettingsState};
@@ -20,6 +28,15 @@ pub struct PreferencesDialog {
 
     settings_state: SettingsState,
 
+    library_repo: library::Repository,
+
+    // Group of library root rows, and the rows currently in it.
+    library_roots_group: adw::PreferencesGroup,
+    library_root_rows: Vec<adw::ExpanderRow>,
+
+    // Scan rules have changed, so the library must be scanned again when the dialog closes.
+    is_rescan_needed: bool,
+
     // Preference values
     settings: Settings,
 }
@@ -43,19 +60,61 @@ pub enum PreferencesInput {
 
     UpdateFaceDetectionMode(FaceDetectionMode),
 
+    UpdateWriteXmpSidecars(bool),
+
     Sort(AlbumSort),
+
+    /// Show folder chooser for a new library root.
+    ChooseLibraryRoot,
+
+    /// Add folder as a library root.
+    AddLibraryRoot(PathBuf),
+
+    RemoveLibraryRoot(LibraryRootId),
+
+    EnableLibraryRoot(LibraryRootId, bool),
+
+    /// Update which files are scanned under a library root.
+    UpdateLibraryRootRules(LibraryRootId, ScanRules),
+
+    /// Preferences dialog has been closed.
+    Closed,
+}
+
+#[derive(Debug)]
+pub enum PreferencesOutput {
+    /// Library roots have been added, removed, enabled, disabled, or had their rules changed.
+    LibraryRootsChanged,
+
+    /// Writing XMP sidecars has been turned on, so existing metadata should be written.
+    WriteXmpSidecarsEnabled,
 }
 
 #[relm4::component(pub)]
 impl SimpleComponent for PreferencesDialog {
-    type Init = (SettingsState, adw::ApplicationWindow);
+    type Init = (SettingsState, library::Repository, adw::ApplicationWindow);
     type Input = PreferencesInput;
-    type Output = ();
+    type Output = PreferencesOutput;
 
     view!{
         adw::PreferencesDialog {
             set_title: &fl!("prefs-title"),
+            connect_closed => PreferencesInput::Closed,
             add = &adw::PreferencesPage {
+                #[local_ref]
+                library_roots_group -> adw::PreferencesGroup {
+                    set_title: &fl!("prefs-library-section"),
+                    set_description: Some(&fl!("prefs-library-section", "description")),
+
+                    #[wrap(Some)]
+                    set_header_suffix = &gtk::Button {
+                        set_icon_name: "list-add-symbolic",
+                        set_valign: gtk::Align::Center,
+                        add_css_class: "flat",
+                        set_tooltip_text: Some(&fl!("prefs-library-add-folder", "tooltip")),
+                        connect_clicked => PreferencesInput::ChooseLibraryRoot,
+                    },
+                },
                 add = &adw::PreferencesGroup {
                     set_title: &fl!("prefs-ui-section"),
                     set_description: Some(&fl!("prefs-ui-section", "description")),
@@ -72,6 +131,18 @@ impl SimpleComponent for PreferencesDialog {
 		                },
                     },
 
+                    adw::SwitchRow {
+                        set_title: &fl!("prefs-ui-write-xmp"),
+                        set_subtitle: &fl!("prefs-ui-write-xmp", "subtitle"),
+
+                        #[watch]
+                        set_active: model.settings.write_xmp_sidecars,
+
+                        connect_active_notify[sender] => move |switch| {
+                            let _ = sender.input_sender().send(PreferencesInput::UpdateWriteXmpSidecars(switch.is_active()));
+                        },
+                    },
+
                     #[local_ref]
                     album_sort_row -> adw::ComboRow {
                         set_title: &fl!("prefs-ui-chronological-album-sort"),
@@ -112,7 +183,7 @@ impl SimpleComponent for PreferencesDialog {
 
 
     fn init(
-        (settings_state, parent): Self::Init,
+        (settings_state, library_repo, parent): Self::Init,
         dialog: Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
@@ -130,8 +201,14 @@ impl SimpleComponent for PreferencesDialog {
         ]);
         album_sort_row.set_model(Some(&list));
 
-        let model = Self {
+        let library_roots_group = adw::PreferencesGroup::new();
+
+        let mut model = Self {
             settings_state: settings_state.clone(),
+            library_repo,
+            library_roots_group: library_roots_group.clone(),
+            library_root_rows: Vec::new(),
+            is_rescan_needed: false,
             parent,
             dialog: dialog.clone(),
             settings: settings_state.read().clone(),
@@ -140,15 +217,18 @@ impl SimpleComponent for PreferencesDialog {
 
         let widgets = view_output!();
 
+        model.refresh_library_roots(&sender);
+
         sender.input(PreferencesInput::SettingsChanged(model.settings.clone()));
 
         ComponentParts { model, widgets }
     }
 
-    fn update(&mut self, msg: Self::Input, _sender: ComponentSender<Self>) {
+    fn update(&mut self, msg: Self::Input, sender: ComponentSender<Self>) {
         match msg {
             PreferencesInput::Present => {
                 self.settings = self.settings_state.read().clone();
+                self.refresh_library_roots(&sender);
                 self.dialog.present(Some(&self.parent));
             },
             PreferencesInput::SettingsChanged(settings) => {
@@ -172,11 +252,229 @@ impl SimpleComponent for PreferencesDialog {
                 self.settings.face_detection_mode = mode;
                 *self.settings_state.write() = self.settings.clone();
             },
+            PreferencesInput::UpdateWriteXmpSidecars(write_xmp_sidecars) => {
+                info!("Update write XMP sidecars: {}", write_xmp_sidecars);
+                self.settings.write_xmp_sidecars = write_xmp_sidecars;
+                *self.settings_state.write() = self.settings.clone();
+                if write_xmp_sidecars {
+                    let _ = sender.output(PreferencesOutput::WriteXmpSidecarsEnabled);
+                }
+            },
             PreferencesInput::Sort(mode) => {
                 info!("Update album sort: {:?}", mode);
                 self.settings.album_sort = mode;
                 *self.settings_state.write() = self.settings.clone();
             },
+            PreferencesInput::ChooseLibraryRoot => {
+                let file_dialog = gtk::FileDialog::builder()
+                    .title(fl!("prefs-library-add-folder", "dialog-title"))
+                    .modal(true)
+                    .build();
+
+                let sender = sender.clone();
+                file_dialog.select_folder(Some(&self.parent), gio::Cancellable::NONE, move |result| {
+                    if let Some(path) = result.ok().and_then(|folder| folder.path()) {
+                        sender.input(PreferencesInput::AddLibraryRoot(path));
+                    }
+                });
+            },
+            PreferencesInput::AddLibraryRoot(path) => {
+                info!("Add library root: {:?}", path);
+                if let Err(e) = self.library_repo.add(&path) {
+                    error!("Failed adding library root {:?}: {}", path, e);
+                    let toast = adw::Toast::new(&fl!("prefs-library-add-folder-failed"));
+                    self.dialog.add_toast(toast);
+                    return;
+                }
+                self.refresh_library_roots(&sender);
+                let _ = sender.output(PreferencesOutput::LibraryRootsChanged);
+            },
+            PreferencesInput::RemoveLibraryRoot(root_id) => {
+                info!("Remove library root: {}", root_id);
+                if let Err(e) = self.library_repo.remove(root_id) {
+                    error!("Failed removing library root {}: {}", root_id, e);
+                    return;
+                }
+                self.refresh_library_roots(&sender);
+                let _ = sender.output(PreferencesOutput::LibraryRootsChanged);
+            },
+            PreferencesInput::EnableLibraryRoot(root_id, is_enabled) => {
+                info!("Enable library root {}: {}", root_id, is_enabled);
+                if let Err(e) = self.library_repo.set_enabled(root_id, is_enabled) {
+                    error!("Failed updating library root {}: {}", root_id, e);
+                    return;
+                }
+                let _ = sender.output(PreferencesOutput::LibraryRootsChanged);
+            },
+            PreferencesInput::UpdateLibraryRootRules(root_id, rules) => {
+                info!("Update library root {} rules: {:?}", root_id, rules);
+                if let Err(e) = self.library_repo.set_rules(root_id, &rules) {
+                    error!("Failed updating library root {} rules: {}", root_id, e);
+                    return;
+                }
+                // Wait for the dialog to close rather than rescanning on every keystroke.
+                self.is_rescan_needed = true;
+            },
+            PreferencesInput::Closed => {
+                if self.is_rescan_needed {
+                    self.is_rescan_needed = false;
+                    let _ = sender.output(PreferencesOutput::LibraryRootsChanged);
+                }
+            },
+        }
+    }
+}
+
+impl PreferencesDialog {
+    /// Rebuild rows for library roots.
+    fn refresh_library_roots(&mut self, sender: &ComponentSender<Self>) {
+        for row in self.library_root_rows.drain(..) {
+            self.library_roots_group.remove(&row);
+        }
+
+        let roots = match self.library_repo.all() {
+            Ok(roots) => roots,
+            Err(e) => {
+                error!("Failed loading library roots: {}", e);
+                return;
+            }
+        };
+
+        // Removing the last root would leave nothing to scan.
+        let can_remove = roots.len() > 1;
+
+        for root in roots {
+            let root_id = root.library_root_id;
+
+            let title = root.path.file_name()
+                .map(|x| x.to_string_lossy().to_string())
+                .unwrap_or_else(|| root.path.to_string_lossy().to_string());
+
+            let row = adw::ExpanderRow::builder()
+                .title(title)
+                .subtitle(root.path.to_string_lossy().to_string())
+                .build();
+
+            let enable_switch = gtk::Switch::builder()
+                .active(root.is_enabled)
+                .valign(gtk::Align::Center)
+                .tooltip_text(fl!("prefs-library-enable-folder", "tooltip"))
+                .build();
+
+            {
+                let sender = sender.clone();
+                enable_switch.connect_active_notify(move |switch| {
+                    sender.input(PreferencesInput::EnableLibraryRoot(root_id, switch.is_active()));
+                });
+            }
+
+            let remove_button = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .tooltip_text(fl!("prefs-library-remove-folder", "tooltip"))
+                .sensitive(can_remove)
+                .build();
+
+            {
+                let sender = sender.clone();
+                remove_button.connect_clicked(move |_| {
+                    sender.input(PreferencesInput::RemoveLibraryRoot(root_id));
+                });
+            }
+
+            row.add_suffix(&enable_switch);
+            row.add_suffix(&remove_button);
+
+            self.add_rule_rows(&row, root_id, &root.rules, sender);
+
+            self.library_roots_group.add(&row);
+            self.library_root_rows.push(row);
+        }
+    }
+
+    /// Add rows for editing the scan rules of a library root.
+    fn add_rule_rows(&self, row: &adw::ExpanderRow, root_id: LibraryRootId, rules: &ScanRules, sender: &ComponentSender<Self>) {
+        let include_row = adw::EntryRow::builder()
+            .title(fl!("prefs-library-include-globs"))
+            .text(rules.include_globs.join(", "))
+            .show_apply_button(true)
+            .build();
+
+        let exclude_row = adw::EntryRow::builder()
+            .title(fl!("prefs-library-exclude-globs"))
+            .text(rules.exclude_globs.join(", "))
+            .show_apply_button(true)
+            .build();
+
+        let skip_hidden_row = adw::SwitchRow::builder()
+            .title(fl!("prefs-library-skip-hidden"))
+            .subtitle(fl!("prefs-library-skip-hidden", "subtitle"))
+            .active(rules.skip_hidden)
+            .build();
+
+        let min_file_size_row = adw::SpinRow::builder()
+            .title(fl!("prefs-library-min-file-size"))
+            .subtitle(fl!("prefs-library-min-file-size", "subtitle"))
+            .adjustment(&gtk::Adjustment::new((rules.min_file_size_bytes / 1024) as f64, 0.0, 1_000_000.0, 10.0, 100.0, 0.0))
+            .build();
+
+        let min_dimension_row = adw::SpinRow::builder()
+            .title(fl!("prefs-library-min-dimension"))
+            .subtitle(fl!("prefs-library-min-dimension", "subtitle"))
+            .adjustment(&gtk::Adjustment::new(rules.min_dimension as f64, 0.0, 10_000.0, 10.0, 100.0, 0.0))
+            .build();
+
+        // Globs are entered as a comma separated list.
+        let to_globs = |text: &str| -> Vec<String> {
+            text.split(',')
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty())
+                .map(String::from)
+                .collect()
+        };
+
+        let update = {
+            let include_row = include_row.clone();
+            let exclude_row = exclude_row.clone();
+            let skip_hidden_row = skip_hidden_row.clone();
+            let min_file_size_row = min_file_size_row.clone();
+            let min_dimension_row = min_dimension_row.clone();
+            let sender = sender.clone();
+            Rc::new(move || {
+                let rules = ScanRules {
+                    include_globs: to_globs(&include_row.text()),
+                    exclude_globs: to_globs(&exclude_row.text()),
+                    skip_hidden: skip_hidden_row.is_active(),
+                    min_file_size_bytes: min_file_size_row.value() as u64 * 1024,
+                    min_dimension: min_dimension_row.value() as u32,
+                };
+                sender.input(PreferencesInput::UpdateLibraryRootRules(root_id, rules));
+            })
+        };
+
+        {
+            let update = update.clone();
+            include_row.connect_apply(move |_| update());
+        }
+        {
+            let update = update.clone();
+            exclude_row.connect_apply(move |_| update());
+        }
+        {
+            let update = update.clone();
+            skip_hidden_row.connect_active_notify(move |_| update());
         }
+        {
+            let update = update.clone();
+            min_file_size_row.connect_value_notify(move |_| update());
+        }
+        min_dimension_row.connect_value_notify(move |_| update());
+
+        row.add_row(&include_row);
+        row.add_row(&exclude_row);
+        row.add_row(&skip_hidden_row);
+        row.add_row(&min_file_size_row);
+        row.add_row(&min_dimension_row);
     }
 }
@@ -21,6 +21,9 @@ pub enum TaskName {
     MotionPhoto,
     DetectFaces,
     RecognizeFaces,
+    Duplicates,
+    Sidecars,
+    Export,
 
     /// FIXME figure out if 'Idle' will be used.
     Idle,
@@ -83,6 +83,15 @@ impl SimpleComponent for ProgressPanel {
                         TaskName::RecognizeFaces => {
                             self.progress_bar.set_text(Some(&fl!("progress-recognize-faces-photos")));
                         },
+                        TaskName::Duplicates => {
+                            self.progress_bar.set_text(Some(&fl!("progress-duplicates-photos")));
+                        },
+                        TaskName::Sidecars => {
+                            self.progress_bar.set_text(Some(&fl!("progress-sidecars-photos")));
+                        },
+                        TaskName::Export => {
+                            self.progress_bar.set_text(Some(&fl!("progress-export-photos")));
+                        },
                         TaskName::Idle => {
                             self.progress_bar.set_text(Some(&fl!("progress-idle")));
                         },
@@ -0,0 +1,145 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::gtk::gio;
+use relm4::gtk::prelude::*;
+
+use fotema_core::photo::xmp;
+use fotema_core::trash;
+use fotema_core::Visual;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use tracing::{error, info};
+
+/// Moves the files of an item to the trash and hides the item from the library.
+/// Returns None if no files could be trashed.
+pub fn trash_item(repo: &mut trash::Repository, visual: &Visual) -> Option<(trash::TrashId, Vec<PathBuf>)> {
+    // Record item before trashing files so the cleanup task never sees an item
+    // whose files are missing without knowing they are in the trash.
+    let trash_id = repo.add(visual)
+        .inspect_err(|e| error!("Failed recording {} in trash: {}", visual.visual_id, e))
+        .ok()?;
+
+    let paths = trash_files(original_files(visual));
+
+    if paths.is_empty() {
+        if let Err(e) = repo.restore(trash_id) {
+            error!("Failed removing {} from trash: {}", visual.visual_id, e);
+        }
+        return None;
+    }
+
+    Some((trash_id, paths))
+}
+
+/// Original files of a visual item, including XMP sidecars, so edits made
+/// by other applications stay with the files.
+pub fn original_files(visual: &Visual) -> Vec<PathBuf> {
+    let originals = visual.original_paths();
+    let sidecars: Vec<PathBuf> = originals
+        .iter()
+        .filter_map(|path| xmp::find_sidecar(path))
+        .collect();
+
+    originals.into_iter().chain(sidecars).collect()
+}
+
+/// Moves files to the system trash, as shown by file managers, returning paths of trashed files.
+pub fn trash_files(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths
+        .into_iter()
+        .filter(|path| {
+            info!("Moving {:?} to trash", path);
+            gio::File::for_path(path)
+                .trash(gio::Cancellable::NONE)
+                .inspect_err(|e| error!("Failed moving {:?} to trash: {}", path, e))
+                .is_ok()
+        })
+        .collect()
+}
+
+/// Original paths of every file in the trash.
+pub fn trashed_paths() -> HashSet<PathBuf> {
+    trashed_files()
+        .into_iter()
+        .map(|(_, orig_path)| orig_path)
+        .collect()
+}
+
+/// Restores files from the trash to their original paths, returning paths of restored files.
+pub fn restore_from_trash(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut remaining: HashSet<PathBuf> = paths.into_iter().collect();
+    let mut restored = Vec::new();
+
+    for (file, orig_path) in trashed_files() {
+        // A path trashed more than once is only restored once.
+        if !remaining.remove(&orig_path) {
+            continue;
+        }
+
+        info!("Restoring {:?} from trash", orig_path);
+        let result = file.move_(
+            &gio::File::for_path(&orig_path),
+            gio::FileCopyFlags::NONE,
+            gio::Cancellable::NONE,
+            None,
+        );
+
+        match result {
+            Ok(_) => restored.push(orig_path),
+            Err(e) => error!("Failed restoring {:?} from trash: {}", orig_path, e),
+        }
+    }
+
+    restored
+}
+
+/// Permanently deletes files from the trash, returning original paths of deleted files.
+pub fn delete_from_trash(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut remaining: HashSet<PathBuf> = paths.into_iter().collect();
+    let mut deleted = Vec::new();
+
+    for (file, orig_path) in trashed_files() {
+        if !remaining.remove(&orig_path) {
+            continue;
+        }
+
+        info!("Deleting {:?} from trash", orig_path);
+        match file.delete(gio::Cancellable::NONE) {
+            Ok(_) => deleted.push(orig_path),
+            Err(e) => error!("Failed deleting {:?} from trash: {}", orig_path, e),
+        }
+    }
+
+    deleted
+}
+
+/// Files in the trash with their original paths.
+fn trashed_files() -> Vec<(gio::File, PathBuf)> {
+    let trash = gio::File::for_uri("trash:///");
+    let children = trash.enumerate_children(
+        "standard::name,trash::orig-path",
+        gio::FileQueryInfoFlags::NONE,
+        gio::Cancellable::NONE,
+    );
+
+    let children = match children {
+        Ok(children) => children,
+        Err(e) => {
+            error!("Failed listing trash: {}", e);
+            return Vec::new();
+        },
+    };
+
+    children
+        .flatten()
+        .filter_map(|info| {
+            let orig_path = info.attribute_byte_string("trash::orig-path")
+                .map(|x| PathBuf::from(x.as_str()))?;
+            Some((trash.child(info.name()), orig_path))
+        })
+        .collect()
+}
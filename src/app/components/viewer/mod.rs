@@ -7,3 +7,4 @@ pub mod view_nav;
 pub mod view_one;
 pub mod face_thumbnails;
 pub mod person_select;
+pub mod photo_editor;
@@ -0,0 +1,796 @@
+// SPDX-FileCopyrightText: © 2024 David Bliss
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use relm4::adw;
+use relm4::adw::prelude::*;
+use relm4::gtk;
+use relm4::gtk::cairo;
+use relm4::gtk::gio;
+use relm4::prelude::*;
+use relm4::*;
+
+use fotema_core::edit::{self, Adjustment, CropRect, Edit, ExportFormat, ExportOptions, Preset};
+use fotema_core::PictureId;
+use fotema_core::Visual;
+
+use image::DynamicImage;
+
+use crate::fl;
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use strum::IntoEnumIterator;
+use tracing::{error, info};
+
+/// Longest edge of the picture shown while editing.
+const PREVIEW_EDGE: u32 = 1280;
+
+/// Smallest crop that can be dragged out, as a fraction of the picture.
+const MIN_CROP: f64 = 0.02;
+
+/// Longest edges a copy can be scaled down to when saved. None keeps the full size.
+const COPY_SIZES: [Option<u32>; 4] = [None, Some(3840), Some(2048), Some(1024)];
+
+#[derive(Debug)]
+pub enum PhotoEditorInput {
+    /// Edit a picture.
+    Edit(Arc<Visual>),
+
+    RotateClockwise,
+
+    RotateCounterClockwise,
+
+    FlipHorizontal,
+
+    FlipVertical,
+
+    /// Straighten by degrees clockwise.
+    Straighten(f64),
+
+    /// Start dragging out a crop rectangle from a point on the canvas.
+    CropBegin(f64, f64),
+
+    /// Drag crop rectangle to an offset from where dragging began.
+    CropUpdate(f64, f64),
+
+    ClearCrop,
+
+    /// Set a colour or exposure adjustment.
+    Adjust(Adjustment, f64),
+
+    /// Replace all adjustments with those of a preset.
+    ApplyPreset(Preset),
+
+    /// Discard all edits so the original picture is shown.
+    Revert,
+
+    /// Choose the format and size of a copy of the picture with edits applied.
+    SaveCopy,
+
+    /// Choose where to save a copy of the picture with edits applied.
+    ChooseCopyPath(ExportOptions),
+
+    /// Save a copy of the picture with edits applied.
+    SaveCopyTo(PathBuf, ExportOptions),
+
+    Cancel,
+
+    /// Save edits and close the editor.
+    Done,
+}
+
+#[derive(Debug)]
+pub enum PhotoEditorOutput {
+    /// Edit of a picture has been saved.
+    Saved(PictureId),
+}
+
+/// What the canvas draws: the picture with everything except the crop applied,
+/// and the crop rectangle on top of it.
+#[derive(Default)]
+struct Preview {
+    surface: Option<cairo::ImageSurface>,
+    crop: Option<CropRect>,
+}
+
+pub struct PhotoEditor {
+    edit_repo: edit::Repository,
+
+    dialog: adw::Dialog,
+
+    toast_overlay: adw::ToastOverlay,
+
+    canvas: gtk::DrawingArea,
+
+    straighten: gtk::Scale,
+
+    /// Slider for each colour and exposure adjustment.
+    adjustment_scales: Vec<(Adjustment, gtk::Scale)>,
+
+    /// Item being edited.
+    visual: Option<Arc<Visual>>,
+
+    /// Edit as last saved, so unchanged edits aren't saved again.
+    saved_edit: Edit,
+
+    /// Edit being made.
+    edit: Edit,
+
+    /// Picture scaled down so the preview stays responsive.
+    source: Option<DynamicImage>,
+
+    /// Shared with the canvas draw function.
+    preview: Rc<RefCell<Preview>>,
+
+    /// Point on canvas where a crop drag began.
+    drag_start: Option<(f64, f64)>,
+}
+
+#[relm4::component(pub async)]
+impl SimpleAsyncComponent for PhotoEditor {
+    type Init = edit::Repository;
+    type Input = PhotoEditorInput;
+    type Output = PhotoEditorOutput;
+
+    view! {
+        adw::Dialog {
+            set_title: &fl!("photo-editor-title"),
+            set_content_width: 1100,
+            set_content_height: 700,
+
+            #[wrap(Some)]
+            set_child = &adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar {
+                    set_show_start_title_buttons: false,
+                    set_show_end_title_buttons: false,
+
+                    pack_start = &gtk::Button {
+                        set_label: &fl!("photo-editor-cancel"),
+                        connect_clicked => PhotoEditorInput::Cancel,
+                    },
+
+                    pack_end = &gtk::Button {
+                        set_label: &fl!("photo-editor-done"),
+                        add_css_class: "suggested-action",
+                        connect_clicked => PhotoEditorInput::Done,
+                    },
+                },
+
+                #[wrap(Some)]
+                #[local_ref]
+                set_content = &toast_overlay -> adw::ToastOverlay {
+                    #[wrap(Some)]
+                    set_child = &gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+
+                        #[local_ref]
+                        canvas -> gtk::DrawingArea {
+                            set_hexpand: true,
+                            set_vexpand: true,
+                            set_margin_all: 12,
+                            set_tooltip_text: Some(&fl!("photo-editor-crop", "tooltip")),
+                        },
+
+                        gtk::ScrolledWindow {
+                            set_hscrollbar_policy: gtk::PolicyType::Never,
+                            set_width_request: 260,
+
+                            #[local_ref]
+                            adjustments_panel -> gtk::Box {
+                                set_orientation: gtk::Orientation::Vertical,
+                                set_spacing: 6,
+                                set_margin_all: 12,
+                            },
+                        },
+                    },
+                },
+
+                add_bottom_bar = &gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_halign: gtk::Align::Center,
+                    set_spacing: 6,
+                    set_margin_all: 6,
+
+                    gtk::Button {
+                        set_icon_name: "object-rotate-left-symbolic",
+                        set_tooltip_text: Some(&fl!("photo-editor-rotate-left", "tooltip")),
+                        connect_clicked => PhotoEditorInput::RotateCounterClockwise,
+                    },
+
+                    gtk::Button {
+                        set_icon_name: "object-rotate-right-symbolic",
+                        set_tooltip_text: Some(&fl!("photo-editor-rotate-right", "tooltip")),
+                        connect_clicked => PhotoEditorInput::RotateClockwise,
+                    },
+
+                    gtk::Button {
+                        set_icon_name: "object-flip-horizontal-symbolic",
+                        set_tooltip_text: Some(&fl!("photo-editor-flip-horizontal", "tooltip")),
+                        connect_clicked => PhotoEditorInput::FlipHorizontal,
+                    },
+
+                    gtk::Button {
+                        set_icon_name: "object-flip-vertical-symbolic",
+                        set_tooltip_text: Some(&fl!("photo-editor-flip-vertical", "tooltip")),
+                        connect_clicked => PhotoEditorInput::FlipVertical,
+                    },
+
+                    gtk::Separator {
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+
+                    gtk::Label {
+                        set_label: &fl!("photo-editor-straighten"),
+                        add_css_class: "dim-label",
+                    },
+
+                    #[local_ref]
+                    straighten -> gtk::Scale {
+                        set_width_request: 200,
+                        set_tooltip_text: Some(&fl!("photo-editor-straighten", "tooltip")),
+                    },
+
+                    gtk::Button {
+                        set_label: &fl!("photo-editor-clear-crop"),
+                        set_tooltip_text: Some(&fl!("photo-editor-clear-crop", "tooltip")),
+                        connect_clicked => PhotoEditorInput::ClearCrop,
+                    },
+
+                    gtk::Separator {
+                        set_orientation: gtk::Orientation::Vertical,
+                    },
+
+                    gtk::Button {
+                        set_label: &fl!("photo-editor-revert"),
+                        set_tooltip_text: Some(&fl!("photo-editor-revert", "tooltip")),
+                        connect_clicked => PhotoEditorInput::Revert,
+                    },
+
+                    gtk::Button {
+                        set_label: &fl!("photo-editor-save-copy"),
+                        set_tooltip_text: Some(&fl!("photo-editor-save-copy", "tooltip")),
+                        connect_clicked => PhotoEditorInput::SaveCopy,
+                    },
+                },
+            },
+        }
+    }
+
+    async fn init(
+        edit_repo: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let toast_overlay = adw::ToastOverlay::new();
+
+        let canvas = gtk::DrawingArea::new();
+
+        let max = edit::model::MAX_STRAIGHTEN_DEGREES;
+        let straighten = gtk::Scale::with_range(gtk::Orientation::Horizontal, -max, max, 0.5);
+        straighten.add_mark(0.0, gtk::PositionType::Bottom, None);
+        straighten.set_value(0.0);
+
+        {
+            let sender = sender.clone();
+            straighten.connect_value_changed(move |scale| {
+                sender.input(PhotoEditorInput::Straighten(scale.value()));
+            });
+        }
+
+        let adjustments_panel = gtk::Box::new(gtk::Orientation::Vertical, 6);
+
+        adjustments_panel.append(&gtk::Label::builder()
+            .label(fl!("photo-editor-presets"))
+            .halign(gtk::Align::Start)
+            .css_classes(["heading"])
+            .build());
+
+        let presets = gtk::FlowBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .max_children_per_line(3)
+            .column_spacing(6)
+            .row_spacing(6)
+            .homogeneous(true)
+            .build();
+
+        for preset in Preset::iter() {
+            let button = gtk::Button::with_label(&preset_name(preset));
+            let sender = sender.clone();
+            button.connect_clicked(move |_| sender.input(PhotoEditorInput::ApplyPreset(preset)));
+            presets.append(&button);
+        }
+
+        adjustments_panel.append(&presets);
+
+        adjustments_panel.append(&gtk::Label::builder()
+            .label(fl!("photo-editor-adjustments"))
+            .halign(gtk::Align::Start)
+            .margin_top(12)
+            .css_classes(["heading"])
+            .build());
+
+        let mut adjustment_scales = Vec::new();
+
+        for adjustment in Adjustment::iter() {
+            let (min, max) = adjustment.range();
+            let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, min, max, 0.05);
+            scale.add_mark(0.0, gtk::PositionType::Bottom, None);
+            scale.set_value(0.0);
+
+            {
+                let sender = sender.clone();
+                scale.connect_value_changed(move |scale| {
+                    sender.input(PhotoEditorInput::Adjust(adjustment, scale.value()));
+                });
+            }
+
+            adjustments_panel.append(&gtk::Label::builder()
+                .label(adjustment_name(adjustment))
+                .halign(gtk::Align::Start)
+                .css_classes(["dim-label"])
+                .build());
+            adjustments_panel.append(&scale);
+
+            adjustment_scales.push((adjustment, scale));
+        }
+
+        let preview = Rc::new(RefCell::new(Preview::default()));
+
+        {
+            let preview = preview.clone();
+            canvas.set_draw_func(move |_, cr, width, height| {
+                draw_preview(&preview.borrow(), cr, width as f64, height as f64);
+            });
+        }
+
+        let drag = gtk::GestureDrag::new();
+
+        {
+            let sender = sender.clone();
+            drag.connect_drag_begin(move |_, x, y| sender.input(PhotoEditorInput::CropBegin(x, y)));
+        }
+
+        {
+            let sender = sender.clone();
+            drag.connect_drag_update(move |_, dx, dy| sender.input(PhotoEditorInput::CropUpdate(dx, dy)));
+        }
+
+        canvas.add_controller(drag);
+
+        let model = PhotoEditor {
+            edit_repo,
+            dialog: root.clone(),
+            toast_overlay: toast_overlay.clone(),
+            canvas: canvas.clone(),
+            straighten: straighten.clone(),
+            adjustment_scales,
+            visual: None,
+            saved_edit: Edit::default(),
+            edit: Edit::default(),
+            source: None,
+            preview,
+            drag_start: None,
+        };
+
+        let widgets = view_output!();
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(&mut self, msg: Self::Input, sender: AsyncComponentSender<Self>) {
+        match msg {
+            PhotoEditorInput::Edit(visual) => {
+                let (Some(picture_id), Some(picture_path)) = (visual.picture_id, visual.picture_path.clone()) else {
+                    return;
+                };
+
+                info!("Editing picture {}", picture_id);
+
+                self.saved_edit = self.edit_repo
+                    .get(picture_id)
+                    .unwrap_or_else(|e| {
+                        error!("Failed loading edit: {}", e);
+                        None
+                    })
+                    .unwrap_or_default();
+
+                self.edit = self.saved_edit.clone();
+                self.visual = Some(visual);
+                self.source = None;
+                self.drag_start = None;
+                *self.preview.borrow_mut() = Preview::default();
+                self.straighten.set_value(self.edit.straighten_degrees);
+                self.update_adjustment_scales();
+                self.canvas.queue_draw();
+
+                let image = match edit::render::load(&picture_path).await {
+                    Ok(image) => image,
+                    Err(e) => {
+                        error!("Failed loading {:?} for editing: {}", picture_path, e);
+                        self.toast_overlay.add_toast(adw::Toast::new(&fl!("photo-editor-load-failed")));
+                        return;
+                    }
+                };
+
+                let source = relm4::spawn_blocking(move || {
+                    if image.width().max(image.height()) > PREVIEW_EDGE {
+                        image.thumbnail(PREVIEW_EDGE, PREVIEW_EDGE)
+                    } else {
+                        image
+                    }
+                })
+                .await;
+
+                self.source = source.ok();
+                self.render().await;
+            },
+            PhotoEditorInput::RotateClockwise => {
+                self.edit.rotate_clockwise();
+                self.render().await;
+            },
+            PhotoEditorInput::RotateCounterClockwise => {
+                self.edit.rotate_counter_clockwise();
+                self.render().await;
+            },
+            PhotoEditorInput::FlipHorizontal => {
+                self.edit.flip_horizontal();
+                self.straighten.set_value(self.edit.straighten_degrees);
+                self.render().await;
+            },
+            PhotoEditorInput::FlipVertical => {
+                self.edit.flip_vertical();
+                self.straighten.set_value(self.edit.straighten_degrees);
+                self.render().await;
+            },
+            PhotoEditorInput::Straighten(degrees) => {
+                // Setting the scale after a flip or revert sends the current value back.
+                if degrees == self.edit.straighten_degrees {
+                    return;
+                }
+                self.edit.set_straighten_degrees(degrees);
+                self.render().await;
+            },
+            PhotoEditorInput::CropBegin(x, y) => {
+                self.drag_start = Some((x, y));
+            },
+            PhotoEditorInput::CropUpdate(dx, dy) => {
+                let Some((x, y)) = self.drag_start else {
+                    return;
+                };
+
+                let (Some(start), Some(end)) = (self.to_fraction(x, y), self.to_fraction(x + dx, y + dy)) else {
+                    return;
+                };
+
+                let crop = CropRect {
+                    x: start.0.min(end.0),
+                    y: start.1.min(end.1),
+                    width: (start.0 - end.0).abs(),
+                    height: (start.1 - end.1).abs(),
+                };
+
+                // Ignore clicks and tiny drags so they don't crop the picture away.
+                if crop.width < MIN_CROP || crop.height < MIN_CROP {
+                    return;
+                }
+
+                self.edit.set_crop(Some(crop));
+                self.preview.borrow_mut().crop = self.edit.crop;
+                self.canvas.queue_draw();
+            },
+            PhotoEditorInput::ClearCrop => {
+                self.edit.set_crop(None);
+                self.preview.borrow_mut().crop = None;
+                self.canvas.queue_draw();
+            },
+            PhotoEditorInput::Adjust(adjustment, value) => {
+                // Setting the scales after a preset or revert sends the current values back.
+                if value == self.edit.adjustments.get(adjustment) {
+                    return;
+                }
+                self.edit.adjustments.set(adjustment, value);
+                self.render().await;
+            },
+            PhotoEditorInput::ApplyPreset(preset) => {
+                self.edit.adjustments = preset.adjustments();
+                self.update_adjustment_scales();
+                self.render().await;
+            },
+            PhotoEditorInput::Revert => {
+                self.edit = Edit::default();
+                self.straighten.set_value(0.0);
+                self.update_adjustment_scales();
+                self.render().await;
+            },
+            PhotoEditorInput::SaveCopy => {
+                let formats: Vec<ExportFormat> = ExportFormat::iter().collect();
+
+                let format_names: Vec<&str> = formats.iter().map(|f| format_name(*f)).collect();
+                let format_row = adw::ComboRow::builder()
+                    .title(fl!("photo-editor-copy-format"))
+                    .model(&gtk::StringList::new(&format_names))
+                    .build();
+
+                let size_names: Vec<String> = COPY_SIZES
+                    .iter()
+                    .map(|size| match size {
+                        Some(edge) => fl!("photo-editor-copy-size-edge", edge = *edge),
+                        None => fl!("photo-editor-copy-size-full"),
+                    })
+                    .collect();
+                let size_names: Vec<&str> = size_names.iter().map(|s| s.as_str()).collect();
+                let size_row = adw::ComboRow::builder()
+                    .title(fl!("photo-editor-copy-size"))
+                    .model(&gtk::StringList::new(&size_names))
+                    .build();
+
+                let group = adw::PreferencesGroup::new();
+                group.add(&format_row);
+                group.add(&size_row);
+
+                let dialog = adw::AlertDialog::builder()
+                    .heading(fl!("photo-editor-save-copy", "dialog-title"))
+                    .extra_child(&group)
+                    .build();
+
+                dialog.add_responses(&[
+                    ("cancel", &fl!("photo-editor-copy-cancel")),
+                    ("save", &fl!("photo-editor-copy-save")),
+                ]);
+                dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+                dialog.set_default_response(Some("save"));
+                dialog.set_close_response("cancel");
+
+                dialog.connect_response(None, move |_, response| {
+                    if response != "save" {
+                        return;
+                    }
+                    let options = ExportOptions {
+                        format: formats[format_row.selected() as usize],
+                        max_edge: COPY_SIZES[size_row.selected() as usize],
+                        quality: edit::export::DEFAULT_QUALITY,
+                    };
+                    sender.input(PhotoEditorInput::ChooseCopyPath(options));
+                });
+
+                dialog.present(Some(&self.dialog));
+            },
+            PhotoEditorInput::ChooseCopyPath(options) => {
+                let Some(picture_path) = self.visual.as_ref().and_then(|v| v.picture_path.clone()) else {
+                    return;
+                };
+
+                let initial_name = picture_path
+                    .file_stem()
+                    .map(|stem| fl!("photo-editor-copy-name", name = stem.to_string_lossy()))
+                    .unwrap_or_default();
+
+                let mut file_dialog = gtk::FileDialog::builder()
+                    .title(fl!("photo-editor-save-copy", "dialog-title"))
+                    .initial_name(format!("{}.{}", initial_name, options.format.extension()))
+                    .modal(true);
+
+                if let Some(folder) = picture_path.parent() {
+                    file_dialog = file_dialog.initial_folder(&gio::File::for_path(folder));
+                }
+
+                let parent = self.dialog.root().and_downcast::<gtk::Window>();
+
+                file_dialog.build().save(parent.as_ref(), gio::Cancellable::NONE, move |result| {
+                    if let Some(path) = result.ok().and_then(|file| file.path()) {
+                        sender.input(PhotoEditorInput::SaveCopyTo(path, options));
+                    }
+                });
+            },
+            PhotoEditorInput::SaveCopyTo(copy_path, options) => {
+                let Some(picture_path) = self.visual.as_ref().and_then(|v| v.picture_path.clone()) else {
+                    return;
+                };
+
+                info!("Saving edited copy of {:?} to {:?}", picture_path, copy_path);
+
+                let edit = self.edit.clone();
+                let result = match edit::render::load(&picture_path).await {
+                    Ok(image) => relm4::spawn_blocking(move || edit::export::save_copy(&edit, image, &options, &copy_path))
+                        .await
+                        .map_err(anyhow::Error::from)
+                        .and_then(|result| result),
+                    Err(e) => Err(e),
+                };
+
+                let message = match result {
+                    Ok(_) => fl!("photo-editor-save-copy", "saved"),
+                    Err(e) => {
+                        error!("Failed saving edited copy: {}", e);
+                        fl!("photo-editor-save-copy", "failed")
+                    }
+                };
+
+                self.toast_overlay.add_toast(adw::Toast::new(&message));
+            },
+            PhotoEditorInput::Cancel => {
+                self.dialog.close();
+            },
+            PhotoEditorInput::Done => {
+                let Some(picture_id) = self.visual.as_ref().and_then(|v| v.picture_id) else {
+                    self.dialog.close();
+                    return;
+                };
+
+                if self.edit != self.saved_edit {
+                    info!("Saving edit of picture {}", picture_id);
+                    if let Err(e) = self.edit_repo.save(picture_id, &self.edit) {
+                        error!("Failed saving edit: {}", e);
+                        self.toast_overlay.add_toast(adw::Toast::new(&fl!("photo-editor-save-failed")));
+                        return;
+                    }
+                    self.saved_edit = self.edit.clone();
+                    let _ = sender.output(PhotoEditorOutput::Saved(picture_id));
+                }
+
+                self.dialog.close();
+            },
+        }
+    }
+}
+
+impl PhotoEditor {
+    /// Redraw the preview with the current edit. The crop is drawn over the
+    /// preview instead of being applied so that it can be adjusted.
+    async fn render(&self) {
+        let Some(ref source) = self.source else {
+            return;
+        };
+
+        let mut edit = self.edit.clone();
+        edit.crop = None;
+        let source = source.clone();
+
+        match relm4::spawn_blocking(move || edit::render::apply(&edit, source)).await {
+            Ok(image) => {
+                let mut preview = self.preview.borrow_mut();
+                preview.surface = to_surface(&image);
+                preview.crop = self.edit.crop;
+            },
+            Err(e) => error!("Failed rendering preview: {}", e),
+        }
+
+        self.canvas.queue_draw();
+    }
+
+    /// Move the adjustment sliders to the values of the current edit.
+    fn update_adjustment_scales(&self) {
+        for (adjustment, scale) in &self.adjustment_scales {
+            scale.set_value(self.edit.adjustments.get(*adjustment));
+        }
+    }
+
+    /// Convert a point on the canvas to fractions of the preview width and height.
+    fn to_fraction(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let preview = self.preview.borrow();
+        let surface = preview.surface.as_ref()?;
+
+        let (image_width, image_height) = (surface.width() as f64, surface.height() as f64);
+        let (left, top, scale) = fit(
+            self.canvas.width() as f64,
+            self.canvas.height() as f64,
+            image_width,
+            image_height,
+        );
+
+        let x = ((x - left) / (image_width * scale)).clamp(0.0, 1.0);
+        let y = ((y - top) / (image_height * scale)).clamp(0.0, 1.0);
+        Some((x, y))
+    }
+}
+
+fn adjustment_name(adjustment: Adjustment) -> String {
+    match adjustment {
+        Adjustment::Exposure => fl!("photo-editor-adjustment", "exposure"),
+        Adjustment::Contrast => fl!("photo-editor-adjustment", "contrast"),
+        Adjustment::Highlights => fl!("photo-editor-adjustment", "highlights"),
+        Adjustment::Shadows => fl!("photo-editor-adjustment", "shadows"),
+        Adjustment::Saturation => fl!("photo-editor-adjustment", "saturation"),
+        Adjustment::Temperature => fl!("photo-editor-adjustment", "temperature"),
+        Adjustment::Tint => fl!("photo-editor-adjustment", "tint"),
+    }
+}
+
+fn preset_name(preset: Preset) -> String {
+    match preset {
+        Preset::Vivid => fl!("photo-editor-preset", "vivid"),
+        Preset::Warm => fl!("photo-editor-preset", "warm"),
+        Preset::Cool => fl!("photo-editor-preset", "cool"),
+        Preset::Mono => fl!("photo-editor-preset", "mono"),
+        Preset::Fade => fl!("photo-editor-preset", "fade"),
+        Preset::Dramatic => fl!("photo-editor-preset", "dramatic"),
+    }
+}
+
+/// Format names are the same in every language.
+fn format_name(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Jpeg => "JPEG",
+        ExportFormat::Png => "PNG",
+        ExportFormat::WebP => "WebP",
+        ExportFormat::Avif => "AVIF",
+    }
+}
+
+/// Position and scale that fits an image in the middle of an area.
+fn fit(area_width: f64, area_height: f64, image_width: f64, image_height: f64) -> (f64, f64, f64) {
+    let scale = f64::min(area_width / image_width, area_height / image_height);
+    let left = (area_width - image_width * scale) / 2.0;
+    let top = (area_height - image_height * scale) / 2.0;
+    (left, top, scale)
+}
+
+fn draw_preview(preview: &Preview, cr: &cairo::Context, width: f64, height: f64) {
+    let Some(ref surface) = preview.surface else {
+        return;
+    };
+
+    let (image_width, image_height) = (surface.width() as f64, surface.height() as f64);
+    let (left, top, scale) = fit(width, height, image_width, image_height);
+
+    let _ = cr.save();
+    cr.translate(left, top);
+    cr.scale(scale, scale);
+    let _ = cr.set_source_surface(surface, 0.0, 0.0);
+    let _ = cr.paint();
+    let _ = cr.restore();
+
+    let Some(crop) = preview.crop else {
+        return;
+    };
+
+    let (width, height) = (image_width * scale, image_height * scale);
+    let crop_left = left + crop.x * width;
+    let crop_top = top + crop.y * height;
+    let crop_width = crop.width * width;
+    let crop_height = crop.height * height;
+
+    // Shade the part of the picture that will be cropped away.
+    cr.set_fill_rule(cairo::FillRule::EvenOdd);
+    cr.rectangle(left, top, width, height);
+    cr.rectangle(crop_left, crop_top, crop_width, crop_height);
+    cr.set_source_rgba(0.0, 0.0, 0.0, 0.6);
+    let _ = cr.fill();
+
+    cr.rectangle(crop_left, crop_top, crop_width, crop_height);
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.set_line_width(2.0);
+    let _ = cr.stroke();
+}
+
+/// Copy an image to a Cairo surface for drawing.
+fn to_surface(image: &DynamicImage) -> Option<cairo::ImageSurface> {
+    let image = image.to_rgba8();
+    let (width, height) = image.dimensions();
+
+    let mut surface = cairo::ImageSurface::create(cairo::Format::ARgb32, width as i32, height as i32).ok()?;
+    let stride = surface.stride() as usize;
+
+    {
+        let mut data = surface.data().ok()?;
+
+        for (y, row) in image.rows().enumerate() {
+            for (x, pixel) in row.enumerate() {
+                let [r, g, b, a] = pixel.0;
+                let premultiply = |c: u8| (c as u32 * a as u32 + 127) / 255;
+
+                // Cairo pixels are premultiplied ARGB in native byte order.
+                let argb = ((a as u32) << 24) | (premultiply(r) << 16) | (premultiply(g) << 8) | premultiply(b);
+
+                let offset = y * stride + x * 4;
+                data[offset..offset + 4].copy_from_slice(&argb.to_ne_bytes());
+            }
+        }
+    }
+
+    Some(surface)
+}
@@ -5,6 +5,7 @@
 /// Properties view for a photo.
 ///Inspired by how Loupe displays its property view.
 
+use fotema_core::camera;
 use fotema_core::VisualId;
 use gtk::prelude::OrientableExt;
 
@@ -17,7 +18,7 @@ use glycin::ImageInfo;
 use std::fs;
 use std::sync::Arc;
 use std::path::PathBuf;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, Utc};
 
 use crate::app::SharedState;
 use crate::fl;
@@ -34,6 +35,8 @@ pub enum ViewInfoInput {
 pub struct ViewInfo {
     state: SharedState,
 
+    camera_repo: camera::Repository,
+
     path: Option<PathBuf>,
     folder: adw::ActionRow,
     file_name: adw::ActionRow,
@@ -52,6 +55,15 @@ pub struct ViewInfo {
     exif_originally_created_at: adw::ActionRow,
     exif_originally_modified_at: adw::ActionRow,
 
+    camera_details: adw::PreferencesGroup,
+    camera_name: adw::ActionRow,
+    camera_lens: adw::ActionRow,
+    camera_focal_length: adw::ActionRow,
+    camera_aperture: adw::ActionRow,
+    camera_exposure_time: adw::ActionRow,
+    camera_iso: adw::ActionRow,
+    camera_flash: adw::ActionRow,
+
     video_details: adw::PreferencesGroup,
     video_dimensions: adw::ActionRow,
     video_container_format: adw::ActionRow,
@@ -65,7 +77,7 @@ pub struct ViewInfo {
 
 #[relm4::component(pub)]
 impl SimpleComponent for ViewInfo {
-    type Init = SharedState;
+    type Init = (SharedState, camera::Repository);
     type Input = ViewInfoInput;
     type Output = ();
 
@@ -159,6 +171,58 @@ impl SimpleComponent for ViewInfo {
                     },
                 },
 
+                #[local_ref]
+                camera_details -> adw::PreferencesGroup {
+                    #[local_ref]
+                    camera_name -> adw::ActionRow {
+                        set_title: &fl!("infobar-camera"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+
+                    #[local_ref]
+                    camera_lens -> adw::ActionRow {
+                        set_title: &fl!("infobar-lens"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+
+                    #[local_ref]
+                    camera_focal_length -> adw::ActionRow {
+                        set_title: &fl!("infobar-focal-length"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+
+                    #[local_ref]
+                    camera_aperture -> adw::ActionRow {
+                        set_title: &fl!("infobar-aperture"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+
+                    #[local_ref]
+                    camera_exposure_time -> adw::ActionRow {
+                        set_title: &fl!("infobar-exposure-time"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+
+                    #[local_ref]
+                    camera_iso -> adw::ActionRow {
+                        set_title: &fl!("infobar-iso"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+
+                    #[local_ref]
+                    camera_flash -> adw::ActionRow {
+                        set_title: &fl!("infobar-flash"),
+                        add_css_class: "property",
+                        set_subtitle_selectable: true,
+                    },
+                },
+
 
                 #[local_ref]
                 video_details -> adw::PreferencesGroup {
@@ -216,7 +280,7 @@ impl SimpleComponent for ViewInfo {
     }
 
     fn init(
-        state: Self::Init,
+        (state, camera_repo): Self::Init,
         _root: Self::Root,
         _sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
@@ -237,6 +301,15 @@ impl SimpleComponent for ViewInfo {
         let exif_originally_created_at = adw::ActionRow::new();
         let exif_originally_modified_at = adw::ActionRow::new();
 
+        let camera_details = adw::PreferencesGroup::new();
+        let camera_name = adw::ActionRow::new();
+        let camera_lens = adw::ActionRow::new();
+        let camera_focal_length = adw::ActionRow::new();
+        let camera_aperture = adw::ActionRow::new();
+        let camera_exposure_time = adw::ActionRow::new();
+        let camera_iso = adw::ActionRow::new();
+        let camera_flash = adw::ActionRow::new();
+
         let video_details = adw::PreferencesGroup::new();
         let video_duration = adw::ActionRow::new();
         let video_dimensions = adw::ActionRow::new();
@@ -248,6 +321,7 @@ impl SimpleComponent for ViewInfo {
 
         let model = ViewInfo {
             state,
+            camera_repo,
 
             folder: folder.clone(),
             file_name: file_name.clone(),
@@ -266,6 +340,15 @@ impl SimpleComponent for ViewInfo {
             exif_originally_created_at: exif_originally_created_at.clone(),
             exif_originally_modified_at: exif_originally_modified_at.clone(),
 
+            camera_details: camera_details.clone(),
+            camera_name: camera_name.clone(),
+            camera_lens: camera_lens.clone(),
+            camera_focal_length: camera_focal_length.clone(),
+            camera_aperture: camera_aperture.clone(),
+            camera_exposure_time: camera_exposure_time.clone(),
+            camera_iso: camera_iso.clone(),
+            camera_flash: camera_flash.clone(),
+
             video_details: video_details.clone(),
             video_file_size: video_file_size.clone(),
             video_originally_created_at: video_originally_created_at.clone(),
@@ -323,6 +406,7 @@ impl SimpleComponent for ViewInfo {
 
                 self.image_details.set_visible(false);
                 self.exif_details.set_visible(false);
+                self.camera_details.set_visible(false);
 
                 let _ = self.update_file_details(vis.clone());
 
@@ -404,15 +488,20 @@ impl ViewInfo {
         if let Some(Ok(exif)) = image_info.details.exif.as_ref().map(|x| x.get_full()) {
             let metadata = fotema_core::photo::metadata::from_raw(exif).ok();
 
+            // EXIF times are the local time kept as if it were UTC.
+            let local_time = |x: DateTime<FixedOffset>| {
+                format!("{} {}", x.naive_utc().format("%Y-%m-%d %H:%M:%S"), x.offset())
+            };
+
             let created_at: Option<String> = metadata
                 .clone()
                 .and_then(|x| x.created_at)
-                .map(|x| x.format("%Y-%m-%d %H:%M:%S %:z").to_string());
+                .map(local_time);
 
             let modified_at: Option<String> = metadata
                 .clone()
                 .and_then(|x| x.modified_at)
-                .map(|x| x.format("%Y-%m-%d %H:%M:%S %:z").to_string());
+                .map(local_time);
 
             let has_exif_details = [
                 Self::update_row(&self.exif_originally_created_at, created_at),
@@ -426,9 +515,43 @@ impl ViewInfo {
             self.exif_details.set_visible(false);
         }
 
+        self.update_camera_details(&vis);
+
         Ok(())
     }
 
+    /// Camera settings are read from the database, as they were saved when
+    /// the picture metadata was scanned.
+    fn update_camera_details(&mut self, vis: &fotema_core::visual::Visual) {
+        let camera = vis.picture_id
+            .and_then(|picture_id| {
+                self.camera_repo.get(picture_id)
+                    .inspect_err(|e| event!(Level::ERROR, "Failed getting camera settings: {}", e))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        let flash = camera.is_flash_fired.map(|is_fired| if is_fired {
+            fl!("infobar-flash", "fired")
+        } else {
+            fl!("infobar-flash", "not-fired")
+        });
+
+        let has_camera_details = [
+            Self::update_row(&self.camera_name, camera.camera_name()),
+            Self::update_row(&self.camera_lens, camera.lens_name()),
+            Self::update_row(&self.camera_focal_length, camera.focal_length_text()),
+            Self::update_row(&self.camera_aperture, camera.aperture_text()),
+            Self::update_row(&self.camera_exposure_time, camera.exposure_time_text()),
+            Self::update_row(&self.camera_iso, camera.iso_text()),
+            Self::update_row(&self.camera_flash, flash),
+        ]
+        .into_iter()
+        .any(|x| x);
+
+        self.camera_details.set_visible(has_camera_details);
+    }
+
     fn update_video_details(&mut self, vis: Arc<fotema_core::visual::Visual>) -> Result<(), String> {
         let Some(ref video_path) = vis.video_path else {
             return Err("No video path".to_string());
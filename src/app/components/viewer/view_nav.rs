@@ -11,13 +11,20 @@ use relm4::actions::{RelmAction, RelmActionGroup};
 use crate::app::components::albums::album_filter::AlbumFilter;
 use super::view_one::{ViewOne, ViewOneInput, ViewOneOutput};
 use super::view_info::{ViewInfo, ViewInfoInput};
+use super::photo_editor::{PhotoEditor, PhotoEditorInput, PhotoEditorOutput};
 use crate::app::components::progress_monitor::ProgressMonitor;
 use crate::app::SharedState;
+use crate::app::SettingsState;
 use crate::adaptive;
 use crate::fl;
 
 use fotema_core::Visual;
+use fotema_core::album;
+use fotema_core::camera;
+use fotema_core::edit;
 use fotema_core::people;
+use fotema_core::photo::xmp;
+use fotema_core::rating;
 use fotema_core::PictureId;
 use fotema_core::VisualId;
 
@@ -78,12 +85,54 @@ pub enum ViewNavInput {
 
     /// Scan for more faces.
     ScanForFaces,
+
+    /// Add or remove item from favourites.
+    ToggleFavourite,
+
+    /// Rate item from one to five stars, or clear rating.
+    Rate(Option<u8>),
+
+    /// Load albums into album menu.
+    LoadAlbums,
+
+    /// Add item to a user album.
+    AddToAlbum(album::AlbumId),
+
+    /// Remove item from user album currently being viewed.
+    RemoveFromAlbum,
+
+    /// Use item as cover of user album currently being viewed.
+    SetAlbumCover,
+
+    /// Move item to the trash and view the next item.
+    Trash,
+
+    /// Crop, rotate, flip, or straighten the photo.
+    Edit,
+
+    /// Edit of a photo has been saved.
+    Edited(PictureId),
 }
 
 #[derive(Debug)]
 pub enum ViewNavOutput {
     TranscodeAll,
     ScanForFaces(PictureId),
+
+    /// Favourite or rating of an item has changed.
+    RatingChanged,
+
+    /// Item has been added to or removed from a user album, or has become an album cover.
+    AlbumsChanged,
+
+    /// Item should be moved to the trash.
+    Trash(Arc<Visual>),
+
+    /// Nothing is left to view, so the viewer should be closed.
+    Close,
+
+    /// Photo has been edited, so its thumbnail must be regenerated.
+    Edited,
 }
 
 pub struct ViewNav {
@@ -91,18 +140,43 @@ pub struct ViewNav {
 
     people_repo: people::Repository,
 
+    rating_repo: rating::Repository,
+
+    album_repo: album::Repository,
+
+    settings_state: SettingsState,
+
     // View one photo or video
     view_one: AsyncController<ViewOne>,
 
     // Info for photo
     view_info: Controller<ViewInfo>,
 
+    // Editor for photo
+    photo_editor: AsyncController<PhotoEditor>,
+
     // Photo and photo info views
     split_view: adw::OverlaySplitView,
 
     left_button: gtk::Button,
     right_button: gtk::Button,
 
+    favourite_button: gtk::ToggleButton,
+    rating_button: gtk::MenuButton,
+
+    // One button per star in rating popover
+    star_buttons: Vec<gtk::Button>,
+
+    album_button: gtk::MenuButton,
+
+    edit_button: gtk::Button,
+
+    // Albums item can be added to
+    album_list: gtk::Box,
+
+    // Actions for album currently being viewed
+    album_actions: gtk::Box,
+
     /// Index into shared state for currently viewed item.
     current_index: Option<usize>,
 
@@ -116,7 +190,17 @@ pub struct ViewNav {
 
 #[relm4::component(pub async)]
 impl SimpleAsyncComponent for ViewNav {
-    type Init = (SharedState, Arc<Reducer<ProgressMonitor>>, Arc<adaptive::LayoutState>, people::Repository);
+    type Init = (
+        SharedState,
+        Arc<Reducer<ProgressMonitor>>,
+        Arc<adaptive::LayoutState>,
+        people::Repository,
+        rating::Repository,
+        album::Repository,
+        edit::Repository,
+        camera::Repository,
+        SettingsState,
+    );
     type Input = ViewNavInput;
     type Output = ViewNavOutput;
 
@@ -136,11 +220,85 @@ impl SimpleAsyncComponent for ViewNav {
                 pack_end = &gtk::Box {
                     set_orientation: gtk::Orientation::Horizontal,
 
+                    #[local_ref]
+                    favourite_button -> gtk::ToggleButton {
+                        set_icon_name: "emblem-favorite-symbolic",
+                        set_tooltip_text: Some(&fl!("viewer-favourite", "tooltip")),
+                        connect_clicked => ViewNavInput::ToggleFavourite,
+                    },
+
+                    #[local_ref]
+                    rating_button -> gtk::MenuButton {
+                        set_icon_name: "non-starred-symbolic",
+                        set_tooltip_text: Some(&fl!("viewer-rating", "tooltip")),
+
+                        #[wrap(Some)]
+                        set_popover = &gtk::Popover {
+                            #[local_ref]
+                            rating_box -> gtk::Box {
+                                set_orientation: gtk::Orientation::Horizontal,
+                            },
+                        },
+                    },
+
+                    #[local_ref]
+                    album_button -> gtk::MenuButton {
+                        set_icon_name: "list-add-symbolic",
+                        set_tooltip_text: Some(&fl!("viewer-albums", "tooltip")),
+
+                        #[wrap(Some)]
+                        set_popover = &gtk::Popover {
+                            connect_show => ViewNavInput::LoadAlbums,
+
+                            gtk::Box {
+                                set_orientation: gtk::Orientation::Vertical,
+                                set_spacing: 6,
+
+                                #[local_ref]
+                                album_list -> gtk::Box {
+                                    set_orientation: gtk::Orientation::Vertical,
+                                },
+
+                                #[local_ref]
+                                album_actions -> gtk::Box {
+                                    set_orientation: gtk::Orientation::Vertical,
+
+                                    gtk::Separator {},
+
+                                    gtk::Button {
+                                        set_label: &fl!("viewer-albums", "remove"),
+                                        add_css_class: "flat",
+                                        connect_clicked => ViewNavInput::RemoveFromAlbum,
+                                    },
+
+                                    gtk::Button {
+                                        set_label: &fl!("viewer-albums", "cover"),
+                                        add_css_class: "flat",
+                                        connect_clicked => ViewNavInput::SetAlbumCover,
+                                    },
+                                },
+                            },
+                        },
+                    },
+
                     gtk::MenuButton {
                         set_icon_name: "sentiment-very-satisfied-symbolic",
                         set_menu_model: Some(&viewnav_menu),
                     },
 
+                    #[local_ref]
+                    edit_button -> gtk::Button {
+                        set_icon_name: "document-edit-symbolic",
+                        set_tooltip_text: Some(&fl!("viewer-edit", "tooltip")),
+                        connect_clicked => ViewNavInput::Edit,
+                    },
+
+                    gtk::Button {
+                        set_icon_name: "user-trash-symbolic",
+                        set_tooltip_text: Some(&fl!("viewer-trash", "tooltip")),
+                        connect_clicked => ViewNavInput::Trash,
+                    },
+
                     gtk::Button {
                         set_icon_name: "info-outline-symbolic",
                         set_tooltip_text: Some(&fl!("viewer-info-tooltip")),
@@ -204,7 +362,7 @@ impl SimpleAsyncComponent for ViewNav {
     }
 
     async fn init(
-        (state, transcode_progress_monitor, layout_state, people_repo): Self::Init,
+        (state, transcode_progress_monitor, layout_state, people_repo, rating_repo, album_repo, edit_repo, camera_repo, settings_state): Self::Init,
         root: Self::Root,
         sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self>  {
@@ -212,7 +370,7 @@ impl SimpleAsyncComponent for ViewNav {
         let split_view = adw::OverlaySplitView::new();
 
         let view_one = ViewOne::builder()
-            .launch((people_repo.clone(), transcode_progress_monitor))
+            .launch((people_repo.clone(), edit_repo.clone(), transcode_progress_monitor))
             .forward(sender.input_sender(), |msg| match msg {
                 ViewOneOutput::PhotoShown(id, info) => ViewNavInput::ShowPhotoInfo(id, info),
                 ViewOneOutput::VideoShown(id) => ViewNavInput::ShowVideoInfo(id),
@@ -220,19 +378,73 @@ impl SimpleAsyncComponent for ViewNav {
             });
 
         let view_info = ViewInfo::builder()
-            .launch(state.clone())
+            .launch((state.clone(), camera_repo))
             .detach();
 
+        let photo_editor = PhotoEditor::builder()
+            .launch(edit_repo)
+            .forward(sender.input_sender(), |msg| match msg {
+                PhotoEditorOutput::Saved(picture_id) => ViewNavInput::Edited(picture_id),
+            });
+
         layout_state.subscribe(sender.input_sender(), |layout| ViewNavInput::Adapt(*layout));
 
         let left_button = gtk::Button::new();
         let right_button = gtk::Button::new();
+        let favourite_button = gtk::ToggleButton::new();
+        let rating_button = gtk::MenuButton::new();
+
+        let rating_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+        let mut star_buttons = Vec::new();
+
+        for stars in 1..=5 {
+            let button = gtk::Button::builder()
+                .icon_name("non-starred-symbolic")
+                .tooltip_text(fl!("viewer-rating-stars", count = stars))
+                .css_classes(["flat"])
+                .build();
+
+            let sender = sender.clone();
+            button.connect_clicked(move |_| sender.input(ViewNavInput::Rate(Some(stars))));
+
+            rating_box.append(&button);
+            star_buttons.push(button);
+        }
+
+        let clear_button = gtk::Button::builder()
+            .icon_name("edit-clear-symbolic")
+            .tooltip_text(fl!("viewer-rating", "clear"))
+            .css_classes(["flat"])
+            .build();
+
+        {
+            let sender = sender.clone();
+            clear_button.connect_clicked(move |_| sender.input(ViewNavInput::Rate(None)));
+        }
+
+        rating_box.append(&clear_button);
+
+        let album_button = gtk::MenuButton::new();
+        let edit_button = gtk::Button::new();
+        let album_list = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        let album_actions = gtk::Box::new(gtk::Orientation::Vertical, 0);
 
         let model = ViewNav {
             state,
             people_repo,
+            rating_repo,
+            album_repo,
+            settings_state,
+            album_button: album_button.clone(),
+            edit_button: edit_button.clone(),
+            album_list: album_list.clone(),
+            album_actions: album_actions.clone(),
+            favourite_button: favourite_button.clone(),
+            rating_button: rating_button.clone(),
+            star_buttons,
             view_one,
             view_info,
+            photo_editor,
             current_index: None,
             left_button: left_button.clone(),
             right_button: right_button.clone(),
@@ -312,6 +524,10 @@ impl SimpleAsyncComponent for ViewNav {
                 self.current_index = Some(index);
 
                 self.update_nav_buttons();
+                self.update_rating_buttons();
+
+                // Only photos can be edited, not videos or the still part of live photos.
+                self.edit_button.set_sensitive(visual.is_photo_only());
 
                 self.view_one.emit(ViewOneInput::View(visual.clone()));
             },
@@ -421,11 +637,230 @@ impl SimpleAsyncComponent for ViewNav {
                     let _ = sender.output(ViewNavOutput::ScanForFaces(picture_id));
                 }
             },
+            ViewNavInput::ToggleFavourite => {
+                let Some(index) = self.current_index else {
+                    return;
+                };
+
+                let mut visual = (*self.filtered_items[index]).clone();
+                visual.is_favourite = !visual.is_favourite;
+
+                info!("Setting favourite to {} for {}", visual.is_favourite, visual.visual_id);
+
+                if let Err(e) = self.rating_repo.set_favourite(&visual, visual.is_favourite) {
+                    error!("Failed setting favourite: {}", e);
+                    self.update_rating_buttons();
+                    return;
+                }
+
+                self.replace_visual(index, visual);
+                self.update_rating_buttons();
+                let _ = sender.output(ViewNavOutput::RatingChanged);
+            },
+            ViewNavInput::Rate(stars) => {
+                self.rating_button.popdown();
+
+                let Some(index) = self.current_index else {
+                    return;
+                };
+
+                let mut visual = (*self.filtered_items[index]).clone();
+                visual.rating = stars;
+
+                info!("Setting rating to {:?} for {}", stars, visual.visual_id);
+
+                if let Err(e) = self.rating_repo.set_rating(&visual, stars) {
+                    error!("Failed setting rating: {}", e);
+                    return;
+                }
+
+                if self.settings_state.read().write_xmp_sidecars {
+                    let paths = [&visual.picture_path, &visual.video_path];
+                    for path in paths.into_iter().flatten() {
+                        if let Err(e) = xmp::write_rating(path, stars) {
+                            error!("Failed writing rating to XMP sidecar for {:?}: {}", path, e);
+                        }
+                    }
+                }
+
+                self.replace_visual(index, visual);
+                self.update_rating_buttons();
+                let _ = sender.output(ViewNavOutput::RatingChanged);
+            },
+            ViewNavInput::LoadAlbums => {
+                while let Some(child) = self.album_list.first_child() {
+                    self.album_list.remove(&child);
+                }
+
+                let albums = self.album_repo.all().unwrap_or_else(|e| {
+                    error!("Failed loading albums: {}", e);
+                    Vec::new()
+                });
+
+                if albums.iter().all(|x| x.is_smart()) {
+                    let label = gtk::Label::builder()
+                        .label(fl!("viewer-albums", "empty"))
+                        .css_classes(["dim-label"])
+                        .margin_top(6)
+                        .margin_bottom(6)
+                        .build();
+                    self.album_list.append(&label);
+                }
+
+                // Smart albums contain items matching a query, so items can't be added to them.
+                for user_album in albums.into_iter().filter(|x| !x.is_smart()) {
+                    let button = gtk::Button::builder()
+                        .label(fl!("viewer-albums-add", name = user_album.name.clone()))
+                        .css_classes(["flat"])
+                        .build();
+
+                    let sender = sender.clone();
+                    let album_id = user_album.album_id;
+                    button.connect_clicked(move |_| sender.input(ViewNavInput::AddToAlbum(album_id)));
+                    self.album_list.append(&button);
+                }
+
+                let is_user_album = matches!(self.filter, AlbumFilter::UserAlbum(_, _));
+                self.album_actions.set_visible(is_user_album);
+            },
+            ViewNavInput::AddToAlbum(album_id) => {
+                self.album_button.popdown();
+
+                let Some(index) = self.current_index else {
+                    return;
+                };
+
+                let visual_id = self.filtered_items[index].visual_id.clone();
+                info!("Adding {} to album {}", visual_id, album_id);
+
+                if let Err(e) = self.album_repo.add(album_id, &[visual_id]) {
+                    error!("Failed adding to album: {}", e);
+                    return;
+                }
+
+                let _ = sender.output(ViewNavOutput::AlbumsChanged);
+            },
+            ViewNavInput::RemoveFromAlbum => {
+                self.album_button.popdown();
+
+                let (Some(index), AlbumFilter::UserAlbum(album_id, _)) = (self.current_index, &self.filter) else {
+                    return;
+                };
+
+                let visual_id = self.filtered_items[index].visual_id.clone();
+                info!("Removing {} from album {}", visual_id, album_id);
+
+                if let Err(e) = self.album_repo.remove(*album_id, &[visual_id]) {
+                    error!("Failed removing from album: {}", e);
+                    return;
+                }
+
+                let _ = sender.output(ViewNavOutput::AlbumsChanged);
+            },
+            ViewNavInput::SetAlbumCover => {
+                self.album_button.popdown();
+
+                let (Some(index), AlbumFilter::UserAlbum(album_id, _)) = (self.current_index, &self.filter) else {
+                    return;
+                };
+
+                let visual_id = &self.filtered_items[index].visual_id;
+                info!("Setting cover of album {} to {}", album_id, visual_id);
+
+                if let Err(e) = self.album_repo.set_cover(*album_id, visual_id) {
+                    error!("Failed setting album cover: {}", e);
+                    return;
+                }
+
+                let _ = sender.output(ViewNavOutput::AlbumsChanged);
+            },
+            ViewNavInput::Trash => {
+                let Some(index) = self.current_index else {
+                    return;
+                };
+
+                let visual = self.filtered_items.remove(index);
+                info!("Moving {} to trash", visual.visual_id);
+                let _ = sender.output(ViewNavOutput::Trash(visual));
+
+                self.view_one.emit(ViewOneInput::Hidden);
+
+                if self.filtered_items.is_empty() {
+                    self.current_index = None;
+                    let _ = sender.output(ViewNavOutput::Close);
+                } else {
+                    // Show the item after the trashed one, or the one before if it was last.
+                    let index = index.min(self.filtered_items.len() - 1);
+                    sender.input(ViewNavInput::ViewByIndex(index));
+                }
+            },
+            ViewNavInput::Edit => {
+                let Some(index) = self.current_index else {
+                    return;
+                };
+
+                let visual = self.filtered_items[index].clone();
+                if !visual.is_photo_only() {
+                    return;
+                }
+
+                self.photo_editor.emit(PhotoEditorInput::Edit(visual));
+                self.photo_editor.widget().present(self.split_view.root().as_ref());
+            },
+            ViewNavInput::Edited(picture_id) => {
+                info!("Picture {} edited", picture_id);
+
+                // Show the edit if the edited picture is still being viewed.
+                let visual = self.current_index.and_then(|index| self.filtered_items.get(index));
+                if let Some(visual) = visual.filter(|v| v.picture_id == Some(picture_id)) {
+                    self.view_one.emit(ViewOneInput::View(visual.clone()));
+                }
+
+                let _ = sender.output(ViewNavOutput::Edited);
+            },
         }
     }
 }
 
 impl ViewNav {
+    /// Replace an item that has been changed in both the filtered items and the shared state.
+    /// Subscribers to the shared state are not notified as that would reload every album.
+    fn replace_visual(&mut self, index: usize, visual: Visual) {
+        let visual = Arc::new(visual);
+
+        {
+            let mut items = self.state.write_inner();
+            if let Some(item) = items.iter_mut().find(|x| x.visual_id == visual.visual_id) {
+                *item = visual.clone();
+            }
+        }
+
+        self.filtered_items[index] = visual;
+    }
+
+    fn update_rating_buttons(&self) {
+        let Some(visual) = self.current_index.and_then(|index| self.filtered_items.get(index)) else {
+            return;
+        };
+
+        self.favourite_button.set_active(visual.is_favourite);
+
+        let stars = visual.rating.unwrap_or(0);
+        if stars > 0 {
+            self.rating_button.set_icon_name("starred-symbolic");
+        } else {
+            self.rating_button.set_icon_name("non-starred-symbolic");
+        }
+
+        for (index, button) in self.star_buttons.iter().enumerate() {
+            if (index as u8) < stars {
+                button.set_icon_name("starred-symbolic");
+            } else {
+                button.set_icon_name("non-starred-symbolic");
+            }
+        }
+    }
+
     fn update_nav_buttons(&self) {
         if self.filtered_items.len() <= 1 {
             self.left_button.set_sensitive(false);
@@ -9,6 +9,7 @@ use strum::IntoEnumIterator;
 use relm4::gtk;
 use relm4::adw::gdk;
 use relm4::gtk::gio;
+use relm4::gtk::glib;
 use relm4::gtk::prelude::*;
 use relm4::*;
 use relm4::prelude::*;
@@ -18,16 +19,23 @@ use chrono::TimeDelta;
 use crate::app::components::progress_monitor::ProgressMonitor;
 use crate::app::components::progress_panel::ProgressPanel;
 use crate::fl;
+use fotema_core::edit;
+use fotema_core::photo::raw;
 use fotema_core::people;
 use super::face_thumbnails::{FaceThumbnails, FaceThumbnailsInput};
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use tracing::{event, Level};
 
 const TEN_SECS_IN_MICROS: i64 = 10_000_000;
 const FIFTEEN_SECS_IN_MICROS: i64 = 15_000_000;
 
+// Lower bound on the delay between frames of an animation, so that a
+// missing or zero delay doesn't spin the main loop.
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+
 #[derive(Debug)]
 pub enum ViewOneInput {
     // View an item.
@@ -95,11 +103,16 @@ pub struct ViewOne {
     broken_status: adw::StatusPage,
 
     face_thumbnails: AsyncController<FaceThumbnails>,
+
+    edit_repo: edit::Repository,
+
+    // Plays the frames of an animated picture
+    animation: Option<glib::JoinHandle<()>>,
 }
 
 #[relm4::component(pub async)]
 impl SimpleAsyncComponent for ViewOne {
-    type Init = (people::Repository, Arc<Reducer<ProgressMonitor>>);
+    type Init = (people::Repository, edit::Repository, Arc<Reducer<ProgressMonitor>>);
     type Input = ViewOneInput;
     type Output = ViewOneOutput;
 
@@ -237,7 +250,7 @@ impl SimpleAsyncComponent for ViewOne {
     }
 
     async fn init(
-        (people_repo, transcode_progress_monitor): Self::Init,
+        (people_repo, edit_repo, transcode_progress_monitor): Self::Init,
         root: Self::Root,
         _sender: AsyncComponentSender<Self>,
     ) -> AsyncComponentParts<Self>  {
@@ -284,6 +297,8 @@ impl SimpleAsyncComponent for ViewOne {
             transcode_progress,
             broken_status: broken_status.clone(),
             face_thumbnails,
+            edit_repo,
+            animation: None,
         };
 
         let widgets = view_output!();
@@ -294,6 +309,7 @@ impl SimpleAsyncComponent for ViewOne {
     async fn update(&mut self, msg: Self::Input, sender: AsyncComponentSender<Self>) {
         match msg {
             ViewOneInput::Hidden => {
+                self.stop_animation();
                 self.video = None;
                 self.picture.set_paintable(None::<&gdk::Paintable>);
                 self.face_thumbnails.emit(FaceThumbnailsInput::Hide);
@@ -301,6 +317,8 @@ impl SimpleAsyncComponent for ViewOne {
             ViewOneInput::View(visual) => {
                 event!(Level::INFO, "Showing item for {}", visual.visual_id);
 
+                self.stop_animation();
+
                 self.picture.set_visible(false);
                 self.transcode_status.set_visible(false);
                 self.video_controls.set_visible(false);
@@ -341,18 +359,41 @@ impl SimpleAsyncComponent for ViewOne {
                 }
 
                 if visual.is_photo_only() {
+                    let edit = visual.picture_id
+                        .and_then(|picture_id| self.edit_repo.get(picture_id).ok().flatten());
+
                     // Apply a CSS transformation to respect the EXIF orientation
                     // NOTE: don't use Glycin to apply the transformation here because it is
-                    // too slow.
-                    let orientation = visual.picture_orientation
-                        .unwrap_or(PictureOrientation::North);
-                    self.picture.add_css_class(orientation.as_ref());
+                    // too slow. Edited pictures are the exception, because edits apply to
+                    // the picture as oriented.
+                    if edit.is_none() {
+                        let orientation = visual.picture_orientation
+                            .unwrap_or(PictureOrientation::North);
+                        self.picture.add_css_class(orientation.as_ref());
+                    }
 
-                    let file = gio::File::for_path(visual_path);
+                    // Glycin can't decode camera RAW files, so show a preview instead.
+                    // Like the RAW file, the preview is oriented with CSS.
+                    let preview = if raw::is_raw(visual_path) {
+                        match raw::preview(visual_path) {
+                            Ok(preview) => Some(preview),
+                            Err(e) => {
+                                event!(Level::ERROR, "Failed loading RAW preview: {:?}", e);
+                                self.broken_status.set_icon_name(Some("sad-computer-symbolic"));
+                                self.broken_status.set_description(Some(&fl!("viewer-error-failed-to-load")));
+                                self.broken_status.set_visible(true);
+                                return;
+                            },
+                        }
+                    } else {
+                        None
+                    };
+
+                    let file = gio::File::for_path(preview.as_ref().map_or(visual_path.as_path(), |x| x.file.path()));
 
                     let mut loader = glycin::Loader::new(file);
                     loader.sandbox_selector(glycin::SandboxSelector::FlatpakSpawn);
-                    loader.apply_transformations(false);
+                    loader.apply_transformations(edit.is_some() && preview.is_none());
 
                     let image = loader.load().await;
 
@@ -373,12 +414,52 @@ impl SimpleAsyncComponent for ViewOne {
                         return;
                     };
 
-                    let texture = frame.texture();
+                    let mut texture = frame.texture();
+                    let is_edited = edit.is_some();
+
+                    if let Some(edit) = edit {
+                        let mut image = edit::render::from_texture(&texture);
+
+                        // Edits apply to the picture as oriented.
+                        if let Some(ref preview) = preview {
+                            image = raw::orient(image, preview.orientation);
+                        }
+
+                        let edited = relm4::spawn_blocking(move || edit::render::apply(&edit, image)).await;
+                        match edited {
+                            Ok(image) => texture = edit::render::to_texture(&image),
+                            Err(e) => event!(Level::ERROR, "Failed applying edit: {:?}", e),
+                        }
+                    }
 
                     self.picture.set_paintable(Some(&texture));
                     self.picture.set_visible(true);
 
                     let _ = sender.output(ViewOneOutput::PhotoShown(visual.visual_id.clone(), image.info().clone()));
+
+                    // Edits are only rendered for the first frame, so edited pictures stay still.
+                    if visual.is_animated() && !is_edited && preview.is_none() {
+                        let picture = self.picture.clone();
+                        let mut delay = frame.delay();
+                        self.animation = Some(relm4::spawn_local(async move {
+                            loop {
+                                let delay = delay.unwrap_or(Duration::from_millis(100))
+                                    .max(MIN_FRAME_DELAY);
+                                glib::timeout_future(delay).await;
+
+                                match image.next_frame().await {
+                                    Ok(frame) => {
+                                        picture.set_paintable(Some(&frame.texture()));
+                                        delay = frame.delay();
+                                    },
+                                    Err(e) => {
+                                        event!(Level::ERROR, "Failed getting animation frame: {:?}", e);
+                                        break;
+                                    },
+                                }
+                            }
+                        }));
+                    }
                 } else { // video or motion photo
                     let is_transcoded = visual.video_transcoded_path.as_ref().is_some_and(|x| x.exists());
 
@@ -557,3 +638,11 @@ impl SimpleAsyncComponent for ViewOne {
         }
     }
 }
+
+impl ViewOne {
+    fn stop_animation(&mut self) {
+        if let Some(animation) = self.animation.take() {
+            animation.abort();
+        }
+    }
+}